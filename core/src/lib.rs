@@ -0,0 +1,743 @@
+// The physics core of orbitabase: the state of a small body around the earth, the forcing on
+// it from the earth, its equatorial bulge, any other point masses and the air, and the
+// integrators that propagate it. It has no dependencies but an optional serde for saving
+// bodies, so it can be reused and tested headlessly; the simulator wraps `Body` in a component.
+use std::ops;
+
+pub type Precision = f64;
+
+pub const G: Precision = 6.6743e-11; // m3 kg-1 s-2
+pub const MASS_EARTH: Precision = 5.972e24;
+pub const EARTH_RADIUS: Precision = 6.371e6;
+pub const J2_EARTH: Precision = 1.08263e-3; // the earth's oblateness, about EARTH_RADIUS
+pub const DEFAULT_DT: Precision = 10.0; // s
+pub const N_HISTORY: usize = 21;
+
+// Bodies have a mass, an id, a current state and a rolling history of states, and feel drag
+// if they have a ballistic coefficient
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Body {
+    pub current_state: State,
+    pub history: StateHistory,
+    pub mass: Precision,
+    pub id: usize,
+    pub ballistic_coefficient: Option<Precision>, // kg/m², m / (Cd A)
+}
+
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct State {
+    pub vx: Precision,
+    pub vy: Precision,
+    pub x: Precision,
+    pub y: Precision,
+}
+
+impl State {
+    pub fn new(x0: Precision, y0: Precision, vx0: Precision, vy0: Precision) -> Self {
+        Self {
+            x: x0,
+            y: y0,
+            vx: vx0,
+            vy: vy0,
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateHistory(pub [Option<State>; N_HISTORY]);
+
+impl StateHistory {
+    pub fn new() -> Self {
+        StateHistory([None; N_HISTORY])
+    }
+
+    pub fn push(&mut self, state: State) {
+        self.0.rotate_right(1);
+        self.0[0] = Some(state);
+    }
+}
+
+impl Default for StateHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Body {
+    pub fn new(
+        id: usize,
+        mass: Precision,
+        x0: Precision,
+        y0: Precision,
+        vx0: Precision,
+        vy0: Precision,
+    ) -> Self {
+        let current_state = State::new(x0, y0, vx0, vy0);
+
+        Self {
+            current_state,
+            history: StateHistory::new(),
+            mass,
+            id,
+            ballistic_coefficient: None,
+        }
+    }
+
+    // The drag on the body in `atmosphere`, if it feels any
+    pub fn drag(&self, atmosphere: Option<Atmosphere>) -> Option<Drag> {
+        Some(Drag {
+            atmosphere: atmosphere?,
+            ballistic_coefficient: self.ballistic_coefficient?,
+        })
+    }
+
+    pub fn update_history(&mut self) {
+        self.history.push(self.current_state);
+    }
+
+    // One step of `dt` seconds
+    pub fn step(&mut self, dt: Precision, thrust: Thrust) {
+        self.step_from(dt, thrust, &[EARTH], None);
+    }
+
+    pub fn step_from(
+        &mut self,
+        dt: Precision,
+        thrust: Thrust,
+        sources: &[Source],
+        atmosphere: Option<Atmosphere>,
+    ) {
+        let drag = self.drag(atmosphere);
+        self.current_state = rk4_from(self.current_state, dt, thrust, sources, drag);
+        self.update_history();
+    }
+}
+
+// Thrust acceleration along the velocity, rotated off-axis by `misalignment` radians
+#[derive(Copy, Clone, Default)]
+pub struct Thrust {
+    pub acceleration: Precision,
+    pub misalignment: Precision,
+}
+
+impl Thrust {
+    // The acceleration along x and y for a body in `state`
+    pub fn components(self, state: &State) -> (Precision, Precision) {
+        let v = (state.vx * state.vx + state.vy * state.vy).sqrt();
+        let (sin, cos) = self.misalignment.sin_cos();
+        (
+            self.acceleration * (cos * state.vx - sin * state.vy) / v,
+            self.acceleration * (sin * state.vx + cos * state.vy) / v,
+        )
+    }
+}
+
+// Base altitude (km), density there (kg/m³) and scale height (km), from Vallado's table
+const LAYERS: [(Precision, Precision, Precision); 28] = [
+    (0.0, 1.225, 7.249),
+    (25.0, 3.899e-2, 6.349),
+    (30.0, 1.774e-2, 6.682),
+    (40.0, 3.972e-3, 7.554),
+    (50.0, 1.057e-3, 8.382),
+    (60.0, 3.206e-4, 7.714),
+    (70.0, 8.770e-5, 6.549),
+    (80.0, 1.905e-5, 5.799),
+    (90.0, 3.396e-6, 5.382),
+    (100.0, 5.297e-7, 5.877),
+    (110.0, 9.661e-8, 7.263),
+    (120.0, 2.438e-8, 9.473),
+    (130.0, 8.484e-9, 12.636),
+    (140.0, 3.845e-9, 16.149),
+    (150.0, 2.070e-9, 22.523),
+    (180.0, 5.464e-10, 29.740),
+    (200.0, 2.789e-10, 37.105),
+    (250.0, 7.248e-11, 45.546),
+    (300.0, 2.418e-11, 53.628),
+    (350.0, 9.518e-12, 53.298),
+    (400.0, 3.725e-12, 58.515),
+    (450.0, 1.585e-12, 60.828),
+    (500.0, 6.967e-13, 63.822),
+    (600.0, 1.454e-13, 71.835),
+    (700.0, 3.614e-14, 88.667),
+    (800.0, 1.170e-14, 124.64),
+    (900.0, 5.245e-15, 181.05),
+    (1000.0, 3.019e-15, 268.00),
+];
+
+pub const ATMOSPHERE_TOP: Precision = 1000e3; // m, above which the density is taken as zero
+
+// Air density over the earth: piecewise exponential between tabulated altitudes, each layer
+// with its own scale height, or a single exponential from sea level
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Atmosphere {
+    #[default]
+    Tabulated,
+    Exponential {
+        surface_density: Precision, // kg/m³
+        scale_height: Precision,    // m
+    },
+}
+
+impl Atmosphere {
+    // kg/m³ at an altitude in metres
+    pub fn density(self, altitude: Precision) -> Precision {
+        if altitude > ATMOSPHERE_TOP {
+            return 0.0;
+        }
+        let altitude = altitude.max(0.0);
+        match self {
+            Atmosphere::Tabulated => {
+                let km = altitude / 1000.0;
+                let i = LAYERS.partition_point(|(base, ..)| *base <= km) - 1;
+                let (base, rho, scale_height) = LAYERS[i];
+                rho * (-(km - base) / scale_height).exp()
+            }
+            Atmosphere::Exponential {
+                surface_density,
+                scale_height,
+            } => surface_density * (-altitude / scale_height).exp(),
+        }
+    }
+}
+
+// Drag against the velocity, from air at rest in the inertial frame
+#[derive(Copy, Clone)]
+pub struct Drag {
+    pub atmosphere: Atmosphere,
+    pub ballistic_coefficient: Precision, // kg/m²
+}
+
+impl Drag {
+    // The acceleration along x and y for a body in `state`
+    pub fn components(self, state: &State) -> (Precision, Precision) {
+        let altitude = state.x.hypot(state.y) - EARTH_RADIUS;
+        let v = state.vx.hypot(state.vy);
+        let f = -0.5 * self.atmosphere.density(altitude) * v / self.ballistic_coefficient;
+        (f * state.vx, f * state.vy)
+    }
+}
+
+// Time derivative of a state
+#[derive(Copy, Clone)]
+pub struct Forcing {
+    pub ax: Precision,
+    pub ay: Precision,
+    pub vy: Precision,
+    pub vx: Precision,
+}
+
+impl ops::Add<&Forcing> for &State {
+    type Output = State;
+
+    fn add(self, rhs: &Forcing) -> Self::Output {
+        State {
+            x: self.x + rhs.vx,
+            y: self.y + rhs.vy,
+            vx: self.vx + rhs.ax,
+            vy: self.vy + rhs.ay,
+        }
+    }
+}
+
+impl ops::Add<&Forcing> for &Forcing {
+    type Output = Forcing;
+
+    fn add(self, rhs: &Forcing) -> Self::Output {
+        Forcing {
+            ax: self.ax + rhs.ax,
+            ay: self.ay + rhs.ay,
+            vx: self.vx + rhs.vx,
+            vy: self.vy + rhs.vy,
+        }
+    }
+}
+
+impl ops::Mul<&Forcing> for Precision {
+    type Output = Forcing;
+
+    fn mul(self, rhs: &Forcing) -> Self::Output {
+        Self::Output {
+            ax: self * rhs.ax,
+            ay: self * rhs.ay,
+            vx: self * rhs.vx,
+            vy: self * rhs.vy,
+        }
+    }
+}
+
+// A mass pulling on the bodies, in kg and m, with the J2 zonal harmonic of its shape. 0 makes
+// it a point mass.
+#[derive(Copy, Clone)]
+pub struct Source {
+    pub mass: Precision,
+    pub x: Precision,
+    pub y: Precision,
+    pub j2: Precision,
+    pub radius: Precision, // equatorial, the one j2 is taken about
+}
+
+pub const EARTH: Source = Source {
+    mass: MASS_EARTH,
+    x: 0.0,
+    y: 0.0,
+    j2: 0.0,
+    radius: EARTH_RADIUS,
+};
+
+// The forcing with the earth as the only source, in a vacuum
+pub fn forcing(state: State, thrust: Thrust) -> Forcing {
+    forcing_from(state, thrust, &[EARTH], None)
+}
+
+pub fn forcing_from(
+    state: State,
+    thrust: Thrust,
+    sources: &[Source],
+    drag: Option<Drag>,
+) -> Forcing {
+    let (thrustx, thrusty) = thrust.components(&state);
+    let (mut ax, mut ay) = (thrustx, thrusty);
+
+    if let Some(drag) = drag {
+        let (dragx, dragy) = drag.components(&state);
+        ax += dragx;
+        ay += dragy;
+    }
+
+    for source in sources {
+        let (dx, dy) = (state.x - source.x, state.y - source.y);
+        let r = (dx * dx + dy * dy).sqrt();
+
+        // Bodies move in the equatorial plane, where the bulge adds to the pull, more the lower
+        // they are, so orbits precess
+        let bulge = 1.5 * source.j2 * (source.radius / r).powi(2);
+        let f = -G * source.mass * (1.0 + bulge) / (r * r * r);
+
+        ax += f * dx;
+        ay += f * dy;
+    }
+
+    Forcing {
+        ax,
+        ay,
+        vx: state.vx,
+        vy: state.vy,
+    }
+}
+
+// One step of `dt` with the classic fourth-order Runge-Kutta, around the earth alone in a vacuum
+pub fn rk4(state: State, dt: Precision, thrust: Thrust) -> State {
+    rk4_from(state, dt, thrust, &[EARTH], None)
+}
+
+pub fn rk4_from(
+    state: State,
+    dt: Precision,
+    thrust: Thrust,
+    sources: &[Source],
+    drag: Option<Drag>,
+) -> State {
+    adaptive_step(&Rk4, state, dt, thrust, sources, drag)
+}
+
+// A way of moving a state on by `dt` under constant thrust, the same sources and the same air
+pub trait Integrator: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn step(
+        &self,
+        state: State,
+        dt: Precision,
+        thrust: Thrust,
+        sources: &[Source],
+        drag: Option<Drag>,
+    ) -> State;
+}
+
+// The classic fourth-order Runge-Kutta
+pub struct Rk4;
+
+impl Integrator for Rk4 {
+    fn name(&self) -> &'static str {
+        "rk4"
+    }
+
+    fn step(
+        &self,
+        state: State,
+        dt: Precision,
+        thrust: Thrust,
+        sources: &[Source],
+        drag: Option<Drag>,
+    ) -> State {
+        let k1 = forcing_from(state, thrust, sources, drag);
+        let k2 = forcing_from(&state + &(0.5 * dt * &k1), thrust, sources, drag);
+        let k3 = forcing_from(&state + &(0.5 * dt * &k2), thrust, sources, drag);
+        let k4 = forcing_from(&state + &(dt * &k3), thrust, sources, drag);
+        &state + &(dt / 6.0 * &(&k1 + &(&(2.0 * &k2) + &(&(2.0 * &k3) + &k4))))
+    }
+}
+
+// Velocity Verlet (kick, drift, kick), symplectic around point masses, so the energy of a
+// coasting orbit wobbles but doesn't drift. Drag and thrust are taken at the ends of the step.
+pub struct Verlet;
+
+impl Integrator for Verlet {
+    fn name(&self) -> &'static str {
+        "verlet"
+    }
+
+    fn step(
+        &self,
+        state: State,
+        dt: Precision,
+        thrust: Thrust,
+        sources: &[Source],
+        drag: Option<Drag>,
+    ) -> State {
+        let k1 = forcing_from(state, thrust, sources, drag);
+        let (hx, hy) = (state.vx + 0.5 * dt * k1.ax, state.vy + 0.5 * dt * k1.ay);
+        let moved = State::new(state.x + dt * hx, state.y + dt * hy, hx, hy);
+        let k2 = forcing_from(moved, thrust, sources, drag);
+        State::new(
+            moved.x,
+            moved.y,
+            hx + 0.5 * dt * k2.ax,
+            hy + 0.5 * dt * k2.ay,
+        )
+    }
+}
+
+// Dormand-Prince RK45: fifth-order steps, with the embedded fourth-order solution as the error
+// estimate, shortened and lengthened within `dt` to keep every component's error under
+// `tolerance` relative to its size (or absolute, for components near zero)
+pub struct DormandPrince {
+    pub tolerance: Precision,
+}
+
+impl Default for DormandPrince {
+    fn default() -> Self {
+        Self { tolerance: 1e-10 }
+    }
+}
+
+const MIN_SUBSTEP: Precision = 1e-3; // s, taken whatever its error
+
+// Butcher tableau, the fifth-order weights are the last row
+const DP_A: [&[Precision]; 6] = [
+    &[1.0 / 5.0],
+    &[3.0 / 40.0, 9.0 / 40.0],
+    &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+    &[
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+    ],
+    &[
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+    ],
+    &[
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+// Fifth-order weights less the fourth-order ones
+const DP_E: [Precision; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+
+// `state` plus `h` times the weighted sum of the stages
+fn offset(state: &State, h: Precision, weights: &[Precision], stages: &[Forcing]) -> State {
+    weights
+        .iter()
+        .zip(stages)
+        .fold(*state, |sum, (weight, stage)| {
+            &sum + &((h * weight) * stage)
+        })
+}
+
+impl DormandPrince {
+    // One step of `h` and its error, in tolerances
+    fn attempt(
+        &self,
+        state: &State,
+        h: Precision,
+        thrust: Thrust,
+        sources: &[Source],
+        drag: Option<Drag>,
+    ) -> (State, Precision) {
+        let mut stages = [forcing_from(*state, thrust, sources, drag); 7];
+        for (i, weights) in DP_A.iter().enumerate() {
+            let stage = offset(state, h, weights, &stages);
+            stages[i + 1] = forcing_from(stage, thrust, sources, drag);
+        }
+        let next = offset(state, h, DP_A[5], &stages);
+        let error = offset(&State::new(0.0, 0.0, 0.0, 0.0), h, &DP_E, &stages);
+
+        let scaled = |error: Precision, a: Precision, b: Precision| {
+            error.abs() / (self.tolerance * (1.0 + a.abs().max(b.abs())))
+        };
+        let worst = [
+            scaled(error.x, state.x, next.x),
+            scaled(error.y, state.y, next.y),
+            scaled(error.vx, state.vx, next.vx),
+            scaled(error.vy, state.vy, next.vy),
+        ]
+        .into_iter()
+        .fold(0.0, Precision::max);
+        (next, worst)
+    }
+}
+
+impl Integrator for DormandPrince {
+    fn name(&self) -> &'static str {
+        "dormand_prince"
+    }
+
+    fn step(
+        &self,
+        mut state: State,
+        dt: Precision,
+        thrust: Thrust,
+        sources: &[Source],
+        drag: Option<Drag>,
+    ) -> State {
+        let (mut done, mut h) = (0.0, dt);
+        while dt - done > MIN_SUBSTEP * 1e-3 {
+            h = h.min(dt - done);
+            let (next, error) = self.attempt(&state, h, thrust, sources, drag);
+            if error <= 1.0 || h <= MIN_SUBSTEP {
+                state = next;
+                done += h;
+            }
+            // The usual controller, with a safety factor and limits on how fast it changes
+            let factor = if error == 0.0 {
+                5.0
+            } else {
+                (0.9 * error.powf(-0.2)).clamp(0.2, 5.0)
+            };
+            h = (h * factor).max(MIN_SUBSTEP);
+        }
+        state
+    }
+}
+
+const STEP_FRACTION: Precision = 1.0 / 80.0; // of the local timescale, the longest substep
+const MAX_SUBSTEPS: usize = 10000; // in a step, however close the pass
+
+// How many substeps a step of `dt` from `state` takes: enough for none to be longer than a
+// small fraction of the local timescale, the shorter of the time to swing around the nearest
+// mass at this distance and the time to cover the distance at this speed. Low orbits around the
+// earth take the step whole; close to periapsis of eccentric orbits, and in close passes by
+// point masses, it's cut up.
+pub fn substeps(state: &State, dt: Precision, sources: &[Source]) -> usize {
+    let speed = state.vx.hypot(state.vy);
+    let timescale = sources
+        .iter()
+        .map(|source| {
+            let r = (state.x - source.x).hypot(state.y - source.y);
+            (r.powi(3) / (G * source.mass)).sqrt().min(r / speed)
+        })
+        .fold(Precision::INFINITY, Precision::min);
+    let substeps = (dt / (STEP_FRACTION * timescale)).ceil();
+    if substeps.is_nan() {
+        1
+    } else {
+        (substeps as usize).clamp(1, MAX_SUBSTEPS)
+    }
+}
+
+// A step of `dt` in as many substeps as the local timescale asks for
+pub fn adaptive_step(
+    integrator: &dyn Integrator,
+    state: State,
+    dt: Precision,
+    thrust: Thrust,
+    sources: &[Source],
+    drag: Option<Drag>,
+) -> State {
+    let n = substeps(&state, dt, sources);
+    let h = dt / n as Precision;
+    (0..n).fold(state, |state, _| {
+        integrator.step(state, h, thrust, sources, drag)
+    })
+}
+
+// The integrator with this name, for the command line and the console
+pub fn integrator(name: &str) -> Result<Box<dyn Integrator>, String> {
+    match name {
+        "rk4" => Ok(Box::new(Rk4)),
+        "verlet" | "leapfrog" => Ok(Box::new(Verlet)),
+        "dormand_prince" => Ok(Box::new(DormandPrince::default())),
+        _ => Err(format!(
+            "unknown integrator \"{}\", try rk4, verlet or dormand_prince",
+            name
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use super::*;
+
+    const LOW_ORBIT: Precision = EARTH_RADIUS + 400e3;
+
+    fn circular(r: Precision) -> State {
+        State::new(r, 0.0, 0.0, (G * MASS_EARTH / r).sqrt())
+    }
+
+    fn energy(state: &State) -> Precision {
+        0.5 * (state.vx * state.vx + state.vy * state.vy) - G * MASS_EARTH / state.x.hypot(state.y)
+    }
+
+    // The largest relative change of the energy over ten orbits of steps of 10 s
+    fn energy_drift(integrator: &dyn Integrator) -> Precision {
+        let start = circular(LOW_ORBIT);
+        let period = TAU * (LOW_ORBIT.powi(3) / (G * MASS_EARTH)).sqrt();
+        let steps = (10.0 * period / DEFAULT_DT) as usize;
+        let mut state = start;
+        let mut drift: Precision = 0.0;
+        for _ in 0..steps {
+            state = adaptive_step(
+                integrator,
+                state,
+                DEFAULT_DT,
+                Thrust::default(),
+                &[EARTH],
+                None,
+            );
+            drift = drift.max((energy(&state) / energy(&start) - 1.0).abs());
+        }
+        drift
+    }
+
+    #[test]
+    fn integrators_keep_a_circular_orbit_energy() {
+        assert!(energy_drift(&Rk4) < 1e-6);
+        assert!(energy_drift(&Verlet) < 1e-4);
+        assert!(energy_drift(&DormandPrince::default()) < 1e-9);
+    }
+
+    #[test]
+    fn verlet_energy_stays_bounded() {
+        // Symplectic: the error wobbles instead of growing with the orbits flown
+        let drift = energy_drift(&Verlet);
+        let start = circular(LOW_ORBIT);
+        let state = (0..20000).fold(start, |state, _| {
+            Verlet.step(state, DEFAULT_DT, Thrust::default(), &[EARTH], None)
+        });
+        assert!((energy(&state) / energy(&start) - 1.0).abs() <= 1.5 * drift);
+    }
+
+    #[test]
+    fn low_orbits_take_the_step_whole() {
+        assert_eq!(substeps(&circular(LOW_ORBIT), DEFAULT_DT, &[EARTH]), 1);
+    }
+
+    #[test]
+    fn close_passes_are_cut_into_substeps() {
+        let moon = Source {
+            mass: 7.342e22,
+            x: LOW_ORBIT + 2e6,
+            ..EARTH
+        };
+        let near = substeps(&circular(LOW_ORBIT), DEFAULT_DT, &[EARTH, moon]);
+        assert!(near > 1 && near < MAX_SUBSTEPS);
+
+        // However close, never more than the cap, and one with nothing to swing around
+        let at = Source {
+            x: LOW_ORBIT + 1.0,
+            ..moon
+        };
+        assert_eq!(
+            substeps(&circular(LOW_ORBIT), DEFAULT_DT, &[at]),
+            MAX_SUBSTEPS
+        );
+        assert_eq!(substeps(&circular(LOW_ORBIT), DEFAULT_DT, &[]), 1);
+    }
+
+    #[test]
+    fn adaptive_step_splits_the_step_evenly() {
+        let moon = Source {
+            mass: 7.342e22,
+            x: LOW_ORBIT + 2e6,
+            ..EARTH
+        };
+        let sources = [EARTH, moon];
+        let start = circular(LOW_ORBIT);
+        let n = substeps(&start, DEFAULT_DT, &sources);
+        let h = DEFAULT_DT / n as Precision;
+        let by_hand = (0..n).fold(start, |state, _| {
+            Rk4.step(state, h, Thrust::default(), &sources, None)
+        });
+        let adaptive = adaptive_step(&Rk4, start, DEFAULT_DT, Thrust::default(), &sources, None);
+        assert_eq!((adaptive.x, adaptive.y), (by_hand.x, by_hand.y));
+    }
+
+    #[test]
+    fn j2_strengthens_the_pull_in_the_equatorial_plane() {
+        let state = circular(LOW_ORBIT);
+        let oblate = Source {
+            j2: J2_EARTH,
+            ..EARTH
+        };
+        let round = forcing_from(state, Thrust::default(), &[EARTH], None);
+        let bulged = forcing_from(state, Thrust::default(), &[oblate], None);
+
+        let expected = 1.0 + 1.5 * J2_EARTH * (EARTH_RADIUS / LOW_ORBIT).powi(2);
+        assert!((bulged.ax / round.ax - expected).abs() < 1e-12);
+        assert_eq!(bulged.ay, 0.0);
+        assert!((round.ax + G * MASS_EARTH / (LOW_ORBIT * LOW_ORBIT)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tabulated_density_follows_the_table() {
+        let atmosphere = Atmosphere::Tabulated;
+        for (base, rho, _) in LAYERS {
+            let density = atmosphere.density(base * 1000.0);
+            assert!((density / rho - 1.0).abs() < 1e-12, "at {} km", base);
+        }
+        assert!(atmosphere.density(-100.0) == 1.225);
+        assert_eq!(atmosphere.density(ATMOSPHERE_TOP + 1.0), 0.0);
+    }
+
+    #[test]
+    fn density_falls_with_altitude() {
+        let atmosphere = Atmosphere::Tabulated;
+        let densities: Vec<Precision> = (0..1000)
+            .map(|km| atmosphere.density(km as Precision * 1000.0))
+            .collect();
+        assert!(densities.windows(2).all(|pair| pair[1] < pair[0]));
+    }
+
+    #[test]
+    fn exponential_density_falls_by_e_every_scale_height() {
+        let atmosphere = Atmosphere::Exponential {
+            surface_density: 1.2,
+            scale_height: 8000.0,
+        };
+        assert_eq!(atmosphere.density(0.0), 1.2);
+        let ratio = atmosphere.density(16000.0) / atmosphere.density(8000.0);
+        assert!((ratio - (-1.0 as Precision).exp()).abs() < 1e-12);
+    }
+}
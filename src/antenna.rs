@@ -0,0 +1,144 @@
+// Antennas and the link budget of each contact: bodies transmit the downlink through their
+// antenna, stations receive it through theirs, and the margin is the Eb/N0 over what the
+// modulation needs. Gains fall off from the peak with the angle off boresight, so where an
+// antenna points matters as much as how big it is. Bodies without an antenna radiate
+// isotropically.
+use std::f64::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::ground::{GroundStation, SPEED_OF_LIGHT};
+use crate::{Precision, State};
+
+const BOLTZMANN: Precision = -228.6; // dBW/K/Hz
+const SIDELOBE_FLOOR: Precision = 30.0; // dB below the peak
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pointing {
+    Nadir,    // straight down, the usual for a small satellite
+    Zenith,   // straight up, a fixed antenna on the ground
+    Tracking, // always at the other end of the link
+}
+
+impl Pointing {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "nadir" => Ok(Pointing::Nadir),
+            "zenith" => Ok(Pointing::Zenith),
+            "tracking" => Ok(Pointing::Tracking),
+            _ => Err(format!(
+                "unknown pointing \"{}\", try nadir, zenith or tracking",
+                name
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pointing::Nadir => "nadir",
+            Pointing::Zenith => "zenith",
+            Pointing::Tracking => "tracking",
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Antenna {
+    pub peak_gain: Precision, // dBi
+    pub beamwidth: Precision, // rad, full width at half power
+    pub pointing: Pointing,
+}
+
+impl Antenna {
+    // A patch antenna looking down at the earth
+    pub fn patch() -> Self {
+        Self {
+            peak_gain: 6.0,
+            beamwidth: 70f64.to_radians(),
+            pointing: Pointing::Nadir,
+        }
+    }
+
+    // dBi at `angle` rad off boresight, a parabolic main lobe down to a flat sidelobe level
+    pub fn gain(&self, angle: Precision) -> Precision {
+        let rolloff = 12.0 * (angle / self.beamwidth).powi(2);
+        self.peak_gain - rolloff.min(SIDELOBE_FLOOR)
+    }
+
+    // Gain towards `target` from an antenna at `from`
+    fn gain_towards(
+        &self,
+        from: (Precision, Precision),
+        target: (Precision, Precision),
+    ) -> Precision {
+        let (dx, dy) = (target.0 - from.0, target.1 - from.1);
+        let r = from.0.hypot(from.1);
+        let (bx, by) = match self.pointing {
+            Pointing::Tracking => return self.peak_gain,
+            Pointing::Nadir => (-from.0 / r, -from.1 / r),
+            Pointing::Zenith => (from.0 / r, from.1 / r),
+        };
+        let cos = ((dx * bx + dy * by) / dx.hypot(dy)).clamp(-1.0, 1.0);
+        self.gain(cos.acos())
+    }
+}
+
+// What the downlink sends and how the stations receive it
+#[derive(Resource)]
+pub struct LinkBudget {
+    pub frequency: Precision,         // Hz, also used for the Doppler readouts
+    pub power: Precision,             // W transmitted
+    pub data_rate: Precision,         // bit/s
+    pub noise_temperature: Precision, // K, of the receiving system
+    pub losses: Precision,            // dB, cables, pointing and atmosphere
+    pub required_eb_n0: Precision,    // dB, BPSK at a bit error rate of 1e-5
+}
+
+impl Default for LinkBudget {
+    fn default() -> Self {
+        Self {
+            frequency: 437.8e6, // 70 cm amateur satellite band
+            power: 1.0,
+            data_rate: 1e5,
+            noise_temperature: 500.0,
+            losses: 3.0,
+            required_eb_n0: 9.6,
+        }
+    }
+}
+
+impl LinkBudget {
+    // dB of margin on the downlink from a body in `state` to a station
+    pub fn margin(
+        &self,
+        transmitter: Option<&Antenna>,
+        state: &State,
+        receiver: &Antenna,
+        station: &GroundStation,
+    ) -> Precision {
+        let (body, ground) = ((state.x, state.y), station.position());
+        let range = (body.0 - ground.0).hypot(body.1 - ground.1);
+
+        let transmit_gain = transmitter.map_or(0.0, |antenna| antenna.gain_towards(body, ground));
+        let receive_gain = receiver.gain_towards(ground, body);
+        let path_loss = 20.0 * (4.0 * PI * range * self.frequency / SPEED_OF_LIGHT).log10();
+
+        let c_n0 = 10.0 * self.power.log10() + transmit_gain - path_loss - self.losses
+            + receive_gain
+            - BOLTZMANN
+            - 10.0 * self.noise_temperature.log10();
+        c_n0 - 10.0 * self.data_rate.log10() - self.required_eb_n0
+    }
+}
+
+// From the warning colour for a link that doesn't close to the station colour with 6 dB to spare
+pub fn margin_color(margin: Precision, bad: Color, good: Color) -> Color {
+    let t = (margin / 6.0).clamp(0.0, 1.0) as f32;
+    let ([r0, g0, b0, a0], [r1, g1, b1, a1]) = (bad.as_rgba_f32(), good.as_rgba_f32());
+    Color::rgba(
+        r0 + t * (r1 - r0),
+        g0 + t * (g1 - g0),
+        b0 + t * (b1 - b0),
+        a0 + t * (a1 - a0),
+    )
+}
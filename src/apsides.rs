@@ -0,0 +1,136 @@
+// Apoapsis and periapsis markers on the selected body's lookahead: where its predicted
+// distance from the earth's centre peaks and bottoms out, labelled with the altitude there, so
+// burns can be timed against them. The extrema are refined between the lookahead's samples
+// with a parabola through the extreme sample and its neighbours.
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::camera::PixelSize;
+use crate::crash::Crashed;
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::prediction::PredictedTrajectory;
+use crate::sky::ViewMode;
+use crate::{Precision, Selected, State, EARTH_RADIUS};
+
+const MARKER_RADIUS: f32 = 6.0; // pixels
+const LABEL_OFFSET: f32 = 10.0; // pixels right of the marker
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Apsis {
+    Apoapsis,
+    Periapsis,
+}
+
+pub struct Extremum {
+    pub apsis: Apsis,
+    pub position: DVec2,
+    pub radius: Precision,
+    pub step: usize, // of the nearest sample
+}
+
+// The first apoapsis and periapsis along states sampled at even steps
+pub fn find_apsides(states: &[State]) -> Vec<Extremum> {
+    let radii: Vec<Precision> = states.iter().map(|state| state.x.hypot(state.y)).collect();
+    let mut found: Vec<Extremum> = Vec::new();
+
+    for i in 1..radii.len().saturating_sub(1) {
+        let (before, here, after) = (radii[i - 1], radii[i], radii[i + 1]);
+        let apsis = if here > before && here >= after {
+            Apsis::Apoapsis
+        } else if here < before && here <= after {
+            Apsis::Periapsis
+        } else {
+            continue;
+        };
+        if found.iter().any(|extremum| extremum.apsis == apsis) {
+            continue;
+        }
+
+        // Vertex of the parabola through the three samples, in steps from the middle one
+        let curvature = before - 2.0 * here + after;
+        let offset = if curvature == 0.0 {
+            0.0
+        } else {
+            (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+        };
+        let position = |state: &State| DVec2::new(state.x, state.y);
+        let neighbour = if offset < 0.0 { i - 1 } else { i + 1 };
+        let middle = position(&states[i]);
+        found.push(Extremum {
+            apsis,
+            position: middle + (position(&states[neighbour]) - middle) * offset.abs(),
+            radius: here - 0.25 * (before - after) * offset,
+            step: i,
+        });
+        if found.len() == 2 {
+            break;
+        }
+    }
+
+    found
+}
+
+#[derive(Component)]
+pub struct ApsisLabel(Apsis);
+
+pub fn spawn_labels(mut commands: Commands) {
+    for apsis in [Apsis::Apoapsis, Apsis::Periapsis] {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                ),
+                text_anchor: Anchor::CenterLeft,
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ApsisLabel(apsis),
+        ));
+    }
+}
+
+pub fn draw_apsides(
+    mut gizmos: Gizmos,
+    (pixel, palette): (Res<PixelSize>, Res<Palette>),
+    interpolation: Interpolation,
+    language: Res<Language>,
+    view: Res<ViewMode>,
+    selected: Query<&PredictedTrajectory, (With<Selected>, Without<Crashed>)>,
+    mut labels: Query<(&ApsisLabel, &mut Text, &mut Transform, &mut Visibility)>,
+) {
+    let mut apsides = Vec::new();
+    if let (ViewMode::Orbit, Ok(prediction)) = (*view, selected.get_single()) {
+        apsides = find_apsides(prediction.states());
+    }
+
+    let scale = pixel.0 as f32;
+    let color = palette.colors().prediction;
+    let view = interpolation.view();
+    for (label, mut text, mut transform, mut visibility) in labels.iter_mut() {
+        let Some(extremum) = apsides.iter().find(|extremum| extremum.apsis == label.0) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let (x, y) = (extremum.position.x, extremum.position.y);
+        let position = view.ahead(&State::new(x, y, 0.0, 0.0), extremum.step);
+        gizmos.circle_2d(position, MARKER_RADIUS * scale, color);
+
+        let key = match label.0 {
+            Apsis::Apoapsis => "apoapsis_marker",
+            Apsis::Periapsis => "periapsis_marker",
+        };
+        let altitude = format!("{:.0}", (extremum.radius - EARTH_RADIUS) / 1000.0);
+        text.sections[0].value = language.text(key, &[&altitude]);
+        transform.translation = (position + Vec2::X * LABEL_OFFSET * scale).extend(1.0);
+        transform.scale = Vec3::splat(scale);
+        *visibility = Visibility::Visible;
+    }
+}
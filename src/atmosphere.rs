@@ -0,0 +1,90 @@
+// The atmosphere bodies with a ballistic coefficient feel drag from, and the band that shows it
+// around the earth. The band's opacity follows the logarithm of the density, so it fades out
+// gradually over the altitudes where drag matters instead of vanishing a few scale heights
+// above the ground. Y adds drag to the lookahead, which otherwise coasts in a vacuum.
+use bevy::prelude::*;
+
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::{Atmosphere, Precision, ATMOSPHERE_TOP, EARTH_RADIUS};
+
+const RINGS: usize = 100;
+const SEGMENTS: usize = 256;
+const MAX_ALPHA: f32 = 0.6;
+
+// None is a vacuum
+#[derive(Resource)]
+pub struct DragSettings {
+    pub atmosphere: Option<Atmosphere>,
+    pub in_lookahead: bool,
+}
+
+impl Default for DragSettings {
+    fn default() -> Self {
+        Self {
+            atmosphere: Some(Atmosphere::Tabulated),
+            in_lookahead: false,
+        }
+    }
+}
+
+pub fn toggle_lookahead_drag(keyboard: Res<Input<KeyCode>>, mut settings: ResMut<DragSettings>) {
+    if keyboard.just_pressed(KeyCode::Y) {
+        settings.in_lookahead = !settings.in_lookahead;
+        info!(
+            "Lookahead {}",
+            if settings.in_lookahead {
+                "with drag"
+            } else {
+                "in a vacuum"
+            }
+        );
+    }
+}
+
+// A shows or hides the band
+#[derive(Resource)]
+pub struct AtmosphereBand {
+    pub shown: bool,
+}
+
+impl Default for AtmosphereBand {
+    fn default() -> Self {
+        Self { shown: true }
+    }
+}
+
+pub fn toggle_atmosphere(keyboard: Res<Input<KeyCode>>, mut band: ResMut<AtmosphereBand>) {
+    if keyboard.just_pressed(KeyCode::A) {
+        band.shown = !band.shown;
+    }
+}
+
+pub fn draw_atmosphere(
+    mut gizmos: Gizmos,
+    band: Res<AtmosphereBand>,
+    settings: Res<DragSettings>,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+) {
+    if !band.shown {
+        return;
+    }
+
+    let atmosphere = settings.atmosphere.unwrap_or_default();
+    let density = |altitude| atmosphere.density(altitude);
+    let (bottom, top) = (density(0.0).ln(), density(ATMOSPHERE_TOP).ln());
+    let color = palette.colors().earth;
+    let centre = interpolation.point(0.0, 0.0);
+    for i in 0..RINGS {
+        let altitude = ATMOSPHERE_TOP * (i as Precision + 0.5) / RINGS as Precision;
+        let opacity = (density(altitude).ln() - top) / (bottom - top);
+        gizmos
+            .circle_2d(
+                centre,
+                (EARTH_RADIUS + altitude) as f32,
+                color.with_a(MAX_ALPHA * opacity as f32),
+            )
+            .segments(SEGMENTS);
+    }
+}
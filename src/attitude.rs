@@ -0,0 +1,77 @@
+// Thrust directions in the body's local frame: along or against the velocity, or away from or
+// towards the earth. The arrow keys pick one while held, and each step re-points the engine
+// since the frame turns with the orbit. The simulation is planar, so there is no normal
+// direction to burn along. Firing engines are drawn with an arrow along what they deliver.
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::autopilot::ThrustCommand;
+use crate::camera::PixelSize;
+use crate::engine::Engine;
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::{Body, State};
+
+const ARROW_LENGTH: f32 = 40.0; // pixels
+const ARROW_HEAD: f32 = 0.3; // of the arrow's length
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThrustDirection {
+    Prograde,
+    Retrograde,
+    RadialOut,
+    RadialIn,
+}
+
+impl ThrustDirection {
+    // Up and Down burn prograde and retrograde, Right and Left radially out and in
+    pub fn from_keys(keyboard: &Input<KeyCode>) -> Option<Self> {
+        [
+            (KeyCode::Up, ThrustDirection::Prograde),
+            (KeyCode::Down, ThrustDirection::Retrograde),
+            (KeyCode::Right, ThrustDirection::RadialOut),
+            (KeyCode::Left, ThrustDirection::RadialIn),
+        ]
+        .into_iter()
+        .find(|(key, _)| keyboard.pressed(*key))
+        .map(|(_, direction)| direction)
+    }
+
+    // Full throttle this way for a body in `state`
+    pub fn command(self, state: &State) -> ThrustCommand {
+        let velocity = DVec2::new(state.vx, state.vy);
+        let radial = DVec2::new(state.x, state.y);
+        let direction = match self {
+            ThrustDirection::Prograde => velocity,
+            ThrustDirection::Retrograde => -velocity,
+            ThrustDirection::RadialOut => radial,
+            ThrustDirection::RadialIn => -radial,
+        };
+        ThrustCommand {
+            throttle: 1.0,
+            angle: velocity.perp_dot(direction).atan2(velocity.dot(direction)),
+        }
+    }
+}
+
+pub fn draw_thrust(
+    mut gizmos: Gizmos,
+    pixel: Res<PixelSize>,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+    query: Query<(&Body, &Engine)>,
+) {
+    let color = palette.colors().text;
+
+    for (body, engine) in query.iter().filter(|(_, engine)| engine.firing) {
+        let (ax, ay) = engine.delivered.components(&body.current_state);
+        let direction = Vec2::new(ax as f32, ay as f32).normalize_or_zero();
+        let start = interpolation.position(body);
+        let end = start + direction * ARROW_LENGTH * pixel.0 as f32;
+        let head = (start - end) * ARROW_HEAD;
+
+        gizmos.line_2d(start, end, color);
+        gizmos.line_2d(end, end + Vec2::from_angle(0.5).rotate(head), color);
+        gizmos.line_2d(end, end + Vec2::from_angle(-0.5).rotate(head), color);
+    }
+}
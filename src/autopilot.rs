@@ -0,0 +1,158 @@
+// Autopilots: guidance laws that set a body's throttle and thrust direction every step in
+// place of the arrow keys. A guidance law implements `Autopilot` and is registered by name
+// from a plugin with `app.register_autopilot(name, factory)`; scripts then engage it on a body,
+// optionally with another body as its target. The engine's faults still apply to whatever
+// the autopilot commands.
+use std::collections::BTreeMap;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::controller::{ExternalController, ExternalPilot};
+use crate::elements::MU;
+use crate::{Precision, State};
+
+const DEADBAND: Precision = 0.01; // m/s of Δv left that isn't worth a burn
+
+// Throttle from -1 (full reverse) to 1 (full), and the thrust direction in radians
+// counterclockwise from the velocity
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ThrustCommand {
+    pub throttle: Precision,
+    pub angle: Precision,
+}
+
+impl ThrustCommand {
+    // As close to an inertial Δv as one step of `dt` seconds at `thrust` can get
+    pub fn towards(own_state: &State, delta_v: DVec2, dt: Precision, thrust: Precision) -> Self {
+        let velocity = DVec2::new(own_state.vx, own_state.vy);
+        if delta_v.length() < DEADBAND {
+            return Self::default();
+        }
+        Self {
+            throttle: (delta_v.length() / (thrust * dt)).min(1.0),
+            angle: velocity.perp_dot(delta_v).atan2(velocity.dot(delta_v)),
+        }
+    }
+}
+
+// Decides on the command for one step of `dt` seconds, the throttle a fraction of `thrust`
+pub trait Autopilot: Send + Sync {
+    fn control(
+        &self,
+        own_state: &State,
+        target_state: Option<&State>,
+        dt: Precision,
+        thrust: Precision,
+    ) -> ThrustCommand;
+}
+
+// An autopilot engaged on a body
+#[derive(Component)]
+pub struct Pilot {
+    pub name: String,
+    pub autopilot: Box<dyn Autopilot>,
+    pub target: Option<Entity>,
+}
+
+type Factory = Box<dyn Fn() -> Box<dyn Autopilot> + Send + Sync>;
+
+// The autopilots scripts can engage, by name
+#[derive(Resource, Default)]
+pub struct Autopilots(BTreeMap<String, Factory>);
+
+impl Autopilots {
+    pub fn create(&self, name: &str) -> Result<Box<dyn Autopilot>, String> {
+        let factory = self.0.get(name).ok_or_else(|| {
+            format!(
+                "no autopilot named \"{}\", there are {}",
+                name,
+                self.names().join(", ")
+            )
+        })?;
+        Ok(factory())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+}
+
+pub trait RegisterAutopilot {
+    fn register_autopilot(
+        &mut self,
+        name: &str,
+        factory: impl Fn() -> Box<dyn Autopilot> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl RegisterAutopilot for App {
+    fn register_autopilot(
+        &mut self,
+        name: &str,
+        factory: impl Fn() -> Box<dyn Autopilot> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.init_resource::<Autopilots>();
+        let mut autopilots = self.world.resource_mut::<Autopilots>();
+        autopilots.0.insert(name.to_string(), Box::new(factory));
+        self
+    }
+}
+
+// Burns towards the circular orbit at the current radius, in the current sense of motion
+struct Circularize;
+
+impl Autopilot for Circularize {
+    fn control(
+        &self,
+        own_state: &State,
+        _: Option<&State>,
+        dt: Precision,
+        thrust: Precision,
+    ) -> ThrustCommand {
+        let position = DVec2::new(own_state.x, own_state.y);
+        let velocity = DVec2::new(own_state.vx, own_state.vy);
+        let sense = position.perp_dot(velocity).signum();
+        let circular = (MU / position.length()).sqrt() * sense * position.normalize().perp();
+        ThrustCommand::towards(own_state, circular - velocity, dt, thrust)
+    }
+}
+
+// Burns until the velocity matches the target's, or coasts without a target
+struct MatchVelocity;
+
+impl Autopilot for MatchVelocity {
+    fn control(
+        &self,
+        own_state: &State,
+        target: Option<&State>,
+        dt: Precision,
+        thrust: Precision,
+    ) -> ThrustCommand {
+        let Some(target) = target else {
+            return ThrustCommand::default();
+        };
+        let delta_v = DVec2::new(target.vx - own_state.vx, target.vy - own_state.vy);
+        ThrustCommand::towards(own_state, delta_v, dt, thrust)
+    }
+}
+
+pub struct AutopilotPlugin;
+
+impl Plugin for AutopilotPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_autopilot("circularize", || Box::new(Circularize))
+            .register_autopilot("match_velocity", || Box::new(MatchVelocity));
+
+        // --controller adds one flown from another process
+        let listener = app
+            .world
+            .get_resource::<ExternalController>()
+            .and_then(|controller| controller.0.clone());
+        if let Some(listener) = listener {
+            app.register_autopilot("external", move || {
+                Box::new(ExternalPilot::new(listener.clone()))
+            });
+        }
+    }
+}
@@ -0,0 +1,406 @@
+// Collision avoidance: every SCREEN_INTERVAL of sim time each craft, a body with an engine, is
+// flown on for LOOKAHEAD alongside the bodies whose radii it can reach, all of them coasting
+// like the lookaheads apart from their planned burns. The Moon and other massive bodies pull on
+// the coasts but are not screened. The closest approach with a collision risk
+// above the threshold is flagged, and the smallest single burn on a step before it that takes
+// the miss out to the target distance is proposed. The burn comes from the craft's state
+// transition matrix: the step whose velocity change moves the craft the furthest across the
+// approach wins. The risk is the chance that the miss, uncertain by POSITION_SIGMA across the
+// approach, comes within HARD_BODY_RADIUS. `0`, the "Accept burn" button or
+// `accept_proposal()` adds the burn to the plan.
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::elements::OrbitalElements;
+use crate::engine::Engine;
+use crate::gravity::GravitySource;
+use crate::hud::format_duration;
+use crate::i18n::Language;
+use crate::maneuver::{Frame, ManeuverNode, ManeuverPlan};
+use crate::optimizer::Proposal;
+use crate::propagator::{Coast, Coasting};
+use crate::{stm, Body, Precision, SimTime, State};
+
+const SCREEN_INTERVAL: Precision = 600.0; // s
+const LOOKAHEAD: Precision = 7200.0; // s
+const SCREEN_PAD: Precision = 20e3; // m around each radius range
+const HARD_BODY_RADIUS: Precision = 10.0; // m, both bodies together
+const POSITION_SIGMA: Precision = 200.0; // m
+const EPSILON: Precision = 1e-6; // s, rounding in the clock
+pub const DEFAULT_THRESHOLD: Precision = 1e-4;
+pub const DEFAULT_TARGET: Precision = 2000.0; // m
+
+#[derive(Clone, Copy)]
+pub struct Warning {
+    entity: Entity,
+    pub body: usize,
+    pub other: usize,
+    pub time: Precision, // of the closest approach
+    pub miss: Precision, // m
+    pub risk: Precision,
+    pub burn: Option<(ManeuverNode, Precision)>, // and the miss after it; none if too late
+}
+
+#[derive(Resource)]
+pub struct Avoidance {
+    pub threshold: Precision,
+    pub target: Precision, // m
+    pub warning: Option<Warning>,
+    screened: Option<Precision>, // when
+}
+
+impl Default for Avoidance {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            target: DEFAULT_TARGET,
+            warning: None,
+            screened: None,
+        }
+    }
+}
+
+impl Avoidance {
+    // Screen again on the next frame, after a change of settings
+    pub fn rescreen(&mut self) {
+        self.screened = None;
+    }
+
+    pub fn describe(&self, now: Precision, language: Language) -> String {
+        let Some(warning) = self.warning else {
+            return String::new();
+        };
+        let mut lines = vec![language.text(
+            "avoidance_warning",
+            &[
+                &warning.body,
+                &warning.other,
+                &format_duration(warning.time - now),
+                &format!("{:.0}", warning.miss),
+                &format!("{:.1e}", warning.risk),
+            ],
+        )];
+        lines.push(match warning.burn {
+            Some((node, miss)) => language.text(
+                "avoidance_burn",
+                &[
+                    &format_duration(node.time - now),
+                    &format!("{:.3}", node.dv[0]),
+                    &format!("{:.3}", node.dv[1]),
+                    &format!("{:.0}", miss),
+                ],
+            ),
+            None => language.text("avoidance_late", &[]),
+        });
+        lines.join("\n")
+    }
+}
+
+// Chance that a miss of `miss` metres is a hit
+pub fn risk(miss: Precision) -> Precision {
+    let x = miss / POSITION_SIGMA;
+    let peak = 2.0 * HARD_BODY_RADIUS / (POSITION_SIGMA * TAU.sqrt());
+    (peak * (-0.5 * x * x).exp()).min(1.0)
+}
+
+// The state at every step of the coast, apart from the planned burns
+fn fly(mut coast: Coast, plan: &[ManeuverNode], steps: usize) -> Vec<State> {
+    let mut states = vec![coast.state];
+    let mut nodes = plan.iter().peekable();
+    for _ in 0..steps {
+        while let Some(node) = nodes.next_if(|node| node.time <= coast.time + EPSILON) {
+            let (dvx, dvy) = node.inertial_dv(&coast.state);
+            coast.state.vx += dvx;
+            coast.state.vy += dvy;
+        }
+        states.push(coast.step());
+    }
+    states
+}
+
+fn position(state: &State) -> DVec2 {
+    DVec2::new(state.x, state.y)
+}
+
+fn velocity(state: &State) -> DVec2 {
+    DVec2::new(state.vx, state.vy)
+}
+
+// Lowest and highest radius a body on this orbit reaches
+fn radii(state: &State) -> (Precision, Precision) {
+    let elements = OrbitalElements::from_state(state);
+    let periapsis = elements.a * (1.0 - elements.e);
+    if elements.e < 1.0 {
+        (periapsis, elements.a * (1.0 + elements.e))
+    } else {
+        (periapsis, Precision::INFINITY)
+    }
+}
+
+struct Approach {
+    step: usize, // the step closest to it
+    time: Precision,
+    miss: DVec2, // the craft's position less the other's
+    relative_velocity: DVec2,
+}
+
+// The close approaches of two trajectories in order, each step taken as a straight line
+// between states. Ones under way at either end of the trajectories count too.
+fn approaches(craft: &[State], other: &[State], now: Precision, dt: Precision) -> Vec<Approach> {
+    let relative = |i: usize| position(&craft[i]) - position(&other[i]);
+    // The closest point of each step
+    let closest: Vec<(Precision, DVec2)> = (0..craft.len() - 1)
+        .map(|i| {
+            let (a, b) = (relative(i), relative(i + 1));
+            let d = b - a;
+            let s = if d.length_squared() > 0.0 {
+                (-a.dot(d) / d.length_squared()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (s, a + d * s)
+        })
+        .collect();
+
+    let distance = |i: usize| closest[i].1.length();
+    (0..closest.len())
+        .filter(|&i| {
+            (i == 0 || distance(i) <= distance(i - 1))
+                && (i + 1 == closest.len() || distance(i) < distance(i + 1))
+        })
+        .map(|i| {
+            let (s, miss) = closest[i];
+            let step = if s < 0.5 { i } else { i + 1 };
+            Approach {
+                step,
+                time: now + (i as Precision + s) * dt,
+                miss,
+                relative_velocity: velocity(&craft[step]) - velocity(&other[step]),
+            }
+        })
+        .collect()
+}
+
+// The smallest burn on a step between now and the closest approach that moves the craft
+// across it from `approach.miss` out to `target`, to first order; none if it's too close
+fn avoidance_burn(coast: Coast, approach: &Approach, target: Precision) -> Option<ManeuverNode> {
+    if approach.step < 2 {
+        return None;
+    }
+    let normal = if approach.miss.length() > 1.0 {
+        approach.miss.normalize()
+    } else {
+        approach.relative_velocity.perp().try_normalize()?
+    };
+
+    // Φ(approach, k) = Φ(approach, 0) Φ(k, 0)⁻¹, and its velocity columns say how far a burn
+    // at step k moves the craft at the approach
+    let transitions = stm::transitions(coast, approach.step);
+    let to_approach = transitions[approach.step];
+    let (step, across) = (1..approach.step)
+        .map(|k| {
+            let phi = to_approach * transitions[k].inverse();
+            let moved = |i| {
+                let column = phi.col(i);
+                DVec2::new(column.x, column.y).dot(normal)
+            };
+            (k, DVec2::new(moved(2), moved(3)))
+        })
+        .max_by(|a, b| a.1.length_squared().total_cmp(&b.1.length_squared()))?;
+    let dv = across * ((target - approach.miss.dot(normal)) / across.length_squared());
+
+    // In the orbital frame of the state the burn meets, coasting
+    let time = coast.time + step as Precision * coast.dt();
+    let burn_state = coast.fly(step)[step];
+    let axis = |dv| {
+        let node = ManeuverNode {
+            time,
+            dv,
+            frame: Frame::Orbital,
+        };
+        let (x, y) = node.inertial_dv(&burn_state);
+        DVec2::new(x, y)
+    };
+    Some(ManeuverNode {
+        time,
+        dv: [dv.dot(axis([1.0, 0.0])), dv.dot(axis([0.0, 1.0]))],
+        frame: Frame::Orbital,
+    })
+}
+
+fn plan(plan: Option<&ManeuverPlan>) -> &[ManeuverNode] {
+    plan.map_or(&[], |plan| plan.0.as_slice())
+}
+
+type Screened<'a> = (Entity, &'a Body, Option<&'a ManeuverPlan>, Has<Engine>);
+
+pub fn screen_conjunctions(
+    time: Res<SimTime>,
+    mut avoidance: ResMut<Avoidance>,
+    mut proposal: ResMut<Proposal>,
+    coasting: Coasting,
+    bodies: Query<Screened, (Without<Crashed>, Without<GravitySource>)>,
+    added: Query<(), Added<Body>>,
+) {
+    let now = time.0;
+    let due = !added.is_empty()
+        || avoidance
+            .screened
+            .is_none_or(|last| now < last || now >= last + SCREEN_INTERVAL);
+    if !due {
+        return;
+    }
+    avoidance.screened = Some(now);
+
+    let propagator = coasting.propagator();
+    let dt = propagator.dt;
+    let steps = (LOOKAHEAD / dt).ceil() as usize;
+    let mut paths: HashMap<Entity, Vec<State>> = HashMap::new();
+    // The first approach of any craft that is risky enough
+    let mut first: Option<(Precision, Entity, Entity, Approach)> = None;
+
+    for (craft, body, craft_plan, _) in bodies.iter().filter(|(.., engine)| *engine) {
+        let path = fly(propagator.coast(body, now), plan(craft_plan), steps);
+        let radius = |state: &State| position(state).length();
+        let low = path
+            .iter()
+            .map(radius)
+            .fold(Precision::INFINITY, Precision::min);
+        let high = path.iter().map(radius).fold(0.0, Precision::max);
+
+        for (other, other_body, other_plan, _) in bodies.iter() {
+            let planned = !plan(other_plan).is_empty();
+            let (periapsis, apoapsis) = radii(&other_body.current_state);
+            let reachable = periapsis - SCREEN_PAD < high && apoapsis + SCREEN_PAD > low;
+            if other == craft || !(planned || reachable) {
+                continue;
+            }
+            let other_path = paths
+                .entry(other)
+                .or_insert_with(|| fly(propagator.coast(other_body, now), plan(other_plan), steps));
+            let risky = approaches(&path, other_path, now, dt)
+                .into_iter()
+                .map(|approach| (risk(approach.miss.length()), approach))
+                .find(|(risk, _)| *risk >= avoidance.threshold);
+            if let Some((risk, approach)) = risky {
+                if first
+                    .as_ref()
+                    .is_none_or(|(.., earliest)| approach.time < earliest.time)
+                {
+                    first = Some((risk, craft, other, approach));
+                }
+            }
+        }
+    }
+
+    let previous = avoidance.warning.take();
+    let warning = first.map(|(risk, entity, other, approach)| {
+        let (_, body, craft_plan, _) = bodies.get(entity).unwrap();
+        let coast = propagator.coast(body, now);
+        let burn = avoidance_burn(coast, &approach, avoidance.target)
+            .filter(|_| craft_plan.is_some())
+            .map(|node| {
+                // What the miss comes to with the burn, flown out in full
+                let mut nodes = plan(craft_plan).to_vec();
+                nodes.push(node);
+                nodes.sort_by(|a, b| a.time.total_cmp(&b.time));
+                let path = fly(coast, &nodes, steps);
+                let after = approaches(&path, &paths[&other], now, dt)
+                    .into_iter()
+                    .min_by(|a, b| {
+                        let off = |candidate: &Approach| (candidate.time - approach.time).abs();
+                        off(a).total_cmp(&off(b))
+                    })
+                    .map_or(Precision::INFINITY, |approach| approach.miss.length());
+                (node, after)
+            });
+        Warning {
+            entity,
+            body: body.id,
+            other: bodies.get(other).unwrap().1.id,
+            time: approach.time,
+            miss: approach.miss.length(),
+            risk,
+            burn,
+        }
+    });
+
+    // A proposal for a conjunction that has been dealt with or has passed goes
+    let proposed = |warning: &Warning| {
+        proposal.0.is_some_and(|(entity, node)| {
+            warning
+                .burn
+                .is_some_and(|(burn, _)| entity == warning.entity && node.time == burn.time)
+        })
+    };
+    if previous.as_ref().is_some_and(proposed) {
+        proposal.0 = None;
+    }
+
+    if let Some(warning) = &warning {
+        let new = previous.is_none_or(|previous| {
+            (previous.body, previous.other) != (warning.body, warning.other)
+        });
+        if new {
+            info!(
+                "Conjunction of body {} with body {} at t = {:.0} s: miss {:.0} m, risk {:.1e}",
+                warning.body, warning.other, warning.time, warning.miss, warning.risk
+            );
+        }
+        if let Some((node, _)) = warning.burn {
+            proposal.0 = Some((warning.entity, node));
+        }
+    }
+    avoidance.warning = warning;
+}
+
+#[derive(Component)]
+pub struct AvoidanceText;
+
+#[derive(Component)]
+pub struct AcceptButton;
+
+// 0 or the button adds the proposed burn, an avoidance burn or any other, to its body's plan
+pub fn accept_proposal(
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<AcceptButton>)>,
+    mut proposal: ResMut<Proposal>,
+    mut plans: Query<(&Body, &mut ManeuverPlan)>,
+) {
+    let clicked = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if !clicked && !keyboard.just_pressed(KeyCode::Key0) {
+        return;
+    }
+    let Some((entity, node)) = proposal.0.take() else {
+        return;
+    };
+    match plans.get_mut(entity) {
+        Ok((body, mut plan)) => {
+            plan.add(node);
+            info!("Planned a burn of body {} at {}", body.id, node);
+        }
+        Err(_) => warn!("The body of the proposal is gone"),
+    }
+}
+
+pub fn show_avoidance(
+    time: Res<SimTime>,
+    avoidance: Res<Avoidance>,
+    proposal: Res<Proposal>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<AvoidanceText>>,
+    mut button: Query<&mut Style, With<AcceptButton>>,
+) {
+    text.single_mut().sections[0].value = avoidance.describe(time.0, *language);
+    button.single_mut().display = if proposal.0.is_some() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+}
@@ -0,0 +1,285 @@
+// Batch runs: `--batch <file>` coasts a list of scenarios without opening a window, carrying
+// out their planned burns, and writes one report with the final orbit, the Δv used and the
+// events of each. A scenario can sweep some of its parameters, which runs every combination
+// of their values. With `--jobs <n>` the runs are spread over n processes of the app itself.
+// The scenarios coast like the lookaheads, with the `--integrator` given and the earth's bulge
+// with `--j2`.
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::process::{Child, Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::elements::OrbitalElements;
+use crate::gravity::Perturbations;
+use crate::maneuver::{ManeuverNode, ManeuverPlan};
+use crate::propagator::{MassivePaths, Propagator};
+use crate::{Integrator, Precision, State, EARTH_RADIUS};
+
+const DEFAULT_REPORT: &str = "batch_report.json";
+
+#[derive(Deserialize)]
+struct BatchFile {
+    report: Option<String>,
+    scenarios: Vec<ScenarioSpec>,
+}
+
+#[derive(Deserialize)]
+struct ScenarioSpec {
+    name: Option<String>,
+    x: Precision,
+    y: Precision,
+    vx: Precision,
+    vy: Precision,
+    duration: Precision,  // s
+    plan: Option<String>, // a plan exported with export_plan
+    #[serde(default)]
+    sweep: BTreeMap<String, Vec<Precision>>,
+}
+
+struct Case {
+    name: String,
+    state: State,
+    duration: Precision,
+    plan: Vec<ManeuverNode>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Summary {
+    name: String,
+    end_time: Precision, // s, earlier than the duration after a crash
+    state: [Precision; 4],
+    semi_major_axis: Precision,
+    eccentricity: Precision,
+    periapsis_altitude: Precision,
+    apoapsis_altitude: Option<Precision>, // none on escape orbits
+    delta_v: Precision,
+    events: Vec<String>,
+}
+
+fn parameter<'a>(case: &'a mut Case, name: &str) -> Result<&'a mut Precision, String> {
+    Ok(match name {
+        "x" => &mut case.state.x,
+        "y" => &mut case.state.y,
+        "vx" => &mut case.state.vx,
+        "vy" => &mut case.state.vy,
+        "duration" => &mut case.duration,
+        _ => {
+            return Err(format!(
+                "can't sweep `{}`, only x, y, vx, vy and duration",
+                name
+            ))
+        }
+    })
+}
+
+// One case per combination of the swept values
+fn expand(spec: &ScenarioSpec, index: usize) -> Result<Vec<Case>, String> {
+    let plan = match &spec.plan {
+        Some(path) => ManeuverPlan::load(path)?,
+        None => Vec::new(),
+    };
+    let name = spec
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("scenario {}", index + 1));
+
+    let mut cases = vec![Case {
+        name,
+        state: State::new(spec.x, spec.y, spec.vx, spec.vy),
+        duration: spec.duration,
+        plan,
+    }];
+    for (swept, values) in &spec.sweep {
+        let mut expanded = Vec::with_capacity(cases.len() * values.len());
+        for case in &cases {
+            for value in values {
+                let mut case = Case {
+                    name: format!("{} {}={}", case.name, swept, value),
+                    plan: case.plan.clone(),
+                    ..*case
+                };
+                *parameter(&mut case, swept)? = *value;
+                expanded.push(case);
+            }
+        }
+        cases = expanded;
+    }
+
+    Ok(cases)
+}
+
+fn load(path: &str) -> Result<(BatchFile, Vec<Case>), String> {
+    let source = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    let batch: BatchFile =
+        serde_json::from_str(&source).map_err(|error| format!("{}: {}", path, error))?;
+
+    let mut cases = Vec::new();
+    for (i, spec) in batch.scenarios.iter().enumerate() {
+        cases.extend(expand(spec, i)?);
+    }
+    Ok((batch, cases))
+}
+
+fn run_case(case: &Case, propagator: &Propagator) -> Summary {
+    let mut coast = propagator.coast_from(case.state, 0.0);
+    let mut plan = case.plan.iter().peekable();
+    let mut delta_v = 0.0;
+    let mut events = Vec::new();
+
+    while coast.time < case.duration {
+        let time = coast.time;
+        while let Some(node) = plan.next_if(|node| node.time <= time) {
+            let (dvx, dvy) = node.inertial_dv(&coast.state);
+            coast.state.vx += dvx;
+            coast.state.vy += dvy;
+            delta_v += dvx.hypot(dvy);
+            events.push(format!("t = {} s: burn of {:.3} m/s", time, dvx.hypot(dvy)));
+        }
+
+        let state = coast.step();
+        let time = coast.time;
+        if state.x.hypot(state.y) < EARTH_RADIUS {
+            let speed = state.vx.hypot(state.vy);
+            events.push(format!("t = {} s: crash at {:.0} m/s", time, speed));
+            break;
+        }
+    }
+
+    let (state, time) = (coast.state, coast.time);
+    let elements = OrbitalElements::from_state(&state);
+    Summary {
+        name: case.name.clone(),
+        end_time: time,
+        state: [state.x, state.y, state.vx, state.vy],
+        semi_major_axis: elements.a,
+        eccentricity: elements.e,
+        periapsis_altitude: elements.a * (1.0 - elements.e) - EARTH_RADIUS,
+        apoapsis_altitude: (elements.e < 1.0)
+            .then_some(elements.a * (1.0 + elements.e) - EARTH_RADIUS),
+        delta_v,
+        events,
+    }
+}
+
+fn spawn_worker(path: &str, index: usize, settings: &Settings) -> Result<Child, String> {
+    let exe = std::env::current_exe().map_err(|error| error.to_string())?;
+    Command::new(exe)
+        .args(["--batch", path, "--batch-case", &index.to_string()])
+        .args(["--dt", &settings.dt.to_string()])
+        .args(["--integrator", settings.integrator.name()])
+        .args(settings.perturbations.j2.then_some("--j2"))
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("could not start a worker: {}", error))
+}
+
+fn collect(child: Child, name: &str) -> Result<Summary, String> {
+    let output = child
+        .wait_with_output()
+        .map_err(|error| error.to_string())?;
+    if !output.status.success() {
+        return Err(format!("the run of {} failed", name));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|error| format!("{}: {}", name, error))
+}
+
+// In parallel, a wave of up to `jobs` worker processes at a time
+fn run_all(
+    path: &str,
+    cases: &[Case],
+    jobs: usize,
+    settings: &Settings,
+) -> Result<Vec<Summary>, String> {
+    if jobs <= 1 {
+        let paths = MassivePaths::default();
+        let propagator = settings.propagator(&paths);
+        return Ok(cases
+            .iter()
+            .map(|case| run_case(case, &propagator))
+            .collect());
+    }
+
+    let mut summaries = Vec::with_capacity(cases.len());
+    let indices: Vec<usize> = (0..cases.len()).collect();
+    for wave in indices.chunks(jobs) {
+        let children = wave
+            .iter()
+            .map(|&i| spawn_worker(path, i, settings))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (child, &i) in children.into_iter().zip(wave) {
+            summaries.push(collect(child, &cases[i].name)?);
+        }
+    }
+    Ok(summaries)
+}
+
+fn print_table(summaries: &[Summary]) {
+    println!(
+        "{:<32} {:>14} {:>10} {:>14} {:>14} {:>10}",
+        "scenario", "a (km)", "e", "periapsis (km)", "apoapsis (km)", "Δv (m/s)"
+    );
+    for summary in summaries {
+        let apoapsis = summary
+            .apoapsis_altitude
+            .map_or("escape".to_string(), |apoapsis| {
+                format!("{:.1}", apoapsis / 1000.0)
+            });
+        println!(
+            "{:<32} {:>14.1} {:>10.6} {:>14.1} {:>14} {:>10.3}",
+            summary.name,
+            summary.semi_major_axis / 1000.0,
+            summary.eccentricity,
+            summary.periapsis_altitude / 1000.0,
+            apoapsis,
+            summary.delta_v
+        );
+    }
+}
+
+// What the scenarios are flown with
+pub struct Settings<'a> {
+    pub integrator: &'a dyn Integrator,
+    pub dt: Precision,
+    pub perturbations: Perturbations,
+}
+
+impl Settings<'_> {
+    fn propagator<'a>(&'a self, paths: &'a MassivePaths) -> Propagator<'a> {
+        Propagator::new(self.integrator, self.dt, &self.perturbations, None, paths)
+    }
+}
+
+// Runs the batch, or the one case a worker was given, and returns the exit code
+pub fn run(path: &str, jobs: usize, case: Option<usize>, settings: Settings) -> i32 {
+    let result = load(path).and_then(|(batch, cases)| {
+        if let Some(index) = case {
+            let case = cases.get(index).ok_or("no such case")?;
+            let paths = MassivePaths::default();
+            let summary = run_case(case, &settings.propagator(&paths));
+            println!("{}", serde_json::to_string(&summary).unwrap());
+            return Ok(());
+        }
+
+        let summaries = run_all(path, &cases, jobs, &settings)?;
+        print_table(&summaries);
+        let report = batch.report.as_deref().unwrap_or(DEFAULT_REPORT);
+        File::create(report)
+            .map_err(|error| error.to_string())
+            .and_then(|file| {
+                let report_data = BTreeMap::from([("scenarios", &summaries)]);
+                serde_json::to_writer_pretty(file, &report_data).map_err(|error| error.to_string())
+            })
+            .map_err(|error| format!("could not write {}: {}", report, error))?;
+        println!("report of {} runs written to {}", summaries.len(), report);
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        }
+    }
+}
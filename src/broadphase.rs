@@ -0,0 +1,89 @@
+// Sweep and prune, the broadphase for close approaches among many bodies: each body's path over
+// a tick, from where it was at the start to where it is at the end, gets a bounding box, the
+// boxes are sorted along x, and only pairs whose boxes overlap on both axes go on to the
+// narrowphase, the exact closest approach of the two paths. With debris spread round an orbit,
+// that is a short sort and a few candidates instead of a check of every pair. Over a tick a body
+// is taken to move in a straight line, close enough while a tick is a small part of an orbit.
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::Precision;
+
+#[derive(Clone, Copy)]
+pub struct Sweep {
+    pub entity: Entity,
+    pub id: usize,
+    pub from: DVec2, // position at the start of the tick
+    pub to: DVec2,   // and at the end
+}
+
+impl Sweep {
+    // A body that stays put, such as a node of the link graph at one instant
+    pub fn at(entity: Entity, id: usize, position: DVec2) -> Self {
+        Self {
+            entity,
+            id,
+            from: position,
+            to: position,
+        }
+    }
+
+    fn low(&self) -> DVec2 {
+        self.from.min(self.to)
+    }
+
+    fn high(&self) -> DVec2 {
+        self.from.max(self.to)
+    }
+}
+
+// The pairs of sweeps, as indices into `sweeps`, whose boxes come within `margin` of each other,
+// each pair once with the lower index first
+pub fn candidates(sweeps: &[Sweep], margin: Precision) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..sweeps.len()).collect();
+    order.sort_by(|a, b| sweeps[*a].low().x.total_cmp(&sweeps[*b].low().x));
+
+    let mut pairs = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let (low, high) = (sweeps[i].low(), sweeps[i].high());
+        // Whatever ends before this box starts along x ends before every box after it too
+        active.retain(|j| sweeps[*j].high().x + margin >= low.x);
+        for j in &active {
+            let other = &sweeps[*j];
+            if other.low().y - margin <= high.y && low.y - margin <= other.high().y {
+                pairs.push((i.min(*j), i.max(*j)));
+            }
+        }
+        active.push(i);
+    }
+    pairs
+}
+
+// The narrowphase: how close two bodies come over the tick, and how far through it, 0 at the
+// start and 1 at the end, they are closest
+pub fn closest_approach(a: &Sweep, b: &Sweep) -> (Precision, Precision) {
+    let start = b.from - a.from;
+    let moved = (b.to - a.to) - start;
+    let fraction = if moved.length_squared() > 0.0 {
+        (-start.dot(moved) / moved.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    ((start + moved * fraction).length(), fraction)
+}
+
+// Every pair of sweeps that comes within `distance`, with how close and when, as in
+// `closest_approach`
+pub fn approaches(
+    sweeps: &[Sweep],
+    distance: Precision,
+) -> Vec<(usize, usize, Precision, Precision)> {
+    candidates(sweeps, distance)
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let (closest, fraction) = closest_approach(&sweeps[i], &sweeps[j]);
+            (closest <= distance).then_some((i, j, closest, fraction))
+        })
+        .collect()
+}
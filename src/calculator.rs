@@ -0,0 +1,154 @@
+// Quick orbital formulas with the simulation's μ, as console commands and as a panel worked
+// out for the selected body
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::elements::{OrbitalElements, MU};
+use crate::hud::format_duration;
+use crate::i18n::Language;
+use crate::{Body, Precision, Selected};
+
+const GEO_RADIUS: Precision = 42164e3; // m
+
+const USAGE: &str = "vis_viva(r, a), circular_speed(r), escape_speed(r), period(a), \
+    hohmann(r1, r2)";
+
+// Whether the panel is shown, toggled with X
+#[derive(Resource, Default)]
+pub struct Calculator {
+    pub shown: bool,
+}
+
+#[derive(Component)]
+pub struct CalculatorText;
+
+pub fn vis_viva(r: Precision, a: Precision) -> Precision {
+    (MU * (2.0 / r - 1.0 / a)).sqrt()
+}
+
+pub fn period(a: Precision) -> Precision {
+    TAU * (a.powi(3) / MU).sqrt()
+}
+
+// Burns at each end of a Hohmann transfer from radius r1 to r2 and the time of flight
+pub fn hohmann(r1: Precision, r2: Precision) -> (Precision, Precision, Precision) {
+    let a = (r1 + r2) / 2.0;
+    (
+        vis_viva(r1, a) - vis_viva(r1, r1),
+        vis_viva(r2, r2) - vis_viva(r2, a),
+        period(a) / 2.0,
+    )
+}
+
+fn describe_hohmann(r1: Precision, r2: Precision) -> String {
+    let (dv1, dv2, flight) = hohmann(r1, r2);
+    format!(
+        "Δv {:.1} + {:.1} = {:.1} m/s, {} of flight",
+        dv1,
+        dv2,
+        dv1.abs() + dv2.abs(),
+        format_duration(flight)
+    )
+}
+
+// The console commands, or None for other names. Lengths are in metres.
+pub fn evaluate(name: &str, arguments: &[Precision]) -> Option<Result<String, String>> {
+    let positive = |values: &[Precision]| {
+        if values.iter().all(|value| *value > 0.0) {
+            Ok(())
+        } else {
+            Err("radii and semi-major axes must be positive".to_string())
+        }
+    };
+
+    let result = match (name, arguments) {
+        ("vis_viva", [r, a]) => positive(&[*r]).and_then(|_| {
+            let v2 = MU * (2.0 / r - 1.0 / a);
+            if v2 < 0.0 {
+                Err(format!(
+                    "an orbit with a = {} m never reaches r = {} m",
+                    a, r
+                ))
+            } else {
+                Ok(format!("v = {:.3} m/s", v2.sqrt()))
+            }
+        }),
+        ("circular_speed", [r]) => {
+            positive(&[*r]).map(|_| format!("v = {:.3} m/s", vis_viva(*r, *r)))
+        }
+        ("escape_speed", [r]) => {
+            positive(&[*r]).map(|_| format!("v = {:.3} m/s", (2.0 * MU / r).sqrt()))
+        }
+        ("period", [a]) => positive(&[*a])
+            .map(|_| format!("T = {:.1} s ({})", period(*a), format_duration(period(*a)))),
+        ("hohmann", [r1, r2]) => positive(&[*r1, *r2]).map(|_| describe_hohmann(*r1, *r2)),
+        ("vis_viva", _) => Err("usage: vis_viva(r, a)".to_string()),
+        ("hohmann", _) => Err("usage: hohmann(r1, r2)".to_string()),
+        ("circular_speed" | "escape_speed", _) => Err(format!("usage: {}(r)", name)),
+        ("period", _) => Err("usage: period(a)".to_string()),
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+// X toggles the panel
+pub fn toggle_calculator(keyboard: Res<Input<KeyCode>>, mut calculator: ResMut<Calculator>) {
+    if keyboard.just_pressed(KeyCode::X) {
+        calculator.shown = !calculator.shown;
+    }
+}
+
+pub fn show_calculator(
+    calculator: Res<Calculator>,
+    selected: Query<&Body, With<Selected>>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<CalculatorText>>,
+) {
+    let mut value = String::new();
+
+    if calculator.shown {
+        value = language.text("calculator", &[&format!("{:.6e}", MU)]);
+
+        if let Ok(body) = selected.get_single() {
+            let state = body.current_state;
+            let r = (state.x * state.x + state.y * state.y).sqrt();
+            let elements = OrbitalElements::from_state(&state);
+
+            value.push('\n');
+            value.push_str(&language.text(
+                "calculator_body",
+                &[
+                    &body.id,
+                    &format!("{:.0}", r / 1000.0),
+                    &format!("{:.1}", vis_viva(r, r)),
+                    &format!("{:.1}", (2.0 * MU / r).sqrt()),
+                ],
+            ));
+            if elements.e < 1.0 {
+                value.push_str(&format!(
+                    "\na = {:.0} km: v = {:.1} m/s, T = {}",
+                    elements.a / 1000.0,
+                    vis_viva(r, elements.a),
+                    format_duration(period(elements.a))
+                ));
+            }
+            let (dv1, dv2, flight) = hohmann(r, GEO_RADIUS);
+            value.push('\n');
+            value.push_str(&language.text(
+                "calculator_hohmann",
+                &[
+                    &format!("{:.1}", dv1),
+                    &format!("{:.1}", dv2),
+                    &format!("{:.1}", dv1.abs() + dv2.abs()),
+                    &format_duration(flight),
+                ],
+            ));
+        }
+        value.push('\n');
+        value.push_str(&language.text("calculator_console", &[&USAGE]));
+    }
+
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,207 @@
+// Camera zoom and panning, following the selected body, the automatic rendezvous zoom, and the
+// size of a screen pixel in metres that trail drawing uses to pick how many of its samples to
+// draw
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+
+use crate::interpolation::Interpolation;
+use crate::perspective::Perspective;
+use crate::sky::ViewMode;
+use crate::tether::Tether;
+use crate::{Body, Precision, Selected, EARTH_RADIUS};
+
+const ZOOM_FACTOR: f32 = 1.25;
+const MIN_SAMPLE_SPACING: Precision = 3.0; // pixels between drawn trail samples
+const DEFAULT_VIEW: f32 = (EARTH_RADIUS * 6.0) as f32; // visible span at scale 1
+const MIN_VIEW: f32 = 5000.0; // m
+const FRAMING: f32 = 4.0; // visible span per unit of separation
+const SMOOTHING: f32 = 0.1; // fraction of the way to the wanted view per frame
+
+// Z toggles zooming in on the selected body and its target whenever both fit in a view
+// smaller than the default one. The target is the nearest untethered body unless set.
+#[derive(Resource, Default)]
+pub struct AutoZoom {
+    pub enabled: bool,
+    pub target: Option<Entity>,
+}
+
+// Space toggles following the selected body: the view glides over to it and then keeps it in
+// place, dragging moves it around the screen and picking another body glides over to that one
+#[derive(Resource, Default)]
+pub struct Follow {
+    pub enabled: bool,
+    offset: Vec2, // m from the body to the view's centre
+    pan: Vec2,    // m, where the offset is easing to
+    body: Option<Entity>,
+}
+
+// Metres per logical pixel at the current zoom
+#[derive(Resource, Default)]
+pub struct PixelSize(pub Precision);
+
+impl PixelSize {
+    // Draw every n-th sample of a trail sampled every `dt` for a body moving at `speed`, so
+    // that drawn samples are a few pixels apart. Zoomed in it draws all of them.
+    pub fn stride(&self, speed: Precision, dt: Precision) -> usize {
+        ((MIN_SAMPLE_SPACING * self.0 / (speed * dt)).floor() as usize).max(1)
+    }
+}
+
+// = and the wheel zoom in, - zooms out. The perspective camera reads the wheel itself.
+pub fn zoom_camera(
+    keyboard: Res<Input<KeyCode>>,
+    perspective: Res<Perspective>,
+    mut wheel: EventReader<MouseWheel>,
+    mut projection: Query<&mut OrthographicProjection>,
+) {
+    let mut projection = projection.single_mut();
+    let notches: f32 = wheel.read().map(|event| event.y.signum()).sum();
+
+    if keyboard.just_pressed(KeyCode::Equals) {
+        projection.scale /= ZOOM_FACTOR;
+    }
+    if keyboard.just_pressed(KeyCode::Minus) {
+        projection.scale *= ZOOM_FACTOR;
+    }
+    if notches != 0.0 && !perspective.enabled {
+        projection.scale /= ZOOM_FACTOR.powf(notches);
+    }
+}
+
+// Dragging with the left button moves the top-down view, or the followed body in it
+pub fn pan_camera(
+    mouse: Res<Input<MouseButton>>,
+    (view, perspective): (Res<ViewMode>, Res<Perspective>),
+    pixel: Res<PixelSize>,
+    mut motion: EventReader<MouseMotion>,
+    mut follow: ResMut<Follow>,
+    mut camera: Query<&mut Transform, With<OrthographicProjection>>,
+) {
+    let dragged: Vec2 = motion.read().map(|event| event.delta).sum();
+    let panning = *view == ViewMode::Orbit && !perspective.enabled;
+    if !panning || !mouse.pressed(MouseButton::Left) || dragged == Vec2::ZERO {
+        return;
+    }
+
+    // Screen y runs down
+    let moved = Vec2::new(-dragged.x, dragged.y) * pixel.0 as f32;
+    if follow.enabled {
+        follow.pan += moved;
+        follow.offset += moved;
+    } else {
+        camera.single_mut().translation += moved.extend(0.0);
+    }
+}
+
+pub fn toggle_follow(keyboard: Res<Input<KeyCode>>, mut follow: ResMut<Follow>) {
+    if keyboard.just_pressed(KeyCode::Space) {
+        follow.enabled = !follow.enabled;
+        follow.body = None;
+        if follow.enabled {
+            info!("Following the selected body");
+        } else {
+            info!("No longer following the selected body");
+        }
+    }
+}
+
+// Keeps the followed body where it was dragged to, easing over from wherever the view was
+// when it started following it; the automatic zoom does its own framing
+pub fn follow_selected(
+    auto_zoom: Res<AutoZoom>,
+    interpolation: Interpolation,
+    mut follow: ResMut<Follow>,
+    selected: Query<(Entity, &Body), With<Selected>>,
+    mut camera: Query<&mut Transform, With<OrthographicProjection>>,
+) {
+    let Ok((entity, body)) = selected.get_single() else {
+        return;
+    };
+    if !follow.enabled || auto_zoom.enabled {
+        return;
+    }
+
+    let mut transform = camera.single_mut();
+    let here = interpolation.position(body);
+    if follow.body != Some(entity) {
+        follow.body = Some(entity);
+        follow.offset = transform.translation.truncate() - here;
+        follow.pan = Vec2::ZERO;
+    }
+    follow.offset = follow.offset.lerp(follow.pan, SMOOTHING);
+    transform.translation = (here + follow.offset).extend(transform.translation.z);
+}
+
+pub fn update_pixel_size(
+    cameras: Query<(&Camera, &OrthographicProjection)>,
+    mut pixel: ResMut<PixelSize>,
+) {
+    let (camera, projection) = cameras.single();
+
+    if let Some(viewport) = camera.logical_viewport_size() {
+        pixel.0 = (projection.area.height() / viewport.y) as Precision;
+    }
+}
+
+pub fn toggle_auto_zoom(
+    keyboard: Res<Input<KeyCode>>,
+    interpolation: Interpolation,
+    mut auto_zoom: ResMut<AutoZoom>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection)>,
+) {
+    if !keyboard.just_pressed(KeyCode::Z) {
+        return;
+    }
+
+    auto_zoom.enabled = !auto_zoom.enabled;
+    if !auto_zoom.enabled {
+        let (mut transform, mut projection) = camera.single_mut();
+        let earth = interpolation.point(0.0, 0.0);
+        transform.translation = earth.extend(transform.translation.z);
+        projection.scale = 1.0;
+    }
+}
+
+pub fn auto_zoom(
+    auto_zoom: Res<AutoZoom>,
+    interpolation: Interpolation,
+    selected: Query<(Entity, &Body), With<Selected>>,
+    bodies: Query<(Entity, &Body), Without<Tether>>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection)>,
+) {
+    let Ok((selected, body)) = selected.get_single() else {
+        return;
+    };
+    if !auto_zoom.enabled {
+        return;
+    }
+
+    let position = |body: &Body| interpolation.position(body);
+    let here = position(body);
+
+    let target = match auto_zoom.target {
+        Some(entity) => bodies.get(entity).ok().map(|(_, body)| position(body)),
+        None => bodies
+            .iter()
+            .filter(|(entity, _)| *entity != selected)
+            .map(|(_, body)| position(body))
+            .min_by(|a, b| a.distance(here).total_cmp(&b.distance(here))),
+    };
+
+    // Both bodies in frame when they are close enough, the whole orbit otherwise
+    let (centre, scale) = match target {
+        Some(target) if target.distance(here) * FRAMING < DEFAULT_VIEW => (
+            (here + target) / 2.0,
+            (target.distance(here) * FRAMING).max(MIN_VIEW) / DEFAULT_VIEW,
+        ),
+        _ => (interpolation.point(0.0, 0.0), 1.0),
+    };
+
+    let (mut transform, mut projection) = camera.single_mut();
+    let current = transform.translation.truncate();
+    let centre = current.lerp(centre, SMOOTHING);
+    transform.translation = centre.extend(transform.translation.z);
+    // Zoom in log space so it eases at the same rate in and out
+    projection.scale =
+        (projection.scale.ln() + (scale.ln() - projection.scale.ln()) * SMOOTHING).exp();
+}
@@ -0,0 +1,281 @@
+// CCSDS Orbit Parameter Messages (OPM, a state vector) and Orbit Mean-Elements Messages (OMM,
+// mean elements like a TLE's), in their key = value form, as operators exchange them. A file
+// can hold several messages one after the other. Both become the same element sets TLE
+// catalogs give, so they go through the same filters and are propagated to the simulation
+// epoch and projected onto its plane the same way. OPM states are taken as earth-centred
+// inertial, and epochs may be in UTC, TAI, TT or GPS time.
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+
+use crate::elements::MU;
+use crate::timescales::{self, TT_MINUS_TAI};
+use crate::tle::Tle;
+use crate::Precision;
+
+const DAY: Precision = 86400.0; // s
+const GPS_MINUS_TAI: Precision = -19.0; // s
+
+// Whether a file holds CCSDS messages rather than a TLE catalog
+pub fn is_message(source: &str) -> bool {
+    let start = source.trim_start();
+    start.starts_with("CCSDS_OPM_VERS") || start.starts_with("CCSDS_OMM_VERS")
+}
+
+pub fn parse_messages(source: &str) -> Result<Vec<Tle>, String> {
+    let mut messages: Vec<(usize, HashMap<&str, &str>)> = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        // Blocks open and close on lines without a value
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key == "COMMENT" {
+            continue;
+        }
+        if key.starts_with("CCSDS_") {
+            messages.push((i + 1, HashMap::new()));
+        }
+        let Some((_, fields)) = messages.last_mut() else {
+            return Err(format!(
+                "line {}: expected a CCSDS_OPM_VERS or CCSDS_OMM_VERS header",
+                i + 1
+            ));
+        };
+        // Values may be followed by their units, like `6655.99 [km]`
+        let value = value.split('[').next().unwrap_or_default().trim();
+        fields.insert(key, value);
+    }
+
+    messages
+        .iter()
+        .map(|(line, fields)| {
+            parse_message(fields).map_err(|error| format!("message at line {}: {}", line, error))
+        })
+        .collect()
+}
+
+fn parse_message(fields: &HashMap<&str, &str>) -> Result<Tle, String> {
+    let text = |key: &str| {
+        fields
+            .get(key)
+            .copied()
+            .ok_or_else(|| format!("missing {}", key))
+    };
+    let number = |key: &str| {
+        let value = text(key)?;
+        value
+            .parse::<Precision>()
+            .map_err(|_| format!("bad number `{}` for {}", value, key))
+    };
+
+    let name = text("OBJECT_NAME")
+        .or_else(|_| text("OBJECT_ID"))?
+        .to_string();
+    let epoch = utc_epoch(text("EPOCH")?, fields.get("TIME_SYSTEM").copied())?;
+
+    if fields.contains_key("CCSDS_OPM_VERS") {
+        let km = |key: &str| Ok::<_, String>(number(key)? * 1000.0);
+        let position = [km("X")?, km("Y")?, km("Z")?];
+        let velocity = [km("X_DOT")?, km("Y_DOT")?, km("Z_DOT")?];
+        return from_state(name, epoch, position, velocity);
+    }
+
+    let e = number("ECCENTRICITY")?;
+    // SGP4 elements give the mean motion, other theories may give the semi-major axis
+    let mean_motion = match number("MEAN_MOTION") {
+        Ok(revolutions_per_day) => revolutions_per_day * TAU / DAY,
+        Err(_) => (MU / (number("SEMI_MAJOR_AXIS")? * 1000.0).powi(3)).sqrt(),
+    };
+    Ok(Tle {
+        name,
+        epoch,
+        inclination: number("INCLINATION")?.to_radians(),
+        raan: number("RA_OF_ASC_NODE")?.to_radians(),
+        e,
+        argument_of_perigee: number("ARG_OF_PERICENTER")?.to_radians(),
+        mean_anomaly: number("MEAN_ANOMALY")?.to_radians(),
+        mean_motion,
+    })
+}
+
+// A UTC Julian date from an epoch like 2024-01-01T12:00:00.000 or 2024-001T12:00:00 in the
+// message's time system
+fn utc_epoch(epoch: &str, system: Option<&str>) -> Result<Precision, String> {
+    let bad = || format!("bad epoch `{}`", epoch);
+    let (date, time) = epoch.split_once('T').unwrap_or((epoch, "00:00:00"));
+    let parts: Vec<i64> = date
+        .split('-')
+        .map(|part| part.parse().map_err(|_| bad()))
+        .collect::<Result<_, _>>()?;
+    let start_of_day = match parts.as_slice() {
+        [year, month @ 1..=12, day @ 1..=31] => timescales::julian_day(*year, *month, *day),
+        [year, day_of_year @ 1..=366] => {
+            timescales::julian_day(*year, 1, 1) + (day_of_year - 1) as Precision
+        }
+        _ => return Err(bad()),
+    };
+    let seconds = time
+        .trim_end_matches('Z')
+        .split(':')
+        .map(|part| part.parse::<Precision>().map_err(|_| bad()))
+        .try_fold(0.0, |total, part| Ok::<_, String>(total * 60.0 + part?))?;
+    let jd = start_of_day + seconds / DAY;
+
+    match system.unwrap_or("UTC") {
+        "UTC" => Ok(jd),
+        "TAI" => Ok(timescales::utc_from_tai(jd).0),
+        "TT" => Ok(timescales::utc_from_tai(jd - TT_MINUS_TAI / DAY).0),
+        "GPS" => Ok(timescales::utc_from_tai(jd - GPS_MINUS_TAI / DAY).0),
+        other => Err(format!(
+            "unsupported TIME_SYSTEM {}, try UTC, TAI, TT or GPS",
+            other
+        )),
+    }
+}
+
+fn cross(a: [Precision; 3], b: [Precision; 3]) -> [Precision; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [Precision; 3], b: [Precision; 3]) -> Precision {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+// Classical elements of an elliptic orbit from its state, in m and m/s. Equatorial orbits get
+// a node along +x, and circular ones a periapsis at the node.
+fn from_state(
+    name: String,
+    epoch: Precision,
+    position: [Precision; 3],
+    velocity: [Precision; 3],
+) -> Result<Tle, String> {
+    let r = dot(position, position).sqrt();
+    let v2 = dot(velocity, velocity);
+    let a = 1.0 / (2.0 / r - v2 / MU);
+    if a <= 0.0 {
+        return Err("the state is on an escape orbit".to_string());
+    }
+
+    let h = cross(position, velocity);
+    let h_norm = dot(h, h).sqrt();
+    let radial_velocity = dot(position, velocity);
+    let eccentricity: [Precision; 3] =
+        std::array::from_fn(|i| ((v2 - MU / r) * position[i] - radial_velocity * velocity[i]) / MU);
+    let e = dot(eccentricity, eccentricity).sqrt();
+
+    let inclination = (h[2] / h_norm).clamp(-1.0, 1.0).acos();
+    let node = [-h[1], h[0], 0.0];
+    let node_norm = dot(node, node).sqrt();
+    let (node, raan) = if node_norm < 1e-9 * h_norm {
+        ([1.0, 0.0, 0.0], 0.0)
+    } else {
+        let node = node.map(|component| component / node_norm);
+        (node, node[1].atan2(node[0]).rem_euclid(TAU))
+    };
+
+    // Angles in the orbit plane from the node, counterclockwise seen from the angular momentum
+    let normal = h.map(|component| component / h_norm);
+    let angle = |vector: [Precision; 3]| {
+        dot(cross(node, vector), normal)
+            .atan2(dot(node, vector))
+            .rem_euclid(TAU)
+    };
+    let argument_of_latitude = angle(position);
+    let argument_of_perigee = if e < 1e-9 { 0.0 } else { angle(eccentricity) };
+
+    let true_anomaly = argument_of_latitude - argument_of_perigee;
+    let eccentric_anomaly = 2.0
+        * ((1.0 - e).sqrt() * (true_anomaly / 2.0).sin())
+            .atan2((1.0 + e).sqrt() * (true_anomaly / 2.0).cos());
+    let mean_anomaly = (eccentric_anomaly - e * eccentric_anomaly.sin()).rem_euclid(TAU);
+
+    Ok(Tle {
+        name,
+        epoch,
+        inclination,
+        raan,
+        e,
+        argument_of_perigee,
+        mean_anomaly,
+        mean_motion: (MU / a.powi(3)).sqrt(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OMM: &str = "\
+CCSDS_OMM_VERS = 2.0
+COMMENT a comment = with an equals sign
+META_START
+OBJECT_NAME = ISS (ZARYA)
+OBJECT_ID = 1998-067A
+TIME_SYSTEM = UTC
+META_STOP
+EPOCH = 2024-032T06:00:00
+MEAN_MOTION = 15.5 [rev/day]
+ECCENTRICITY = 0.0005
+INCLINATION = 51.64 [deg]
+RA_OF_ASC_NODE = 10.0
+ARG_OF_PERICENTER = 20.0
+MEAN_ANOMALY = 30.0
+";
+
+    const OPM: &str = "\
+CCSDS_OPM_VERS = 2.0
+OBJECT_ID = 2024-001A
+EPOCH = 2024-01-01T00:00:37.000
+TIME_SYSTEM = TAI
+X = 7000.0 [km]
+Y = 0.0
+Z = 0.0
+X_DOT = 0.0
+Y_DOT = {speed}
+Z_DOT = 0.0
+";
+
+    #[test]
+    fn reads_mean_elements() {
+        assert!(is_message(OMM));
+        let tle = &parse_messages(OMM).unwrap()[0];
+        assert_eq!(tle.name, "ISS (ZARYA)");
+        let epoch = timescales::julian_day(2024, 2, 1) + 0.25;
+        assert!((tle.epoch - epoch).abs() < 1e-8);
+        assert!((tle.mean_motion * DAY / TAU - 15.5).abs() < 1e-12);
+        assert!((tle.inclination.to_degrees() - 51.64).abs() < 1e-12);
+        assert_eq!(tle.e, 0.0005);
+    }
+
+    #[test]
+    fn reads_a_state_in_its_time_system() {
+        let speed = (MU / 7000e3).sqrt() / 1000.0;
+        let opm = OPM.replace("{speed}", &speed.to_string());
+        let messages = format!("{}\n{}", opm, OMM);
+        let tles = parse_messages(&messages).unwrap();
+        assert_eq!(tles.len(), 2);
+        let tle = &tles[0];
+        assert_eq!(tle.name, "2024-001A");
+        // 37 s of TAI - UTC in 2024
+        assert!((tle.epoch - timescales::julian_day(2024, 1, 1)).abs() < 1e-8);
+        assert!((tle.semi_major_axis() - 7000e3).abs() < 1e-3);
+        assert!(tle.e < 1e-9 && tle.inclination.abs() < 1e-12);
+        assert!(tle.mean_anomaly.abs() < 1e-9);
+    }
+
+    #[test]
+    fn reports_bad_messages() {
+        let error = |source: &str| parse_messages(source).err().unwrap();
+        assert!(error("EPOCH = 2024-01-01").contains("header"));
+        let missing = error(&OMM.replace("ECCENTRICITY", "ECCENTRICITYX"));
+        assert_eq!(missing, "message at line 1: missing ECCENTRICITY");
+        assert!(error(&OMM.replace("= UTC", "= TDB")).contains("unsupported TIME_SYSTEM TDB"));
+        assert!(error(&OMM.replace("2024-032", "2024-13-01")).contains("bad epoch"));
+        assert!(error(&OPM.replace("{speed}", "20.0")).contains("escape"));
+    }
+}
@@ -0,0 +1,233 @@
+// Training scenarios: a seed picks a random starting orbit for the selected body and a
+// random target orbit, with a Δv budget that the insertion wizard's transfer fits in.
+// R moves on to the next seed.
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::elements::OrbitalElements;
+use crate::engine::Engine;
+use crate::events::{EventKind, SimEvent};
+use crate::i18n::Language;
+use crate::insertion::plan_insertion;
+use crate::maneuver::ManeuverPlan;
+use crate::{Body, Precision, Selected, SimTime, StateHistory, DEFAULT_DT, EARTH_RADIUS};
+
+const BUDGET_MARGIN: Precision = 1.2; // budget per Δv of the wizard's transfer
+const MIN_DELTA_V: Precision = 100.0; // m/s
+const MAX_DELTA_V: Precision = 2500.0; // m/s
+const TOLERANCE: Precision = 50e3; // m, on each apsis
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Flying,
+    Reached,
+    OverBudget,
+}
+
+pub struct Challenge {
+    seed: u64,
+    body: Entity,
+    periapsis: Precision, // target radii, m
+    apoapsis: Precision,
+    budget: Precision, // m/s
+    impulses: Precision,
+    used: Precision,         // impulses and engine burns
+    engine_start: Precision, // the engine's Δv counter when the scenario began
+    status: Status,
+}
+
+// The seed of the next scenario to set up, and the one being flown
+#[derive(Resource, Default)]
+pub struct Training {
+    pending: Option<u64>,
+    challenge: Option<Challenge>,
+}
+
+impl Training {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            pending: seed,
+            challenge: None,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ChallengeText;
+
+type Trainee = (
+    Entity,
+    &'static mut Body,
+    Option<&'static Engine>,
+    Option<&'static mut ManeuverPlan>,
+);
+
+fn random_orbit(
+    rng: &mut StdRng,
+    altitudes: (Precision, Precision),
+    max_e: Precision,
+) -> OrbitalElements {
+    let periapsis = EARTH_RADIUS + rng.gen_range(altitudes.0..altitudes.1);
+    let e = rng.gen_range(0.0..max_e);
+
+    OrbitalElements {
+        a: periapsis / (1.0 - e),
+        e,
+        argument_of_periapsis: rng.gen_range(0.0..TAU),
+        mean_anomaly: rng.gen_range(0.0..TAU),
+    }
+}
+
+// A starting orbit, a target orbit and the Δv of the wizard's transfer between them, drawn
+// until the transfer is neither trivial nor too expensive
+fn generate(seed: u64) -> (OrbitalElements, OrbitalElements, Precision) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    loop {
+        let start = random_orbit(&mut rng, (300e3, 1500e3), 0.2);
+        let target = random_orbit(&mut rng, (300e3, 4000e3), 0.4);
+        let altitude = target.a * (1.0 - target.e) - EARTH_RADIUS;
+        // Only the Δv counts, so it doesn't matter which step the burns would land on
+        let start_state = start.to_state(false);
+        let Ok(nodes) = plan_insertion(&start_state, (0.0, DEFAULT_DT), altitude, target.e) else {
+            continue;
+        };
+
+        let delta_v: Precision = nodes.iter().map(|node| node.dv[0].abs()).sum();
+        if (MIN_DELTA_V..MAX_DELTA_V).contains(&delta_v) {
+            return (start, target, delta_v);
+        }
+    }
+}
+
+// R starts the scenario after the current one
+pub fn next_challenge(keyboard: Res<Input<KeyCode>>, mut training: ResMut<Training>) {
+    if keyboard.just_pressed(KeyCode::R) {
+        if let Some(challenge) = &training.challenge {
+            training.pending = Some(challenge.seed + 1);
+        }
+    }
+}
+
+pub fn start_challenge(
+    mut training: ResMut<Training>,
+    mut selected: Query<Trainee, With<Selected>>,
+) {
+    let Some(seed) = training.pending else {
+        return;
+    };
+    // The body is spawned by a startup command, so it may not be there yet
+    let Ok((entity, mut body, engine, plan)) = selected.get_single_mut() else {
+        return;
+    };
+    training.pending = None;
+
+    let (start, target, delta_v) = generate(seed);
+    body.current_state = start.to_state(false);
+    body.history = StateHistory::new();
+    if let Some(mut plan) = plan {
+        plan.0.clear();
+    }
+
+    let budget = (BUDGET_MARGIN * delta_v / 10.0).ceil() * 10.0;
+    info!(
+        "Scenario {}: reach {:.0} x {:.0} km with {:.0} m/s",
+        seed,
+        (target.a * (1.0 - target.e) - EARTH_RADIUS) / 1000.0,
+        (target.a * (1.0 + target.e) - EARTH_RADIUS) / 1000.0,
+        budget
+    );
+    training.challenge = Some(Challenge {
+        seed,
+        body: entity,
+        periapsis: target.a * (1.0 - target.e),
+        apoapsis: target.a * (1.0 + target.e),
+        budget,
+        impulses: 0.0,
+        used: 0.0,
+        engine_start: engine.map_or(0.0, |engine| engine.delta_v),
+        status: Status::Flying,
+    });
+}
+
+pub fn score_challenge(
+    time: Res<SimTime>,
+    mut training: ResMut<Training>,
+    mut events: EventReader<SimEvent>,
+    bodies: Query<(&Body, Option<&Engine>)>,
+) {
+    let Some(challenge) = training.challenge.as_mut() else {
+        events.clear();
+        return;
+    };
+    let Ok((body, engine)) = bodies.get(challenge.body) else {
+        return;
+    };
+
+    let impulses: Precision = events
+        .read()
+        .filter(|event| event.body == body.id)
+        .filter_map(|event| match event.kind {
+            EventKind::Impulse { dvx, dvy } => Some((dvx * dvx + dvy * dvy).sqrt()),
+            _ => None,
+        })
+        .sum();
+    if challenge.status != Status::Flying {
+        return;
+    }
+    let engine = engine.map_or(0.0, |engine| engine.delta_v) - challenge.engine_start;
+    challenge.impulses += impulses;
+    challenge.used = challenge.impulses + engine;
+    let used = challenge.used;
+
+    let elements = OrbitalElements::from_state(&body.current_state);
+    let reached = elements.e < 1.0
+        && (elements.a * (1.0 - elements.e) - challenge.periapsis).abs() < TOLERANCE
+        && (elements.a * (1.0 + elements.e) - challenge.apoapsis).abs() < TOLERANCE;
+
+    if used > challenge.budget {
+        challenge.status = Status::OverBudget;
+        info!(
+            "t = {:.0} s: scenario {} is over budget",
+            time.0, challenge.seed
+        );
+    } else if reached {
+        challenge.status = Status::Reached;
+        info!(
+            "t = {:.0} s: scenario {} reached with {:.0} m/s",
+            time.0, challenge.seed, used
+        );
+    }
+}
+
+pub fn show_challenge(
+    training: Res<Training>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<ChallengeText>>,
+) {
+    let mut value = String::new();
+
+    if let Some(challenge) = &training.challenge {
+        let status = match challenge.status {
+            Status::Flying => "scenario_flying",
+            Status::Reached => "scenario_reached",
+            Status::OverBudget => "scenario_over_budget",
+        };
+        value = language.text(
+            "scenario",
+            &[
+                &challenge.seed,
+                &format!("{:.0}", (challenge.periapsis - EARTH_RADIUS) / 1000.0),
+                &format!("{:.0}", (challenge.apoapsis - EARTH_RADIUS) / 1000.0),
+                &format!("{:.0}", challenge.used),
+                &format!("{:.0}", challenge.budget),
+                &language.text(status, &[]),
+            ],
+        );
+    }
+
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,165 @@
+// Conservation diagnostics: a body coasting around the earth alone keeps its specific orbital
+// energy and angular momentum, so how far they drift from their starting values is how far the
+// integrator has gone wrong. Every tick each body's are compared with what they were when the
+// body was first seen, last fired its engine or `reset_drift()` was run, and the worst relative
+// drifts over all bodies go to Bevy's diagnostics as `energy_drift` and `momentum_drift`.
+// Semicolon shows the selected body's on the HUD. Drag, the Moon and impulses change them for
+// real, and show up as drift too.
+use bevy::diagnostic::{DiagnosticId, Diagnostics};
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::elements::MU;
+use crate::engine::Engine;
+use crate::gravity::GravitySource;
+use crate::i18n::Language;
+use crate::{Body, Precision, Selected, State};
+
+pub const ENERGY_DRIFT: DiagnosticId = DiagnosticId::from_u128(0x5a1d7e0c2c4b4f0e9a51e4e2d1f00001);
+pub const MOMENTUM_DRIFT: DiagnosticId =
+    DiagnosticId::from_u128(0x5a1d7e0c2c4b4f0e9a51e4e2d1f00002);
+pub const HISTORY: usize = 60; // measurements the diagnostics keep
+
+// Specific orbital energy and angular momentum about the earth's centre
+#[derive(Clone, Copy)]
+pub struct Invariants {
+    pub energy: Precision,   // J/kg
+    pub momentum: Precision, // m²/s
+}
+
+impl Invariants {
+    pub fn of(state: &State) -> Self {
+        let speed = state.vx.hypot(state.vy);
+        Self {
+            energy: 0.5 * speed * speed - MU / state.x.hypot(state.y),
+            momentum: state.x * state.vy - state.y * state.vx,
+        }
+    }
+}
+
+// What a body's invariants started at and are now
+#[derive(Component)]
+pub struct Conserved {
+    initial: Invariants,
+    current: Invariants,
+}
+
+impl Conserved {
+    // Relative drifts of the energy and the angular momentum
+    fn drift(&self) -> (Precision, Precision) {
+        let relative = |now: Precision, then: Precision| (now - then) / then.abs();
+        (
+            relative(self.current.energy, self.initial.energy),
+            relative(self.current.momentum, self.initial.momentum),
+        )
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Conservation {
+    pub shown: bool,
+    pub reset: bool,               // start every body's drift again at the next tick
+    worst: (Precision, Precision), // largest drifts in size, with their signs
+    bodies: usize,
+}
+
+impl Conservation {
+    pub fn describe(&self, language: Language) -> String {
+        language.text(
+            "conservation_worst",
+            &[
+                &self.bodies,
+                &format!("{:.2e}", self.worst.0),
+                &format!("{:.2e}", self.worst.1),
+            ],
+        )
+    }
+}
+
+// Semicolon shows or hides the readout
+pub fn toggle_conservation(keyboard: Res<Input<KeyCode>>, mut conservation: ResMut<Conservation>) {
+    if keyboard.just_pressed(KeyCode::Semicolon) {
+        conservation.shown = !conservation.shown;
+    }
+}
+
+fn larger(a: Precision, b: Precision) -> Precision {
+    if b.abs() > a.abs() {
+        b
+    } else {
+        a
+    }
+}
+
+type Tracked<'a> = (
+    Entity,
+    &'a Body,
+    Option<&'a Engine>,
+    Option<&'a mut Conserved>,
+);
+
+// After each physics tick
+pub fn track_invariants(
+    mut commands: Commands,
+    mut conservation: ResMut<Conservation>,
+    mut diagnostics: Diagnostics,
+    mut bodies: Query<Tracked, (Without<GravitySource>, Without<Crashed>)>,
+) {
+    let reset = std::mem::take(&mut conservation.reset);
+    let mut worst = (0.0, 0.0);
+    let mut count = 0;
+
+    for (entity, body, engine, conserved) in bodies.iter_mut() {
+        let now = Invariants::of(&body.current_state);
+        let Some(mut conserved) = conserved else {
+            commands.entity(entity).insert(Conserved {
+                initial: now,
+                current: now,
+            });
+            continue;
+        };
+        // Thrust changes them on purpose
+        if reset || engine.is_some_and(|engine| engine.firing) {
+            conserved.initial = now;
+        }
+        conserved.current = now;
+
+        let (energy, momentum) = conserved.drift();
+        worst = (larger(worst.0, energy), larger(worst.1, momentum));
+        count += 1;
+    }
+
+    conservation.worst = worst;
+    conservation.bodies = count;
+    diagnostics.add_measurement(ENERGY_DRIFT, || worst.0);
+    diagnostics.add_measurement(MOMENTUM_DRIFT, || worst.1);
+}
+
+#[derive(Component)]
+pub struct ConservationText;
+
+pub fn show_conservation(
+    conservation: Res<Conservation>,
+    language: Res<Language>,
+    selected: Query<&Conserved, With<Selected>>,
+    mut text: Query<&mut Text, With<ConservationText>>,
+) {
+    let mut value = String::new();
+    if conservation.shown {
+        if let Ok(conserved) = selected.get_single() {
+            let (energy, momentum) = conserved.drift();
+            value = language.text(
+                "conservation",
+                &[
+                    &format!("{:.6e}", conserved.current.energy),
+                    &format!("{:.2e}", energy),
+                    &format!("{:.6e}", conserved.current.momentum),
+                    &format!("{:.2e}", momentum),
+                ],
+            );
+            value.push('\n');
+        }
+        value.push_str(&conservation.describe(*language));
+    }
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,92 @@
+// In-app console running script commands against the live simulation. ` opens and closes
+// it; other keyboard controls are off while it is open.
+use bevy::prelude::*;
+
+use crate::script::Sim;
+
+const MAX_LINES: usize = 10;
+const PROMPT: &str = "> ";
+
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    lines: Vec<String>,
+}
+
+impl Console {
+    fn print(&mut self, line: String) {
+        self.lines.push(line);
+        if self.lines.len() > MAX_LINES {
+            self.lines.remove(0);
+        }
+    }
+
+    // Opens the console with a hint and the start of a command
+    pub fn open_with(&mut self, hint: &str, input: &str) {
+        self.open = true;
+        self.print(hint.to_string());
+        self.input = input.to_string();
+    }
+}
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+pub fn console_closed(console: Res<Console>) -> bool {
+    !console.open
+}
+
+pub fn toggle_console(keyboard: Res<Input<KeyCode>>, mut console: ResMut<Console>) {
+    if keyboard.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+    }
+}
+
+pub fn run_console(
+    keyboard: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut console: ResMut<Console>,
+    mut sim: Sim,
+) {
+    if !console.open {
+        characters.clear();
+        return;
+    }
+
+    for event in characters.read() {
+        if event.char != '`' && !event.char.is_control() {
+            console.input.push(event.char);
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+
+    if keyboard.just_pressed(KeyCode::Return) {
+        let input = std::mem::take(&mut console.input);
+        if input.trim().is_empty() {
+            return;
+        }
+
+        let output = match sim.run_typed(&input) {
+            Ok(output) => output,
+            Err(error) => format!("error: {}", error),
+        };
+        console.print(format!("{}{}", PROMPT, input));
+        console.print(output);
+    }
+}
+
+pub fn show_console(console: Res<Console>, mut text: Query<&mut Text, With<ConsoleText>>) {
+    let mut text = text.single_mut();
+
+    text.sections[0].value = if console.open {
+        let mut value = console.lines.join("\n");
+        value.push_str(&format!("\n{}{}_", PROMPT, console.input));
+        value
+    } else {
+        String::new()
+    };
+}
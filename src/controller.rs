@@ -0,0 +1,219 @@
+// External controllers: with --controller <address> the app listens for TCP connections and
+// the "external" autopilot asks whoever connects for the thrust, so guidance can be written in
+// any language. The protocol is one JSON object per line. The sim sends the latest state
+//
+//     {"dt":10.0,"state":{"x":..,"y":..,"vx":..,"vy":..},"target":null}
+//
+// and reads back {"throttle":0.5,"angle":0.0}, as in `ThrustCommand`, then sends the state it
+// has got to by then. The exchange runs on a thread of its own, so the physics goes on with the
+// last command it got while the controller thinks, and under warp that command holds for many
+// steps. Until a controller connects, or after it goes away or answers late, the body coasts;
+// the next one to connect takes over.
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::autopilot::{Autopilot, ThrustCommand};
+use crate::{Precision, State};
+
+const TIMEOUT: Duration = Duration::from_secs(1); // for each answer
+
+#[derive(Resource, Default)]
+pub struct ExternalController(pub Option<Arc<TcpListener>>);
+
+impl ExternalController {
+    pub fn bind(address: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        eprintln!("Waiting for controllers on {}", listener.local_addr()?);
+        Ok(Self(Some(Arc::new(listener))))
+    }
+}
+
+#[derive(Serialize)]
+struct StateMessage {
+    x: Precision,
+    y: Precision,
+    vx: Precision,
+    vy: Precision,
+}
+
+impl From<&State> for StateMessage {
+    fn from(state: &State) -> Self {
+        Self {
+            x: state.x,
+            y: state.y,
+            vx: state.vx,
+            vy: state.vy,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Request {
+    dt: Precision,
+    state: StateMessage,
+    target: Option<StateMessage>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    throttle: Precision,
+    #[serde(default)]
+    angle: Precision,
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    line: String,
+}
+
+impl Connection {
+    fn open(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            line: String::new(),
+        })
+    }
+
+    fn exchange(&mut self, request: &Request) -> io::Result<ThrustCommand> {
+        let mut message = serde_json::to_vec(request)?;
+        message.push(b'\n');
+        self.writer.write_all(&message)?;
+
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "connection closed",
+            ));
+        }
+        let reply: Reply = serde_json::from_str(&self.line)?;
+        Ok(ThrustCommand {
+            throttle: reply.throttle,
+            angle: reply.angle,
+        })
+    }
+}
+
+// A connection answering on a thread of its own, so the physics never waits on the network
+struct Link {
+    requests: Sender<Request>,
+    replies: Receiver<ThrustCommand>,
+    command: ThrustCommand, // the latest answer, held until the next one
+}
+
+impl Link {
+    fn spawn(mut connection: Connection) -> Self {
+        let (requests, pending) = mpsc::channel::<Request>();
+        let (answers, replies) = mpsc::channel();
+        thread::spawn(move || {
+            // Only the newest state is worth an answer, the ones sent meanwhile are dropped
+            while let Ok(request) = pending.recv() {
+                let request = pending.try_iter().last().unwrap_or(request);
+                let command = match connection.exchange(&request) {
+                    Ok(command) if command.throttle.is_finite() && command.angle.is_finite() => {
+                        command
+                    }
+                    Ok(_) => ThrustCommand::default(),
+                    Err(error) => {
+                        warn!("Controller disconnected: {}", error);
+                        return;
+                    }
+                };
+                if answers.send(command).is_err() {
+                    return;
+                }
+            }
+        });
+        Self {
+            requests,
+            replies,
+            command: ThrustCommand::default(),
+        }
+    }
+
+    // Sends the state and gives the latest answer, None once the connection is gone
+    fn command(&mut self, request: Request) -> Option<ThrustCommand> {
+        loop {
+            match self.replies.try_recv() {
+                Ok(command) => self.command = command,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return None,
+            }
+        }
+        self.requests.send(request).ok()?;
+        Some(self.command)
+    }
+}
+
+// Each body flown by an external controller has a connection of its own
+pub struct ExternalPilot {
+    listener: Arc<TcpListener>,
+    link: Mutex<Option<Link>>,
+}
+
+impl ExternalPilot {
+    pub fn new(listener: Arc<TcpListener>) -> Self {
+        Self {
+            listener,
+            link: Mutex::new(None),
+        }
+    }
+
+    fn accept(&self) -> Option<Connection> {
+        match self.listener.accept() {
+            Ok((stream, address)) => match Connection::open(stream) {
+                Ok(connection) => {
+                    info!("Controller connected from {}", address);
+                    Some(connection)
+                }
+                Err(error) => {
+                    warn!("Controller from {} refused: {}", address, error);
+                    None
+                }
+            },
+            Err(error) if error.kind() == ErrorKind::WouldBlock => None,
+            Err(error) => {
+                warn!("Could not accept a controller: {}", error);
+                None
+            }
+        }
+    }
+}
+
+impl Autopilot for ExternalPilot {
+    fn control(
+        &self,
+        own_state: &State,
+        target: Option<&State>,
+        dt: Precision,
+        _: Precision,
+    ) -> ThrustCommand {
+        let mut link = self.link.lock().unwrap();
+        if link.is_none() {
+            *link = self.accept().map(Link::spawn);
+        }
+        let request = Request {
+            dt,
+            state: own_state.into(),
+            target: target.map(StateMessage::from),
+        };
+        let command = link.as_mut().and_then(|link| link.command(request));
+        if command.is_none() {
+            *link = None;
+        }
+        command.unwrap_or_default()
+    }
+}
@@ -0,0 +1,197 @@
+// Ground coverage of the bodies carrying sensors. The surface is the equator, the circle of the
+// orbital plane, split into one-degree cells of longitude east of Greenwich that turn with the
+// earth under the orbits. Each update counts the sim time since the last one to the cells seen
+// then, however many steps the tick ran.
+use std::f64::consts::{FRAC_PI_2, TAU};
+use std::fs::File;
+use std::io::{self, Write};
+
+use bevy::prelude::*;
+
+use crate::ephemeris::Epoch;
+use crate::groundtrack::SubPoint;
+use crate::i18n::Language;
+use crate::{Body, Precision, SimTime, EARTH_RADIUS};
+
+const N_CELLS: usize = 360;
+const CELLS_PER_BAND: usize = 30;
+const EXPORT_PATH: &str = "coverage.csv";
+
+// Nadir-pointing sensor cone
+#[derive(Component)]
+pub struct Sensor {
+    pub half_angle: Precision, // rad
+}
+
+impl Sensor {
+    // Earth-central half-angle of the footprint seen from radius r
+    pub fn footprint(&self, r: Precision) -> Precision {
+        let earth_angular_radius = (EARTH_RADIUS / r).min(1.0).asin();
+
+        if self.half_angle >= earth_angular_radius {
+            // The cone reaches past the horizon
+            FRAC_PI_2 - earth_angular_radius
+        } else {
+            let elevation = (self.half_angle.sin() / earth_angular_radius.sin()).acos();
+            FRAC_PI_2 - self.half_angle - elevation
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Cell {
+    covered_time: Precision,
+    last_seen: Option<Precision>,
+    max_gap: Precision,
+}
+
+#[derive(Resource)]
+pub struct Coverage {
+    cells: [Cell; N_CELLS],
+    elapsed: Precision,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self {
+            cells: [Cell {
+                covered_time: 0.0,
+                last_seen: None,
+                max_gap: 0.0,
+            }; N_CELLS],
+            elapsed: 0.0,
+        }
+    }
+}
+
+// Coverage fraction and longest revisit gap of a group of cells
+struct BandStatistics {
+    coverage: Precision,
+    max_gap: Precision,
+}
+
+impl Coverage {
+    // Gaps still open count up to now; cells never seen have been waiting all along
+    fn gap(&self, cell: &Cell) -> Precision {
+        let open = self.elapsed - cell.last_seen.unwrap_or(0.0);
+        cell.max_gap.max(open)
+    }
+
+    fn statistics(&self, cells: &[Cell]) -> BandStatistics {
+        let elapsed = self.elapsed.max(Precision::MIN_POSITIVE);
+        let covered: Precision = cells.iter().map(|cell| cell.covered_time).sum();
+
+        BandStatistics {
+            coverage: covered / (elapsed * cells.len() as Precision),
+            max_gap: cells
+                .iter()
+                .map(|cell| self.gap(cell))
+                .fold(0.0, Precision::max),
+        }
+    }
+
+    fn bands(&self) -> impl Iterator<Item = (usize, BandStatistics)> + '_ {
+        self.cells
+            .chunks(CELLS_PER_BAND)
+            .enumerate()
+            .map(|(i, cells)| (i * CELLS_PER_BAND, self.statistics(cells)))
+    }
+
+    fn export(&self, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "longitude_start_deg, coverage, max_revisit_gap_s")?;
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            let statistics = self.statistics(std::slice::from_ref(cell));
+            writeln!(
+                file,
+                "{}, {}, {}",
+                i, statistics.coverage, statistics.max_gap
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn update_coverage(
+    time: Res<SimTime>,
+    epoch: Res<Epoch>,
+    mut coverage: ResMut<Coverage>,
+    query: Query<(&Body, &Sensor)>,
+) {
+    let now = time.0;
+    let mut seen = [false; N_CELLS];
+
+    for (body, sensor) in query.iter() {
+        let state = body.current_state;
+        let r = (state.x * state.x + state.y * state.y).sqrt();
+        let nadir = SubPoint::of(&state, now, &epoch).longitude.rem_euclid(TAU);
+        let footprint = sensor.footprint(r);
+
+        for (i, seen) in seen.iter_mut().enumerate() {
+            let centre = (i as Precision + 0.5) * TAU / N_CELLS as Precision;
+            let distance = (centre - nadir + TAU / 2.0).rem_euclid(TAU) - TAU / 2.0;
+            *seen |= distance.abs() <= footprint;
+        }
+    }
+
+    // Nothing to count after a load takes the clock back
+    let elapsed = (now - coverage.elapsed).max(0.0);
+    coverage.elapsed = now;
+
+    for (cell, seen) in coverage.cells.iter_mut().zip(seen) {
+        if seen {
+            cell.covered_time += elapsed;
+            if let Some(last_seen) = cell.last_seen {
+                cell.max_gap = cell.max_gap.max(now - last_seen);
+            }
+            cell.last_seen = Some(now);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct CoverageText;
+
+pub fn show_coverage(
+    coverage: Res<Coverage>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<CoverageText>>,
+) {
+    let global = coverage.statistics(&coverage.cells);
+
+    let mut value = language.text(
+        "coverage",
+        &[
+            &format!("{:.0}", coverage.elapsed),
+            &format!("{:.1}", 100.0 * global.coverage),
+            &format!("{:.0}", global.max_gap),
+        ],
+    );
+    value.push('\n');
+
+    for (start, band) in coverage.bands() {
+        value.push_str(&format!(
+            "{:>3}°-{:>3}°: {:>5.1}%  {:>6.0} s\n",
+            start,
+            start + CELLS_PER_BAND,
+            100.0 * band.coverage,
+            band.max_gap
+        ));
+    }
+
+    text.single_mut().sections[0].value = value;
+}
+
+// C writes the per-cell statistics to a CSV file
+pub fn export_coverage(keyboard: Res<Input<KeyCode>>, coverage: Res<Coverage>) {
+    if !keyboard.just_pressed(KeyCode::C) {
+        return;
+    }
+
+    match coverage.export(EXPORT_PATH) {
+        Ok(()) => info!("Coverage statistics written to {}", EXPORT_PATH),
+        Err(error) => error!("Could not write {}: {}", EXPORT_PATH, error),
+    }
+}
@@ -0,0 +1,488 @@
+// Circular restricted three-body problem in the Earth-Moon rotating frame, in normalised
+// units (the Earth-Moon distance, their total mass and the inverse of the Moon's mean
+// motion). The Earth sits at (-μ, 0) and the Moon at (1 - μ, 0). The three-body view finds
+// planar Lyapunov orbits around L1 and L2 by differential correction and walks along the
+// family towards lower or higher energy, and shows the zero-velocity curves that bound
+// where a body with a given Jacobi constant can go.
+use bevy::math::{DMat4, DVec2, DVec4};
+use bevy::prelude::*;
+
+use crate::ephemeris::Ephemeris;
+use crate::i18n::Language;
+use crate::palette::Palette;
+use crate::sky::ViewMode;
+use crate::{Body, Precision, State, EARTH_RADIUS};
+
+pub const MU: Precision = 0.012150585609624;
+const LENGTH_UNIT: Precision = 3.844e8; // m
+const TIME_UNIT: Precision = 375190.0; // s
+const STEP: Precision = 1.0e-3;
+const MAX_STEPS: usize = 20000;
+const MAX_ITERATIONS: usize = 20;
+const TOLERANCE: Precision = 1.0e-11; // vx at the half-period crossing
+const FIRST_AMPLITUDE: Precision = 0.002;
+const AMPLITUDE_STEP: Precision = 0.004;
+const PLOT_SCALE: f32 = (EARTH_RADIUS * 4.0) as f32; // per length unit
+const PLOT_CENTRE: Precision = 0.55;
+const GRID_SIZE: usize = 151;
+const GRID_EXTENT: Precision = 0.75; // half-width of the zero-velocity grid
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LagrangePoint {
+    L1,
+    L2,
+}
+
+// Distances to the Earth and the Moon
+fn distances(x: Precision, y: Precision) -> (Precision, Precision) {
+    (
+        ((x + MU) * (x + MU) + y * y).sqrt(),
+        ((x - 1.0 + MU) * (x - 1.0 + MU) + y * y).sqrt(),
+    )
+}
+
+// State vectors are (x, y, vx, vy)
+fn derivative(s: DVec4) -> DVec4 {
+    let (r1, r2) = distances(s.x, s.y);
+    let (k1, k2) = ((1.0 - MU) / (r1 * r1 * r1), MU / (r2 * r2 * r2));
+
+    DVec4::new(
+        s.z,
+        s.w,
+        2.0 * s.w + s.x - k1 * (s.x + MU) - k2 * (s.x - 1.0 + MU),
+        -2.0 * s.z + s.y - k1 * s.y - k2 * s.y,
+    )
+}
+
+// Second derivatives of the pseudo-potential
+fn hessian(x: Precision, y: Precision) -> (Precision, Precision, Precision) {
+    let (r1, r2) = distances(x, y);
+    let (k1, k2) = ((1.0 - MU) / r1.powi(3), MU / r2.powi(3));
+    let (l1, l2) = (3.0 * (1.0 - MU) / r1.powi(5), 3.0 * MU / r2.powi(5));
+    let (dx1, dx2) = (x + MU, x - 1.0 + MU);
+
+    (
+        1.0 - k1 - k2 + l1 * dx1 * dx1 + l2 * dx2 * dx2,
+        l1 * dx1 * y + l2 * dx2 * y,
+        1.0 - k1 - k2 + l1 * y * y + l2 * y * y,
+    )
+}
+
+fn jacobian(s: DVec4) -> DMat4 {
+    let (uxx, uxy, uyy) = hessian(s.x, s.y);
+
+    DMat4::from_cols(
+        DVec4::new(0.0, 0.0, uxx, uxy),
+        DVec4::new(0.0, 0.0, uxy, uyy),
+        DVec4::new(1.0, 0.0, 0.0, -2.0),
+        DVec4::new(0.0, 1.0, 2.0, 0.0),
+    )
+}
+
+// rk4 step of the state and its transition matrix
+fn step(s: DVec4, phi: DMat4, h: Precision) -> (DVec4, DMat4) {
+    let (k1, l1) = (derivative(s), jacobian(s) * phi);
+    let (s2, p2) = (s + 0.5 * h * k1, phi + l1 * (0.5 * h));
+    let (k2, l2) = (derivative(s2), jacobian(s2) * p2);
+    let (s3, p3) = (s + 0.5 * h * k2, phi + l2 * (0.5 * h));
+    let (k3, l3) = (derivative(s3), jacobian(s3) * p3);
+    let (s4, p4) = (s + h * k3, phi + l3 * h);
+    let (k4, l4) = (derivative(s4), jacobian(s4) * p4);
+
+    (
+        s + h / 6.0 * (k1 + 2.0 * k2 + 2.0 * k3 + k4),
+        phi + (l1 + l2 * 2.0 + l3 * 2.0 + l4) * (h / 6.0),
+    )
+}
+
+pub fn jacobi_constant(s: DVec4) -> Precision {
+    let (r1, r2) = distances(s.x, s.y);
+    let potential = 0.5 * (s.x * s.x + s.y * s.y) + (1.0 - MU) / r1 + MU / r2;
+
+    2.0 * potential - (s.z * s.z + s.w * s.w)
+}
+
+// x coordinate of a collinear Lagrange point, where the pseudo-potential is flat
+pub fn lagrange_point(point: LagrangePoint) -> Precision {
+    let mut x = match point {
+        LagrangePoint::L1 => 0.8,
+        LagrangePoint::L2 => 1.15,
+    };
+
+    for _ in 0..50 {
+        let ax = derivative(DVec4::new(x, 0.0, 0.0, 0.0)).z;
+        let (uxx, _, _) = hessian(x, 0.0);
+        x -= ax / uxx;
+    }
+
+    x
+}
+
+// Propagates until the orbit crosses y = 0 again, returning the state, the transition
+// matrix and the time at the crossing
+fn half_period(initial: DVec4) -> Option<(DVec4, DMat4, Precision)> {
+    let (mut s, mut phi, mut t) = (initial, DMat4::IDENTITY, 0.0);
+
+    for i in 0..MAX_STEPS {
+        let (next, next_phi) = step(s, phi, STEP);
+
+        if i > 0 && s.y * next.y <= 0.0 {
+            // Newton iterations on the time of the crossing, from the last step
+            let mut h = 0.0;
+            let mut crossing = (s, phi);
+            for _ in 0..5 {
+                h -= crossing.0.y / crossing.0.w;
+                crossing = step(s, phi, h);
+            }
+            return Some((crossing.0, crossing.1, t + h));
+        }
+
+        (s, phi, t) = (next, next_phi, t + STEP);
+    }
+
+    None
+}
+
+pub struct LyapunovOrbit {
+    pub x0: Precision,
+    pub vy0: Precision,
+    pub period: Precision,
+    pub jacobi: Precision,
+    points: Vec<DVec2>,
+}
+
+// Corrects vy0, keeping x0, until the orbit crosses the x axis perpendicularly, which by
+// symmetry makes it periodic
+pub fn correct(x0: Precision, mut vy0: Precision) -> Result<LyapunovOrbit, String> {
+    for _ in 0..MAX_ITERATIONS {
+        let initial = DVec4::new(x0, 0.0, 0.0, vy0);
+        let (end, phi, t) = half_period(initial).ok_or("the orbit never comes back")?;
+
+        if end.z.abs() < TOLERANCE {
+            let mut points = Vec::new();
+            let mut s = initial;
+            let steps = (2.0 * t / STEP).ceil() as usize;
+            for _ in 0..=steps {
+                points.push(DVec2::new(s.x, s.y));
+                s = step(s, DMat4::IDENTITY, 2.0 * t / steps as Precision).0;
+            }
+
+            return Ok(LyapunovOrbit {
+                x0,
+                vy0,
+                period: 2.0 * t,
+                jacobi: jacobi_constant(initial),
+                points,
+            });
+        }
+
+        // Sensitivity of vx at the crossing to vy0, with the crossing time free
+        let acceleration = derivative(end);
+        let sensitivity = phi.col(3).z - acceleration.z / end.w * phi.col(3).y;
+        vy0 -= end.z / sensitivity;
+    }
+
+    Err(format!(
+        "no periodic orbit after {} iterations",
+        MAX_ITERATIONS
+    ))
+}
+
+// Smallest family member, from the linearised motion around the Lagrange point
+fn first_orbit(point: LagrangePoint) -> Result<LyapunovOrbit, String> {
+    let x = lagrange_point(point);
+    let (r1, r2) = distances(x, 0.0);
+    let c2 = (1.0 - MU) / r1.powi(3) + MU / r2.powi(3);
+    let s2 = (c2 - 2.0 + (9.0 * c2 * c2 - 8.0 * c2).sqrt()) / 2.0;
+    let amplitude = -FIRST_AMPLITUDE;
+
+    correct(x + amplitude, -(s2 + 1.0 + 2.0 * c2) / 2.0 * amplitude)
+}
+
+// Members of the family found so far, by growing amplitude and so falling Jacobi constant
+#[derive(Resource)]
+pub struct ThreeBody {
+    point: LagrangePoint,
+    family: Vec<LyapunovOrbit>,
+    selected: usize,
+}
+
+impl Default for ThreeBody {
+    fn default() -> Self {
+        Self {
+            point: LagrangePoint::L1,
+            family: Vec::new(),
+            selected: 0,
+        }
+    }
+}
+
+impl ThreeBody {
+    // Continues the family one member further, extrapolating vy0 from the last members
+    fn extend(&mut self) -> Result<(), String> {
+        let orbit = match self.family.as_slice() {
+            [] => first_orbit(self.point)?,
+            // For small orbits vy0 grows in proportion to the amplitude
+            [last] => {
+                let amplitude = last.x0 - lagrange_point(self.point);
+                let scale = (amplitude - AMPLITUDE_STEP) / amplitude;
+                correct(last.x0 - AMPLITUDE_STEP, last.vy0 * scale)?
+            }
+            [.., previous, last] => {
+                correct(last.x0 - AMPLITUDE_STEP, 2.0 * last.vy0 - previous.vy0)?
+            }
+        };
+
+        self.family.push(orbit);
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+pub struct ThreeBodyText;
+
+// In the three-body view L switches between L1 and L2, ] moves to the next (lower energy)
+// family member and [ back to the previous one
+pub fn three_body_controls(
+    keyboard: Res<Input<KeyCode>>,
+    view: Res<ViewMode>,
+    mut three_body: ResMut<ThreeBody>,
+) {
+    if *view != ViewMode::ThreeBody {
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::L) {
+        three_body.point = match three_body.point {
+            LagrangePoint::L1 => LagrangePoint::L2,
+            LagrangePoint::L2 => LagrangePoint::L1,
+        };
+        three_body.family.clear();
+        three_body.selected = 0;
+    }
+
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        three_body.selected = three_body.selected.saturating_sub(1);
+    }
+
+    let wanted = if keyboard.just_pressed(KeyCode::BracketRight) {
+        three_body.selected + 1
+    } else {
+        three_body.selected
+    };
+
+    while three_body.family.len() <= wanted {
+        if let Err(error) = three_body.extend() {
+            error!("Could not continue the Lyapunov family: {}", error);
+            break;
+        }
+    }
+    three_body.selected = wanted.min(three_body.family.len().saturating_sub(1));
+}
+
+fn plot_point(x: Precision, y: Precision) -> Vec2 {
+    Vec2::new((x - PLOT_CENTRE) as f32, y as f32) * PLOT_SCALE
+}
+
+// Normalised rotating-frame state of an earth-centred state, with the x axis through the
+// Moon. The simulation doesn't move the Earth around the barycentre, so it is taken to sit
+// at (-μ, 0) and the frame to turn at the Moon's mean motion.
+pub fn rotating_state(state: &State, ephemeris: &Ephemeris) -> DVec4 {
+    let (mx, my) = ephemeris.moon;
+    let (sin, cos) = my.atan2(mx).sin_cos();
+    let speed_unit = LENGTH_UNIT / TIME_UNIT;
+
+    let x = (cos * state.x + sin * state.y) / LENGTH_UNIT - MU;
+    let y = (-sin * state.x + cos * state.y) / LENGTH_UNIT;
+    let vx = (cos * state.vx + sin * state.vy) / speed_unit;
+    let vy = (-sin * state.vx + cos * state.vy) / speed_unit;
+
+    // Take out the frame rotation, ω × r with ω = 1
+    DVec4::new(x, y, vx + y, vy - x)
+}
+
+// Twice the pseudo-potential on a grid over the plot, so that the zero-velocity curve of a
+// Jacobi constant C is the contour where it equals C
+fn potential_grid() -> Vec<Precision> {
+    let mut grid = Vec::with_capacity(GRID_SIZE * GRID_SIZE);
+
+    for j in 0..GRID_SIZE {
+        for i in 0..GRID_SIZE {
+            let (x, y) = grid_point(i, j);
+            grid.push(jacobi_constant(DVec4::new(x, y, 0.0, 0.0)));
+        }
+    }
+
+    grid
+}
+
+fn grid_point(i: usize, j: usize) -> (Precision, Precision) {
+    let spacing = 2.0 * GRID_EXTENT / (GRID_SIZE - 1) as Precision;
+    (
+        PLOT_CENTRE - GRID_EXTENT + i as Precision * spacing,
+        -GRID_EXTENT + j as Precision * spacing,
+    )
+}
+
+// Marching squares over the potential grid. Bodies can only be where the potential is above
+// their constant, so the curve bounds the region each of them can reach.
+fn draw_zero_velocity_curve(
+    gizmos: &mut Gizmos,
+    grid: &[Precision],
+    jacobi: Precision,
+    color: Color,
+) {
+    let value = |i: usize, j: usize| grid[j * GRID_SIZE + i] - jacobi;
+    let point = |i: usize, j: usize| {
+        let (x, y) = grid_point(i, j);
+        plot_point(x, y)
+    };
+
+    for j in 0..GRID_SIZE - 1 {
+        for i in 0..GRID_SIZE - 1 {
+            let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+            let mut crossings = Vec::new();
+
+            for k in 0..4 {
+                let (a, b) = (corners[k], corners[(k + 1) % 4]);
+                let (va, vb) = (value(a.0, a.1), value(b.0, b.1));
+
+                if (va < 0.0) != (vb < 0.0) {
+                    let t = (va / (va - vb)) as f32;
+                    crossings.push(point(a.0, a.1).lerp(point(b.0, b.1), t));
+                }
+            }
+
+            for pair in crossings.chunks_exact(2) {
+                gizmos.line_2d(pair[0], pair[1], color);
+            }
+        }
+    }
+}
+
+// Earth, Moon, L1, L2, the selected family member and the bodies in the rotating frame,
+// with the zero-velocity curves of the bodies and of the family member
+pub fn draw_three_body(
+    mut gizmos: Gizmos,
+    (view, language, palette): (Res<ViewMode>, Res<Language>, Res<Palette>),
+    three_body: Res<ThreeBody>,
+    ephemeris: Res<Ephemeris>,
+    bodies: Query<&Body>,
+    mut grid: Local<Vec<Precision>>,
+    mut text: Query<&mut Text, With<ThreeBodyText>>,
+) {
+    let mut text = text.single_mut();
+
+    if *view != ViewMode::ThreeBody {
+        text.sections[0].value.clear();
+        return;
+    }
+
+    let colors = palette.colors();
+
+    if grid.is_empty() {
+        *grid = potential_grid();
+    }
+
+    gizmos.circle_2d(plot_point(-MU, 0.0), 0.05 * PLOT_SCALE, colors.earth);
+    gizmos.circle_2d(plot_point(1.0 - MU, 0.0), 0.015 * PLOT_SCALE, colors.faint);
+
+    for point in [LagrangePoint::L1, LagrangePoint::L2] {
+        let centre = plot_point(lagrange_point(point), 0.0);
+        let size = 0.01 * PLOT_SCALE;
+        gizmos.line_2d(
+            centre - Vec2::X * size,
+            centre + Vec2::X * size,
+            colors.guide,
+        );
+        gizmos.line_2d(
+            centre - Vec2::Y * size,
+            centre + Vec2::Y * size,
+            colors.guide,
+        );
+    }
+
+    let name = match three_body.point {
+        LagrangePoint::L1 => "L1",
+        LagrangePoint::L2 => "L2",
+    };
+
+    let mut value = match three_body.family.get(three_body.selected) {
+        Some(orbit) => {
+            gizmos.linestrip_2d(
+                orbit.points.iter().map(|p| plot_point(p.x, p.y)),
+                colors.lookahead,
+            );
+            draw_zero_velocity_curve(&mut gizmos, &grid, orbit.jacobi, colors.lookahead);
+
+            language.text(
+                "lyapunov_orbit",
+                &[
+                    &name,
+                    &(three_body.selected + 1),
+                    &format!("{:.6}", orbit.jacobi),
+                    &format!("{:.2}", orbit.period * TIME_UNIT / 86400.0),
+                    &format!(
+                        "{:.0}",
+                        (orbit.x0 - lagrange_point(three_body.point)) * LENGTH_UNIT / 1000.0
+                    ),
+                ],
+            )
+        }
+        None => language.text("no_lyapunov_orbit", &[&name]),
+    };
+    value.push('\n');
+
+    for body in bodies.iter() {
+        let state = rotating_state(&body.current_state, &ephemeris);
+        let jacobi = jacobi_constant(state);
+
+        gizmos.circle_2d(
+            plot_point(state.x, state.y),
+            0.005 * PLOT_SCALE,
+            colors.body,
+        );
+        draw_zero_velocity_curve(&mut gizmos, &grid, jacobi, colors.body);
+        value.push_str(&language.text("jacobi", &[&body.id, &format!("{:.4}", jacobi)]));
+        value.push('\n');
+    }
+
+    text.sections[0].value = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lagrange_points_are_where_the_pull_balances() {
+        for point in [LagrangePoint::L1, LagrangePoint::L2] {
+            let x = lagrange_point(point);
+            assert!(derivative(DVec4::new(x, 0.0, 0.0, 0.0)).z.abs() < 1e-12);
+        }
+        // Either side of the Moon
+        assert!(lagrange_point(LagrangePoint::L1) < 1.0 - MU);
+        assert!(lagrange_point(LagrangePoint::L2) > 1.0 - MU);
+    }
+
+    #[test]
+    fn corrected_orbits_close_on_themselves() {
+        let mut family = ThreeBody::default();
+        for _ in 0..3 {
+            family.extend().unwrap();
+        }
+
+        for orbit in &family.family {
+            let initial = DVec4::new(orbit.x0, 0.0, 0.0, orbit.vy0);
+            let (end, _, t) = half_period(initial).unwrap();
+            assert!(end.z.abs() < TOLERANCE);
+            assert!((2.0 * t - orbit.period).abs() < 1e-12);
+
+            let (first, last) = (orbit.points[0], *orbit.points.last().unwrap());
+            assert!(first.distance(last) < 1e-6, "{} from {}", last, first);
+            assert!((jacobi_constant(initial) - orbit.jacobi).abs() < 1e-12);
+        }
+        // Growing the orbits lowers their energy threshold
+        assert!(family.family[0].jacobi > family.family[2].jacobi);
+    }
+}
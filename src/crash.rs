@@ -0,0 +1,80 @@
+// Crashes into the surface, caught at the integration step that goes under it rather than at
+// the end of the tick, with the crossing found between that step's two states. A crashed body
+// is frozen where it hit, with the velocity it hit at, and drops out of the propagation; the
+// orbit view marks it with a cross and the elements panel says it crashed.
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::events::{EventKind, SimEvent};
+use crate::{Body, Precision, State, EARTH_RADIUS};
+
+#[derive(Component, Clone, Copy, Serialize, Deserialize)]
+pub struct Crashed {
+    pub time: Precision,  // s
+    pub speed: Precision, // m/s
+}
+
+// Where an advance went under the surface, `time` seconds into it
+#[derive(Clone, Copy)]
+pub struct Crossing {
+    pub time: Precision,
+    pub state: State,
+}
+
+impl Crossing {
+    pub fn later(self, by: Precision) -> Self {
+        Self {
+            time: self.time + by,
+            ..self
+        }
+    }
+}
+
+// The crossing within a step of `dt` from `start`, if the step goes from above the surface to
+// under it, interpolated linearly in the distance from the earth's centre
+pub fn crossing(
+    before: &State,
+    after: &State,
+    start: Precision,
+    dt: Precision,
+) -> Option<Crossing> {
+    let (r0, r1) = (before.x.hypot(before.y), after.x.hypot(after.y));
+    if r0 < EARTH_RADIUS || r1 >= EARTH_RADIUS {
+        return None;
+    }
+
+    let fraction = (r0 - EARTH_RADIUS) / (r0 - r1);
+    let lerp = |a: Precision, b: Precision| a + fraction * (b - a);
+    Some(Crossing {
+        time: start + fraction * dt,
+        state: State::new(
+            lerp(before.x, after.x),
+            lerp(before.y, after.y),
+            lerp(before.vx, after.vx),
+            lerp(before.vy, after.vy),
+        ),
+    })
+}
+
+#[derive(SystemParam)]
+pub struct Crashes<'w, 's> {
+    pub commands: Commands<'w, 's>,
+    events: EventWriter<'w, SimEvent>,
+}
+
+impl Crashes<'_, '_> {
+    // Freezes the body at the point it hit, at sim time `time`
+    pub fn crash(&mut self, entity: Entity, body: &mut Body, time: Precision, state: State) {
+        body.current_state = state;
+        body.update_history();
+        let speed = state.vx.hypot(state.vy);
+
+        self.commands.entity(entity).insert(Crashed { time, speed });
+        self.events.send(SimEvent {
+            time,
+            body: body.id,
+            kind: EventKind::Crash { speed },
+        });
+    }
+}
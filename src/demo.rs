@@ -0,0 +1,257 @@
+// Demo mode: `--demo` runs a tour of the simulator for a kiosk or a classroom screen, with no
+// one at the controls. Each scene sets the warp and the camera (the whole orbit, following a
+// body or the tilted perspective) and runs console commands as it starts or some sim seconds
+// into it, spawning craft and planning their burns. After the last scene the craft it spawned
+// are removed, the starting bodies are put back as they were and the tour starts over. Any key
+// or mouse button ends it and hands over the controls.
+use bevy::prelude::*;
+
+use crate::camera::Follow;
+use crate::conservation::Conservation;
+use crate::crash::Crashed;
+use crate::engine::{Engine, Tank};
+use crate::ghost::Fleet;
+use crate::maneuver::ManeuverPlan;
+use crate::origin::FloatingOrigin;
+use crate::perspective::Perspective;
+use crate::scheduler::Scheduler;
+use crate::script::{self, Sim};
+use crate::swarm::Groups;
+use crate::{Body, Precision, SimTime};
+
+const SMOOTHING: f32 = 0.05; // fraction of the way to the scene's view per frame
+
+#[derive(Clone, Copy)]
+enum View {
+    Whole,       // centred on the earth
+    Following,   // the selected body
+    Perspective, // tilted, around the selected body
+}
+
+struct Scene {
+    caption: &'static str, // message key
+    seconds: f32,          // of real time
+    view: View,
+    scale: f32,                               // of the default view
+    commands: &'static [(f64, &'static str)], // and the sim seconds into the scene they run at
+}
+
+const SCENES: &[Scene] = &[
+    Scene {
+        caption: "demo_station",
+        seconds: 15.0,
+        view: View::Whole,
+        scale: 1.0,
+        commands: &[(0.0, "set_warp(1)")],
+    },
+    Scene {
+        caption: "demo_follow",
+        seconds: 15.0,
+        view: View::Following,
+        scale: 0.1,
+        commands: &[(0.0, "body(\"ISS\").focus()")],
+    },
+    // A Hohmann transfer from 400 km up to geostationary orbit, half an orbit between burns
+    Scene {
+        caption: "demo_transfer",
+        seconds: 40.0,
+        view: View::Whole,
+        scale: 2.5,
+        commands: &[
+            (0.0, "spawn_craft(6778000, 0, 0, -7668.6, \"Transfer\")"),
+            (10.0, "body(\"Transfer\").focus()"),
+            (10.0, "body(\"Transfer\").plan_burn(60, 2397.5, 0)"),
+            (10.0, "body(\"Transfer\").plan_burn(19110, 1456.5, 0)"),
+        ],
+    },
+    // Four craft 21 km apart at 7000 km, going round the way the station does, spread out over
+    // about five orbits
+    Scene {
+        caption: "demo_swarm",
+        seconds: 55.0,
+        view: View::Whole,
+        scale: 1.2,
+        commands: &[
+            (0.0, "spawn_craft(7000000, 0, 0, -7546, \"Swarm 1\")"),
+            (0.0, "spawn_craft(6999969, 21000, 23, -7546, \"Swarm 2\")"),
+            (0.0, "spawn_craft(6999874, 42000, 45, -7546, \"Swarm 3\")"),
+            (0.0, "spawn_craft(6999717, 62999, 68, -7546, \"Swarm 4\")"),
+            (10.0, "body(\"Swarm 1\").join(\"swarm\")"),
+            (10.0, "body(\"Swarm 2\").join(\"swarm\")"),
+            (10.0, "body(\"Swarm 3\").join(\"swarm\")"),
+            (10.0, "body(\"Swarm 4\").join(\"swarm\")"),
+            (20.0, "group(\"swarm\").spread(30, 30000)"),
+        ],
+    },
+    Scene {
+        caption: "demo_perspective",
+        seconds: 20.0,
+        view: View::Perspective,
+        scale: 0.5,
+        commands: &[(0.0, "body(\"ISS\").focus()")],
+    },
+];
+
+#[derive(Resource, Default)]
+pub struct Demo {
+    pub running: bool,
+    scene: Option<(usize, f32)>, // and the real time it started at
+    start: Option<Vec<Start>>,   // the bodies when the tour began
+    resetting: bool,
+}
+
+impl Demo {
+    pub fn new(running: bool) -> Self {
+        Self {
+            running,
+            ..default()
+        }
+    }
+}
+
+struct Start {
+    body: orbitabase_core::Body,
+    tank: Option<Tank>,
+    delta_v: Precision,
+}
+
+#[derive(Component)]
+pub struct DemoText;
+
+type Restored<'a> = (
+    Entity,
+    &'a mut Body,
+    Option<&'a mut ManeuverPlan>,
+    Option<&'a mut Engine>,
+);
+
+// Remembers the bodies the tour starts with, and puts them back when it starts over
+pub fn reset_demo(
+    mut commands: Commands,
+    mut demo: ResMut<Demo>,
+    mut bodies: Query<Restored>,
+    (mut scheduler, mut groups): (ResMut<Scheduler>, ResMut<Groups>),
+    (mut fleet, mut conservation): (ResMut<Fleet>, ResMut<Conservation>),
+) {
+    if !demo.running {
+        return;
+    }
+    let Some(start) = &demo.start else {
+        let start = bodies
+            .iter()
+            .map(|(_, body, _, engine)| Start {
+                body: body.0.clone(),
+                tank: engine.and_then(|engine| engine.tank),
+                delta_v: engine.map_or(0.0, |engine| engine.delta_v),
+            })
+            .collect();
+        demo.start = Some(start);
+        return;
+    };
+    if !demo.resetting {
+        return;
+    }
+
+    for (entity, mut body, plan, engine) in bodies.iter_mut() {
+        let Some(saved) = start.iter().find(|saved| saved.body.id == body.id) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        **body = saved.body.clone();
+        commands.entity(entity).remove::<Crashed>();
+        if let Some(mut plan) = plan {
+            plan.0.clear();
+        }
+        if let Some(mut engine) = engine {
+            engine.tank = saved.tank;
+            engine.delta_v = saved.delta_v;
+        }
+    }
+    scheduler.clear();
+    groups.disband("swarm");
+    fleet.clear();
+    conservation.reset = true;
+    demo.resetting = false;
+}
+
+type Camera<'w, 's, 'a> = Query<'w, 's, (&'a mut Transform, &'a mut OrthographicProjection)>;
+
+// Moves the tour on and eases the camera towards the scene's view
+pub fn run_demo(
+    (keyboard, mouse): (Res<Input<KeyCode>>, Res<Input<MouseButton>>),
+    (real_time, time): (Res<Time<Real>>, Res<SimTime>),
+    mut demo: ResMut<Demo>,
+    mut sim: Sim,
+    (mut follow, mut perspective, mut camera): (ResMut<Follow>, ResMut<Perspective>, Camera),
+    origin: Res<FloatingOrigin>,
+    mut text: Query<&mut Text, With<DemoText>>,
+) {
+    if !demo.running || demo.resetting || demo.start.is_none() {
+        return;
+    }
+    let text = &mut text.single_mut().sections[0].value;
+    let interrupted =
+        keyboard.get_just_pressed().next().is_some() || mouse.get_just_pressed().next().is_some();
+    if interrupted {
+        demo.running = false;
+        follow.enabled = false;
+        perspective.enabled = false;
+        text.clear();
+        info!("Demo over, the controls are yours");
+        return;
+    }
+
+    let now = real_time.elapsed_seconds();
+    let next = match demo.scene {
+        None => Some(0),
+        Some((scene, started)) if now - started >= SCENES[scene].seconds => {
+            Some((scene + 1) % SCENES.len())
+        }
+        Some(_) => None,
+    };
+    if let Some(next) = next {
+        // Starting over waits a frame for the bodies to be put back
+        if next == 0 && demo.scene.is_some() {
+            demo.scene = None;
+            demo.resetting = true;
+            return;
+        }
+        demo.scene = Some((next, now));
+        let scene = &SCENES[next];
+        for (delay, command) in scene.commands {
+            let command = if *delay > 0.0 {
+                format!("at({}).{}", time.0 + delay, command)
+            } else {
+                command.to_string()
+            };
+            let result = script::parse(&command).and_then(|calls| sim.execute(&calls));
+            if let Err(error) = result {
+                warn!("Demo: {}: {}", command, error);
+            }
+        }
+        follow.enabled = matches!(scene.view, View::Following);
+        perspective.enabled = matches!(scene.view, View::Perspective);
+    }
+
+    let Some((scene, _)) = demo.scene else {
+        return;
+    };
+    let scene = &SCENES[scene];
+    let language = sim.language();
+    *text = format!(
+        "{}\n{}",
+        language.text(scene.caption, &[]),
+        language.text("demo_hint", &[])
+    );
+
+    // Following and the perspective centre the view themselves
+    let (mut transform, mut projection) = camera.single_mut();
+    if matches!(scene.view, View::Whole) {
+        // The earth's centre, in the inertial frame the tour is drawn in
+        let earth = -origin.0.as_vec2();
+        let centre = transform.translation.truncate().lerp(earth, SMOOTHING);
+        transform.translation = centre.extend(transform.translation.z);
+    }
+    let scale = projection.scale.ln();
+    projection.scale = (scale + (scene.scale.ln() - scale) * SMOOTHING).exp();
+}
@@ -0,0 +1,187 @@
+// Batch least-squares orbit determination: Gauss-Newton iterations on the state at the first
+// observation, with the state transition matrix mapping each measurement back to it. The
+// inverse of the normal matrix is the covariance of the estimate. The orbit is flown like the
+// lookaheads, on the coast of the body being determined with the estimate in place of its state.
+use bevy::math::{DMat4, DVec4};
+
+use crate::observation::{Observation, ANGLE_NOISE, RANGE_NOISE};
+use crate::propagator::Coast;
+use crate::{stm, Precision, State};
+
+const MAX_ITERATIONS: usize = 20;
+const TOLERANCE: Precision = 1e-3; // m, size of the last position correction
+const MAX_GAP: Precision = 300.0; // s between the two observations of the first guess
+
+pub struct Solution {
+    pub epoch: Precision, // s, time of the first observation
+    pub state: State,
+    pub covariance: DMat4,
+    pub iterations: usize,
+    pub rms: [Precision; 2], // m and rad, of the final residuals
+}
+
+// Position from the first measurement, velocity from it and the next one close in time
+pub fn first_guess(observations: &[Observation]) -> Option<State> {
+    let pair = observations.windows(2).find(|pair| {
+        (pair[1].time - pair[0].time).abs() <= MAX_GAP && pair[1].time > pair[0].time
+    })?;
+    let (a, b) = (pair[0].position(), pair[1].position());
+    let velocity = (b - a) / (pair[1].time - pair[0].time);
+
+    // Moved back to the first observation if the pair doesn't start with it
+    let offset = pair[0].time - observations[0].time;
+    let start = a - velocity * offset;
+    Some(State::new(start.x, start.y, velocity.x, velocity.y))
+}
+
+pub fn determine(observations: &[Observation], mut coast: Coast) -> Result<Solution, String> {
+    if observations.len() < 3 {
+        return Err(format!(
+            "{} observations, at least 3 are needed",
+            observations.len()
+        ));
+    }
+    let epoch = observations[0].time;
+    let mut estimate =
+        first_guess(observations).ok_or("no two observations are close enough in time")?;
+    let weights = [RANGE_NOISE.powi(-2), ANGLE_NOISE.powi(-2)];
+
+    for iteration in 1..=MAX_ITERATIONS {
+        let mut normal = DMat4::ZERO;
+        let mut rhs = DVec4::ZERO;
+        let mut squares = [0.0; 2];
+
+        // Walk along the arc, chaining the transition matrices between observations
+        let (mut state, mut phi, mut time) = (estimate, DMat4::IDENTITY, epoch);
+        for observation in observations {
+            let steps = ((observation.time - time) / coast.dt()).round() as usize;
+            (coast.state, coast.time) = (state, time);
+            let (next, step_phi) = stm::propagate(coast, steps);
+            (state, phi, time) = (next, step_phi * phi, observation.time);
+
+            let (predicted, gradients) = Observation::predict(observation.station, &state);
+            let measured = [observation.range, observation.angle];
+            for i in 0..2 {
+                let residual = measured[i] - predicted[i];
+                let row = phi.transpose() * gradients[i];
+                normal += outer(row, row * weights[i]);
+                rhs += row * (weights[i] * residual);
+                squares[i] += residual * residual;
+            }
+        }
+
+        if normal.determinant().abs() < Precision::MIN_POSITIVE {
+            return Err("the observations don't pin down the orbit".to_string());
+        }
+        let covariance = normal.inverse();
+        let correction = covariance * rhs;
+        if !correction.is_finite() {
+            return Err("orbit determination diverged".to_string());
+        }
+        estimate = State::new(
+            estimate.x + correction.x,
+            estimate.y + correction.y,
+            estimate.vx + correction.z,
+            estimate.vy + correction.w,
+        );
+
+        let n = observations.len() as Precision;
+        if correction.truncate().truncate().length() < TOLERANCE {
+            return Ok(Solution {
+                epoch,
+                state: estimate,
+                covariance,
+                iterations: iteration,
+                rms: [(squares[0] / n).sqrt(), (squares[1] / n).sqrt()],
+            });
+        }
+    }
+
+    Err(format!(
+        "no convergence after {} iterations",
+        MAX_ITERATIONS
+    ))
+}
+
+fn outer(a: DVec4, b: DVec4) -> DMat4 {
+    DMat4::from_cols(a * b.x, a * b.y, a * b.z, a * b.w)
+}
+
+// The estimate and its position and velocity standard deviations, moved along the coast to
+// `time`
+pub fn propagate(
+    solution: &Solution,
+    time: Precision,
+    mut coast: Coast,
+) -> (State, Precision, Precision) {
+    let steps = ((time - solution.epoch) / coast.dt()).round().max(0.0) as usize;
+    (coast.state, coast.time) = (solution.state, solution.epoch);
+    let (state, phi) = stm::propagate(coast, steps);
+    let covariance = phi * solution.covariance * phi.transpose();
+    let position = (covariance.x_axis.x + covariance.y_axis.y).sqrt();
+    let velocity = (covariance.z_axis.z + covariance.w_axis.w).sqrt();
+    (state, position, velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity::Perturbations;
+    use crate::propagator::{MassivePaths, Propagator};
+    use crate::{DormandPrince, EARTH_RADIUS};
+    use bevy::math::DVec2;
+
+    // Noiseless measurements every minute from a station under the start of the arc
+    fn observations(coast: Coast, count: usize) -> Vec<Observation> {
+        let station = DVec2::new(EARTH_RADIUS, 0.0);
+        let mut coast = coast;
+        (0..count)
+            .map(|i| {
+                if i > 0 {
+                    for _ in 0..6 {
+                        coast.step();
+                    }
+                }
+                let ([range, angle], _) = Observation::predict(station, &coast.state);
+                Observation {
+                    time: coast.time,
+                    station,
+                    range,
+                    angle,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recovers_the_state_at_the_first_observation() {
+        let paths = MassivePaths::default();
+        let perturbations = Perturbations { j2: true };
+        let integrator = DormandPrince::default();
+        let propagator = Propagator::new(&integrator, 10.0, &perturbations, None, &paths);
+        let truth = State::new(EARTH_RADIUS + 500e3, -300e3, 400.0, 7600.0);
+        let observations = observations(propagator.coast_from(truth, 0.0), 8);
+
+        let solution = determine(&observations, propagator.coast_from(truth, 0.0)).unwrap();
+        assert_eq!(solution.epoch, 0.0);
+        assert!((solution.state.x - truth.x).hypot(solution.state.y - truth.y) < 0.01);
+        assert!((solution.state.vx - truth.vx).hypot(solution.state.vy - truth.vy) < 1e-4);
+        assert!(solution.rms[0] < 1e-3 && solution.rms[1] < 1e-9);
+
+        // The uncertainty grows away from the arc
+        let (_, near, _) = propagate(&solution, 60.0, propagator.coast_from(truth, 0.0));
+        let (_, far, _) = propagate(&solution, 3000.0, propagator.coast_from(truth, 0.0));
+        assert!(far > near);
+    }
+
+    #[test]
+    fn needs_three_observations() {
+        let paths = MassivePaths::default();
+        let perturbations = Perturbations { j2: true };
+        let integrator = DormandPrince::default();
+        let propagator = Propagator::new(&integrator, 10.0, &perturbations, None, &paths);
+        let truth = State::new(EARTH_RADIUS + 500e3, 0.0, 0.0, 7600.0);
+        let observations = observations(propagator.coast_from(truth, 0.0), 2);
+        assert!(determine(&observations, propagator.coast_from(truth, 0.0)).is_err());
+    }
+}
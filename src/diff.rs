@@ -0,0 +1,353 @@
+// Scenario diffs: `--diff <a> <b>` compares two scenario files, two quicksaves or one of each,
+// and prints what changed from the first to the second, so an edit to a scenario can be
+// reviewed before it's run. Bodies are matched by name, or by their place in the file (a
+// scenario) or their id (a quicksave) when they have none. Each body added or removed is
+// listed with its orbit, and each one in both with how its mass, drag and extras changed and
+// how far its state moved, with the change in its osculating elements. Quicksaves add their
+// time, epoch, step and scheduled commands. Like diff(1) it exits with 0 when the two are the
+// same, 1 when they differ and 2 when one can't be read.
+use crate::elements::OrbitalElements;
+use crate::ephemeris::Epoch;
+use crate::quicksave;
+use crate::scenario::Scenario;
+use crate::{Precision, State, EARTH_RADIUS};
+
+const POSITION_TOLERANCE: Precision = 1e-3; // m
+const VELOCITY_TOLERANCE: Precision = 1e-6; // m/s
+
+// A body as either kind of file has it
+struct Entry {
+    key: String, // what it is matched and shown by
+    mass: Precision,
+    state: State,
+    ballistic_coefficient: Option<Precision>,
+    extras: Vec<(&'static str, String)>, // compared as they read
+}
+
+struct Snapshot {
+    settings: Vec<(&'static str, String)>,
+    bodies: Vec<Entry>,
+}
+
+impl Snapshot {
+    // Quicksaves are JSON, everything else is taken as a RON scenario
+    fn load(path: &str) -> Result<Self, String> {
+        if path.ends_with(".json") {
+            let save = quicksave::load(path).map_err(|error| format!("{}: {}", path, error))?;
+            Ok(Self::of_save(save))
+        } else {
+            Ok(Self::of_scenario(Scenario::load(path)?))
+        }
+    }
+
+    fn of_scenario(scenario: Scenario) -> Self {
+        let bodies = scenario
+            .bodies
+            .iter()
+            .enumerate()
+            .map(|(i, spec)| {
+                let body = spec.body(i + 1);
+                Entry {
+                    key: key(spec.name.as_deref(), i + 1),
+                    mass: body.mass,
+                    state: body.current_state,
+                    ballistic_coefficient: body.ballistic_coefficient,
+                    extras: spec
+                        .color
+                        .iter()
+                        .map(|color| ("color", color.clone()))
+                        .collect(),
+                }
+            })
+            .collect();
+        Self {
+            settings: Vec::new(),
+            bodies,
+        }
+    }
+
+    fn of_save(save: quicksave::Save) -> Self {
+        let epoch = Epoch(save.epoch);
+        let mut settings = vec![
+            (
+                "time",
+                format!("{} s ({})", save.time, epoch.timestamp(save.time)),
+            ),
+            ("epoch", epoch.timestamp(0.0)),
+            ("dt", format!("{} s", save.dt)),
+        ];
+        settings.extend(
+            save.scheduled
+                .iter()
+                .map(|(time, calls)| ("scheduled", format!("at {} s: {}", time, calls))),
+        );
+
+        let bodies = save
+            .bodies
+            .iter()
+            .map(|saved| {
+                let mut extras = Vec::new();
+                if let Some(tank) = saved.tank {
+                    extras.push(("propellant", format!("{:.1} kg", tank.propellant)));
+                }
+                if !saved.plan.is_empty() {
+                    extras.push(("planned burns", saved.plan.len().to_string()));
+                }
+                if let Some(fidelity) = saved.fidelity {
+                    extras.push(("fidelity", fidelity.name().to_string()));
+                }
+                if saved.on_rails {
+                    extras.push(("rails", "on".to_string()));
+                }
+                if let Some(tether) = &saved.tether {
+                    extras.push(("tether", format!("to body {}", tether.anchor)));
+                }
+                if let Some(crashed) = saved.crashed {
+                    extras.push(("crashed", format!("at {} s", crashed.time)));
+                }
+                Entry {
+                    key: key(saved.name.as_deref(), saved.body.id),
+                    mass: saved.body.mass,
+                    state: saved.body.current_state,
+                    ballistic_coefficient: saved.body.ballistic_coefficient,
+                    extras,
+                }
+            })
+            .collect();
+        Self { settings, bodies }
+    }
+}
+
+fn key(name: Option<&str>, number: usize) -> String {
+    match name {
+        Some(name) => format!("\"{}\"", name),
+        None => format!("body {}", number),
+    }
+}
+
+fn describe_orbit(state: &State) -> String {
+    let elements = OrbitalElements::from_state(state);
+    let periapsis = elements.periapsis_altitude() / 1000.0;
+    match elements.apoapsis_altitude() {
+        Some(apoapsis) => format!("{:.1} × {:.1} km", periapsis, apoapsis / 1000.0),
+        None => format!("escaping, periapsis {:.1} km", periapsis),
+    }
+}
+
+fn describe(entry: &Entry) -> String {
+    let r = entry.state.x.hypot(entry.state.y);
+    if r <= EARTH_RADIUS {
+        return format!("{} kg, under the surface", entry.mass);
+    }
+    format!("{} kg, {}", entry.mass, describe_orbit(&entry.state))
+}
+
+fn optional(value: Option<Precision>) -> String {
+    value.map_or("none".to_string(), |value| value.to_string())
+}
+
+// How a body in both changed, nothing when it didn't
+fn compare(a: &Entry, b: &Entry) -> Vec<String> {
+    let mut changes = Vec::new();
+    if a.mass != b.mass {
+        changes.push(format!("mass {} → {} kg", a.mass, b.mass));
+    }
+    if a.ballistic_coefficient != b.ballistic_coefficient {
+        changes.push(format!(
+            "ballistic coefficient {} → {} kg/m²",
+            optional(a.ballistic_coefficient),
+            optional(b.ballistic_coefficient)
+        ));
+    }
+
+    let (sa, sb) = (a.state, b.state);
+    let moved = (sb.x - sa.x).hypot(sb.y - sa.y);
+    let dv = (sb.vx - sa.vx).hypot(sb.vy - sa.vy);
+    if moved > POSITION_TOLERANCE || dv > VELOCITY_TOLERANCE {
+        let (ea, eb) = (
+            OrbitalElements::from_state(&sa),
+            OrbitalElements::from_state(&sb),
+        );
+        let turn = (eb.argument_of_periapsis - ea.argument_of_periapsis).to_degrees();
+        changes.push(format!(
+            "state moved {:.3} km and {:.3} m/s; a {:+.3} km, e {:+.6}, ω {:+.3}°, now {}",
+            moved / 1000.0,
+            dv,
+            (eb.a - ea.a) / 1000.0,
+            eb.e - ea.e,
+            (turn + 180.0).rem_euclid(360.0) - 180.0,
+            describe_orbit(&sb)
+        ));
+    }
+
+    let value = |extras: &[(&str, String)], label: &str| {
+        extras
+            .iter()
+            .find(|(name, _)| *name == label)
+            .map(|(_, value)| value.clone())
+    };
+    let mut labels: Vec<&str> = a.extras.iter().chain(&b.extras).map(|(l, _)| *l).collect();
+    labels.sort();
+    labels.dedup();
+    for label in labels {
+        let (before, after) = (value(&a.extras, label), value(&b.extras, label));
+        if before != after {
+            let none = || "none".to_string();
+            changes.push(format!(
+                "{} {} → {}",
+                label,
+                before.unwrap_or_else(none),
+                after.unwrap_or_else(none)
+            ));
+        }
+    }
+    changes
+}
+
+// How many bodies the diff adds, removes and changes
+#[derive(Default, Debug, PartialEq)]
+struct Counts {
+    added: usize,
+    removed: usize,
+    changed: usize,
+}
+
+// Each setting of `from` that `to` has fewer of, as many times as it's short
+fn missing<'a>(
+    from: &'a [(&'static str, String)],
+    to: &[(&'static str, String)],
+) -> Vec<&'a (&'static str, String)> {
+    let mut left: Vec<_> = to.iter().collect();
+    let mut missing = Vec::new();
+    for setting in from {
+        match left.iter().position(|other| *other == setting) {
+            Some(i) => {
+                left.swap_remove(i);
+            }
+            None => missing.push(setting),
+        }
+    }
+    missing
+}
+
+// The lines of the diff, empty when the two are the same
+fn lines(a: &Snapshot, b: &Snapshot) -> (Vec<String>, Counts) {
+    let mut lines = Vec::new();
+    let mut counts = Counts::default();
+
+    // Settings are compared as multisets, so a command scheduled twice in one and once in the
+    // other shows up once as removed
+    for (label, value) in missing(&a.settings, &b.settings) {
+        lines.push(format!("- {}: {}", label, value));
+    }
+    for (label, value) in missing(&b.settings, &a.settings) {
+        lines.push(format!("+ {}: {}", label, value));
+    }
+
+    let find = |snapshot: &'_ Snapshot, key: &str| {
+        snapshot.bodies.iter().position(|entry| entry.key == key)
+    };
+    for entry in &a.bodies {
+        match find(b, &entry.key) {
+            None => {
+                lines.push(format!("- {} removed: {}", entry.key, describe(entry)));
+                counts.removed += 1;
+            }
+            Some(i) => {
+                let changes = compare(entry, &b.bodies[i]);
+                if !changes.is_empty() {
+                    lines.push(format!("~ {}: {}", entry.key, changes.join("; ")));
+                    counts.changed += 1;
+                }
+            }
+        }
+    }
+    for entry in &b.bodies {
+        if find(a, &entry.key).is_none() {
+            lines.push(format!("+ {} added: {}", entry.key, describe(entry)));
+            counts.added += 1;
+        }
+    }
+    (lines, counts)
+}
+
+pub fn run(a: &str, b: &str) -> i32 {
+    let (before, after) = match (Snapshot::load(a), Snapshot::load(b)) {
+        (Ok(before), Ok(after)) => (before, after),
+        (Err(error), _) | (_, Err(error)) => {
+            eprintln!("--diff: {}", error);
+            return 2;
+        }
+    };
+
+    let (lines, counts) = lines(&before, &after);
+    println!("--- {}\n+++ {}", a, b);
+    for line in &lines {
+        println!("{}", line);
+    }
+    println!(
+        "bodies: {} added, {} removed, {} changed",
+        counts.added, counts.removed, counts.changed
+    );
+    i32::from(!lines.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(bodies: &str) -> Snapshot {
+        Snapshot::of_scenario(Scenario::parse(&format!("(bodies: [{}])", bodies)).unwrap())
+    }
+
+    const ISS: &str = r#"(name: "ISS", mass: 420000, position: (6779000, 0), velocity: (0, 7668))"#;
+    const PROBE: &str = "(mass: 1, position: (0, 7000000), velocity: (-7546, 0))";
+
+    #[test]
+    fn the_same_file_has_no_lines() {
+        let bodies = format!("{}, {}", ISS, PROBE);
+        let (lines, counts) = lines(&snapshot(&bodies), &snapshot(&bodies));
+        assert!(lines.is_empty());
+        assert_eq!(counts, Counts::default());
+    }
+
+    #[test]
+    fn bodies_are_matched_by_name_then_place() {
+        let moved = ISS.replace("420000", "400000").replace("7668", "7670");
+        let (lines, counts) = lines(&snapshot(&format!("{}, {}", ISS, PROBE)), &snapshot(&moved));
+        assert_eq!(lines.len(), 2, "{:?}", lines);
+        assert!(lines[0].starts_with("~ \"ISS\": mass 420000 → 400000 kg; state moved 0.000 km"));
+        assert!(
+            lines[1].starts_with("- body 2 removed: 1 kg, "),
+            "{}",
+            lines[1]
+        );
+        let expected = Counts {
+            added: 0,
+            removed: 1,
+            changed: 1,
+        };
+        assert_eq!(counts, expected);
+    }
+
+    #[test]
+    fn bodies_without_changes_are_left_out() {
+        let before = snapshot(&format!("{}, {}", PROBE, ISS));
+        let after = snapshot(&format!("{}, {}, {}", PROBE, ISS, PROBE));
+        let (lines, counts) = lines(&before, &after);
+        assert_eq!(lines, ["+ body 3 added: 1 kg, 629.0 × 629.2 km"]);
+        assert_eq!(counts.added, 1);
+    }
+
+    #[test]
+    fn repeated_settings_are_counted() {
+        let settings = |times: usize| Snapshot {
+            settings: vec![("scheduled", "at 60 s: set_warp(10)".to_string()); times],
+            bodies: Vec::new(),
+        };
+        let (lines, counts) = lines(&settings(2), &settings(1));
+        assert_eq!(lines, ["- scheduled: at 60 s: set_warp(10)"]);
+        assert_eq!(counts, Counts::default());
+        assert_eq!(super::lines(&settings(1), &settings(3)).0.len(), 2);
+    }
+}
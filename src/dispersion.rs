@@ -0,0 +1,137 @@
+// Burn execution errors: a body with them misses every planned burn's Δv by a random fraction
+// of its size and a random angle off its direction. The orbit view draws the fan of
+// trajectories these errors can give after the next planned burn, each Monte Carlo sample
+// carrying out the whole plan with errors of its own, so the spread shows how a sloppy burn
+// grows downstream and what later burns do about it.
+use std::collections::HashMap;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::crash::Crashed;
+use crate::interpolation::Interpolation;
+use crate::maneuver::ManeuverPlan;
+use crate::observation::gaussian;
+use crate::palette::Palette;
+use crate::propagator::Coasting;
+use crate::{Body, Lookahead, Precision, SimTime, EARTH_RADIUS};
+
+const REFRESH_INTERVAL: Precision = 60.0; // s
+const SAMPLES: usize = 20;
+const STRIDE: usize = 10; // steps between the points drawn
+const MAX_LEAD: Precision = 86400.0; // s, burns further ahead get no fan
+
+#[derive(Component, Clone, Copy)]
+pub struct BurnErrors {
+    pub magnitude: Precision, // fraction of the Δv, standard deviation
+    pub direction: Precision, // rad, standard deviation
+}
+
+impl Default for BurnErrors {
+    fn default() -> Self {
+        Self {
+            magnitude: 0.01,
+            direction: 1f64.to_radians(),
+        }
+    }
+}
+
+impl BurnErrors {
+    // An inertial Δv as the engine might actually deliver it
+    pub fn perturb(
+        &self,
+        (dvx, dvy): (Precision, Precision),
+        rng: &mut impl Rng,
+    ) -> (Precision, Precision) {
+        let scale = 1.0 + gaussian(rng, self.magnitude);
+        let (sin, cos) = gaussian(rng, self.direction).sin_cos();
+        (
+            scale * (dvx * cos - dvy * sin),
+            scale * (dvx * sin + dvy * cos),
+        )
+    }
+}
+
+type Replanned = Or<(Changed<ManeuverPlan>, Changed<BurnErrors>)>;
+
+// Points along each sampled trajectory, from the next burn on
+#[derive(Resource, Default)]
+pub struct Fans {
+    fans: HashMap<Entity, Vec<Vec<DVec2>>>,
+    computed_at: Option<Precision>,
+}
+
+pub fn predict_fans(
+    (time, lookahead): (Res<SimTime>, Res<Lookahead>),
+    mut fans: ResMut<Fans>,
+    coasting: Coasting,
+    bodies: Query<(Entity, &Body, &ManeuverPlan, &BurnErrors), Without<Crashed>>,
+    replanned: Query<(), Replanned>,
+) {
+    let stale = fans
+        .computed_at
+        .is_none_or(|computed_at| time.0 - computed_at >= REFRESH_INTERVAL);
+    // Plans and errors changed from scripts get a fresh fan straight away
+    if !stale && replanned.is_empty() {
+        return;
+    }
+    fans.computed_at = Some(time.0);
+    fans.fans.clear();
+
+    let mut rng = rand::thread_rng();
+    let propagator = coasting.propagator();
+
+    for (entity, body, plan, errors) in bodies.iter() {
+        let Some(first) = plan.0.first() else {
+            continue;
+        };
+        if first.time - time.0 > MAX_LEAD {
+            continue;
+        }
+        let lead = ((first.time - time.0) / propagator.dt).ceil().max(0.0) as usize;
+
+        let samples = (0..SAMPLES)
+            .map(|_| {
+                let mut coast = propagator.coast(body, time.0);
+                let mut nodes = plan.0.iter().peekable();
+                let mut points = Vec::with_capacity(lookahead.0 / STRIDE + 1);
+                for i in 0..lead + lookahead.0 {
+                    while let Some(node) = nodes.next_if(|node| node.time <= coast.time) {
+                        let dv = errors.perturb(node.inertial_dv(&coast.state), &mut rng);
+                        coast.state.vx += dv.0;
+                        coast.state.vy += dv.1;
+                    }
+                    let state = coast.state;
+                    if state.x.hypot(state.y) < EARTH_RADIUS {
+                        break;
+                    }
+                    if i >= lead && (i - lead).is_multiple_of(STRIDE) {
+                        points.push(DVec2::new(state.x, state.y));
+                    }
+                    coast.step();
+                }
+                points
+            })
+            .collect();
+        fans.fans.insert(entity, samples);
+    }
+}
+
+pub fn draw_fans(
+    mut gizmos: Gizmos,
+    fans: Res<Fans>,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+) {
+    let color = palette.colors().prediction.with_a(0.3);
+
+    for samples in fans.fans.values() {
+        for points in samples {
+            let points = points
+                .iter()
+                .map(|point| interpolation.point(point.x, point.y));
+            gizmos.linestrip_2d(points, color);
+        }
+    }
+}
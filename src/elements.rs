@@ -0,0 +1,235 @@
+// Osculating orbital elements of a planar orbit around the earth, and the panel that shows
+// the selected body's as it flies
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::engine::Engine;
+use crate::hud::format_duration;
+use crate::i18n::Language;
+use crate::{Body, Precision, Selected, State, EARTH_RADIUS, G, MASS_EARTH};
+
+pub const MU: Precision = G * MASS_EARTH;
+
+// Angles are measured from the +x axis in the direction of motion, so retrograde
+// (clockwise) orbits get the same values as their mirror image.
+#[derive(Copy, Clone, Debug)]
+pub struct OrbitalElements {
+    pub a: Precision, // semi-major axis, m (negative when hyperbolic)
+    pub e: Precision,
+    pub argument_of_periapsis: Precision, // rad
+    pub mean_anomaly: Precision,          // rad, only meaningful when e < 1
+}
+
+impl OrbitalElements {
+    pub fn from_state(state: &State) -> Self {
+        // Mirror clockwise orbits so that angles grow along the motion
+        let sign = if state.x * state.vy - state.y * state.vx < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let (x, y, vx, vy) = (state.x, sign * state.y, state.vx, sign * state.vy);
+
+        let r = (x * x + y * y).sqrt();
+        let v2 = vx * vx + vy * vy;
+        let rv = x * vx + y * vy;
+
+        let a = 1.0 / (2.0 / r - v2 / MU);
+
+        let ex = ((v2 - MU / r) * x - rv * vx) / MU;
+        let ey = ((v2 - MU / r) * y - rv * vy) / MU;
+        let e = (ex * ex + ey * ey).sqrt();
+
+        let argument_of_periapsis = ey.atan2(ex).rem_euclid(TAU);
+        let true_anomaly = (y.atan2(x) - argument_of_periapsis).rem_euclid(TAU);
+
+        let eccentric_anomaly =
+            2.0 * (((1.0 - e) / (1.0 + e)).sqrt() * (true_anomaly / 2.0).tan()).atan();
+        let mean_anomaly = (eccentric_anomaly - e * eccentric_anomaly.sin()).rem_euclid(TAU);
+
+        Self {
+            a,
+            e,
+            argument_of_periapsis,
+            mean_anomaly,
+        }
+    }
+
+    // Kepler's equation by Newton's method, for elliptic orbits
+    fn eccentric_anomaly(&self) -> Precision {
+        let e = self.e;
+        let mut eccentric_anomaly = self.mean_anomaly;
+        for _ in 0..50 {
+            let step = (eccentric_anomaly - e * eccentric_anomaly.sin() - self.mean_anomaly)
+                / (1.0 - e * eccentric_anomaly.cos());
+            eccentric_anomaly -= step;
+            if step.abs() < 1e-12 {
+                break;
+            }
+        }
+        eccentric_anomaly
+    }
+
+    // rad from the periapsis, for elliptic orbits
+    pub fn true_anomaly(&self) -> Precision {
+        let (e, eccentric_anomaly) = (self.e, self.eccentric_anomaly());
+        2.0 * ((1.0 + e).sqrt() * (eccentric_anomaly / 2.0).sin())
+            .atan2((1.0 - e).sqrt() * (eccentric_anomaly / 2.0).cos())
+    }
+
+    // Above the earth's surface, m
+    pub fn periapsis_altitude(&self) -> Precision {
+        self.a * (1.0 - self.e) - EARTH_RADIUS
+    }
+
+    // None when the orbit isn't closed
+    pub fn apoapsis_altitude(&self) -> Option<Precision> {
+        (self.e < 1.0).then_some(self.a * (1.0 + self.e) - EARTH_RADIUS)
+    }
+
+    pub fn period(&self) -> Option<Precision> {
+        (self.e < 1.0).then(|| TAU * (self.a.powi(3) / MU).sqrt())
+    }
+
+    // Inverse of from_state for elliptic orbits, mirrored back when the orbit is clockwise
+    pub fn to_state(self, clockwise: bool) -> State {
+        let (a, e) = (self.a, self.e);
+        let eccentric_anomaly = self.eccentric_anomaly();
+        let true_anomaly = self.true_anomaly();
+        let r = a * (1.0 - e * eccentric_anomaly.cos());
+        let h = (MU * a * (1.0 - e * e)).sqrt();
+        let angle = self.argument_of_periapsis + true_anomaly;
+
+        let radial = MU / h * e * true_anomaly.sin();
+        let transverse = h / r;
+        let (sin, cos) = angle.sin_cos();
+        let sign = if clockwise { -1.0 } else { 1.0 };
+
+        State::new(
+            r * cos,
+            sign * r * sin,
+            radial * cos - transverse * sin,
+            sign * (radial * sin + transverse * cos),
+        )
+    }
+
+    pub fn mean_longitude(&self) -> Precision {
+        (self.argument_of_periapsis + self.mean_anomaly).rem_euclid(TAU)
+    }
+}
+
+#[derive(Component)]
+pub struct ElementsText;
+
+type Shown = (
+    &'static Body,
+    Option<&'static Crashed>,
+    Option<&'static Engine>,
+);
+
+pub fn show_elements(
+    language: Res<Language>,
+    selected: Query<Shown, With<Selected>>,
+    mut text: Query<&mut Text, With<ElementsText>>,
+) {
+    let value = match selected.get_single() {
+        Ok((body, Some(crashed), _)) => language.text(
+            "elements_crashed",
+            &[
+                &body.id,
+                &format_duration(crashed.time),
+                &format!("{:.0}", crashed.speed),
+            ],
+        ),
+        Ok((body, None, engine)) => {
+            let elements = OrbitalElements::from_state(&body.current_state);
+            let km = |metres: Precision| format!("{:.1}", metres / 1000.0);
+            let mut value = language.text(
+                "elements",
+                &[
+                    &body.id,
+                    &km(elements.a),
+                    &format!("{:.4}", elements.e),
+                    &km(elements.periapsis_altitude()),
+                ],
+            );
+            value.push('\n');
+            value.push_str(&match (elements.apoapsis_altitude(), elements.period()) {
+                (Some(apoapsis), Some(period)) => language.text(
+                    "elements_closed",
+                    &[
+                        &km(apoapsis),
+                        &format_duration(period),
+                        &format!(
+                            "{:.1}",
+                            elements.true_anomaly().rem_euclid(TAU).to_degrees()
+                        ),
+                    ],
+                ),
+                _ => language.text("elements_escaping", &[]),
+            });
+            if let Some(tank) = engine.and_then(|engine| engine.tank) {
+                value.push('\n');
+                value.push_str(&if tank.propellant > 0.0 {
+                    language.text(
+                        "elements_propellant",
+                        &[
+                            &format!("{:.0}", tank.delta_v()),
+                            &format!("{:.1}", tank.propellant),
+                        ],
+                    )
+                } else {
+                    language.text("elements_tank_empty", &[])
+                });
+            }
+            value
+        }
+        Err(_) => String::new(),
+    };
+    text.single_mut().sections[0].value = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &State, b: &State) {
+        let position = (a.x - b.x).hypot(a.y - b.y);
+        let velocity = (a.vx - b.vx).hypot(a.vy - b.vy);
+        assert!(
+            position < 1e-3 && velocity < 1e-6,
+            "{} m and {} m/s off",
+            position,
+            velocity
+        );
+    }
+
+    #[test]
+    fn elements_give_back_the_state() {
+        let r = EARTH_RADIUS + 400e3;
+        let v = (MU / r).sqrt();
+        for (vx, vy) in [
+            (0.0, v),
+            (300.0, 1.2 * v),
+            (-500.0, 0.8 * v),
+            (250.0, -0.9 * v),
+        ] {
+            let state = State::new(0.6 * r, 0.8 * r, vx, vy);
+            let elements = OrbitalElements::from_state(&state);
+            let clockwise = state.x * state.vy - state.y * state.vx < 0.0;
+            assert!(elements.e < 1.0);
+            assert_close(&elements.to_state(clockwise), &state);
+        }
+    }
+
+    #[test]
+    fn circular_orbit_elements() {
+        let r = EARTH_RADIUS + 400e3;
+        let elements = OrbitalElements::from_state(&State::new(r, 0.0, 0.0, (MU / r).sqrt()));
+        assert!((elements.a / r - 1.0).abs() < 1e-12);
+        assert!(elements.e < 1e-12);
+        assert!((elements.periapsis_altitude() - 400e3).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,223 @@
+// Engines, their propellant and their failure modes. An engine with a tank pushes with a fixed
+// force, so it accelerates harder as the propellant burns off, and stops when the tank runs
+// dry; one without a tank gives the --thrust acceleration forever.
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::autopilot::ThrustCommand;
+use crate::i18n::Language;
+use crate::quicksave::Loaded;
+use crate::{Body, Precision, Selected, SimTime, Thrust, TimeStep};
+
+// Chance per step that a healthy engine develops a random fault
+const FAULT_PROBABILITY: f64 = 1e-5;
+// Standard gravity, which specific impulses are given against
+const G0: Precision = 9.80665; // m/s²
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum EngineFault {
+    #[default]
+    Nominal,
+    StuckOff,
+    Reduced(Precision),    // fraction of nominal thrust still available
+    Misaligned(Precision), // thrust axis offset from the velocity, in radians
+}
+
+impl EngineFault {
+    // What's wrong with the engine, if anything
+    pub fn describe(self, language: Language) -> Option<String> {
+        match self {
+            EngineFault::Nominal => None,
+            EngineFault::StuckOff => Some(language.text("engine_stuck", &[])),
+            EngineFault::Reduced(fraction) => {
+                Some(language.text("engine_reduced", &[&format!("{:.0}", 100.0 * fraction)]))
+            }
+            EngineFault::Misaligned(angle) => Some(language.text(
+                "engine_misaligned",
+                &[&format!("{:.1}", angle.to_degrees())],
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Tank {
+    pub dry_mass: Precision,   // kg
+    pub propellant: Precision, // kg
+    pub isp: Precision,        // specific impulse, s
+    pub thrust: Precision,     // N at full throttle
+}
+
+impl Tank {
+    pub fn mass(&self) -> Precision {
+        self.dry_mass + self.propellant
+    }
+
+    // What the propellant left can still give, by the rocket equation
+    pub fn delta_v(&self) -> Precision {
+        self.isp * G0 * (self.mass() / self.dry_mass).ln()
+    }
+
+    // Burns what an acceleration held for `duration` seconds takes at the current mass
+    fn burn(&mut self, acceleration: Precision, duration: Precision) {
+        let burnt = self.mass() * acceleration.abs() * duration / (self.isp * G0);
+        self.propellant = (self.propellant - burnt).max(0.0);
+    }
+}
+
+// Bodies without an engine can't thrust at all
+#[derive(Component, Default)]
+pub struct Engine {
+    pub fault: EngineFault,
+    pub tank: Option<Tank>,
+    pub firing: bool,       // whether it delivered any thrust in the last step
+    pub delivered: Thrust,  // what it delivered in the last step
+    pub delta_v: Precision, // total delivered, m/s
+}
+
+impl Engine {
+    // The acceleration at full throttle, for the current mass, `tankless` without a tank
+    pub fn full_thrust(&self, tankless: Precision) -> Precision {
+        match self.tank {
+            None => tankless,
+            Some(tank) if tank.propellant <= 0.0 => 0.0,
+            Some(tank) => tank.thrust / tank.mass(),
+        }
+    }
+
+    // What the engine actually delivers for a throttle and direction, from the arrow keys or
+    // an autopilot
+    pub fn command(&self, command: ThrustCommand, tankless: Precision) -> Thrust {
+        let acceleration = command.throttle.clamp(-1.0, 1.0) * self.full_thrust(tankless);
+
+        match self.fault {
+            EngineFault::Nominal => Thrust {
+                acceleration,
+                misalignment: command.angle,
+            },
+            EngineFault::StuckOff => Thrust::default(),
+            EngineFault::Reduced(fraction) => Thrust {
+                acceleration: fraction * acceleration,
+                misalignment: command.angle,
+            },
+            EngineFault::Misaligned(angle) => Thrust {
+                acceleration,
+                misalignment: command.angle + angle,
+            },
+        }
+    }
+
+    // For autopilots, whose throttle is a fraction of the --thrust acceleration `thrust` so
+    // their burns come out the same whatever the engine, up to what it can give
+    pub fn guided(&self, command: ThrustCommand, thrust: Precision) -> Thrust {
+        let full = self.full_thrust(thrust);
+        let acceleration = (command.throttle * thrust).clamp(-full, full);
+        let command = ThrustCommand {
+            throttle: if full > 0.0 { acceleration / full } else { 0.0 },
+            ..command
+        };
+        self.command(command, thrust)
+    }
+
+    // What it delivered over the last `steps` steps of `dt`, which leaves it as it was without
+    // any
+    pub fn record(&mut self, thrust: Thrust, steps: u32, dt: Precision) {
+        if steps == 0 {
+            return;
+        }
+        self.firing = thrust.acceleration != 0.0;
+        self.delivered = thrust;
+        self.delta_v += thrust.acceleration.abs() * dt * steps as Precision;
+        if let Some(tank) = &mut self.tank {
+            tank.burn(thrust.acceleration, dt * steps as Precision);
+        }
+    }
+
+    pub fn set_fault(&mut self, fault: EngineFault, body: &Body, time: Precision) {
+        self.fault = fault;
+        info!(
+            "t = {:.0} s: engine of body {} is now {:?}",
+            time, body.id, fault
+        );
+    }
+}
+
+fn random_fault(rng: &mut impl Rng) -> EngineFault {
+    match rng.gen_range(0..3) {
+        0 => EngineFault::StuckOff,
+        1 => EngineFault::Reduced(rng.gen_range(0.1..0.9)),
+        _ => EngineFault::Misaligned(rng.gen_range(-0.5..0.5)),
+    }
+}
+
+// Whether engines, transmitters and batteries fail at random, on unless `set_faults("off")`
+// turns it off, and the dice they roll. A seed makes the failures the same on every run.
+#[derive(Resource)]
+pub struct RandomFaults {
+    pub enabled: bool,
+    pub rng: StdRng,
+}
+
+impl RandomFaults {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            enabled: true,
+            rng: seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64),
+        }
+    }
+}
+
+impl Default for RandomFaults {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+// Healthy engines occasionally break on their own, with the chance of every step run since the
+// last tick
+pub fn random_faults(
+    (time, step): (Res<SimTime>, Res<TimeStep>),
+    mut faults: ResMut<RandomFaults>,
+    (mut last, mut loaded): (Local<Option<Precision>>, EventReader<Loaded>),
+    mut query: Query<(&Body, &mut Engine)>,
+) {
+    if loaded.read().count() > 0 {
+        *last = None;
+    }
+    let steps = (time.0 - last.replace(time.0).unwrap_or(time.0)) / step.0;
+    if !faults.enabled || steps <= 0.0 {
+        return;
+    }
+    let chance = 1.0 - (1.0 - FAULT_PROBABILITY).powf(steps);
+    let rng = &mut faults.rng;
+
+    for (body, mut engine) in query.iter_mut() {
+        if engine.fault == EngineFault::Nominal && rng.gen_bool(chance) {
+            let fault = random_fault(rng);
+            engine.set_fault(fault, body, time.0);
+        }
+    }
+}
+
+// F cycles the selected body's engine through the failure modes
+pub fn cycle_fault(
+    keyboard: Res<Input<KeyCode>>,
+    time: Res<SimTime>,
+    mut query: Query<(&Body, &mut Engine), With<Selected>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F) {
+        return;
+    }
+
+    for (body, mut engine) in query.iter_mut() {
+        let fault = match engine.fault {
+            EngineFault::Nominal => EngineFault::StuckOff,
+            EngineFault::StuckOff => EngineFault::Reduced(0.5),
+            EngineFault::Reduced(_) => EngineFault::Misaligned(10f64.to_radians()),
+            EngineFault::Misaligned(_) => EngineFault::Nominal,
+        };
+        engine.set_fault(fault, body, time.0);
+    }
+}
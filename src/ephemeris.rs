@@ -0,0 +1,332 @@
+// Low-precision analytic Sun and Moon positions for the simulation epoch. The simulation
+// plane is the earth's equator with +x towards the vernal equinox, so positions are given
+// in equatorial coordinates and projected onto it. Once the Moon flies as a body, it is
+// where the body is instead.
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::governor::Governor;
+use crate::gravity::{GravitySource, MOON};
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::timescales::{self, TT_MINUS_TAI};
+use crate::{Body, Precision, SimTime, SimulationSpeed, State, EARTH_RADIUS};
+
+const AU: Precision = 1.495978707e11; // m
+const J2000: Precision = 2451545.0; // Julian date
+const OBLIQUITY: Precision = 23.43929111; // deg
+const MOON_RADIUS: Precision = 1.7374e6; // m
+
+// UTC Julian date at t = 0. Sim times are SI seconds from it, so the UTC date of a sim time
+// counts any leap seconds in between.
+#[derive(Resource)]
+pub struct Epoch(pub Precision);
+
+impl Default for Epoch {
+    fn default() -> Self {
+        // 2024-01-01 00:00 UTC
+        Self(2460310.5)
+    }
+}
+
+impl Epoch {
+    pub fn tai(&self, time: Precision) -> Precision {
+        timescales::tai_from_utc(self.0) + time / 86400.0
+    }
+
+    pub fn terrestrial_time(&self, time: Precision) -> Precision {
+        self.tai(time) + TT_MINUS_TAI / 86400.0
+    }
+
+    // UTC
+    pub fn julian_date(&self, time: Precision) -> Precision {
+        timescales::utc_from_tai(self.tai(time)).0
+    }
+
+    // ISO 8601 UTC timestamp of a sim time, to the nearest second, reading 23:59:60 through a
+    // leap second
+    pub fn timestamp(&self, time: Precision) -> String {
+        self.timestamp_to(time, 0)
+    }
+
+    // The same to `decimals` places of a second
+    pub fn timestamp_to(&self, time: Precision, decimals: usize) -> String {
+        let scale = 10u64.pow(decimals as u32);
+        let tai = self.tai(time + 0.5 / scale as Precision);
+        let (utc, leap) = timescales::utc_from_tai(tai);
+        // Half a second back into the day it ends, then shown as 23:59:59 plus one and however
+        // far into the leap second it is
+        let (utc, extra) = if leap {
+            (utc - 0.5 / 86400.0, 1)
+        } else {
+            (utc, 0)
+        };
+        let (year, month, day, seconds) = calendar_date(utc);
+        let seconds = if leap {
+            86400.0 - (timescales::tai_from_utc(utc + 0.5 / 86400.0) - tai) * 86400.0
+        } else {
+            seconds
+        };
+        let ticks = (seconds * scale as Precision).floor() as u64;
+        let (seconds, fraction) = (ticks / scale, ticks % scale);
+
+        let fraction = match decimals {
+            0 => String::new(),
+            _ => format!(".{:0width$}", fraction, width = decimals),
+        };
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60 + extra,
+            fraction
+        )
+    }
+}
+
+// Greenwich mean sidereal time, the angle from the vernal equinox to the prime meridian, in
+// radians
+pub fn sidereal_angle(jd: Precision) -> Precision {
+    (280.46061837 + 360.98564736629 * (jd - J2000))
+        .rem_euclid(360.0)
+        .to_radians()
+}
+
+// Year, month, day and seconds into the day of a Julian date (Meeus, chapter 7)
+pub fn calendar_date(jd: Precision) -> (i64, i64, i64, Precision) {
+    let jd = jd + 0.5;
+    let z = jd.floor();
+    let f = jd - z;
+
+    let a = if z < 2299161.0 {
+        z
+    } else {
+        let alpha = ((z - 1867216.25) / 36524.25).floor();
+        z + 1.0 + alpha - (alpha / 4.0).floor()
+    };
+
+    let b = a + 1524.0;
+    let c = ((b - 122.1) / 365.25).floor();
+    let d = (365.25 * c).floor();
+    let e = ((b - d) / 30.6001).floor();
+
+    let day = b - d - (30.6001 * e).floor();
+    let month = if e < 14.0 { e - 1.0 } else { e - 13.0 };
+    let year = if month > 2.0 { c - 4716.0 } else { c - 4715.0 };
+
+    (year as i64, month as i64, day as i64, f * 86400.0)
+}
+
+// Equatorial position of the Sun, in metres (Astronomical Almanac low-precision formulae)
+pub fn sun_position(jd: Precision) -> [Precision; 3] {
+    let n = jd - J2000;
+    let mean_longitude = 280.460 + 0.9856474 * n;
+    let g = (357.528 + 0.9856003 * n).to_radians();
+
+    let longitude = (mean_longitude + 1.915 * g.sin() + 0.020 * (2.0 * g).sin()).to_radians();
+    let obliquity = (23.439 - 0.0000004 * n).to_radians();
+    let distance = AU * (1.00014 - 0.01671 * g.cos() - 0.00014 * (2.0 * g).cos());
+
+    [
+        distance * longitude.cos(),
+        distance * obliquity.cos() * longitude.sin(),
+        distance * obliquity.sin() * longitude.sin(),
+    ]
+}
+
+// Equatorial position of the Moon, in metres (Montenbruck & Gill, section 3.3.2)
+pub fn moon_position(jd: Precision) -> [Precision; 3] {
+    let t = (jd - J2000) / 36525.0;
+    let deg = |x: Precision| x.to_radians();
+
+    let l0 = 218.31617 + 481267.88088 * t - 1.3972 * t;
+    let l = deg(134.96292 + 477198.86753 * t);
+    let lp = deg(357.52543 + 35999.04944 * t);
+    let f = deg(93.27283 + 483202.01873 * t);
+    let d = deg(297.85027 + 445267.11135 * t);
+
+    let longitude = l0
+        + (22640.0 * l.sin() + 769.0 * (2.0 * l).sin() - 4586.0 * (l - 2.0 * d).sin()
+            + 2370.0 * (2.0 * d).sin()
+            - 668.0 * lp.sin()
+            - 412.0 * (2.0 * f).sin()
+            - 212.0 * (2.0 * l - 2.0 * d).sin()
+            - 206.0 * (l + lp - 2.0 * d).sin()
+            + 192.0 * (l + 2.0 * d).sin()
+            - 165.0 * (lp - 2.0 * d).sin()
+            + 148.0 * (l - lp).sin()
+            - 125.0 * d.sin()
+            - 110.0 * (l + lp).sin()
+            - 55.0 * (2.0 * f - 2.0 * d).sin())
+            / 3600.0;
+
+    let latitude = (18520.0
+        * (f + deg(longitude - l0) + deg((412.0 * (2.0 * f).sin() + 541.0 * lp.sin()) / 3600.0))
+            .sin()
+        - 526.0 * (f - 2.0 * d).sin()
+        + 44.0 * (l + f - 2.0 * d).sin()
+        - 31.0 * (-l + f - 2.0 * d).sin()
+        - 25.0 * (-2.0 * l + f).sin()
+        - 23.0 * (lp + f - 2.0 * d).sin()
+        + 21.0 * (-l + f).sin()
+        + 11.0 * (-lp + f - 2.0 * d).sin())
+        / 3600.0;
+
+    let distance = 1000.0
+        * (385000.0
+            - 20905.0 * l.cos()
+            - 3699.0 * (2.0 * d - l).cos()
+            - 2956.0 * (2.0 * d).cos()
+            - 570.0 * (2.0 * l).cos()
+            + 246.0 * (2.0 * l - 2.0 * d).cos()
+            - 205.0 * (lp - 2.0 * d).cos()
+            - 171.0 * (l + 2.0 * d).cos()
+            - 152.0 * (l + lp - 2.0 * d).cos());
+
+    let (longitude, latitude) = (deg(longitude), deg(latitude));
+    let (sin_e, cos_e) = deg(OBLIQUITY).sin_cos();
+
+    // Ecliptic to equatorial
+    let x = distance * latitude.cos() * longitude.cos();
+    let y = distance * latitude.cos() * longitude.sin();
+    let z = distance * latitude.sin();
+
+    [x, cos_e * y - sin_e * z, sin_e * y + cos_e * z]
+}
+
+// Sun and Moon positions projected onto the simulation plane, in metres
+#[derive(Resource, Default)]
+pub struct Ephemeris {
+    pub sun: (Precision, Precision),
+    pub moon: (Precision, Precision),
+    // The id of the Moon body when there is one
+    pub moon_body: Option<usize>,
+}
+
+impl Ephemeris {
+    pub fn sun_direction(&self) -> (Precision, Precision) {
+        let (x, y) = self.sun;
+        let d = (x * x + y * y).sqrt();
+        (x / d, y / d)
+    }
+
+    // Cylindrical earth shadow
+    pub fn in_eclipse(&self, state: &State) -> bool {
+        let (sx, sy) = self.sun_direction();
+        let along = state.x * sx + state.y * sy;
+        let across = state.x * sy - state.y * sx;
+
+        along < 0.0 && across.abs() < EARTH_RADIUS
+    }
+}
+
+type Massive = (With<GravitySource>, Without<Crashed>);
+
+pub fn update_ephemeris(
+    time: Res<SimTime>,
+    epoch: Res<Epoch>,
+    mut ephemeris: ResMut<Ephemeris>,
+    masses: Query<(&Body, &Name), Massive>,
+) {
+    // The formulae take TT, which runs about a minute ahead of UTC
+    let jd = epoch.terrestrial_time(time.0);
+    let [sx, sy, _] = sun_position(jd);
+    let moon = masses.iter().find(|(_, name)| name.as_str() == MOON);
+
+    ephemeris.sun = (sx, sy);
+    ephemeris.moon_body = moon.map(|(body, _)| body.id);
+    ephemeris.moon = match moon {
+        Some((body, _)) => (body.current_state.x, body.current_state.y),
+        None => {
+            let [mx, my, _] = moon_position(jd);
+            (mx, my)
+        }
+    };
+}
+
+// Sun direction, earth shadow and the Moon
+pub fn draw_ephemeris(
+    mut gizmos: Gizmos,
+    ephemeris: Res<Ephemeris>,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+) {
+    let colors = palette.colors();
+    let (sx, sy) = ephemeris.sun_direction();
+    let point = |x: Precision, y: Precision| interpolation.point(x, y);
+    let (sx, sy) = (sx * EARTH_RADIUS, sy * EARTH_RADIUS);
+
+    gizmos.line_2d(point(sx, sy), point(2.5 * sx, 2.5 * sy), colors.sun);
+    for side in [1.0, -1.0] {
+        let (ax, ay) = (-sy * side, sx * side);
+        gizmos.line_2d(
+            point(ax, ay),
+            point(ax - 4.0 * sx, ay - 4.0 * sy),
+            colors.guide,
+        );
+    }
+
+    // The Moon body is drawn with the other bodies
+    if ephemeris.moon_body.is_none() {
+        let (mx, my) = ephemeris.moon;
+        gizmos.circle_2d(point(mx, my), MOON_RADIUS as f32, colors.faint);
+    }
+}
+
+#[derive(Component)]
+pub struct EphemerisText;
+
+pub fn show_ephemeris(
+    time: Res<SimTime>,
+    epoch: Res<Epoch>,
+    (speed, governor): (Res<SimulationSpeed>, Res<Governor>),
+    ephemeris: Res<Ephemeris>,
+    language: Res<Language>,
+    bodies: Query<&Body>,
+    mut text: Query<&mut Text, With<EphemerisText>>,
+) {
+    let (sx, sy) = ephemeris.sun;
+    let (mx, my) = ephemeris.moon;
+
+    let mut value = format!(
+        "{}  {}\n{}\n",
+        epoch.timestamp(time.0),
+        governor.describe(speed.0, *language),
+        language.text(
+            "sun_moon",
+            &[
+                &format!("{:.1}", sy.atan2(sx).to_degrees()),
+                &format!("{:.1}", my.atan2(mx).to_degrees()),
+                &format!("{:.0}", (mx * mx + my * my).sqrt() / 1000.0),
+            ]
+        )
+    );
+
+    for body in bodies.iter() {
+        if ephemeris.in_eclipse(&body.current_state) {
+            value.push_str(&language.text("in_eclipse", &[&body.id]));
+            value.push('\n');
+        }
+    }
+
+    text.single_mut().sections[0].value = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_keep_fractions_of_a_second_through_a_leap_second() {
+        let epoch = Epoch(timescales::julian_day(2016, 12, 31));
+        assert_eq!(epoch.timestamp(86399.25), "2016-12-31T23:59:59Z");
+        assert_eq!(epoch.timestamp_to(86399.25, 3), "2016-12-31T23:59:59.250Z");
+        assert_eq!(epoch.timestamp(86400.4), "2016-12-31T23:59:60Z");
+        assert_eq!(epoch.timestamp_to(86400.5, 3), "2016-12-31T23:59:60.500Z");
+        assert_eq!(epoch.timestamp_to(86401.125, 1), "2017-01-01T00:00:00.1Z");
+    }
+}
@@ -0,0 +1,297 @@
+// Time-tagged simulation events (burns, apsis crossings, eclipses, conjunctions, crashes).
+// Crashes are sent by the integration, at the time the body hit. Apsides are put where the
+// radial velocity passed through zero during the tick, taking it to change evenly in between
+// (a warp that runs half an orbit a tick passes some by). Conjunctions go through the
+// broadphase, on the bodies' paths over the tick, so a pair is caught at its closest however far
+// it moved, and found among many bodies without checking every pair.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use bevy::math::DVec2;
+
+use crate::broadphase::{self, Sweep};
+use crate::engine::Engine;
+use crate::ephemeris::{Ephemeris, Epoch};
+use crate::interpolation::PreviousStates;
+use crate::quicksave::Loaded;
+use crate::{Body, Precision, SimTime, EARTH_RADIUS};
+
+pub const CONJUNCTION_DISTANCE: Precision = 5000.0; // m
+const JSON_PATH: &str = "events.json";
+const CSV_PATH: &str = "events.csv";
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventKind {
+    BurnStart,
+    BurnEnd,
+    Impulse { dvx: Precision, dvy: Precision },
+    Periapsis { altitude: Precision },
+    Apoapsis { altitude: Precision },
+    EclipseEntry,
+    EclipseExit,
+    Conjunction { other: usize, distance: Precision },
+    Crash { speed: Precision },
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventKind::BurnStart => write!(f, "burn start"),
+            EventKind::BurnEnd => write!(f, "burn end"),
+            EventKind::Impulse { dvx, dvy } => {
+                write!(f, "impulse of ({:.3}, {:.3}) m/s", dvx, dvy)
+            }
+            EventKind::Periapsis { altitude } => {
+                write!(f, "periapsis at {:.1} km", altitude / 1000.0)
+            }
+            EventKind::Apoapsis { altitude } => {
+                write!(f, "apoapsis at {:.1} km", altitude / 1000.0)
+            }
+            EventKind::EclipseEntry => write!(f, "eclipse entry"),
+            EventKind::EclipseExit => write!(f, "eclipse exit"),
+            EventKind::Conjunction { other, distance } => {
+                write!(f, "conjunction with body {} at {:.0} m", other, distance)
+            }
+            EventKind::Crash { speed } => write!(f, "crash at {:.0} m/s", speed),
+        }
+    }
+}
+
+#[derive(Event, Clone, Serialize)]
+pub struct SimEvent {
+    pub time: Precision,
+    pub body: usize,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+// Everything that has happened so far, in order
+#[derive(Resource, Default)]
+pub struct EventLog(pub Vec<SimEvent>);
+
+#[derive(Serialize)]
+struct Record<'a> {
+    epoch: String,
+    #[serde(flatten)]
+    event: &'a SimEvent,
+}
+
+impl EventLog {
+    fn export_json(&self, epoch: &Epoch, path: &str) -> io::Result<()> {
+        let records: Vec<Record> = self
+            .0
+            .iter()
+            .map(|event| Record {
+                epoch: epoch.timestamp(event.time),
+                event,
+            })
+            .collect();
+
+        serde_json::to_writer_pretty(File::create(path)?, &records)?;
+        Ok(())
+    }
+
+    fn export_csv(&self, epoch: &Epoch, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "time, epoch, body, event")?;
+
+        for event in self.0.iter() {
+            writeln!(
+                file,
+                "{}, {}, {}, {}",
+                event.time,
+                epoch.timestamp(event.time),
+                event.body,
+                event.kind
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// What each body was doing at the last tick, to spot transitions
+pub struct Tracker {
+    firing: bool,
+    radial_velocity: Precision,
+    radius: Precision,
+    time: Precision,
+    in_eclipse: bool,
+}
+
+impl Tracker {
+    // When the radial velocity went through zero on its way to `radial_velocity` at `now`, and
+    // the altitude there, the radius growing by the mean radial velocity up to it
+    fn apsis(&self, radial_velocity: Precision, now: Precision) -> (Precision, Precision) {
+        let fraction = self.radial_velocity / (self.radial_velocity - radial_velocity);
+        let elapsed = fraction * (now - self.time);
+        let radius = self.radius + 0.5 * self.radial_velocity * elapsed;
+        (self.time + elapsed, radius - EARTH_RADIUS)
+    }
+}
+
+pub fn detect_events(
+    time: Res<SimTime>,
+    ephemeris: Res<Ephemeris>,
+    bodies: Query<(Entity, &Body, Option<&Engine>)>,
+    previous: Res<PreviousStates>,
+    mut trackers: Local<HashMap<Entity, Tracker>>,
+    (mut conjunctions, mut loaded): (Local<HashSet<(usize, usize)>>, EventReader<Loaded>),
+    mut events: EventWriter<SimEvent>,
+) {
+    // A load puts the bodies somewhere else, which isn't a crossing or a burn
+    if loaded.read().count() > 0 {
+        trackers.clear();
+        conjunctions.clear();
+    }
+    let mut send = |body: &Body, time: Precision, kind: EventKind| {
+        events.send(SimEvent {
+            time,
+            body: body.id,
+            kind,
+        })
+    };
+    let now = time.0;
+
+    for (entity, body, engine) in bodies.iter() {
+        let state = body.current_state;
+        let r = (state.x * state.x + state.y * state.y).sqrt();
+        let radial_velocity = (state.x * state.vx + state.y * state.vy) / r;
+        let firing = engine.is_some_and(|engine| engine.firing);
+        let in_eclipse = ephemeris.in_eclipse(&state);
+
+        let Some(tracker) = trackers.get_mut(&entity) else {
+            trackers.insert(
+                entity,
+                Tracker {
+                    firing,
+                    radial_velocity,
+                    radius: r,
+                    time: now,
+                    in_eclipse,
+                },
+            );
+            continue;
+        };
+
+        if firing != tracker.firing {
+            send(
+                body,
+                now,
+                if firing {
+                    EventKind::BurnStart
+                } else {
+                    EventKind::BurnEnd
+                },
+            );
+        }
+
+        if tracker.radial_velocity > 0.0 && radial_velocity <= 0.0 {
+            let (time, altitude) = tracker.apsis(radial_velocity, now);
+            send(body, time, EventKind::Apoapsis { altitude });
+        }
+        if tracker.radial_velocity < 0.0 && radial_velocity >= 0.0 {
+            let (time, altitude) = tracker.apsis(radial_velocity, now);
+            send(body, time, EventKind::Periapsis { altitude });
+        }
+
+        if in_eclipse != tracker.in_eclipse {
+            send(
+                body,
+                now,
+                if in_eclipse {
+                    EventKind::EclipseEntry
+                } else {
+                    EventKind::EclipseExit
+                },
+            );
+        }
+
+        tracker.firing = firing;
+        tracker.radial_velocity = radial_velocity;
+        tracker.radius = r;
+        tracker.time = now;
+        tracker.in_eclipse = in_eclipse;
+    }
+
+    // Conjunctions are reported once, at the closest point of the tick a pair first comes close
+    let sweeps: Vec<Sweep> = bodies
+        .iter()
+        .map(|(entity, body, _)| Sweep {
+            entity,
+            id: body.id,
+            from: previous.start(body),
+            to: DVec2::new(body.current_state.x, body.current_state.y),
+        })
+        .collect();
+    let close = broadphase::approaches(&sweeps, CONJUNCTION_DISTANCE);
+    let ordered = |i: usize, j: usize| {
+        let (a, b) = (&sweeps[i], &sweeps[j]);
+        if a.id < b.id {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    };
+    let pairs: HashSet<(usize, usize)> = close
+        .iter()
+        .map(|(i, j, ..)| {
+            let (a, b) = ordered(*i, *j);
+            (a.id, b.id)
+        })
+        .collect();
+    conjunctions.retain(|pair| pairs.contains(pair));
+
+    for (i, j, distance, fraction) in close {
+        let (a, b) = ordered(i, j);
+        if conjunctions.insert((a.id, b.id)) {
+            events.send(SimEvent {
+                time: previous.time() + fraction * (time.0 - previous.time()),
+                body: a.id,
+                kind: EventKind::Conjunction {
+                    other: b.id,
+                    distance,
+                },
+            });
+        }
+    }
+}
+
+pub fn record_events(
+    epoch: Res<Epoch>,
+    mut events: EventReader<SimEvent>,
+    mut log: ResMut<EventLog>,
+) {
+    for event in events.read() {
+        info!(
+            "{} body {}: {}",
+            epoch.timestamp(event.time),
+            event.body,
+            event.kind
+        );
+        log.0.push(event.clone());
+    }
+}
+
+// E writes the event log as JSON and CSV
+pub fn export_events(keyboard: Res<Input<KeyCode>>, epoch: Res<Epoch>, log: Res<EventLog>) {
+    if !keyboard.just_pressed(KeyCode::E) {
+        return;
+    }
+
+    for (path, result) in [
+        (JSON_PATH, log.export_json(&epoch, JSON_PATH)),
+        (CSV_PATH, log.export_csv(&epoch, CSV_PATH)),
+    ] {
+        match result {
+            Ok(()) => info!("Event log written to {}", path),
+            Err(error) => error!("Could not write {}: {}", path, error),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+// Per-body propagation fidelity, so one scene can mix a carefully integrated player body with
+// thousands of cheap background ones. Bodies without the component use the standard tier. The
+// integrator the tiers step with is shared by all bodies, RK4 unless picked otherwise with
+// `--integrator` or `set_integrator`, and steps are cut into shorter substeps where the orbit
+// turns fast, near periapsis or close to a point mass.
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::atmosphere::DragSettings;
+use crate::crash::{self, Crossing};
+use crate::elements::{OrbitalElements, MU};
+use crate::gravity::Perturbations;
+use crate::{
+    adaptive_step, Atmosphere, Body, Integrator, Precision, Rk4, Source, State, Thrust, TimeStep,
+};
+
+const SUBSTEPS: usize = 10; // of the high tier in each step
+
+#[derive(Resource)]
+pub struct Integration(pub Box<dyn Integrator>);
+
+impl Default for Integration {
+    fn default() -> Self {
+        Self(Box::new(Rk4))
+    }
+}
+
+// What every body is propagated through and with
+#[derive(SystemParam)]
+pub struct Propagation<'w> {
+    pub drag: Res<'w, DragSettings>,
+    pub integration: Res<'w, Integration>,
+    pub perturbations: Res<'w, Perturbations>,
+    pub step: Res<'w, TimeStep>,
+}
+
+#[derive(Component, Clone, Copy, Default, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fidelity {
+    High, // the integrator with a tenth of the step
+    #[default]
+    Standard, // the integrator with the step
+    Analytic, // the Kepler solution, jumping a whole tick at a time while coasting alone
+}
+
+impl Fidelity {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "high" => Ok(Fidelity::High),
+            "standard" => Ok(Fidelity::Standard),
+            "analytic" => Ok(Fidelity::Analytic),
+            _ => Err(format!(
+                "unknown fidelity \"{}\", try high, standard or analytic",
+                name
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Fidelity::High => "high",
+            Fidelity::Standard => "standard",
+            Fidelity::Analytic => "analytic",
+        }
+    }
+
+    // Moves the body `steps` steps of `dt` on with `integrator`, with the same thrust, sources
+    // and air throughout, and stops short where it goes under the surface
+    pub fn advance(
+        self,
+        body: &mut Body,
+        thrust: Thrust,
+        steps: u32,
+        sources: &[Source],
+        atmosphere: Option<Atmosphere>,
+        (integrator, dt): (&dyn Integrator, Precision),
+    ) -> Option<Crossing> {
+        let drag = body.drag(atmosphere);
+        match self {
+            Fidelity::High => {
+                let substep = dt / SUBSTEPS as Precision;
+                for i in 0..steps {
+                    for j in 0..SUBSTEPS {
+                        let before = body.current_state;
+                        body.current_state =
+                            adaptive_step(integrator, before, substep, thrust, sources, drag);
+                        let start = i as Precision * dt + j as Precision * substep;
+                        let crossing =
+                            crash::crossing(&before, &body.current_state, start, substep);
+                        if crossing.is_some() {
+                            return crossing;
+                        }
+                    }
+                    body.update_history();
+                }
+                None
+            }
+            // With the earth as the only source, round, and no drag the Kepler solution holds
+            Fidelity::Analytic
+                if thrust.acceleration == 0.0
+                    && steps > 0
+                    && sources.len() == 1
+                    && sources[0].j2 == 0.0
+                    && drag.is_none() =>
+            {
+                match kepler(&body.current_state, steps as Precision * dt) {
+                    Some(state) => {
+                        body.current_state = state;
+                        body.update_history();
+                        None
+                    }
+                    None => Fidelity::Standard.advance(
+                        body,
+                        thrust,
+                        steps,
+                        sources,
+                        atmosphere,
+                        (integrator, dt),
+                    ),
+                }
+            }
+            // Analytic bodies integrate while they thrust, feel drag, the bulge or other masses
+            Fidelity::Standard | Fidelity::Analytic => {
+                for i in 0..steps {
+                    let before = body.current_state;
+                    body.current_state =
+                        adaptive_step(integrator, before, dt, thrust, sources, drag);
+                    body.update_history();
+                    let crossing =
+                        crash::crossing(&before, &body.current_state, i as Precision * dt, dt);
+                    if crossing.is_some() {
+                        return crossing;
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+// The state `duration` seconds later on the unperturbed orbit, for elliptic orbits that stay
+// clear of the surface only
+fn kepler(state: &State, duration: Precision) -> Option<State> {
+    let mut elements = OrbitalElements::from_state(state);
+    if elements.e >= 1.0 || elements.periapsis_altitude() < 0.0 {
+        return None;
+    }
+    elements.mean_anomaly += (MU / elements.a.powi(3)).sqrt() * duration;
+    let clockwise = state.x * state.vy - state.y * state.vx < 0.0;
+    Some(elements.to_state(clockwise))
+}
@@ -0,0 +1,102 @@
+// Several craft at once: every body with an engine can fly, but the arrow keys only fire the
+// focused one, the ControlledBody, while the others carry on with their autopilots and plans or
+// coast. Shift+Tab moves the focus on to the next craft by id and selects it, so the panels
+// follow it too. `spawn_craft(x, y, vx, vy, ["name"])` adds a craft with a tank like the
+// station's, drawn in a color of its own so its trail and lookahead can be told apart.
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::engine::Engine;
+use crate::maneuver::ManeuverPlan;
+use crate::style::{BodyStyle, CustomStyle};
+use crate::{Body, BodyIds, Selected, State, STATION_TANK};
+
+// Picked by id, the station's red left out
+const CRAFT_COLORS: [Color; 6] = [
+    Color::BLUE,
+    Color::ORANGE,
+    Color::PURPLE,
+    Color::DARK_GREEN,
+    Color::PINK,
+    Color::CYAN,
+];
+const LOOKAHEAD_ALPHA: f32 = 0.5;
+
+// The craft the arrow keys fly
+#[derive(Component)]
+pub struct ControlledBody;
+
+fn craft_style(id: usize) -> BodyStyle {
+    let color = CRAFT_COLORS[id % CRAFT_COLORS.len()];
+    BodyStyle {
+        color,
+        trail_color: color,
+        lookahead_color: color.with_a(LOOKAHEAD_ALPHA),
+        ..default()
+    }
+}
+
+// A craft with a full tank at `state`, returning its id
+pub fn spawn_craft(
+    commands: &mut Commands,
+    ids: &mut BodyIds,
+    state: State,
+    name: Option<&str>,
+) -> usize {
+    let tank = STATION_TANK;
+    let id = ids.next();
+    let body = Body::new(id, tank.mass(), state.x, state.y, state.vx, state.vy);
+
+    let mut entity = commands.spawn((
+        body,
+        Engine {
+            tank: Some(tank),
+            ..default()
+        },
+        ManeuverPlan::default(),
+        craft_style(id),
+        CustomStyle,
+    ));
+    if let Some(name) = name {
+        entity.insert(Name::new(name.to_string()));
+    }
+    id
+}
+
+type Craft<'a> = (Entity, &'a Body, Has<ControlledBody>);
+
+// Shift+Tab focuses the next craft that hasn't crashed
+pub fn cycle_focus(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    craft: Query<Craft, (With<Engine>, Without<Crashed>)>,
+    selected: Query<Entity, With<Selected>>,
+) {
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keyboard.just_pressed(KeyCode::Tab) {
+        return;
+    }
+
+    let mut craft: Vec<_> = craft.iter().collect();
+    if craft.is_empty() {
+        info!("No craft to focus");
+        return;
+    }
+    craft.sort_by_key(|(_, body, _)| body.id);
+    let next = craft
+        .iter()
+        .position(|(.., controlled)| *controlled)
+        .map_or(0, |i| (i + 1) % craft.len());
+
+    for (entity, _, controlled) in &craft {
+        if *controlled {
+            commands.entity(*entity).remove::<ControlledBody>();
+        }
+    }
+    for entity in selected.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+    let (entity, body, _) = craft[next];
+    commands.entity(entity).insert((ControlledBody, Selected));
+    info!("Flying body {}", body.id);
+}
@@ -0,0 +1,226 @@
+// Chief/deputy pairs for formation-flying experiments
+use std::f64::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::elements::{OrbitalElements, MU};
+use crate::engine::Engine;
+use crate::events::SimEvent;
+use crate::i18n::Language;
+use crate::perturbation::apply_impulse;
+use crate::style::BodyStyle;
+use crate::{Body, BodyIds, Precision, SimTime};
+
+const DEPUTY_SEPARATION: Precision = 1000.0; // m behind the chief
+
+// Formation keeping fires at most this often, and only burns above the deadband
+const BURN_INTERVAL: Precision = 600.0; // s
+const MIN_BURN: Precision = 0.001; // m/s
+const MAX_BURN: Precision = 0.5; // m/s
+
+// Time constant for closing the along-track error
+const CONVERGENCE_TIME: Precision = 20000.0; // s
+
+// A body flying in formation with a chief body
+#[derive(Component)]
+pub struct Deputy {
+    pub chief: Entity,
+}
+
+// Autopilot holding a deputy at a fixed distance behind its chief with small tangential burns
+#[derive(Component)]
+pub struct FormationKeeping {
+    pub separation: Precision, // m
+    pub dv_used: Precision,    // m/s
+    last_burn: Precision,
+}
+
+impl FormationKeeping {
+    pub fn new(separation: Precision) -> Self {
+        Self {
+            separation,
+            dv_used: 0.0,
+            last_burn: Precision::NEG_INFINITY,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct RelativeElementsText;
+
+// Quasi-nonsingular relative orbital elements of a deputy with respect to its chief.
+// The orbits are coplanar, so the relative inclination vector is always zero; it's kept
+// so the readout matches the usual formulation.
+pub struct RelativeElements {
+    pub da: Precision, // (a_d - a_c) / a_c
+    pub de: (Precision, Precision),
+    pub di: (Precision, Precision),
+    pub dlambda: Precision, // relative mean longitude, rad
+}
+
+impl RelativeElements {
+    pub fn between(chief: &OrbitalElements, deputy: &OrbitalElements) -> Self {
+        let (sin_c, cos_c) = chief.argument_of_periapsis.sin_cos();
+        let (sin_d, cos_d) = deputy.argument_of_periapsis.sin_cos();
+
+        let dlambda =
+            (deputy.mean_longitude() - chief.mean_longitude() + PI).rem_euclid(2.0 * PI) - PI;
+
+        Self {
+            da: (deputy.a - chief.a) / chief.a,
+            de: (
+                deputy.e * cos_d - chief.e * cos_c,
+                deputy.e * sin_d - chief.e * sin_c,
+            ),
+            di: (0.0, 0.0),
+            dlambda,
+        }
+    }
+}
+
+// D spawns a deputy trailing every body with an engine, on the same orbit
+pub fn spawn_deputy(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    mut ids: ResMut<BodyIds>,
+    query: Query<(Entity, &Body), With<Engine>>,
+) {
+    if !keyboard.just_pressed(KeyCode::D) {
+        return;
+    }
+
+    for (chief, body) in query.iter() {
+        let id = ids.next();
+        let state = body.current_state;
+        let r = (state.x * state.x + state.y * state.y).sqrt();
+
+        // Rotating position and velocity together keeps the orbit and only shifts the phase
+        let direction = (state.x * state.vy - state.y * state.vx).signum();
+        let (sin, cos) = (-direction * DEPUTY_SEPARATION / r).sin_cos();
+        let rotate = |x: Precision, y: Precision| (cos * x - sin * y, sin * x + cos * y);
+
+        let (x, y) = rotate(state.x, state.y);
+        let (vx, vy) = rotate(state.vx, state.vy);
+
+        commands.spawn((
+            Body::new(id, body.mass, x, y, vx, vy),
+            BodyStyle::default(),
+            Deputy { chief },
+        ));
+
+        info!("Spawned deputy {} behind body {}", id, body.id);
+    }
+}
+
+// K toggles formation keeping on every deputy
+pub fn toggle_formation_keeping(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    deputies: Query<(Entity, Has<FormationKeeping>), With<Deputy>>,
+) {
+    if !keyboard.just_pressed(KeyCode::K) {
+        return;
+    }
+
+    for (entity, keeping) in deputies.iter() {
+        if keeping {
+            commands.entity(entity).remove::<FormationKeeping>();
+        } else {
+            commands
+                .entity(entity)
+                .insert(FormationKeeping::new(DEPUTY_SEPARATION));
+        }
+    }
+}
+
+// Along-track position is steered through the semi-major axis: a deputy on a higher orbit
+// drifts backwards at 1.5 n δa, so the autopilot picks the δa that closes the error over
+// CONVERGENCE_TIME and makes the tangential burn that produces it.
+pub fn keep_formation(
+    time: Res<SimTime>,
+    mut deputies: Query<(&mut Body, &Deputy, &mut FormationKeeping)>,
+    bodies: Query<&Body, Without<Deputy>>,
+    mut events: EventWriter<SimEvent>,
+) {
+    for (mut deputy, pair, mut keeping) in deputies.iter_mut() {
+        if time.0 - keeping.last_burn < BURN_INTERVAL {
+            continue;
+        }
+        let Ok(chief) = bodies.get(pair.chief) else {
+            continue;
+        };
+
+        let chief_elements = OrbitalElements::from_state(&chief.current_state);
+        let deputy_elements = OrbitalElements::from_state(&deputy.current_state);
+        let roe = RelativeElements::between(&chief_elements, &deputy_elements);
+
+        let error = roe.dlambda + keeping.separation / chief_elements.a;
+        let mean_motion = (MU / chief_elements.a.powi(3)).sqrt();
+        let target_da = error / (1.5 * mean_motion * CONVERGENCE_TIME);
+
+        let state = deputy.current_state;
+        let v = (state.vx * state.vx + state.vy * state.vy).sqrt();
+        let dv = (0.5 * v * (target_da - roe.da)).clamp(-MAX_BURN, MAX_BURN);
+
+        if dv.abs() < MIN_BURN {
+            continue;
+        }
+
+        apply_impulse(
+            &mut deputy,
+            dv * state.vx / v,
+            dv * state.vy / v,
+            time.0,
+            &mut events,
+        );
+        keeping.dv_used += dv.abs();
+        keeping.last_burn = time.0;
+    }
+}
+
+pub fn show_relative_elements(
+    deputies: Query<(&Body, &Deputy, Option<&FormationKeeping>)>,
+    bodies: Query<&Body>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<RelativeElementsText>>,
+) {
+    let mut value = String::new();
+
+    for (deputy, pair, keeping) in deputies.iter() {
+        let Ok(chief) = bodies.get(pair.chief) else {
+            continue;
+        };
+
+        let chief_elements = OrbitalElements::from_state(&chief.current_state);
+        let deputy_elements = OrbitalElements::from_state(&deputy.current_state);
+        let roe = RelativeElements::between(&chief_elements, &deputy_elements);
+
+        value.push_str(&language.text(
+            "relative_elements",
+            &[
+                &deputy.id,
+                &chief.id,
+                &format!("{:.1}", chief_elements.a * roe.da),
+                &format!("{:.2e}", roe.de.0),
+                &format!("{:.2e}", roe.de.1),
+                &format!("{:.2e}", roe.di.0),
+                &format!("{:.2e}", roe.di.1),
+                &format!("{:.1}", chief_elements.a * roe.dlambda),
+            ],
+        ));
+        value.push('\n');
+
+        if let Some(keeping) = keeping {
+            value.push_str(&language.text(
+                "formation_keeping",
+                &[
+                    &format!("{:.0}", keeping.separation),
+                    &format!("{:.3}", keeping.dv_used),
+                ],
+            ));
+            value.push('\n');
+        }
+    }
+
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,56 @@
+// Reference frames: F2, or `set_frame("...")`, switches what the bodies, their trails and their
+// lookaheads are drawn relative to. The earth-centred inertial frame is the one the physics runs
+// in. The earth-fixed frame turns with the earth, so a geostationary satellite stands still and
+// a low orbit winds round the globe. The body-centred frame keeps the selected body in the
+// middle, so the craft near it show their motion relative to it, the way station keeping and
+// rendezvous are flown. Only the drawing changes: the physics, the elements and the console
+// stay inertial.
+use bevy::prelude::*;
+
+use crate::Precision;
+
+pub const EARTH_ROTATION: Precision = 7.292115855e-5; // rad/s, once a sidereal day
+
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub enum ReferenceFrame {
+    #[default]
+    Inertial,
+    EarthFixed,
+    BodyCentred,
+}
+
+impl ReferenceFrame {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "inertial" => Some(Self::Inertial),
+            "earth_fixed" => Some(Self::EarthFixed),
+            "body" => Some(Self::BodyCentred),
+            _ => None,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Inertial => "earth-centred inertial",
+            Self::EarthFixed => "earth-fixed rotating",
+            Self::BodyCentred => "body-centred",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Inertial => Self::EarthFixed,
+            Self::EarthFixed => Self::BodyCentred,
+            Self::BodyCentred => Self::Inertial,
+        }
+    }
+}
+
+// F2 moves on to the next frame
+pub fn cycle_frame(keyboard: Res<Input<KeyCode>>, mut frame: ResMut<ReferenceFrame>) {
+    if !keyboard.just_pressed(KeyCode::F2) {
+        return;
+    }
+    *frame = frame.next();
+    info!("Drawing in the {} frame", frame.describe());
+}
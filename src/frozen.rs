@@ -0,0 +1,126 @@
+// Frozen orbits: ones whose shape the earth's bulge leaves alone. In the equatorial plane the
+// bodies fly in, J2 turns the periapsis of every eccentric orbit forward at 3 n J2 (R/p)², so no
+// eccentricity and argument of periapsis hold still and the only frozen orbits are circular,
+// flown a little faster than around a point mass since the bulge pulls harder there:
+// v² = μ/r (1 + 3/2 J2 (R/r)²). J3, which freezes the eccentricity of inclined orbits, pulls
+// only across the equatorial plane, so it changes nothing here: in this planar model the
+// frozen-orbit design degenerates to picking a circular orbit. `spawn_frozen(altitude,
+// ["name"])` spawns a craft on one and `body(...).freeze_orbit()` proposes the burn that puts a
+// body on the one at its radius. With set_j2("off") they are plain circular orbits.
+use std::f64::consts::TAU;
+
+use crate::elements::{OrbitalElements, MU};
+use crate::maneuver::{Frame, ManeuverNode};
+use crate::propagator::Coast;
+use crate::{Precision, Source, State};
+
+const DAY: Precision = 86400.0; // s
+
+// Osculating eccentricity below which an orbit counts as circular already: J2 alone makes a
+// circular orbit's about 1.5 J2 (R/r)², 0.0014 in low orbit
+const NEARLY_CIRCULAR: Precision = 0.005;
+
+// The speed of a circular orbit of radius `r` around `earth`, bulge and all
+pub fn frozen_speed(r: Precision, earth: &Source) -> Precision {
+    (MU / r * (1.0 + 1.5 * earth.j2 * (earth.radius / r).powi(2))).sqrt()
+}
+
+// The state on the frozen orbit at `altitude`, on the x axis going anticlockwise
+pub fn frozen_state(altitude: Precision, earth: &Source) -> State {
+    let r = earth.radius + altitude;
+    State::new(r, 0.0, 0.0, frozen_speed(r, earth))
+}
+
+// How fast the bulge turns the periapsis of the orbit at `state`, rad/s, None off an ellipse
+pub fn apsidal_rate(state: &State, earth: &Source) -> Option<Precision> {
+    let elements = OrbitalElements::from_state(state);
+    let n = TAU / elements.period()?;
+    let p = elements.a * (1.0 - elements.e * elements.e);
+    Some(3.0 * n * earth.j2 * (earth.radius / p).powi(2))
+}
+
+pub struct Freeze {
+    pub eccentricity: Precision,  // osculating, now
+    pub drift: Option<Precision>, // °/day the periapsis turns now
+    pub altitude: Precision,      // m, of the frozen orbit at the burn
+    pub speed: Precision,         // m/s, on it
+}
+
+impl Freeze {
+    pub fn describe(&self) -> String {
+        let drift = match self.drift {
+            None => "the body is on an escape orbit".to_string(),
+            Some(_) if self.eccentricity < NEARLY_CIRCULAR => {
+                "the orbit is nearly circular already".to_string()
+            }
+            Some(drift) => format!("the periapsis turns {:.3}°/day", drift),
+        };
+        format!(
+            "{}; the frozen orbit at {:.1} km is circular, at {:.3} m/s",
+            drift,
+            self.altitude / 1000.0,
+            self.speed
+        )
+    }
+}
+
+// A node one step along the coast onto the frozen orbit at the radius the body is at then,
+// going round the way it already does
+pub fn freeze_orbit(mut coast: Coast, earth: &Source) -> Result<(Freeze, ManeuverNode), String> {
+    let now = coast.time;
+    let eccentricity = OrbitalElements::from_state(&coast.state).e;
+    let drift = apsidal_rate(&coast.state, earth).map(|rate| (rate * DAY).to_degrees());
+    let state = coast.step();
+    let r = state.x.hypot(state.y);
+    if r <= earth.radius {
+        return Err("the body would be under the surface".to_string());
+    }
+
+    let sense = if state.x * state.vy - state.y * state.vx < 0.0 {
+        -1.0
+    } else {
+        1.0
+    };
+    let speed = frozen_speed(r, earth);
+    let (vx, vy) = (-state.y / r * sense * speed, state.x / r * sense * speed);
+    Ok((
+        Freeze {
+            eccentricity,
+            drift,
+            altitude: r - earth.radius,
+            speed,
+        },
+        ManeuverNode {
+            time: now + coast.dt(),
+            dv: [vx - state.vx, vy - state.vy],
+            frame: Frame::Inertial,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity::Perturbations;
+    use crate::propagator::{MassivePaths, Propagator};
+    use crate::DormandPrince;
+
+    #[test]
+    fn eccentricity_stays_bounded_over_many_periods() {
+        let paths = MassivePaths::default();
+        let perturbations = Perturbations { j2: true };
+        let integrator = DormandPrince::default();
+        let propagator = Propagator::new(&integrator, 10.0, &perturbations, None, &paths);
+        let earth = perturbations.earth();
+        let state = frozen_state(700e3, &earth);
+        let periods = 50.0 * OrbitalElements::from_state(&state).period().unwrap();
+
+        let mut coast = propagator.coast_from(state, 0.0);
+        let mut largest: Precision = 0.0;
+        while coast.time < periods {
+            largest = largest.max(OrbitalElements::from_state(&coast.step()).e);
+        }
+        // It reaches twice that from the point-mass circular speed
+        assert!(largest < 2e-3, "e reached {}", largest);
+    }
+}
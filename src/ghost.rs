@@ -0,0 +1,236 @@
+// The ghost fleet: shadow copies of a body, each flown on from where the body was by an
+// autopilot of its own (or none, to coast), so control strategies can be compared side by side
+// in the same scenario. Ghosts carry a copy of the body's engine and tank and feel the same
+// gravity and drag, but nothing else sees them: they don't pull, link, log events or get
+// picked. The orbit view draws them translucent with dashed trails, and the HUD lists each
+// one's strategy, Δv spent and how far it has drifted from the real body.
+use std::collections::VecDeque;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::autopilot::{Autopilot, Autopilots};
+use crate::elements::OrbitalElements;
+use crate::engine::Engine;
+use crate::fidelity::Propagation;
+use crate::gravity::GravitySource;
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::style;
+use crate::{
+    adaptive_step, Body, Drag, FullThrust, Precision, SimTime, Source, State, Thrust, EARTH_RADIUS,
+};
+
+pub const COAST: &str = "coast"; // the strategy of flying no autopilot at all
+const TRAIL_LENGTH: usize = 1000; // steps
+const MARKER_SIZE: f32 = 50000.0; // m
+const ALPHA: f32 = 0.5;
+// One per ghost of a fleet, with the names the HUD gives them
+const COLORS: [(&str, Color); 5] = [
+    ("orange", Color::ORANGE),
+    ("purple", Color::PURPLE),
+    ("cyan", Color::CYAN),
+    ("pink", Color::PINK),
+    ("yellow", Color::YELLOW),
+];
+pub const MAX_GHOSTS: usize = COLORS.len();
+
+pub struct Ghost {
+    of: Entity,
+    strategy: String,
+    autopilot: Option<Box<dyn Autopilot>>, // none coasts
+    target: Option<Entity>,
+    state: State,
+    time: Precision, // sim time of `state`
+    engine: Engine,
+    ballistic_coefficient: Option<Precision>,
+    trail: VecDeque<DVec2>,
+    separation: Precision, // m from the real body
+    crashed: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct Fleet(Vec<Ghost>);
+
+impl Fleet {
+    // Ghosts of `body` from now on, one for each strategy
+    pub fn spawn(
+        &mut self,
+        autopilots: &Autopilots,
+        (entity, body, engine): (Entity, &Body, Option<&Engine>),
+        target: Option<Entity>,
+        strategies: &[String],
+        now: Precision,
+    ) -> Result<(), String> {
+        if self.0.len() + strategies.len() > MAX_GHOSTS {
+            return Err(format!("a fleet has at most {} ghosts", MAX_GHOSTS));
+        }
+        let mut ghosts = Vec::new();
+        for strategy in strategies {
+            let autopilot = match strategy.as_str() {
+                COAST => None,
+                _ if engine.is_none() => {
+                    return Err(format!("body {} has no engine to fly", body.id))
+                }
+                name => Some(autopilots.create(name)?),
+            };
+            ghosts.push(Ghost {
+                of: entity,
+                strategy: strategy.clone(),
+                autopilot,
+                target,
+                state: body.current_state,
+                time: now,
+                engine: Engine {
+                    fault: engine.map(|engine| engine.fault).unwrap_or_default(),
+                    tank: engine.and_then(|engine| engine.tank),
+                    ..default()
+                },
+                ballistic_coefficient: body.ballistic_coefficient,
+                trail: VecDeque::new(),
+                separation: 0.0,
+                crashed: false,
+            });
+        }
+        self.0.extend(ghosts);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn describe(&self, language: Language) -> String {
+        if self.0.is_empty() {
+            return language.text("ghosts_none", &[]);
+        }
+        self.0
+            .iter()
+            .zip(COLORS)
+            .map(|(ghost, (color, _))| {
+                let delta_v = format!("{:.1}", ghost.engine.delta_v);
+                if ghost.crashed {
+                    return language.text("ghost_crashed", &[&color, &ghost.strategy, &delta_v]);
+                }
+                let elements = OrbitalElements::from_state(&ghost.state);
+                language.text(
+                    "ghost",
+                    &[
+                        &color,
+                        &ghost.strategy,
+                        &delta_v,
+                        &format!("{:.1}", ghost.separation / 1000.0),
+                        &format!("{:.0}", elements.a / 1000.0),
+                        &format!("{:.4}", elements.e),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// After the bodies' own steps, the same steps for every ghost
+pub fn fly_ghosts(
+    (time, thrust): (Res<SimTime>, Res<FullThrust>),
+    propagation: Propagation,
+    mut fleet: ResMut<Fleet>,
+    gravity: Query<(Entity, &GravitySource)>,
+    bodies: Query<&Body>,
+) {
+    for ghost in fleet.0.iter_mut() {
+        if !ghost.crashed {
+            fly(ghost, (time.0, thrust.0), &propagation, &gravity, &bodies);
+        }
+        if let Ok(body) = bodies.get(ghost.of) {
+            let (state, real) = (ghost.state, body.current_state);
+            ghost.separation = (state.x - real.x).hypot(state.y - real.y);
+        }
+    }
+}
+
+// Up to `now`, with the autopilot deciding on every step
+fn fly(
+    ghost: &mut Ghost,
+    (now, full): (Precision, Precision),
+    propagation: &Propagation,
+    gravity: &Query<(Entity, &GravitySource)>,
+    bodies: &Query<&Body>,
+) {
+    let (integrator, dt) = (propagation.integration.0.as_ref(), propagation.step.0);
+    let steps = ((now - ghost.time) / dt).round().max(0.0) as usize;
+    ghost.time += steps as Precision * dt;
+    let target = ghost
+        .target
+        .and_then(|target| Some(bodies.get(target).ok()?.current_state));
+    let sources: Vec<Source> = gravity
+        .iter()
+        .filter(|(source, _)| *source != ghost.of)
+        .map(|(_, source)| source.source(&propagation.perturbations))
+        .collect();
+    let air = ghost
+        .ballistic_coefficient
+        .and_then(|ballistic_coefficient| {
+            Some(Drag {
+                atmosphere: propagation.drag.atmosphere?,
+                ballistic_coefficient,
+            })
+        });
+
+    for _ in 0..steps {
+        let thrust = match &ghost.autopilot {
+            Some(autopilot) => {
+                let command = autopilot.control(&ghost.state, target.as_ref(), dt, full);
+                ghost.engine.guided(command, full)
+            }
+            None => Thrust::default(),
+        };
+        ghost.engine.record(thrust, 1, dt);
+        ghost.state = adaptive_step(integrator, ghost.state, dt, thrust, &sources, air);
+
+        let position = DVec2::new(ghost.state.x, ghost.state.y);
+        ghost.trail.push_back(position);
+        if ghost.trail.len() > TRAIL_LENGTH {
+            ghost.trail.pop_front();
+        }
+        if ghost.state.x.hypot(ghost.state.y) < EARTH_RADIUS {
+            ghost.crashed = true;
+            break;
+        }
+    }
+}
+
+pub fn draw_ghosts(mut gizmos: Gizmos, fleet: Res<Fleet>, interpolation: Interpolation) {
+    let point = |point: &DVec2| interpolation.point(point.x, point.y);
+    for (ghost, (_, color)) in fleet.0.iter().zip(COLORS) {
+        let color = color.with_a(ALPHA);
+        let position = interpolation.point(ghost.state.x, ghost.state.y);
+        let marker = if ghost.crashed {
+            style::Marker::Cross
+        } else {
+            style::Marker::Circle
+        };
+        style::draw_marker(&mut gizmos, marker, position, MARKER_SIZE, color);
+        style::draw_line(
+            &mut gizmos,
+            style::LineStyle::Dashed,
+            ghost.trail.iter().map(point),
+            color,
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct GhostText;
+
+pub fn show_ghosts(
+    fleet: Res<Fleet>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<GhostText>>,
+) {
+    text.single_mut().sections[0].value = if fleet.0.is_empty() {
+        String::new()
+    } else {
+        fleet.describe(*language)
+    };
+}
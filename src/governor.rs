@@ -0,0 +1,206 @@
+// The warp governor: under a high warp each physics tick jumps many steps, and everything that
+// only looks at the bodies between ticks (planned burns, events, autopilot links, the other
+// masses' pull) sees them in jumps. Before each tick the governor finds the nearest of the
+// coming burns, the selected body's periapsis, conjunctions and sphere of influence crossings,
+// and holds the warp down to the largest power of ten that leaves LEAD_TICKS ticks until it.
+// Once it has passed the warp goes back to what was set. `6` and `set_governor(...)` turn it
+// on and off.
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::elements::OrbitalElements;
+use crate::engine::Engine;
+use crate::events::CONJUNCTION_DISTANCE;
+use crate::gravity::GravitySource;
+use crate::i18n::Language;
+use crate::maneuver::ManeuverPlan;
+use crate::spatial::SpatialIndex;
+use crate::{Body, Precision, Selected, SimTime, SimulationSpeed, State, TimeStep, MAX_WARP};
+
+const LEAD_TICKS: Precision = 3.0; // ticks the governed warp leaves before an event
+const MIN_ECCENTRICITY: Precision = 0.05; // rounder orbits have no periapsis worth slowing for
+const WATCH_RANGE: Precision = 200e3; // m, pairs closer than this are watched for conjunctions
+
+#[derive(Clone, Copy)]
+pub enum Reason {
+    Burn,
+    Periapsis,
+    Conjunction,
+    SphereOfInfluence,
+}
+
+impl Reason {
+    fn describe(self, language: Language) -> String {
+        let key = match self {
+            Reason::Burn => "governor_burn",
+            Reason::Periapsis => "governor_periapsis",
+            Reason::Conjunction => "governor_conjunction",
+            Reason::SphereOfInfluence => "governor_soi",
+        };
+        language.text(key, &[])
+    }
+}
+
+#[derive(Resource)]
+pub struct Governor {
+    pub enabled: bool,
+    hold: Option<(u32, Reason)>, // the warp the nearest event allows, when below MAX_WARP
+}
+
+impl Default for Governor {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hold: None,
+        }
+    }
+}
+
+impl Governor {
+    // The warp a tick runs at, `speed` unless an event holds it down
+    pub fn warp(&self, speed: u32) -> u32 {
+        match self.hold {
+            Some((warp, _)) if self.enabled => speed.min(warp),
+            _ => speed,
+        }
+    }
+
+    // What the HUD shows next to the date
+    pub fn describe(&self, speed: u32, language: Language) -> String {
+        match self.hold {
+            Some((_, reason)) if self.warp(speed) < speed => language.text(
+                "governed",
+                &[&speed, &self.warp(speed), &reason.describe(language)],
+            ),
+            _ => format!("{}x", speed),
+        }
+    }
+}
+
+// The largest power of ten warp that takes LEAD_TICKS ticks of `dt` steps to cover `time`
+fn warp_for(time: Precision, dt: Precision) -> u32 {
+    let mut warp = MAX_WARP;
+    while warp > 1 && warp as Precision * dt * LEAD_TICKS > time {
+        warp /= 10;
+    }
+    warp
+}
+
+// Seconds to the nearest periapsis, ahead or just behind
+fn to_periapsis(state: &State) -> Option<Precision> {
+    let elements = OrbitalElements::from_state(state);
+    let period = elements
+        .period()
+        .filter(|_| elements.e >= MIN_ECCENTRICITY)?;
+    let since = elements.mean_anomaly.rem_euclid(TAU) / TAU * period;
+    Some(since.min(period - since))
+}
+
+// Seconds to the closest approach of two bodies flying straight on, if they come within
+// CONJUNCTION_DISTANCE of each other
+fn to_conjunction(a: &State, b: &State) -> Option<Precision> {
+    let (x, y, vx, vy) = (b.x - a.x, b.y - a.y, b.vx - a.vx, b.vy - a.vy);
+    if x.hypot(y) < CONJUNCTION_DISTANCE {
+        return Some(0.0);
+    }
+    let time = -(x * vx + y * vy) / (vx * vx + vy * vy);
+    let miss = (x + vx * time).hypot(y + vy * time);
+    (time > 0.0 && miss < CONJUNCTION_DISTANCE).then_some(time)
+}
+
+// Seconds until `state` crosses the sphere of influence of `body`, in or out, going on as it
+// is going now
+fn to_crossing(state: &State, body: &Body, source: &GravitySource) -> Option<Precision> {
+    let other = body.current_state;
+    let (x, y, vx, vy) = (
+        state.x - other.x,
+        state.y - other.y,
+        state.vx - other.vx,
+        state.vy - other.vy,
+    );
+    let radius = source.sphere_of_influence();
+    let distance = x.hypot(y);
+    let rate = (x * vx + y * vy) / distance;
+    let time = (radius - distance) / rate;
+    (time >= 0.0).then_some(time)
+}
+
+type Watched<'a> = (
+    &'a Body,
+    Option<&'a ManeuverPlan>,
+    Option<&'a Engine>,
+    Has<Selected>,
+);
+
+// Before each physics tick
+pub fn govern(
+    (time, step): (Res<SimTime>, Res<TimeStep>),
+    speed: Res<SimulationSpeed>,
+    index: Res<SpatialIndex>,
+    mut governor: ResMut<Governor>,
+    bodies: Query<Watched, Without<Crashed>>,
+    sources: Query<(&Body, &GravitySource)>,
+) {
+    let mut events: Vec<(Precision, Reason)> = Vec::new();
+    for (body, plan, engine, selected) in bodies.iter() {
+        let state = body.current_state;
+        if engine.is_some_and(|engine| engine.firing) {
+            events.push((0.0, Reason::Burn));
+        }
+        if let Some(node) = plan.and_then(|plan| plan.0.first()) {
+            events.push(((node.time - time.0).max(0.0), Reason::Burn));
+        }
+        if selected {
+            if let Some(until) = to_periapsis(&state) {
+                events.push((until, Reason::Periapsis));
+            }
+        }
+        for (other, source) in sources.iter().filter(|(other, _)| other.id != body.id) {
+            if let Some(until) = to_crossing(&state, other, source) {
+                events.push((until, Reason::SphereOfInfluence));
+            }
+        }
+    }
+    for (a, b, _) in index.pairs_within(WATCH_RANGE) {
+        let (Ok((a, ..)), Ok((b, ..))) = (bodies.get(a.entity), bodies.get(b.entity)) else {
+            continue;
+        };
+        if let Some(until) = to_conjunction(&a.current_state, &b.current_state) {
+            events.push((until, Reason::Conjunction));
+        }
+    }
+
+    let hold = events
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(until, reason)| (warp_for(until, step.0), reason))
+        .filter(|(warp, _)| *warp < MAX_WARP);
+
+    let before = governor.warp(speed.0);
+    governor.hold = hold;
+    let after = governor.warp(speed.0);
+    if after < before {
+        if let Some((_, reason)) = hold {
+            info!(
+                "Warp held at {}x for {}",
+                after,
+                reason.describe(Language::English)
+            );
+        }
+    } else if after > before {
+        info!("Warp back up to {}x", after);
+    }
+}
+
+// 6 turns the governor on and off
+pub fn toggle_governor(keyboard: Res<Input<KeyCode>>, mut governor: ResMut<Governor>) {
+    if keyboard.just_pressed(KeyCode::Key6) {
+        governor.enabled = !governor.enabled;
+        info!(
+            "Warp governor {}",
+            if governor.enabled { "on" } else { "off" }
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// Point masses pulling on the bodies: the earth, fixed at the origin, and every body heavy
+// enough to matter, like the Moon, from `--moon`, the console or a file. Sources are taken where
+// they are at the start of each tick, so under a high warp the others see them move in jumps.
+// The lookaheads fly through them where their own lookaheads have them; bodies on rails follow
+// the earth alone. Each massive body is drawn with its sphere of influence, inside which its
+// pull on a craft matters more than the earth's, the region a lunar flyby bends in.
+//
+// The earth is a point mass unless Perturbations says otherwise: `--j2`, or `set_j2("on")`, adds
+// the J2 term of its equatorial bulge. Orbits here lie in the equatorial plane, so it shows as
+// apsidal precession, the line of apsides turning the way the orbit goes at about 8° a day in a
+// low orbit, with a slightly shorter period; nodal regression needs an inclined orbit, which
+// the plane doesn't have. The predictions include the bulge too, and analytic bodies are
+// integrated while it is on; bodies on rails keep to their unperturbed orbits.
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::elements::MU;
+use crate::ephemeris::{moon_position, Epoch};
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::style::BodyStyle;
+use crate::{Body, BodyIds, Precision, SimTime, Source, EARTH, EARTH_RADIUS, J2_EARTH, MASS_EARTH};
+
+const MIN_MASS: Precision = 1e12; // kg, lighter bodies don't pull on the others
+const MOON_MASS: Precision = 7.342e22; // kg
+const MOON_DISTANCE: Precision = 3.844e8; // m
+const SPHERE_SEGMENTS: usize = 128;
+
+// The departures from point masses the gravity includes
+#[derive(Resource, Default, Clone, Copy)]
+pub struct Perturbations {
+    pub j2: bool, // the earth's bulge
+}
+
+impl Perturbations {
+    // The earth as a source, for what is flown around it alone
+    pub fn earth(&self) -> Source {
+        Source {
+            j2: if self.j2 { J2_EARTH } else { 0.0 },
+            ..EARTH
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct GravitySource {
+    pub mass: Precision,
+    pub position: DVec2,
+    oblate: bool, // with a bulge when Perturbations has one, only the earth
+}
+
+impl GravitySource {
+    // For bodies heavy enough to be one
+    pub fn of(body: &Body) -> Option<Self> {
+        (body.mass >= MIN_MASS).then_some(Self {
+            mass: body.mass,
+            position: DVec2::new(body.current_state.x, body.current_state.y),
+            oblate: false,
+        })
+    }
+
+    pub fn source(&self, perturbations: &Perturbations) -> Source {
+        Source {
+            j2: if self.oblate && perturbations.j2 {
+                J2_EARTH
+            } else {
+                0.0
+            },
+            ..point_mass(self.mass, self.position.x, self.position.y)
+        }
+    }
+
+    // Laplace's radius, from the earth's distance and the two masses; 0 for the earth itself
+    pub fn sphere_of_influence(&self) -> Precision {
+        self.position.length() * (self.mass / MASS_EARTH).powf(0.4)
+    }
+}
+
+pub fn point_mass(mass: Precision, x: Precision, y: Precision) -> Source {
+    Source {
+        mass,
+        x,
+        y,
+        j2: 0.0,
+        radius: EARTH_RADIUS,
+    }
+}
+
+pub fn spawn_earth(mut commands: Commands) {
+    commands.spawn((
+        GravitySource {
+            mass: MASS_EARTH,
+            position: DVec2::ZERO,
+            oblate: true,
+        },
+        Name::new("Earth"),
+    ));
+}
+
+// The Moon on a circular orbit the way the real one goes, starting in the direction the
+// ephemeris has it at `time`
+// The name the Moon goes by, which it keeps through quicksaves and scenario files
+pub const MOON: &str = "Moon";
+
+pub fn spawn_moon(
+    commands: &mut Commands,
+    ids: &mut BodyIds,
+    time: Precision,
+    epoch: &Epoch,
+) -> usize {
+    let [x, y, _] = moon_position(epoch.terrestrial_time(time));
+    let direction = DVec2::new(x, y).normalize();
+    let (position, velocity) = (
+        direction * MOON_DISTANCE,
+        direction.perp() * (MU / MOON_DISTANCE).sqrt(),
+    );
+    let body = Body::new(
+        ids.next(),
+        MOON_MASS,
+        position.x,
+        position.y,
+        velocity.x,
+        velocity.y,
+    );
+    let id = body.id;
+    let source = GravitySource::of(&body).unwrap();
+    commands.spawn((body, source, Name::new(MOON), BodyStyle::default()));
+    id
+}
+
+// --moon starts with it
+pub fn add_moon(
+    mut commands: Commands,
+    mut ids: ResMut<BodyIds>,
+    time: Res<SimTime>,
+    epoch: Res<Epoch>,
+) {
+    let id = spawn_moon(&mut commands, &mut ids, time.0, &epoch);
+    info!("Starting with the Moon as body {}", id);
+}
+
+// Massive bodies carry their pull with them, once they have moved
+pub fn follow_sources(mut sources: Query<(&Body, &mut GravitySource)>) {
+    for (body, mut source) in sources.iter_mut() {
+        source.position = DVec2::new(body.current_state.x, body.current_state.y);
+    }
+}
+
+pub fn draw_spheres_of_influence(
+    mut gizmos: Gizmos,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+    sources: Query<(&Body, &GravitySource), Without<Crashed>>,
+) {
+    let color = palette.colors().faint;
+    for (body, source) in sources.iter() {
+        let radius = source.sphere_of_influence() as f32;
+        if radius <= 0.0 {
+            continue;
+        }
+        gizmos
+            .circle_2d(interpolation.position(body), radius, color)
+            .segments(SPHERE_SEGMENTS);
+    }
+}
@@ -0,0 +1,260 @@
+// Ground stations on the surface and their links to the bodies they can see, coloured by
+// the margin of their link budget. The stations turn with the earth, at its sidereal rate from
+// the epoch's Greenwich sidereal time, so a body sees them come and go under it.
+use std::f64::consts::FRAC_PI_2;
+use std::fs::File;
+use std::io::{self, Write};
+
+use bevy::prelude::*;
+
+use crate::antenna::{self, Antenna, LinkBudget, Pointing};
+use crate::ephemeris::{sidereal_angle, Epoch};
+use crate::frames::EARTH_ROTATION;
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::{Body, Precision, SimTime, State, EARTH_RADIUS};
+
+pub const SPEED_OF_LIGHT: Precision = 299_792_458.0; // m/s
+const MIN_ELEVATION: Precision = 0.0; // rad
+const EXPORT_PATH: &str = "links.csv";
+
+#[derive(Component)]
+pub struct GroundStation {
+    pub name: String,
+    pub longitude: Precision, // rad, east of Greenwich
+    angle: Precision,         // rad, from the +x axis now, as far as the earth has turned
+}
+
+// Geometry of the line of sight from a station to a body
+pub struct Link {
+    pub range: Precision,      // m
+    pub range_rate: Precision, // m/s, positive when receding
+    pub elevation: Precision,  // rad
+    pub azimuth: Precision,    // rad; in the orbital plane it's either east or west
+}
+
+impl Link {
+    pub fn doppler(&self, frequency: Precision) -> Precision {
+        -frequency * self.range_rate / SPEED_OF_LIGHT
+    }
+
+    pub fn visible(&self) -> bool {
+        self.elevation > MIN_ELEVATION
+    }
+}
+
+impl GroundStation {
+    pub fn position(&self) -> (Precision, Precision) {
+        self.position_in(0.0)
+    }
+
+    // Where the station is `seconds` from now, negative for before
+    pub fn position_in(&self, seconds: Precision) -> (Precision, Precision) {
+        let (sin, cos) = (self.angle + EARTH_ROTATION * seconds).sin_cos();
+        (EARTH_RADIUS * cos, EARTH_RADIUS * sin)
+    }
+
+    pub fn link(&self, state: &State) -> Link {
+        self.link_in(state, 0.0)
+    }
+
+    // The line of sight to a body at `state` `seconds` from now
+    pub fn link_in(&self, state: &State, seconds: Precision) -> Link {
+        let (sx, sy) = self.position_in(seconds);
+        let (dx, dy) = (state.x - sx, state.y - sy);
+        let range = (dx * dx + dy * dy).sqrt();
+
+        // The station moves east with the surface
+        let (svx, svy) = (-EARTH_ROTATION * sy, EARTH_ROTATION * sx);
+        let range_rate = (dx * (state.vx - svx) + dy * (state.vy - svy)) / range;
+
+        // Local topocentric frame: up, and east towards increasing longitude
+        let (upx, upy) = (sx / EARTH_RADIUS, sy / EARTH_RADIUS);
+        let (eastx, easty) = (-upy, upx);
+
+        let elevation = ((dx * upx + dy * upy) / range).asin();
+        let azimuth = if dx * eastx + dy * easty >= 0.0 {
+            FRAC_PI_2
+        } else {
+            3.0 * FRAC_PI_2
+        };
+
+        Link {
+            range,
+            range_rate,
+            elevation,
+            azimuth,
+        }
+    }
+}
+
+// A tracking Yagi, a steerable dish and a fixed patch looking up
+pub fn add_stations(mut commands: Commands) {
+    for (name, longitude, peak_gain, beamwidth, pointing) in [
+        ("Buenos Aires", -58.4, 14.0, 30.0, Pointing::Tracking),
+        ("Madrid", -3.7, 25.0, 10.0, Pointing::Tracking),
+        ("Canberra", 149.1, 6.0, 70.0, Pointing::Zenith),
+    ] {
+        commands.spawn((
+            GroundStation {
+                name: name.to_string(),
+                longitude: Precision::to_radians(longitude),
+                angle: Precision::to_radians(longitude),
+            },
+            Antenna {
+                peak_gain,
+                beamwidth: Precision::to_radians(beamwidth),
+                pointing,
+            },
+        ));
+    }
+}
+
+// Each tick, after the step, turns the stations to where the earth has got to
+pub fn rotate_stations(
+    time: Res<SimTime>,
+    epoch: Res<Epoch>,
+    mut stations: Query<&mut GroundStation>,
+) {
+    let turned = sidereal_angle(epoch.julian_date(time.0));
+    for mut station in stations.iter_mut() {
+        station.angle = station.longitude + turned;
+    }
+}
+
+struct LinkSample {
+    time: Precision,
+    station: String,
+    body: usize,
+    range: Precision,
+    range_rate: Precision,
+    doppler: Precision,
+    margin: Precision,
+}
+
+// Every visible link at every step, for export
+#[derive(Resource, Default)]
+pub struct LinkLog(Vec<LinkSample>);
+
+impl LinkLog {
+    fn export(&self, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(
+            file,
+            "time, station, body, range, range_rate, doppler, margin"
+        )?;
+
+        for sample in self.0.iter() {
+            writeln!(
+                file,
+                "{}, {}, {}, {}, {}, {}, {}",
+                sample.time,
+                sample.station,
+                sample.body,
+                sample.range,
+                sample.range_rate,
+                sample.doppler,
+                sample.margin
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+pub struct StationText;
+
+pub fn draw_stations(
+    mut gizmos: Gizmos,
+    palette: Res<Palette>,
+    budget: Res<LinkBudget>,
+    interpolation: Interpolation,
+    stations: Query<(&GroundStation, &Antenna)>,
+    bodies: Query<(&Body, Option<&Antenna>)>,
+) {
+    let colors = palette.colors();
+    let color = colors.station;
+    for (station, receiver) in stations.iter() {
+        let (sx, sy) = station.position();
+        let station_position = interpolation.point(sx, sy);
+
+        gizmos.circle_2d(station_position, 80000.0, color);
+
+        for (body, transmitter) in bodies.iter() {
+            let state = body.current_state;
+            if station.link(&state).visible() {
+                let margin = budget.margin(transmitter, &state, receiver, station);
+                gizmos.line_2d(
+                    station_position,
+                    interpolation.position(body),
+                    antenna::margin_color(margin, colors.warning, color),
+                );
+            }
+        }
+    }
+}
+
+pub fn update_links(
+    time: Res<SimTime>,
+    budget: Res<LinkBudget>,
+    mut log: ResMut<LinkLog>,
+    stations: Query<(&GroundStation, &Antenna)>,
+    bodies: Query<(&Body, Option<&Antenna>)>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<StationText>>,
+) {
+    let mut value = String::new();
+
+    for (station, receiver) in stations.iter() {
+        value.push_str(&format!("{}\n", station.name));
+
+        for (body, transmitter) in bodies.iter() {
+            let state = body.current_state;
+            let link = station.link(&state);
+            if !link.visible() {
+                continue;
+            }
+
+            let doppler = link.doppler(budget.frequency);
+            let margin = budget.margin(transmitter, &state, receiver, station);
+
+            value.push_str(&language.text(
+                "link",
+                &[
+                    &body.id,
+                    &format!("{:.1}", link.range / 1000.0),
+                    &format!("{:+.1}", link.range_rate),
+                    &format!("{:+.0}", doppler),
+                    &format!("{:+.1}", margin),
+                ],
+            ));
+            value.push('\n');
+
+            log.0.push(LinkSample {
+                time: time.0,
+                station: station.name.clone(),
+                body: body.id,
+                range: link.range,
+                range_rate: link.range_rate,
+                doppler,
+                margin,
+            });
+        }
+    }
+
+    text.single_mut().sections[0].value = value;
+}
+
+// G writes every link sample so far to a CSV file
+pub fn export_links(keyboard: Res<Input<KeyCode>>, log: Res<LinkLog>) {
+    if !keyboard.just_pressed(KeyCode::G) {
+        return;
+    }
+
+    match log.export(EXPORT_PATH) {
+        Ok(()) => info!("Link log written to {}", EXPORT_PATH),
+        Err(error) => error!("Could not write {}: {}", EXPORT_PATH, error),
+    }
+}
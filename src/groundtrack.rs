@@ -0,0 +1,192 @@
+// Ground tracks: the point on the earth under each body, in longitude east of Greenwich and
+// latitude, as the earth turns beneath the orbit. F3 shows the selected body's, how far west
+// its track moves each orbit and where its lookahead takes it over the next two hours. Every
+// body's point is sampled each minute of sim time, and F4, or `export_ground_track("path")`,
+// writes the samples so far to a CSV file. Orbits here lie in the equatorial plane, so every
+// latitude is 0.
+use std::f64::consts::{PI, TAU};
+use std::fs::File;
+use std::io::{self, Write};
+
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::elements::OrbitalElements;
+use crate::ephemeris::{sidereal_angle, Epoch};
+use crate::frames::EARTH_ROTATION;
+use crate::gravity::GravitySource;
+use crate::i18n::Language;
+use crate::prediction::PredictedTrajectory;
+use crate::{Body, Precision, Selected, SimTime, State, EARTH_RADIUS};
+
+const SAMPLE_INTERVAL: Precision = 60.0; // s of sim time between samples
+const AHEAD_INTERVAL: Precision = 1200.0; // s between the points ahead in the panel
+const AHEAD_POINTS: usize = 6;
+const EXPORT_PATH: &str = "ground_track.csv";
+
+// Where on the earth a body is overhead
+#[derive(Clone, Copy)]
+pub struct SubPoint {
+    pub longitude: Precision, // rad, east of Greenwich, -π to π
+    pub latitude: Precision,  // rad
+    pub altitude: Precision,  // m
+}
+
+impl SubPoint {
+    pub fn of(state: &State, time: Precision, epoch: &Epoch) -> Self {
+        let angle = state.y.atan2(state.x) - sidereal_angle(epoch.julian_date(time));
+        Self {
+            longitude: PI - (PI - angle).rem_euclid(TAU),
+            latitude: 0.0,
+            altitude: state.x.hypot(state.y) - EARTH_RADIUS,
+        }
+    }
+
+    fn describe(&self, language: Language) -> String {
+        let longitude = self.longitude.to_degrees();
+        let latitude = self.latitude.to_degrees();
+        let east = if longitude >= 0.0 { "east" } else { "west" };
+        let north = if latitude >= 0.0 { "north" } else { "south" };
+        format!(
+            "{:.1}° {}, {:.1}° {}",
+            longitude.abs(),
+            language.text(east, &[]),
+            latitude.abs(),
+            language.text(north, &[])
+        )
+    }
+}
+
+struct TrackSample {
+    time: Precision,
+    body: usize,
+    point: SubPoint,
+}
+
+#[derive(Resource, Default)]
+pub struct GroundTrack {
+    pub shown: bool,
+    samples: Vec<TrackSample>,
+    sampled_at: Option<Precision>,
+}
+
+impl GroundTrack {
+    // The number of samples written
+    pub fn export(&self, path: &str, epoch: &Epoch) -> io::Result<usize> {
+        let mut file = File::create(path)?;
+        writeln!(file, "time, epoch, body, longitude, latitude, altitude")?;
+        for sample in &self.samples {
+            writeln!(
+                file,
+                "{}, {}, {}, {}, {}, {}",
+                sample.time,
+                epoch.timestamp(sample.time),
+                sample.body,
+                sample.point.longitude.to_degrees(),
+                sample.point.latitude.to_degrees(),
+                sample.point.altitude
+            )?;
+        }
+        Ok(self.samples.len())
+    }
+}
+
+type Tracked = (Without<GravitySource>, Without<Crashed>);
+
+// Every SAMPLE_INTERVAL, or every frame if the warp takes longer over one
+pub fn record_ground_track(
+    time: Res<SimTime>,
+    epoch: Res<Epoch>,
+    mut track: ResMut<GroundTrack>,
+    bodies: Query<&Body, Tracked>,
+) {
+    if track
+        .sampled_at
+        .is_some_and(|sampled_at| time.0 - sampled_at < SAMPLE_INTERVAL)
+    {
+        return;
+    }
+    track.sampled_at = Some(time.0);
+
+    let samples = bodies.iter().map(|body| TrackSample {
+        time: time.0,
+        body: body.id,
+        point: SubPoint::of(&body.current_state, time.0, &epoch),
+    });
+    track.samples.extend(samples);
+}
+
+// F3 shows or hides the panel
+pub fn toggle_ground_track(keyboard: Res<Input<KeyCode>>, mut track: ResMut<GroundTrack>) {
+    if keyboard.just_pressed(KeyCode::F3) {
+        track.shown = !track.shown;
+    }
+}
+
+// F4 writes the samples
+pub fn export_ground_track(
+    keyboard: Res<Input<KeyCode>>,
+    epoch: Res<Epoch>,
+    track: Res<GroundTrack>,
+) {
+    if !keyboard.just_pressed(KeyCode::F4) {
+        return;
+    }
+    match track.export(EXPORT_PATH, &epoch) {
+        Ok(count) => info!("{} ground track samples written to {}", count, EXPORT_PATH),
+        Err(error) => error!("Could not write {}: {}", EXPORT_PATH, error),
+    }
+}
+
+#[derive(Component)]
+pub struct GroundTrackText;
+
+type Shown<'a> = (&'a Body, Option<&'a PredictedTrajectory>);
+
+pub fn show_ground_track(
+    (time, epoch): (Res<SimTime>, Res<Epoch>),
+    track: Res<GroundTrack>,
+    language: Res<Language>,
+    selected: Query<Shown, (With<Selected>, Without<Crashed>)>,
+    mut text: Query<&mut Text, With<GroundTrackText>>,
+) {
+    let mut value = String::new();
+    if let (true, Ok((body, prediction))) = (track.shown, selected.get_single()) {
+        let state = body.current_state;
+        let point = SubPoint::of(&state, time.0, &epoch);
+        value = language.text(
+            "ground_track",
+            &[
+                &point.describe(*language),
+                &format!("{:.0}", point.altitude / 1000.0),
+            ],
+        );
+
+        // The earth turns east under the orbit while it goes round once
+        if let Some(period) = OrbitalElements::from_state(&state).period() {
+            let shift = (EARTH_ROTATION * period).to_degrees() % 360.0;
+            value.push('\n');
+            value.push_str(&language.text("ground_track_shift", &[&format!("{:.1}", shift)]));
+        }
+
+        let ahead: Vec<String> = (1..=AHEAD_POINTS)
+            .map(|i| i as Precision * AHEAD_INTERVAL)
+            .filter_map(|seconds| {
+                let prediction = prediction?;
+                let step = (seconds / prediction.dt()).round() as usize;
+                let state = prediction.states().get(step)?;
+                let point = SubPoint::of(state, time.0 + seconds, &epoch);
+                Some(format!(
+                    "+{:.0} min {}",
+                    seconds / 60.0,
+                    point.describe(*language)
+                ))
+            })
+            .collect();
+        if !ahead.is_empty() {
+            value.push('\n');
+            value.push_str(&ahead.join("\n"));
+        }
+    }
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,158 @@
+// Headless runs: `--headless <days>` propagates the starting bodies, the --scenario's or the
+// station, without Bevy or a window, and writes their trajectories to a CSV file for numerical
+// experiments. Bodies coast under the earth, with its bulge with --j2, each other's pull if
+// they are heavy enough and drag, with the integrator --integrator picks and steps of --dt;
+// every --sample seconds each body gets a row with its state and elements. A body that hits
+// the ground stops there.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::atmosphere::DragSettings;
+use crate::elements::OrbitalElements;
+use crate::ephemeris::Epoch;
+use crate::fidelity::Fidelity;
+use crate::gravity::{GravitySource, Perturbations};
+use crate::{Body, Integrator, Precision, Source, Thrust};
+
+pub const DEFAULT_OUTPUT: &str = "trajectory.csv";
+pub const DEFAULT_SAMPLE: Precision = 60.0; // s
+const DAY: Precision = 86400.0; // s
+
+pub struct Run<'a> {
+    pub bodies: Vec<(Option<String>, Body)>,
+    pub days: Precision,
+    pub sample: Precision, // s between rows
+    pub dt: Precision,
+    pub output: String,
+    pub integrator: &'a dyn Integrator,
+    pub perturbations: Perturbations,
+}
+
+fn write_row(
+    file: &mut impl Write,
+    epoch: &Epoch,
+    time: Precision,
+    (name, body): &(Option<String>, Body),
+) -> io::Result<()> {
+    let state = body.current_state;
+    let elements = OrbitalElements::from_state(&state);
+    let apoapsis = elements
+        .apoapsis_altitude()
+        .map_or_else(String::new, |altitude| altitude.to_string());
+    writeln!(
+        file,
+        "{}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}",
+        time,
+        epoch.timestamp(time),
+        body.id,
+        name.as_deref().unwrap_or(""),
+        state.x,
+        state.y,
+        state.vx,
+        state.vy,
+        elements.a,
+        elements.e,
+        elements.argument_of_periapsis.to_degrees(),
+        elements.mean_anomaly.to_degrees(),
+        elements.periapsis_altitude(),
+        apoapsis
+    )
+}
+
+impl Run<'_> {
+    fn write(&mut self) -> io::Result<usize> {
+        let epoch = Epoch::default();
+        let atmosphere = DragSettings::default().atmosphere;
+        let dt = self.dt;
+        let steps = (self.days * DAY / dt).ceil() as usize;
+        let stride = (self.sample / dt).round().max(1.0) as usize;
+
+        let mut file = BufWriter::new(File::create(&self.output)?);
+        writeln!(
+            file,
+            "time, epoch, body, name, x, y, vx, vy, semi_major_axis, eccentricity, \
+            argument_of_periapsis, mean_anomaly, periapsis_altitude, apoapsis_altitude"
+        )?;
+        let mut rows = 0;
+        for body in &self.bodies {
+            write_row(&mut file, &epoch, 0.0, body)?;
+            rows += 1;
+        }
+
+        let mut crashed = vec![false; self.bodies.len()];
+        for i in 1..=steps {
+            let time = i as Precision * dt;
+            // Every mass but the body's own pulls on it, where it was at the start of the step
+            let masses: Vec<(usize, Source)> = self
+                .bodies
+                .iter()
+                .enumerate()
+                .filter_map(|(j, (_, body))| {
+                    Some((j, GravitySource::of(body)?.source(&self.perturbations)))
+                })
+                .collect();
+
+            for (j, (name, body)) in self.bodies.iter_mut().enumerate() {
+                if crashed[j] {
+                    continue;
+                }
+                let sources: Vec<Source> = std::iter::once(self.perturbations.earth())
+                    .chain(
+                        masses
+                            .iter()
+                            .filter(|(k, _)| *k != j)
+                            .map(|(_, source)| *source),
+                    )
+                    .collect();
+                let crossing = Fidelity::Standard.advance(
+                    body,
+                    Thrust::default(),
+                    1,
+                    &sources,
+                    atmosphere,
+                    (self.integrator, dt),
+                );
+                if let Some(crossing) = crossing {
+                    crashed[j] = true;
+                    println!(
+                        "body {}{} hit the ground at t = {:.0} s",
+                        body.id,
+                        name.as_ref()
+                            .map_or_else(String::new, |name| format!(" ({})", name)),
+                        time - dt + crossing.time
+                    );
+                }
+            }
+
+            if i % stride == 0 || i == steps {
+                for (j, body) in self.bodies.iter().enumerate() {
+                    if !crashed[j] {
+                        write_row(&mut file, &epoch, time, body)?;
+                        rows += 1;
+                    }
+                }
+            }
+        }
+        file.flush()?;
+        Ok(rows)
+    }
+}
+
+pub fn run(mut run: Run) -> i32 {
+    match run.write() {
+        Ok(rows) => {
+            println!(
+                "{} rows for {} bodies over {} days written to {}",
+                rows,
+                run.bodies.len(),
+                run.days,
+                run.output
+            );
+            0
+        }
+        Err(error) => {
+            eprintln!("could not write {}: {}", run.output, error);
+            1
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// Spacecraft health: a battery charged by a solar array while the body is in sunlight and
+// drained by its load, failures of the engine, the downlink and the battery, and a panel that
+// gathers the state of every subsystem a body has. Failures happen at random or from scripts,
+// and a flat battery shuts down the payload until the Sun charges it again. Q shows
+// the panel for the selected body.
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{Engine, EngineFault, RandomFaults};
+use crate::ephemeris::Ephemeris;
+use crate::i18n::Language;
+use crate::quicksave::Loaded;
+use crate::radiation::Radiation;
+use crate::recorder::{CommsFault, Recorder};
+use crate::thermal::Thermal;
+use crate::{Body, Precision, Selected, SimTime, TimeStep};
+
+// Chance per step that a healthy transmitter or battery fails on its own
+const FAILURE_PROBABILITY: f64 = 1e-5;
+const HOUR: Precision = 3600.0;
+
+// A solar array and a battery sized for a small satellite in LEO, with a margin over the
+// eclipses
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct Power {
+    pub capacity: Precision, // Wh when new
+    pub health: Precision,   // fraction of the capacity the cells still hold
+    pub charge: Precision,   // Wh
+    pub array: Precision,    // W generated in sunlight
+    pub load: Precision,     // W drawn by everything on board
+    updated: Option<Precision>,
+}
+
+impl Power {
+    pub fn new(capacity: Precision, array: Precision, load: Precision) -> Self {
+        Self {
+            capacity,
+            health: 1.0,
+            charge: capacity,
+            array,
+            load,
+            updated: None,
+        }
+    }
+
+    pub fn flat(&self) -> bool {
+        self.charge <= 0.0
+    }
+
+    fn usable(&self) -> Precision {
+        self.capacity * self.health
+    }
+}
+
+impl Default for Power {
+    fn default() -> Self {
+        Self::new(80.0, 150.0, 60.0)
+    }
+}
+
+// A subsystem breaking, or being repaired with a nominal fault or a battery back at 1
+#[derive(Clone, Copy, Debug)]
+pub enum Failure {
+    Engine(EngineFault),
+    Comms(CommsFault),
+    Battery(Precision), // fraction of the capacity left
+}
+
+impl Failure {
+    // False when the body doesn't have the subsystem
+    pub fn apply(
+        self,
+        body: &Body,
+        engine: Option<&mut Engine>,
+        recorder: Option<&mut Recorder>,
+        power: Option<&mut Power>,
+        time: Precision,
+    ) -> bool {
+        match self {
+            Failure::Engine(fault) => engine.map(|engine| engine.set_fault(fault, body, time)),
+            Failure::Comms(fault) => recorder.map(|recorder| {
+                recorder.fault = fault;
+                info!(
+                    "t = {:.0} s: transmitter of body {} is now {:?}",
+                    time, body.id, fault
+                );
+            }),
+            Failure::Battery(health) => power.map(|power| {
+                power.health = health;
+                power.charge = power.charge.min(power.usable());
+                info!(
+                    "t = {:.0} s: battery of body {} now holds {:.0}% of its capacity",
+                    time,
+                    body.id,
+                    100.0 * health
+                );
+            }),
+        }
+        .is_some()
+    }
+}
+
+// Healthy transmitters and batteries occasionally fail on their own, like engines do: with the
+// chance of every step run since the last tick, unless `set_faults("off")` stops them
+pub fn random_failures(
+    (time, step): (Res<SimTime>, Res<TimeStep>),
+    mut faults: ResMut<RandomFaults>,
+    (mut last, mut loaded): (Local<Option<Precision>>, EventReader<Loaded>),
+    mut query: Query<(&Body, Option<&mut Recorder>, Option<&mut Power>)>,
+) {
+    if loaded.read().count() > 0 {
+        *last = None;
+    }
+    let steps = (time.0 - last.replace(time.0).unwrap_or(time.0)) / step.0;
+    if !faults.enabled || steps <= 0.0 {
+        return;
+    }
+    let chance = 1.0 - (1.0 - FAILURE_PROBABILITY).powf(steps);
+    let rng = &mut faults.rng;
+
+    for (body, mut recorder, mut power) in query.iter_mut() {
+        let comms_healthy = recorder
+            .as_ref()
+            .is_some_and(|recorder| recorder.fault == CommsFault::Nominal);
+        if comms_healthy && rng.gen_bool(chance) {
+            let fault = if rng.gen_bool(0.5) {
+                CommsFault::Reduced(rng.gen_range(0.1..0.9))
+            } else {
+                CommsFault::Dead
+            };
+            Failure::Comms(fault).apply(body, None, recorder.as_deref_mut(), None, time.0);
+        }
+        let battery_healthy = power.as_ref().is_some_and(|power| power.health == 1.0);
+        if battery_healthy && rng.gen_bool(chance) {
+            let failure = Failure::Battery(rng.gen_range(0.3..0.9));
+            failure.apply(body, None, None, power.as_deref_mut(), time.0);
+        }
+    }
+}
+
+// Charges or drains each battery over the time since the last frame, in the light or shadow
+// the body is in now
+pub fn update_power(
+    time: Res<SimTime>,
+    ephemeris: Res<Ephemeris>,
+    mut query: Query<(&Body, &mut Power)>,
+) {
+    for (body, mut power) in query.iter_mut() {
+        let elapsed = power.updated.map_or(0.0, |updated| time.0 - updated);
+        power.updated = Some(time.0);
+        if elapsed <= 0.0 {
+            continue;
+        }
+
+        let sunlit = !ephemeris.in_eclipse(&body.current_state);
+        let generated = if sunlit { power.array } else { 0.0 };
+        let was_flat = power.flat();
+        power.charge =
+            (power.charge + (generated - power.load) * elapsed / HOUR).clamp(0.0, power.usable());
+
+        if power.flat() && !was_flat {
+            warn!(
+                "t = {:.0} s: battery of body {} is flat, payload off",
+                time.0, body.id
+            );
+        } else if was_flat && !power.flat() {
+            info!(
+                "t = {:.0} s: battery of body {} is charging again, payload on",
+                time.0, body.id
+            );
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct HealthPanel {
+    pub shown: bool,
+}
+
+pub fn toggle_health(keyboard: Res<Input<KeyCode>>, mut panel: ResMut<HealthPanel>) {
+    if keyboard.just_pressed(KeyCode::Q) {
+        panel.shown = !panel.shown;
+    }
+}
+
+#[derive(Component)]
+pub struct HealthText;
+
+type Subsystems = (
+    &'static Body,
+    Option<&'static Power>,
+    Option<&'static Thermal>,
+    Option<&'static Recorder>,
+    Option<&'static Engine>,
+);
+
+// A line per subsystem the body has
+fn describe(
+    (body, power, thermal, recorder, engine): (
+        &Body,
+        Option<&Power>,
+        Option<&Thermal>,
+        Option<&Recorder>,
+        Option<&Engine>,
+    ),
+    radiation: &Radiation,
+    language: Language,
+) -> String {
+    let mut lines = vec![language.text("health", &[&body.id])];
+    if let Some(power) = power {
+        let state = if power.flat() {
+            "power_flat"
+        } else if power.charge >= power.usable() {
+            "power_full"
+        } else {
+            "power_ok"
+        };
+        lines.push(language.text(
+            "health_power",
+            &[
+                &format!("{:.0}", 100.0 * power.charge / power.usable()),
+                &format!("{:.0}", power.usable()),
+                &language.text(state, &[]),
+            ],
+        ));
+    }
+    if let Some(thermal) = thermal {
+        lines.push(language.text(
+            "health_thermal",
+            &[
+                &format!("{:.1}", thermal.celsius()),
+                &thermal.status.describe(language),
+            ],
+        ));
+    }
+    lines.push(language.text(
+        "health_radiation",
+        &[&format!("{:.2}", radiation.dose(body.id))],
+    ));
+    if let Some(recorder) = recorder {
+        let contact = if recorder.contact() {
+            "recorder_contact"
+        } else {
+            "recorder_no_contact"
+        };
+        lines.push(language.text(
+            "health_comms",
+            &[
+                &recorder.fault.describe(language),
+                &language.text(contact, &[]),
+            ],
+        ));
+    }
+    if let Some(engine) = engine {
+        let fault = engine
+            .fault
+            .describe(language)
+            .unwrap_or_else(|| language.text("health_nominal", &[]));
+        lines.push(language.text("health_engine", &[&fault]));
+    }
+    lines.join("\n")
+}
+
+pub fn show_health(
+    panel: Res<HealthPanel>,
+    radiation: Res<Radiation>,
+    language: Res<Language>,
+    selected: Query<Subsystems, With<Selected>>,
+    mut text: Query<&mut Text, With<HealthText>>,
+) {
+    let mut value = String::new();
+    if let (true, Ok(subsystems)) = (panel.shown, selected.get_single()) {
+        value = describe(subsystems, &radiation, *language);
+    }
+    text.single_mut().sections[0].value = value;
+}
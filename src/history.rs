@@ -0,0 +1,218 @@
+// Strip charts of the selected body's osculating elements over time, with a least-squares
+// drift rate for each so perturbations show up as numbers and not just as wobbles
+use std::f64::consts::{PI, TAU};
+
+use bevy::prelude::*;
+
+use crate::elements::OrbitalElements;
+use crate::i18n::Language;
+use crate::palette::Palette;
+use crate::{Body, Precision, Selected, SimTime};
+
+const SAMPLE_INTERVAL: Precision = 60.0; // s
+const MAX_SAMPLES: usize = 1000;
+const MIN_FIT_SAMPLES: usize = 10;
+const SIGNIFICANCE: Precision = 3.0; // standard errors for a drift to be flagged
+const DAY: Precision = 86400.0;
+// Chart placement as fractions of the visible area, from its bottom-right corner
+const CHART_WIDTH: f32 = 0.35;
+const STRIP_HEIGHT: f32 = 0.08;
+const MARGIN: f32 = 0.02;
+
+#[derive(Resource, Default)]
+pub struct ElementHistory {
+    pub shown: bool,
+    body: Option<Entity>,
+    // Time and (a, e, ω) with ω unwrapped so that it drifts continuously
+    samples: Vec<(Precision, [Precision; 3])>,
+}
+
+#[derive(Component)]
+pub struct DriftText;
+
+struct Drift {
+    rate: Precision, // per second
+    significant: bool,
+}
+
+// Least-squares slope and whether it stands out from the scatter around the fit
+fn fit_drift(samples: &[(Precision, [Precision; 3])], element: usize) -> Option<Drift> {
+    if samples.len() < MIN_FIT_SAMPLES {
+        return None;
+    }
+
+    let n = samples.len() as Precision;
+    let mean_t = samples.iter().map(|(t, _)| t).sum::<Precision>() / n;
+    let mean_y = samples.iter().map(|(_, y)| y[element]).sum::<Precision>() / n;
+    let stt: Precision = samples.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+    let sty: Precision = samples
+        .iter()
+        .map(|(t, y)| (t - mean_t) * (y[element] - mean_y))
+        .sum();
+    if stt == 0.0 {
+        return None;
+    }
+
+    let rate = sty / stt;
+    let residuals: Precision = samples
+        .iter()
+        .map(|(t, y)| (y[element] - mean_y - rate * (t - mean_t)).powi(2))
+        .sum();
+    let standard_error = (residuals / (n - 2.0) / stt).sqrt();
+
+    Some(Drift {
+        rate,
+        significant: rate.abs() > SIGNIFICANCE * standard_error,
+    })
+}
+
+// H toggles the charts
+pub fn toggle_history(keyboard: Res<Input<KeyCode>>, mut history: ResMut<ElementHistory>) {
+    if keyboard.just_pressed(KeyCode::H) {
+        history.shown = !history.shown;
+    }
+}
+
+pub fn record_history(
+    time: Res<SimTime>,
+    mut history: ResMut<ElementHistory>,
+    selected: Query<(Entity, &Body), With<Selected>>,
+) {
+    let Ok((entity, body)) = selected.get_single() else {
+        return;
+    };
+
+    // Start over when another body is selected
+    if history.body != Some(entity) {
+        history.body = Some(entity);
+        history.samples.clear();
+    }
+    if let Some((last, _)) = history.samples.last() {
+        if time.0 - last < SAMPLE_INTERVAL {
+            return;
+        }
+    }
+
+    let elements = OrbitalElements::from_state(&body.current_state);
+    let mut omega = elements.argument_of_periapsis;
+    if let Some((_, [_, _, previous])) = history.samples.last() {
+        omega = previous + (PI - (PI - (omega - previous)).rem_euclid(TAU));
+    }
+
+    if history.samples.len() == MAX_SAMPLES {
+        history.samples.remove(0);
+    }
+    history
+        .samples
+        .push((time.0, [elements.a, elements.e, omega]));
+}
+
+// One strip per element along the bottom-right of the view, each scaled to its own range,
+// with the fitted drift over it
+pub fn draw_history(
+    mut gizmos: Gizmos,
+    history: Res<ElementHistory>,
+    palette: Res<Palette>,
+    camera: Query<(&Transform, &OrthographicProjection)>,
+) {
+    if !history.shown || history.samples.len() < 2 {
+        return;
+    }
+
+    let (transform, projection) = camera.single();
+    let area = projection.area;
+    let (width, height) = (area.width(), area.height());
+    let corner = transform.translation.truncate() + Vec2::new(area.max.x, area.min.y);
+    let size = Vec2::new(CHART_WIDTH * width, STRIP_HEIGHT * height);
+
+    let colors = palette.colors();
+    let samples = &history.samples;
+    let (start, end) = (samples[0].0, samples[samples.len() - 1].0);
+
+    for element in 0..3 {
+        let origin = corner
+            + Vec2::new(
+                -MARGIN * width - size.x,
+                MARGIN * height + element as f32 * (size.y + MARGIN * height),
+            );
+        gizmos.rect_2d(origin + size / 2.0, 0.0, size, colors.guide);
+
+        let (low, high) = samples
+            .iter()
+            .map(|(_, y)| y[element])
+            .fold((Precision::MAX, Precision::MIN), |(low, high), y| {
+                (low.min(y), high.max(y))
+            });
+        let span = if high > low { high - low } else { 1.0 };
+        let point = |t: Precision, y: Precision| {
+            origin
+                + Vec2::new(
+                    ((t - start) / (end - start)) as f32 * size.x,
+                    ((y - low) / span) as f32 * size.y,
+                )
+        };
+
+        gizmos.linestrip_2d(
+            samples.iter().map(|(t, y)| point(*t, y[element])),
+            colors.earth,
+        );
+
+        if let Some(drift) = fit_drift(samples, element) {
+            let n = samples.len() as Precision;
+            let mean_t = samples.iter().map(|(t, _)| t).sum::<Precision>() / n;
+            let mean_y = samples.iter().map(|(_, y)| y[element]).sum::<Precision>() / n;
+            let color = if drift.significant {
+                colors.warning
+            } else {
+                colors.guide
+            };
+            gizmos.line_2d(
+                point(start, mean_y + drift.rate * (start - mean_t)),
+                point(end, mean_y + drift.rate * (end - mean_t)),
+                color,
+            );
+        }
+    }
+}
+
+pub fn show_drift(
+    history: Res<ElementHistory>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<DriftText>>,
+) {
+    let mut value = String::new();
+
+    if history.shown {
+        let samples = &history.samples;
+        let span = match (samples.first(), samples.last()) {
+            (Some((start, _)), Some((end, _))) => end - start,
+            _ => 0.0,
+        };
+        value = language.text("element_history", &[&format!("{:.2}", span / DAY)]);
+
+        let per_day = language.text("per_day", &[]);
+        let rows = [
+            ("a", 1.0 / 1000.0, "km"),
+            ("e", 1.0, ""),
+            ("ω", (180.0 / PI), "°"),
+        ];
+        for (element, (name, scale, unit)) in rows.iter().enumerate() {
+            if let Some(drift) = fit_drift(samples, element) {
+                value.push('\n');
+                value.push_str(&language.text(
+                    "drift",
+                    &[
+                        name,
+                        &format!("{:+.3e}", drift.rate * DAY * scale),
+                        &format!("{}{}", unit, per_day),
+                    ],
+                ));
+                if drift.significant {
+                    value.push_str(&language.text("secular", &[]));
+                }
+            }
+        }
+    }
+
+    text.single_mut().sections[0].value = value;
+}
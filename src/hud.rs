@@ -0,0 +1,204 @@
+// On-screen text panels, stacked in a column in the top-left corner, and the console below
+use bevy::prelude::*;
+
+use crate::avoidance::{AcceptButton, AvoidanceText};
+use crate::calculator::CalculatorText;
+use crate::challenge::ChallengeText;
+use crate::conservation::ConservationText;
+use crate::console::ConsoleText;
+use crate::coverage::CoverageText;
+use crate::cr3bp::ThreeBodyText;
+use crate::demo::DemoText;
+use crate::elements::ElementsText;
+use crate::ephemeris::EphemerisText;
+use crate::formation::RelativeElementsText;
+use crate::ghost::GhostText;
+use crate::ground::StationText;
+use crate::groundtrack::GroundTrackText;
+use crate::health::HealthText;
+use crate::history::DriftText;
+use crate::i18n::Language;
+use crate::impact::ImpactText;
+use crate::isl::TopologyText;
+use crate::kalman::KalmanText;
+use crate::loading::{LoadingBar, LoadingFill, LoadingText};
+use crate::logbook::LogText;
+use crate::nodes::NodeText;
+use crate::passes::{PassText, WarpButton};
+use crate::postmortem::CrashText;
+use crate::radiation::RadiationText;
+use crate::recorder::RecorderText;
+use crate::regime::RegimeText;
+use crate::repeat::RepeatText;
+use crate::sky::SkyText;
+use crate::thermal::ThermalText;
+use crate::Precision;
+
+pub fn panel() -> TextBundle {
+    TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 16.0,
+            color: Color::BLACK,
+            ..default()
+        },
+    )
+}
+
+// Button labels follow the language, the panels set theirs every frame
+pub fn label_buttons(
+    language: Res<Language>,
+    warp: Query<&Children, With<WarpButton>>,
+    accept: Query<&Children, With<AcceptButton>>,
+    mut labels: Query<&mut Text>,
+) {
+    let warp = warp.iter().map(|children| ("warp_button", children));
+    let accept = accept.iter().map(|children| ("accept_button", children));
+    for (key, children) in warp.chain(accept) {
+        for child in children.iter() {
+            if let Ok(mut label) = labels.get_mut(*child) {
+                label.sections[0].value = language.text(key, &[]);
+            }
+        }
+    }
+}
+
+// Signed h:mm:ss
+pub fn format_duration(seconds: Precision) -> String {
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    let total = seconds.abs().round() as u64;
+
+    format!(
+        "{}{}:{:02}:{:02}",
+        sign,
+        total / 3600,
+        total / 60 % 60,
+        total % 60
+    )
+}
+
+fn button(hud: &mut ChildBuilder, label: &str, marker: impl Component) {
+    hud.spawn((
+        ButtonBundle {
+            style: Style {
+                padding: UiRect::all(Val::Px(4.0)),
+                align_self: AlignSelf::FlexStart,
+                ..default()
+            },
+            background_color: Color::GRAY.into(),
+            ..default()
+        },
+        marker,
+    ))
+    .with_children(|button| {
+        button.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font_size: 16.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+    });
+}
+
+pub fn setup_hud(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                top: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|hud| {
+            hud.spawn((panel(), DemoText));
+            hud.spawn((panel(), ChallengeText));
+            hud.spawn((panel(), EphemerisText));
+            hud.spawn((panel(), ElementsText));
+            hud.spawn((panel(), NodeText));
+            hud.spawn((panel(), SkyText));
+            hud.spawn((panel(), ThreeBodyText));
+            hud.spawn((panel(), RelativeElementsText));
+            hud.spawn((panel(), CoverageText));
+            hud.spawn((panel(), StationText));
+            hud.spawn((panel(), TopologyText));
+            hud.spawn((panel(), PassText));
+            hud.spawn((panel(), ImpactText));
+            hud.spawn((panel(), AvoidanceText));
+            hud.spawn((panel(), DriftText));
+            hud.spawn((panel(), KalmanText));
+            hud.spawn((panel(), ConservationText));
+            hud.spawn((panel(), RepeatText));
+            hud.spawn((panel(), GroundTrackText));
+            hud.spawn((panel(), GhostText));
+            hud.spawn((panel(), LogText));
+            hud.spawn((panel(), CrashText));
+            hud.spawn((panel(), RadiationText));
+            hud.spawn((panel(), ThermalText));
+            hud.spawn((panel(), RecorderText));
+            hud.spawn((panel(), HealthText));
+            hud.spawn((panel(), RegimeText));
+            hud.spawn((panel(), CalculatorText));
+            button(hud, "", WarpButton);
+            button(hud, "", AcceptButton);
+        });
+
+    commands.spawn((
+        panel().with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            ..default()
+        }),
+        ConsoleText,
+    ));
+
+    // Progress of the bodies loaded from a file, hidden when there are none left
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    display: Display::None,
+                    ..default()
+                },
+                ..default()
+            },
+            LoadingBar,
+        ))
+        .with_children(|bar| {
+            bar.spawn((panel(), LoadingText));
+            bar.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(200.0),
+                    height: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::GRAY.into(),
+                ..default()
+            })
+            .with_children(|track| {
+                track.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: Color::BLUE.into(),
+                        ..default()
+                    },
+                    LoadingFill,
+                ));
+            });
+        });
+}
@@ -0,0 +1,451 @@
+// Translations of the HUD text. Each message has a key and a template per language, with
+// {0}, {1}, ... standing for its arguments so translations can reorder them. Console
+// replies stay in English, like the commands themselves.
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+// Key, English, Spanish
+const MESSAGES: &[(&str, &str, &str)] = &[
+    (
+        "sun_moon",
+        "Sun at {0}°, Moon at {1}° ({2} km)",
+        "Sol a {0}°, Luna a {1}° ({2} km)",
+    ),
+    ("in_eclipse", "body {0} in eclipse", "cuerpo {0} en eclipse"),
+    ("sky_view", "Sky view from {0}", "Vista del cielo desde {0}"),
+    (
+        "lyapunov_orbit",
+        "{0} Lyapunov orbit {1}: C = {2}, period = {3} days, x0 = {4} km from {0}",
+        "Órbita de Lyapunov {0} n.º {1}: C = {2}, período = {3} días, x0 = {4} km de {0}",
+    ),
+    (
+        "no_lyapunov_orbit",
+        "No {0} Lyapunov orbit found",
+        "No se encontró una órbita de Lyapunov {0}",
+    ),
+    ("jacobi", "body {0}: C = {1}", "cuerpo {0}: C = {1}"),
+    (
+        "relative_elements",
+        "Deputy {0} / chief {1}\na δa: {2} m\nδe: ({3}, {4})\nδi: ({5}, {6})\n\
+         along-track: {7} m",
+        "Seguidor {0} / líder {1}\na δa: {2} m\nδe: ({3}, {4})\nδi: ({5}, {6})\n\
+         a lo largo de la trayectoria: {7} m",
+    ),
+    (
+        "formation_keeping",
+        "keeping {0} m, Δv used: {1} m/s",
+        "manteniendo {0} m, Δv usado: {1} m/s",
+    ),
+    (
+        "coverage",
+        "Coverage over {0} s: {1}%, max gap {2} s",
+        "Cobertura en {0} s: {1}%, brecha máxima {2} s",
+    ),
+    (
+        "link",
+        "  body {0}: {1} km, {2} m/s, {3} Hz, margin {4} dB",
+        "  cuerpo {0}: {1} km, {2} m/s, {3} Hz, margen {4} dB",
+    ),
+    (
+        "passes",
+        "Passes (AOS, LOS, max el.)",
+        "Pasos (AOS, LOS, elev. máx.)",
+    ),
+    ("warp_button", "Warp to next pass", "Saltar al próximo paso"),
+    ("accept_button", "Accept burn", "Aceptar encendido"),
+    (
+        "avoidance_warning",
+        "Conjunction of body {0} with body {1} in {2}: miss {3} m, risk {4}",
+        "Conjunción del cuerpo {0} con el cuerpo {1} en {2}: distancia {3} m, riesgo {4}",
+    ),
+    (
+        "avoidance_burn",
+        "avoidance burn in {0}: {1} m/s prograde, {2} m/s outward, for a miss of {3} m",
+        "encendido de evasión en {0}: {1} m/s progrado, {2} m/s hacia fuera, \
+         para una distancia de {3} m",
+    ),
+    (
+        "avoidance_late",
+        "too close for an avoidance burn",
+        "demasiado cerca para un encendido de evasión",
+    ),
+    (
+        "demo_station",
+        "The station on its orbit around the earth",
+        "La estación en su órbita alrededor de la tierra",
+    ),
+    (
+        "demo_follow",
+        "Up close: the camera follows the station",
+        "De cerca: la cámara sigue a la estación",
+    ),
+    (
+        "demo_transfer",
+        "A Hohmann transfer: two burns from low orbit up to geostationary",
+        "Una transferencia de Hohmann: dos encendidos desde la órbita baja a la geoestacionaria",
+    ),
+    (
+        "demo_swarm",
+        "A swarm of four craft spreads out around its orbit",
+        "Un enjambre de cuatro naves se reparte a lo largo de su órbita",
+    ),
+    (
+        "demo_perspective",
+        "The same orbits, seen from an angle",
+        "Las mismas órbitas, vistas en perspectiva",
+    ),
+    (
+        "demo_hint",
+        "Press any key to take the controls",
+        "Pulsa cualquier tecla para tomar los controles",
+    ),
+    (
+        "impact",
+        "impact in {0} at {1}°{2}, {3} m/s",
+        "impacto en {0} a {1}°{2}, {3} m/s",
+    ),
+    ("footprint", ", footprint ±{0} km", ", huella ±{0} km"),
+    ("body", "Body {0}", "Cuerpo {0}"),
+    (
+        "elements",
+        "Body {0}: a = {1} km, e = {2}, periapsis {3} km",
+        "Cuerpo {0}: a = {1} km, e = {2}, periapsis {3} km",
+    ),
+    (
+        "elements_closed",
+        "apoapsis {0} km, T = {1}, true anomaly {2}°",
+        "apoapsis {0} km, T = {1}, anomalía verdadera {2}°",
+    ),
+    (
+        "elements_crashed",
+        "Body {0} crashed at {1}, at {2} m/s",
+        "El cuerpo {0} se estrelló a las {1}, a {2} m/s",
+    ),
+    (
+        "elements_propellant",
+        "Δv left {0} m/s, {1} kg of propellant",
+        "Δv restante {0} m/s, {1} kg de propelente",
+    ),
+    ("elements_tank_empty", "out of propellant", "sin propelente"),
+    (
+        "node",
+        "Node in {0} s: {1} m/s prograde, {2} m/s outward (Page Up/Down, Home/End, Delete)",
+        "Nodo en {0} s: {1} m/s progrado, {2} m/s hacia afuera (Re Pág/Av Pág, Inicio/Fin, Supr)",
+    ),
+    (
+        "node_inertial",
+        "Node in {0} s: ({1}, {2}) m/s inertial (Page Up/Down, Home/End, Delete)",
+        "Nodo en {0} s: ({1}, {2}) m/s inercial (Re Pág/Av Pág, Inicio/Fin, Supr)",
+    ),
+    (
+        "topology",
+        "Inter-satellite links: {0} within {1} km, {2} hops to a gateway on average, {3} cut off",
+        "Enlaces entre satélites: {0} a menos de {1} km, {2} saltos a una pasarela en promedio, \
+        {3} aislados",
+    ),
+    ("apoapsis_marker", "Ap {0} km", "Ap {0} km"),
+    ("periapsis_marker", "Pe {0} km", "Pe {0} km"),
+    (
+        "elements_escaping",
+        "escaping on a hyperbolic orbit",
+        "escapando en una órbita hiperbólica",
+    ),
+    ("east", "E", "E"),
+    ("west", "W", "O"),
+    ("north", "N", "N"),
+    ("south", "S", "S"),
+    (
+        "element_history",
+        "Element history over {0} days",
+        "Historial de elementos en {0} días",
+    ),
+    (
+        "crash_report",
+        "Body {0} crashed at t = {1}, at {2} m/s, after {3} of flight, with {4} m/s of Δv used. \
+         Before entry: a = {5} km, e = {6}, periapsis at {7} km",
+        "El cuerpo {0} se estrelló en t = {1}, a {2} m/s, tras {3} de vuelo, con {4} m/s de Δv \
+         usados. Antes de la entrada: a = {5} km, e = {6}, periapsis a {7} km",
+    ),
+    (
+        "crash_export",
+        "B writes the report with the last 10 minutes of telemetry",
+        "B guarda el informe con los últimos 10 minutos de telemetría",
+    ),
+    (
+        "radiation",
+        "Body {0}: {1} mGy of radiation so far, {2} mGy/h here",
+        "Cuerpo {0}: {1} mGy de radiación hasta ahora, {2} mGy/h aquí",
+    ),
+    ("drift", "{0} drift: {1} {2}", "deriva de {0}: {1} {2}"),
+    ("secular", " (secular)", " (secular)"),
+    ("per_day", "/day", "/día"),
+    ("regimes", "Regimes: {0}", "Regímenes: {0}"),
+    ("hidden", " (hidden)", " (oculto)"),
+    ("escape", "escape", "escape"),
+    (
+        "calculator",
+        "Calculator, μ = {0} m³/s²",
+        "Calculadora, μ = {0} m³/s²",
+    ),
+    (
+        "calculator_body",
+        "Body {0} at r = {1} km: circular {2} m/s, escape {3} m/s",
+        "Cuerpo {0} a r = {1} km: circular {2} m/s, escape {3} m/s",
+    ),
+    (
+        "calculator_hohmann",
+        "Hohmann from r to GEO: Δv {0} + {1} = {2} m/s, {3} of flight",
+        "Hohmann de r a GEO: Δv {0} + {1} = {2} m/s, {3} de vuelo",
+    ),
+    ("calculator_console", "Console: {0}", "Consola: {0}"),
+    (
+        "scenario",
+        "Scenario {0}: reach {1} x {2} km\nΔv used {3} of {4} m/s, {5}",
+        "Escenario {0}: alcanzar {1} x {2} km\nΔv usado {3} de {4} m/s, {5}",
+    ),
+    (
+        "scenario_flying",
+        "R for a new scenario",
+        "R para un escenario nuevo",
+    ),
+    (
+        "scenario_reached",
+        "target reached! R for the next scenario",
+        "¡objetivo alcanzado! R para el siguiente escenario",
+    ),
+    (
+        "scenario_over_budget",
+        "over budget, R for the next scenario",
+        "presupuesto superado, R para el siguiente escenario",
+    ),
+    ("reading", "Reading {0}", "Leyendo {0}"),
+    (
+        "loading",
+        "Loading {0}: {1} of {2} bodies",
+        "Cargando {0}: {1} de {2} cuerpos",
+    ),
+    (
+        "kalman",
+        "Kalman filter on body {0}, {1} measurements\n\
+         position error {2} m (σ {3} m), velocity error {4} m/s (σ {5} m/s)",
+        "Filtro de Kalman sobre el cuerpo {0}, {1} mediciones\n\
+         error de posición {2} m (σ {3} m), error de velocidad {4} m/s (σ {5} m/s)",
+    ),
+    (
+        "kalman_waiting",
+        "Kalman filter: waiting for measurements of the selected body",
+        "Filtro de Kalman: esperando mediciones del cuerpo seleccionado",
+    ),
+    (
+        "narration",
+        "{0}: body {1} at {2} km altitude, {3} km/s, periapsis {4} km",
+        "{0}: cuerpo {1} a {2} km de altitud, {3} km/s, periapsis {4} km",
+    ),
+    (
+        "narration_apoapsis",
+        ", apoapsis {0} km",
+        ", apoapsis {0} km",
+    ),
+    (
+        "narration_escaping",
+        ", on an escape orbit",
+        ", en una órbita de escape",
+    ),
+    ("warning", "Warning: {0}", "Aviso: {0}"),
+    (
+        "narration_conjunction",
+        "body {0} within {1} m of body {2}",
+        "cuerpo {0} a {1} m del cuerpo {2}",
+    ),
+    (
+        "narration_crash",
+        "body {0} crashed at {1} m/s",
+        "cuerpo {0} se estrelló a {1} m/s",
+    ),
+    (
+        "recorder",
+        "Body {0} recorder: {1}% full, {2} of {3} MB, {4} MB downlinked, {5} MB lost, {6}",
+        "Registrador del cuerpo {0}: {1}% lleno, {2} de {3} MB, {4} MB descargados, \
+        {5} MB perdidos, {6}",
+    ),
+    ("recorder_contact", "downlinking", "descargando"),
+    ("recorder_no_contact", "no contact", "sin contacto"),
+    (
+        "thermal",
+        "Body {0}: {1} °C, {2}",
+        "Cuerpo {0}: {1} °C, {2}",
+    ),
+    ("thermal_nominal", "within limits", "dentro de los límites"),
+    (
+        "thermal_cold",
+        "too cold to operate",
+        "demasiado frío para operar",
+    ),
+    (
+        "thermal_hot",
+        "too hot to operate",
+        "demasiado caliente para operar",
+    ),
+    (
+        "thermal_failed",
+        "thermal failure, engine off",
+        "falla térmica, motor apagado",
+    ),
+    (
+        "conservation",
+        "Energy {0} J/kg, drift {1}\nAngular momentum {2} m²/s, drift {3}",
+        "Energía {0} J/kg, deriva {1}\nMomento angular {2} m²/s, deriva {3}",
+    ),
+    (
+        "conservation_worst",
+        "Worst drift over {0} bodies: energy {1}, angular momentum {2}",
+        "Mayor deriva en {0} cuerpos: energía {1}, momento angular {2}",
+    ),
+    (
+        "repeat_cycle",
+        "Ground track: {0} orbits a sidereal day, repeats after {1} orbits in {2} days, {3} km off",
+        "Traza: {0} órbitas por día sidéreo, se repite tras {1} órbitas en {2} días, a {3} km",
+    ),
+    (
+        "repeat_nearest",
+        "Ground track: {0} orbits a sidereal day, {3} km off repeating after {1} orbits in {2} days",
+        "Traza: {0} órbitas por día sidéreo, a {3} km de repetirse tras {1} órbitas en {2} días",
+    ),
+    (
+        "repeat_nudge",
+        "{0} m/s prograde now puts it on the repeat orbit",
+        "{0} m/s prógrados ahora la llevan a la órbita de repetición",
+    ),
+    (
+        "repeat_escape",
+        "Ground track: escape orbit, never repeats",
+        "Traza: órbita de escape, nunca se repite",
+    ),
+    (
+        "ground_track",
+        "Over {0}, {1} km up",
+        "Sobre {0}, a {1} km de altura",
+    ),
+    (
+        "ground_track_shift",
+        "The track moves {0}° west each orbit",
+        "La traza se corre {0}° al oeste en cada órbita",
+    ),
+    (
+        "ghost",
+        "Ghost {0}, {1}: Δv {2} m/s, {3} km from the body, a = {4} km, e = {5}",
+        "Fantasma {0}, {1}: Δv {2} m/s, a {3} km del cuerpo, a = {4} km, e = {5}",
+    ),
+    (
+        "ghost_crashed",
+        "Ghost {0}, {1}: crashed after Δv {2} m/s",
+        "Fantasma {0}, {1}: estrellado tras Δv {2} m/s",
+    ),
+    ("ghosts_none", "no ghosts", "sin fantasmas"),
+    ("log_empty", "Mission log: nothing yet", "Bitácora: nada todavía"),
+    (
+        "log_header",
+        "Mission log, session {0}: {1} to {2} of {3}",
+        "Bitácora, sesión {0}: {1} a {2} de {3}",
+    ),
+    ("log_search", " with \"{0}\"", " con \"{0}\""),
+    ("log_event", "{0}  body {1}: {2}", "{0}  cuerpo {1}: {2}"),
+    ("log_note", "{0}  note: {1}", "{0}  nota: {1}"),
+    ("governed", "{0}x, held at {1}x for {2}", "{0}x, frenado a {1}x por {2}"),
+    ("governor_burn", "a burn", "un encendido"),
+    ("governor_periapsis", "periapsis", "el periapsis"),
+    ("governor_conjunction", "a conjunction", "una conjunción"),
+    ("governor_soi", "a sphere of influence", "una esfera de influencia"),
+    ("health", "Body {0} health", "Estado del cuerpo {0}"),
+    ("health_nominal", "nominal", "normal"),
+    (
+        "health_power",
+        "power: {0}% of {1} Wh, {2}",
+        "energía: {0}% de {1} Wh, {2}",
+    ),
+    ("power_full", "fully charged", "carga completa"),
+    ("power_ok", "on battery", "con batería"),
+    (
+        "power_flat",
+        "flat, payload off",
+        "agotada, carga útil apagada",
+    ),
+    (
+        "health_thermal",
+        "thermal: {0} °C, {1}",
+        "térmico: {0} °C, {1}",
+    ),
+    (
+        "health_radiation",
+        "radiation: {0} mGy",
+        "radiación: {0} mGy",
+    ),
+    (
+        "health_comms",
+        "comms: {0}, {1}",
+        "comunicaciones: {0}, {1}",
+    ),
+    (
+        "comms_reduced",
+        "downlink at {0}% of its rate",
+        "bajada al {0}% de su tasa",
+    ),
+    ("comms_dead", "transmitter dead", "transmisor muerto"),
+    ("health_engine", "engine: {0}", "motor: {0}"),
+    ("engine_stuck", "engine stuck off", "motor trabado apagado"),
+    (
+        "engine_reduced",
+        "engine at {0}% thrust",
+        "motor al {0}% de empuje",
+    ),
+    (
+        "engine_misaligned",
+        "engine misaligned by {0}°",
+        "motor desalineado {0}°",
+    ),
+    (
+        "insertion_hint",
+        "Orbit insertion: enter the target periapsis altitude (m) and eccentricity",
+        "Inserción orbital: ingrese la altitud del periapsis (m) y la excentricidad buscadas",
+    ),
+];
+
+impl Language {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "en" | "english" => Ok(Language::English),
+            "es" | "spanish" | "español" => Ok(Language::Spanish),
+            _ => Err(format!(
+                "unknown language `{}`, expected \"en\" or \"es\"",
+                name
+            )),
+        }
+    }
+
+    // The message with its arguments filled in. Unknown keys come out as they are.
+    pub fn text(self, key: &str, arguments: &[&dyn fmt::Display]) -> String {
+        let template = MESSAGES.iter().find(|(other, ..)| *other == key).map_or(
+            key,
+            |(_, english, spanish)| match self {
+                Language::English => english,
+                Language::Spanish => spanish,
+            },
+        );
+
+        let mut text = template.to_string();
+        for (i, argument) in arguments.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", i), &argument.to_string());
+        }
+        text
+    }
+}
@@ -0,0 +1,237 @@
+// Predicted impact points of bodies whose coasting trajectory runs into the surface, flown like
+// every other look ahead and with the air the body feels, with a Monte Carlo footprint from
+// velocity knowledge errors. The point hit is a place on the turning earth: its longitude comes
+// from the sidereal time at impact, and it is drawn where that longitude is now.
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::crash::Crashed;
+use crate::ephemeris::{sidereal_angle, Epoch};
+use crate::hud::format_duration;
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::propagator::{Coast, Coasting};
+use crate::{Body, Lookahead, Precision, SimTime, State, EARTH_RADIUS};
+
+const REFRESH_INTERVAL: Precision = 60.0; // s
+const SAMPLES: usize = 30;
+const VELOCITY_SIGMA: Precision = 1.0; // m/s per axis
+
+pub struct Impact {
+    pub time: Precision,      // sim time
+    pub longitude: Precision, // rad, east of Greenwich
+    pub speed: Precision,
+    pub footprint: Option<Precision>, // rad, two standard deviations along the surface
+}
+
+impl Impact {
+    pub fn describe(&self, now: Precision, language: Language) -> String {
+        let longitude = self.longitude.to_degrees();
+        let side = if longitude >= 0.0 { "east" } else { "west" };
+        let mut text = language.text(
+            "impact",
+            &[
+                &format_duration(self.time - now),
+                &format!("{:.1}", longitude.abs()),
+                &language.text(side, &[]),
+                &format!("{:.0}", self.speed),
+            ],
+        );
+        if let Some(footprint) = self.footprint {
+            text.push_str(&language.text(
+                "footprint",
+                &[&format!("{:.0}", footprint * EARTH_RADIUS / 1000.0)],
+            ));
+        }
+        text
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ImpactPredictions {
+    impacts: HashMap<Entity, Impact>,
+    computed_at: Option<Precision>,
+}
+
+impl ImpactPredictions {
+    pub fn get(&self, entity: Entity) -> Option<&Impact> {
+        self.impacts.get(&entity)
+    }
+}
+
+#[derive(Component)]
+pub struct ImpactText;
+
+fn radius(state: &State) -> Precision {
+    (state.x * state.x + state.y * state.y).sqrt()
+}
+
+// Time from now and state at the first crossing of the surface within `steps` steps
+fn find_impact(mut coast: Coast, steps: usize) -> Option<(Precision, State)> {
+    let mut state = coast.state;
+    if radius(&state) <= EARTH_RADIUS {
+        return None;
+    }
+
+    for i in 0..steps {
+        let next = coast.step();
+        let (r0, r1) = (radius(&state), radius(&next));
+
+        if r1 < EARTH_RADIUS {
+            // Linear interpolation within the step
+            let f = (r0 - EARTH_RADIUS) / (r0 - r1);
+            let at = |a: Precision, b: Precision| a + f * (b - a);
+            let state = State::new(
+                at(state.x, next.x),
+                at(state.y, next.y),
+                at(state.vx, next.vx),
+                at(state.vy, next.vy),
+            );
+            return Some(((i as Precision + f) * coast.dt(), state));
+        }
+
+        state = next;
+    }
+
+    None
+}
+
+fn gaussian(rng: &mut impl Rng) -> Precision {
+    let (u1, u2): (Precision, Precision) = (rng.gen_range(Precision::EPSILON..1.0), rng.gen());
+    (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+// Wrapped to (-π, π]
+fn wrap(angle: Precision) -> Precision {
+    PI - (PI - angle).rem_euclid(TAU)
+}
+
+pub fn predict_impacts(
+    (time, epoch, lookahead): (Res<SimTime>, Res<Epoch>, Res<Lookahead>),
+    mut predictions: ResMut<ImpactPredictions>,
+    coasting: Coasting,
+    bodies: Query<(Entity, &Body), Without<Crashed>>,
+) {
+    if let Some(computed_at) = predictions.computed_at {
+        if time.0 - computed_at < REFRESH_INTERVAL {
+            return;
+        }
+    }
+    predictions.computed_at = Some(time.0);
+    predictions.impacts.clear();
+
+    let mut rng = rand::thread_rng();
+    let propagator = coasting.propagator();
+
+    for (entity, body) in bodies.iter() {
+        let coast = propagator.coast(body, time.0);
+        let Some((delay, at_impact)) = find_impact(coast, lookahead.0) else {
+            continue;
+        };
+        let impact_time = time.0 + delay;
+        let longitude = |state: &State, time: Precision| {
+            state.y.atan2(state.x) - sidereal_angle(epoch.julian_date(time))
+        };
+        let ground = longitude(&at_impact, impact_time);
+
+        // Spread of the impact point on the ground over velocity errors, as longitudes from the
+        // nominal one
+        let offsets: Vec<Precision> = (0..SAMPLES)
+            .filter_map(|_| {
+                let mut coast = coast;
+                coast.state.vx += VELOCITY_SIGMA * gaussian(&mut rng);
+                coast.state.vy += VELOCITY_SIGMA * gaussian(&mut rng);
+                find_impact(coast, lookahead.0)
+                    .map(|(delay, s)| wrap(longitude(&s, time.0 + delay) - ground))
+            })
+            .collect();
+
+        let footprint = (offsets.len() > 1).then(|| {
+            let n = offsets.len() as Precision;
+            let mean = offsets.iter().sum::<Precision>() / n;
+            let variance = offsets
+                .iter()
+                .map(|o| (o - mean).powi(2))
+                .sum::<Precision>()
+                / (n - 1.0);
+            2.0 * variance.sqrt()
+        });
+
+        predictions.impacts.insert(
+            entity,
+            Impact {
+                time: impact_time,
+                longitude: wrap(ground),
+                speed: (at_impact.vx * at_impact.vx + at_impact.vy * at_impact.vy).sqrt(),
+                footprint,
+            },
+        );
+    }
+}
+
+// Cross at the impact point, where the earth has turned it to now, and an arc over the footprint
+pub fn draw_impacts(
+    mut gizmos: Gizmos,
+    (time, epoch): (Res<SimTime>, Res<Epoch>),
+    predictions: Res<ImpactPredictions>,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+) {
+    let color = palette.colors().prediction;
+    let radius = EARTH_RADIUS as f32;
+    let centre = interpolation.point(0.0, 0.0);
+    let sidereal = sidereal_angle(epoch.julian_date(time.0));
+
+    for impact in predictions.impacts.values() {
+        let (sin, cos) = (impact.longitude + sidereal).sin_cos();
+        let point = interpolation.point(cos * EARTH_RADIUS, sin * EARTH_RADIUS);
+        let size = 150000.0;
+        gizmos.line_2d(point - Vec2::splat(size), point + Vec2::splat(size), color);
+        gizmos.line_2d(
+            point + Vec2::new(-size, size),
+            point + Vec2::new(size, -size),
+            color,
+        );
+
+        if let Some(footprint) = impact.footprint {
+            // Gizmo arcs measure their direction clockwise from +y
+            let along = point - centre;
+            gizmos.arc_2d(
+                centre,
+                (FRAC_PI_2 - along.y.atan2(along.x) as Precision) as f32,
+                2.0 * footprint as f32,
+                radius * 1.02,
+                color,
+            );
+        }
+    }
+}
+
+pub fn show_impacts(
+    time: Res<SimTime>,
+    predictions: Res<ImpactPredictions>,
+    bodies: Query<&Body>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<ImpactText>>,
+) {
+    let mut value = String::new();
+
+    for (entity, impact) in predictions.impacts.iter() {
+        let Ok(body) = bodies.get(*entity) else {
+            continue;
+        };
+
+        value.push_str(&format!(
+            "{} {}",
+            language.text("body", &[&body.id]),
+            impact.describe(time.0, *language)
+        ));
+        value.push('\n');
+    }
+
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,93 @@
+// Orbit insertion wizard: the two prograde or retrograde burns that take a body from its
+// orbit into one with a given periapsis altitude and eccentricity. The first burn, at the
+// next periapsis, moves the opposite apsis to the target apoapsis, and the second, half a
+// transfer orbit later, sets the periapsis. A circular target makes them the usual raise
+// and circularisation.
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::console::Console;
+use crate::elements::{OrbitalElements, MU};
+use crate::i18n::Language;
+use crate::maneuver::{Frame, ManeuverNode};
+use crate::{Body, Precision, Selected, State, EARTH_RADIUS};
+
+const MIN_BURN: Precision = 1e-3; // m/s, smaller burns are left out
+
+fn speed(r: Precision, a: Precision) -> Precision {
+    (MU * (2.0 / r - 1.0 / a)).sqrt()
+}
+
+// Node time on an integration step of `dt`, so the burn isn't applied late
+fn on_step(time: Precision, dt: Precision) -> Precision {
+    (time / dt).ceil() * dt
+}
+
+pub fn plan_insertion(
+    state: &State,
+    (now, dt): (Precision, Precision),
+    altitude: Precision,
+    eccentricity: Precision,
+) -> Result<Vec<ManeuverNode>, String> {
+    if altitude <= 0.0 {
+        return Err("the target periapsis must be above the surface".to_string());
+    }
+    if !(0.0..1.0).contains(&eccentricity) {
+        return Err("the target eccentricity must be at least 0 and below 1".to_string());
+    }
+
+    let current = OrbitalElements::from_state(state);
+    if current.e >= 1.0 || current.a <= 0.0 {
+        return Err("the body is on an escape orbit".to_string());
+    }
+
+    let periapsis = EARTH_RADIUS + altitude;
+    let apoapsis = periapsis * (1.0 + eccentricity) / (1.0 - eccentricity);
+    let target_a = (periapsis + apoapsis) / 2.0;
+
+    // First burn at the next periapsis of the current orbit
+    let n = (MU / current.a.powi(3)).sqrt();
+    let to_periapsis = (TAU - current.mean_anomaly).rem_euclid(TAU) / n;
+    let r1 = current.a * (1.0 - current.e);
+    let transfer_a = (r1 + apoapsis) / 2.0;
+    let first = ManeuverNode {
+        time: on_step(now + to_periapsis, dt),
+        dv: [speed(r1, transfer_a) - speed(r1, current.a), 0.0],
+        frame: Frame::Orbital,
+    };
+
+    // Second at the other apsis of the transfer orbit
+    let half_transfer = TAU / 2.0 * (transfer_a.powi(3) / MU).sqrt();
+    let second = ManeuverNode {
+        time: on_step(first.time + half_transfer, dt),
+        dv: [speed(apoapsis, target_a) - speed(apoapsis, transfer_a), 0.0],
+        frame: Frame::Orbital,
+    };
+
+    Ok([first, second]
+        .into_iter()
+        .filter(|node| node.dv[0].abs() >= MIN_BURN)
+        .collect())
+}
+
+// N opens the console with the insertion command for the selected body, for the target
+// orbit to be filled in
+pub fn open_insertion_wizard(
+    keyboard: Res<Input<KeyCode>>,
+    mut console: ResMut<Console>,
+    selected: Query<&Body, With<Selected>>,
+    language: Res<Language>,
+) {
+    if !keyboard.just_pressed(KeyCode::N) {
+        return;
+    }
+    let Ok(body) = selected.get_single() else {
+        return;
+    };
+
+    console.open_with(
+        &language.text("insertion_hint", &[]),
+        &format!("body({}).insert_orbit(", body.id),
+    );
+}
@@ -0,0 +1,190 @@
+// Physics runs on Bevy's fixed timestep, a tick at a time (60 a second unless --tick says
+// otherwise), whatever the frame rate. Bodies are drawn between the states at the last two
+// ticks, by how far the clock has got towards the next one, so they move smoothly when frames
+// come faster than ticks. Drawing goes through the reference frame and the floating origin as
+// well, so everything drawn with the same Interpolation lines up in it and keeps its precision.
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::ephemeris::{sidereal_angle, Epoch};
+use crate::frames::{ReferenceFrame, EARTH_ROTATION};
+use crate::origin::FloatingOrigin;
+use crate::prediction::PredictedTrajectory;
+use crate::{Body, Precision, Selected, SimTime, State, TimeStep};
+
+pub const DEFAULT_TICK: Precision = 60.0; // Hz
+
+// Every body's state before the current tick, and the sim time then
+#[derive(Resource, Default)]
+pub struct PreviousStates(HashMap<usize, State>, Precision);
+
+impl PreviousStates {
+    // Where the body is, `alpha` of the way from the last tick to the current one
+    pub fn position(&self, body: &Body, alpha: Precision) -> DVec2 {
+        let current = body.current_state;
+        let previous = self.0.get(&body.id).unwrap_or(&current);
+        DVec2::new(
+            previous.x + alpha * (current.x - previous.x),
+            previous.y + alpha * (current.y - previous.y),
+        )
+    }
+
+    // Where the body was at the start of the current tick
+    pub fn start(&self, body: &Body) -> DVec2 {
+        let previous = self.0.get(&body.id).unwrap_or(&body.current_state);
+        DVec2::new(previous.x, previous.y)
+    }
+
+    // The sim time at the start of the current tick
+    pub fn time(&self) -> Precision {
+        self.1
+    }
+}
+
+// First thing in each tick
+pub fn remember_states(
+    mut previous: ResMut<PreviousStates>,
+    time: Res<SimTime>,
+    bodies: Query<&Body>,
+) {
+    previous.0.clear();
+    previous
+        .0
+        .extend(bodies.iter().map(|body| (body.id, body.current_state)));
+    previous.1 = time.0;
+}
+
+// How far the clock is past the last tick, as a fraction of one
+pub fn alpha(fixed: &Time<Fixed>) -> Precision {
+    fixed.overstep_percentage_f64().clamp(0.0, 1.0)
+}
+
+type Centre<'a> = (&'a Body, Option<&'a PredictedTrajectory>);
+
+// Where to draw bodies this frame
+#[derive(SystemParam)]
+pub struct Interpolation<'w, 's> {
+    previous: Res<'w, PreviousStates>,
+    fixed: Res<'w, Time<Fixed>>,
+    frame: Res<'w, ReferenceFrame>,
+    time: Res<'w, SimTime>,
+    step: Res<'w, TimeStep>,
+    epoch: Res<'w, Epoch>,
+    origin: Res<'w, FloatingOrigin>,
+    selected: Query<'w, 's, Centre<'static>, With<Selected>>,
+}
+
+impl Interpolation<'_, '_> {
+    pub fn position(&self, body: &Body) -> Vec2 {
+        self.view().position(body)
+    }
+
+    // A fixed point of the inertial frame, such as the earth's centre
+    pub fn point(&self, x: Precision, y: Precision) -> Vec2 {
+        self.view().point(x, y)
+    }
+
+    // The point of the inertial frame drawn at `point`, such as where the mouse is
+    pub fn world(&self, point: Vec2) -> DVec2 {
+        self.view().world(point)
+    }
+
+    // The frame as it is this frame, for drawing many points in it
+    pub fn view(&self) -> View<'_> {
+        let alpha = alpha(&self.fixed);
+        let time = self.previous.1 + alpha * (self.time.0 - self.previous.1);
+        let angle = match *self.frame {
+            ReferenceFrame::EarthFixed => sidereal_angle(self.epoch.julian_date(time)),
+            _ => 0.0,
+        };
+        let centre = match *self.frame {
+            ReferenceFrame::BodyCentred => self.selected.get_single().ok(),
+            _ => None,
+        };
+        View {
+            previous: &self.previous,
+            alpha,
+            now: self.time.0,
+            time,
+            dt: self.step.0,
+            angle,
+            rotating: *self.frame == ReferenceFrame::EarthFixed,
+            centre,
+            origin: self.origin.0,
+        }
+    }
+}
+
+pub struct View<'a> {
+    previous: &'a PreviousStates,
+    alpha: Precision,
+    now: Precision,             // sim time of the bodies' current states
+    time: Precision,            // sim time drawn, between the last two ticks
+    dt: Precision,              // s, between the states of trails and lookaheads
+    angle: Precision,           // of the prime meridian at `time`, in the earth-fixed frame
+    rotating: bool,             // with the earth
+    centre: Option<Centre<'a>>, // the body in the middle, in the body-centred frame
+    origin: DVec2,              // the floating origin, in the frame
+}
+
+impl View<'_> {
+    // Only the difference from the floating origin, which is small on screen, goes to f32
+    fn place(&self, position: DVec2, centre: DVec2, time: Precision) -> Vec2 {
+        let mut relative = position - centre;
+        if self.rotating {
+            let angle = self.angle + EARTH_ROTATION * (time - self.time);
+            relative = DVec2::from_angle(-angle).rotate(relative);
+        }
+        (relative - self.origin).as_vec2()
+    }
+
+    fn centre(&self) -> DVec2 {
+        self.centre.map_or(DVec2::ZERO, |(centre, _)| {
+            self.previous.position(centre, self.alpha)
+        })
+    }
+
+    pub fn position(&self, body: &Body) -> Vec2 {
+        let position = self.previous.position(body, self.alpha);
+        self.place(position, self.centre(), self.time)
+    }
+
+    pub fn point(&self, x: Precision, y: Precision) -> Vec2 {
+        self.place(DVec2::new(x, y), self.centre(), self.time)
+    }
+
+    // The other way from `point`
+    pub fn world(&self, point: Vec2) -> DVec2 {
+        let mut relative = point.as_dvec2() + self.origin;
+        if self.rotating {
+            relative = DVec2::from_angle(self.angle).rotate(relative);
+        }
+        relative + self.centre()
+    }
+
+    // A state `steps` steps before the current one, from a trail
+    pub fn past(&self, state: &State, steps: usize) -> Vec2 {
+        let centre = self.centre.map_or(DVec2::ZERO, |(centre, _)| {
+            let state = centre.history.0.get(steps).copied().flatten();
+            let state = state.unwrap_or(centre.current_state);
+            DVec2::new(state.x, state.y)
+        });
+        let time = self.now - steps as Precision * self.dt;
+        self.place(DVec2::new(state.x, state.y), centre, time)
+    }
+
+    // A state `steps` steps after the current one, from a lookahead
+    pub fn ahead(&self, state: &State, steps: usize) -> Vec2 {
+        let centre = self.centre.map_or(DVec2::ZERO, |(centre, prediction)| {
+            let state = prediction
+                .and_then(|prediction| prediction.states().get(steps))
+                .unwrap_or(&centre.current_state);
+            DVec2::new(state.x, state.y)
+        });
+        let time = self.now + steps as Precision * self.dt;
+        self.place(DVec2::new(state.x, state.y), centre, time)
+    }
+}
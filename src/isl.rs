@@ -0,0 +1,174 @@
+// Inter-satellite links: which bodies can talk to each other, in range and with the line
+// between them clear of the earth and the thick of the atmosphere, and how many such hops
+// each is from a gateway, a body a ground station can see. Tab shows the link graph on the
+// orbit view, gateways circled, and the HUD sums it up: links, the average hop count to a
+// gateway and how many bodies have no way down at all.
+use std::collections::{HashMap, VecDeque};
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::broadphase::{self, Sweep};
+use crate::crash::Crashed;
+use crate::gravity::GravitySource;
+use crate::ground::GroundStation;
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::{Body, Precision, EARTH_RADIUS};
+
+pub const DEFAULT_RANGE: Precision = 5000e3; // m
+const GRAZING_ALTITUDE: Precision = 100e3; // m, lowest a link's line may pass
+const GATEWAY_RADIUS: f32 = 120000.0; // m
+
+#[derive(Resource)]
+pub struct Topology {
+    pub shown: bool,
+    pub range: Precision,
+    links: Vec<(Entity, Entity)>,
+    hops: HashMap<Entity, usize>, // to the nearest gateway, for the bodies that have one
+    bodies: usize,
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Self {
+            shown: false,
+            range: DEFAULT_RANGE,
+            links: Vec::new(),
+            hops: HashMap::new(),
+            bodies: 0,
+        }
+    }
+}
+
+impl Topology {
+    pub fn describe(&self, language: Language) -> String {
+        let cut_off = self.bodies - self.hops.len();
+        let average = if self.hops.is_empty() {
+            "-".to_string()
+        } else {
+            let total: usize = self.hops.values().sum();
+            format!("{:.2}", total as Precision / self.hops.len() as Precision)
+        };
+        language.text(
+            "topology",
+            &[
+                &self.links.len(),
+                &format!("{:.0}", self.range / 1000.0),
+                &average,
+                &cut_off,
+            ],
+        )
+    }
+}
+
+// Whether the segment between two points stays above the grazing altitude
+fn clear_line(a: DVec2, b: DVec2) -> bool {
+    let along = b - a;
+    // Closest point of the segment to the earth's centre
+    let t = (-a.dot(along) / along.length_squared()).clamp(0.0, 1.0);
+    (a + along * t).length() > EARTH_RADIUS + GRAZING_ALTITUDE
+}
+
+// Tab shows or hides the link graph, Shift+Tab being the focus's
+pub fn toggle_topology(keyboard: Res<Input<KeyCode>>, mut topology: ResMut<Topology>) {
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keyboard.just_pressed(KeyCode::Tab) && !shift {
+        topology.shown = !topology.shown;
+    }
+}
+
+type Linked = (Without<GravitySource>, Without<Crashed>);
+
+pub fn update_topology(
+    mut topology: ResMut<Topology>,
+    stations: Query<&GroundStation>,
+    bodies: Query<(Entity, &Body), Linked>,
+) {
+    let nodes: Vec<Sweep> = bodies
+        .iter()
+        .map(|(entity, body)| {
+            let state = body.current_state;
+            Sweep::at(entity, body.id, DVec2::new(state.x, state.y))
+        })
+        .collect();
+
+    // The pairs in range, from the broadphase, then the ones that can see each other
+    let mut neighbours: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    let mut links = Vec::new();
+    for (i, j, ..) in broadphase::approaches(&nodes, topology.range) {
+        let (a, b) = (nodes[i], nodes[j]);
+        if clear_line(a.from, b.from) {
+            links.push((a.entity, b.entity));
+            neighbours.entry(a.entity).or_default().push(b.entity);
+            neighbours.entry(b.entity).or_default().push(a.entity);
+        }
+    }
+
+    // Breadth-first from every gateway at once
+    let mut hops = HashMap::new();
+    let mut queue = VecDeque::new();
+    for (entity, body) in bodies.iter() {
+        let state = body.current_state;
+        if stations
+            .iter()
+            .any(|station| station.link(&state).visible())
+        {
+            hops.insert(entity, 0);
+            queue.push_back(entity);
+        }
+    }
+    while let Some(entity) = queue.pop_front() {
+        let next = hops[&entity] + 1;
+        for other in neighbours.get(&entity).into_iter().flatten() {
+            if !hops.contains_key(other) {
+                hops.insert(*other, next);
+                queue.push_back(*other);
+            }
+        }
+    }
+
+    topology.links = links;
+    topology.hops = hops;
+    topology.bodies = nodes.len();
+}
+
+pub fn draw_topology(
+    mut gizmos: Gizmos,
+    topology: Res<Topology>,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+    bodies: Query<&Body>,
+) {
+    if !topology.shown {
+        return;
+    }
+
+    let color = palette.colors().station;
+    for (a, b) in &topology.links {
+        if let (Ok(a), Ok(b)) = (bodies.get(*a), bodies.get(*b)) {
+            gizmos.line_2d(interpolation.position(a), interpolation.position(b), color);
+        }
+    }
+    for (entity, hops) in &topology.hops {
+        if let (0, Ok(body)) = (hops, bodies.get(*entity)) {
+            gizmos.circle_2d(interpolation.position(body), GATEWAY_RADIUS, color);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct TopologyText;
+
+pub fn show_topology(
+    topology: Res<Topology>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<TopologyText>>,
+) {
+    text.single_mut().sections[0].value = if topology.shown {
+        topology.describe(*language)
+    } else {
+        String::new()
+    };
+}
@@ -0,0 +1,286 @@
+// Extended Kalman filter on the selected body, fed with the station measurements as they
+// come in. Between measurements the estimate coasts with the state transition matrix and its
+// covariance grows with a little process noise; each range and angle then pulls it towards
+// the measurement. The coasts are flown like the lookaheads. The orbit view shows the estimate and its 3σ position ellipse.
+use std::f64::consts::TAU;
+
+use bevy::math::{DMat2, DMat4, DVec2, DVec4};
+use bevy::prelude::*;
+
+use crate::determination;
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::observation::{Observation, Tracking, ANGLE_NOISE, RANGE_NOISE};
+use crate::palette::Palette;
+use crate::propagator::{Coast, Coasting};
+use crate::{stm, Body, Precision, Selected, SimTime, State};
+
+const INITIAL_POSITION_SIGMA: Precision = 1000.0; // m
+const INITIAL_VELOCITY_SIGMA: Precision = 10.0; // m/s
+const PROCESS_NOISE: Precision = 1e-6; // (m/s)² of velocity variance added per second
+const ELLIPSE_SIGMAS: Precision = 3.0;
+const ELLIPSE_POINTS: usize = 48;
+
+struct Estimate {
+    time: Precision,
+    state: State,
+    covariance: DMat4,
+}
+
+impl Estimate {
+    // Along `coast`, the body's, from the estimate
+    fn coast_to(&mut self, time: Precision, mut coast: Coast) {
+        let dt = coast.dt();
+        let steps = ((time - self.time) / dt).round().max(0.0) as usize;
+        if steps == 0 {
+            return;
+        }
+        (coast.state, coast.time) = (self.state, self.time);
+        let (state, phi) = stm::propagate(coast, steps);
+        let noise = PROCESS_NOISE * steps as Precision * dt;
+        self.state = state;
+        self.covariance = phi * self.covariance * phi.transpose()
+            + DMat4::from_diagonal(DVec4::new(0.0, 0.0, noise, noise));
+        self.time += steps as Precision * dt;
+    }
+
+    // Range and angle one at a time, with the Joseph form of the covariance update
+    fn update(&mut self, observation: &Observation) {
+        let measured = [observation.range, observation.angle];
+        let variances = [RANGE_NOISE * RANGE_NOISE, ANGLE_NOISE * ANGLE_NOISE];
+
+        for i in 0..2 {
+            let (predicted, gradients) = Observation::predict(observation.station, &self.state);
+            let h = gradients[i];
+            let ph = self.covariance * h;
+            let gain = ph / (h.dot(ph) + variances[i]);
+            let correction = gain * (measured[i] - predicted[i]);
+
+            self.state = State::new(
+                self.state.x + correction.x,
+                self.state.y + correction.y,
+                self.state.vx + correction.z,
+                self.state.vy + correction.w,
+            );
+            let i_kh = DMat4::IDENTITY - outer(gain, h);
+            self.covariance =
+                i_kh * self.covariance * i_kh.transpose() + outer(gain, gain * variances[i]);
+        }
+    }
+
+    fn position_covariance(&self) -> DMat2 {
+        let p = self.covariance;
+        DMat2::from_cols(
+            DVec2::new(p.x_axis.x, p.x_axis.y),
+            DVec2::new(p.y_axis.x, p.y_axis.y),
+        )
+    }
+}
+
+fn outer(a: DVec4, b: DVec4) -> DMat4 {
+    DMat4::from_cols(a * b.x, a * b.y, a * b.z, a * b.w)
+}
+
+// J toggles the filter on the selected body
+#[derive(Resource, Default)]
+pub struct KalmanFilter {
+    pub shown: bool,
+    body: Option<Entity>,
+    estimate: Option<Estimate>,
+    track: Option<Precision>, // start of the measurements it follows
+    processed: usize,         // measurements of the body taken in so far
+}
+
+pub fn toggle_filter(keyboard: Res<Input<KeyCode>>, mut filter: ResMut<KalmanFilter>) {
+    if keyboard.just_pressed(KeyCode::J) {
+        filter.shown = !filter.shown;
+    }
+}
+
+pub fn run_filter(
+    time: Res<SimTime>,
+    tracking: Res<Tracking>,
+    mut filter: ResMut<KalmanFilter>,
+    coasting: Coasting,
+    selected: Query<(Entity, &Body), With<Selected>>,
+) {
+    let Ok((entity, body)) = selected.get_single() else {
+        return;
+    };
+    if !filter.shown {
+        return;
+    }
+
+    let observations = tracking.get(body.id);
+    let Some((started, taken)) = tracking.progress(body.id) else {
+        filter.estimate = None;
+        return;
+    };
+    // Start over on another body, or when its measurements started over after a burn
+    if filter.body != Some(entity) || filter.track != Some(started) {
+        filter.body = Some(entity);
+        filter.estimate = None;
+        filter.track = Some(started);
+        filter.processed = 0;
+    }
+
+    if filter.estimate.is_none() {
+        let Some(state) = determination::first_guess(observations) else {
+            return;
+        };
+        let (p, v) = (
+            INITIAL_POSITION_SIGMA * INITIAL_POSITION_SIGMA,
+            INITIAL_VELOCITY_SIGMA * INITIAL_VELOCITY_SIGMA,
+        );
+        filter.estimate = Some(Estimate {
+            time: observations[0].time,
+            state,
+            covariance: DMat4::from_diagonal(DVec4::new(p, p, v, v)),
+        });
+    }
+
+    // The new measurements are the last ones
+    let new = (taken - filter.processed).min(observations.len());
+    let estimate = filter.estimate.as_mut().unwrap();
+    let propagator = coasting.propagator();
+    let coast = propagator.coast(body, time.0);
+    for observation in &observations[observations.len() - new..] {
+        estimate.coast_to(observation.time, coast);
+        estimate.update(observation);
+    }
+    estimate.coast_to(time.0, coast);
+    filter.processed = taken;
+}
+
+// The estimate as a cross and its position uncertainty as an ellipse
+pub fn draw_filter(
+    mut gizmos: Gizmos,
+    filter: Res<KalmanFilter>,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+) {
+    let Some(estimate) = filter.estimate.as_ref().filter(|_| filter.shown) else {
+        return;
+    };
+    let color = palette.colors().prediction;
+    let centre = interpolation.point(estimate.state.x, estimate.state.y);
+
+    let size = 30000.0;
+    gizmos.line_2d(centre - Vec2::X * size, centre + Vec2::X * size, color);
+    gizmos.line_2d(centre - Vec2::Y * size, centre + Vec2::Y * size, color);
+
+    // Principal axes of the 2x2 position covariance
+    let p = estimate.position_covariance();
+    let (a, b, c) = (p.x_axis.x, p.x_axis.y, p.y_axis.y);
+    let mean = (a + c) / 2.0;
+    let spread = (((a - c) / 2.0).powi(2) + b * b).sqrt();
+    let (major, minor) = ((mean + spread).sqrt(), (mean - spread).max(0.0).sqrt());
+    let angle = 0.5 * (2.0 * b).atan2(a - c);
+    let (sin, cos) = angle.sin_cos();
+
+    let points = (0..=ELLIPSE_POINTS).map(|i| {
+        let t = TAU * i as Precision / ELLIPSE_POINTS as Precision;
+        let (u, v) = (
+            ELLIPSE_SIGMAS * major * t.cos(),
+            ELLIPSE_SIGMAS * minor * t.sin(),
+        );
+        centre + Vec2::new((u * cos - v * sin) as f32, (u * sin + v * cos) as f32)
+    });
+    gizmos.linestrip_2d(points, color);
+}
+
+#[derive(Component)]
+pub struct KalmanText;
+
+pub fn show_filter(
+    filter: Res<KalmanFilter>,
+    language: Res<Language>,
+    bodies: Query<&Body>,
+    mut text: Query<&mut Text, With<KalmanText>>,
+) {
+    let mut value = String::new();
+
+    if filter.shown {
+        let body = filter.body.and_then(|entity| bodies.get(entity).ok());
+        value = match (body, &filter.estimate) {
+            (Some(body), Some(estimate)) => {
+                let truth = body.current_state;
+                let p = estimate.covariance;
+                language.text(
+                    "kalman",
+                    &[
+                        &body.id,
+                        &filter.processed,
+                        &format!(
+                            "{:.1}",
+                            (estimate.state.x - truth.x).hypot(estimate.state.y - truth.y)
+                        ),
+                        &format!("{:.1}", (p.x_axis.x + p.y_axis.y).sqrt()),
+                        &format!(
+                            "{:.4}",
+                            (estimate.state.vx - truth.vx).hypot(estimate.state.vy - truth.vy)
+                        ),
+                        &format!("{:.4}", (p.z_axis.z + p.w_axis.w).sqrt()),
+                    ],
+                )
+            }
+            _ => language.text("kalman_waiting", &[]),
+        };
+    }
+
+    text.single_mut().sections[0].value = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity::Perturbations;
+    use crate::propagator::{MassivePaths, Propagator};
+    use crate::{DormandPrince, EARTH_RADIUS};
+
+    #[test]
+    fn measurements_pull_the_estimate_in_and_shrink_it() {
+        let paths = MassivePaths::default();
+        let perturbations = Perturbations { j2: true };
+        let integrator = DormandPrince::default();
+        let propagator = Propagator::new(&integrator, 10.0, &perturbations, None, &paths);
+        let station = DVec2::new(EARTH_RADIUS, 0.0);
+        let mut truth =
+            propagator.coast_from(State::new(EARTH_RADIUS + 500e3, 0.0, 0.0, 7600.0), 0.0);
+
+        let (p, v) = (
+            INITIAL_POSITION_SIGMA * INITIAL_POSITION_SIGMA,
+            INITIAL_VELOCITY_SIGMA * INITIAL_VELOCITY_SIGMA,
+        );
+        let mut estimate = Estimate {
+            time: 0.0,
+            state: State::new(EARTH_RADIUS + 500e3 + 800.0, -600.0, 5.0, 7600.0),
+            covariance: DMat4::from_diagonal(DVec4::new(p, p, v, v)),
+        };
+        let error = |estimate: &Estimate, truth: &State| {
+            (estimate.state.x - truth.x).hypot(estimate.state.y - truth.y)
+        };
+        let first = error(&estimate, &truth.state);
+
+        for _ in 0..10 {
+            for _ in 0..6 {
+                truth.step();
+            }
+            estimate.coast_to(
+                truth.time,
+                propagator.coast_from(estimate.state, estimate.time),
+            );
+            assert_eq!(estimate.time, truth.time);
+            let ([range, angle], _) = Observation::predict(station, &truth.state);
+            let spread = estimate.position_covariance().determinant();
+            estimate.update(&Observation {
+                time: truth.time,
+                station,
+                range,
+                angle,
+            });
+            assert!(estimate.position_covariance().determinant() < spread);
+        }
+        assert!(error(&estimate, &truth.state) < first / 10.0);
+    }
+}
@@ -0,0 +1,187 @@
+// Loading large sets of bodies from a file without stalling the first frames: the file is
+// read and parsed on the async compute pool, and the bodies are spawned a batch per frame
+// behind a progress bar. Files are CSV body lists, CCSDS OPM or OMM messages or TLE catalogs.
+use std::fs;
+
+use bevy::prelude::*;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+
+use crate::ccsds;
+use crate::ephemeris::Epoch;
+use crate::fidelity::Fidelity;
+use crate::gravity::GravitySource;
+use crate::i18n::Language;
+use crate::rails::OnRails;
+use crate::style::BodyStyle;
+use crate::tle::{self, Tle, TleFilter};
+use crate::{Body, BodyIds, Precision};
+
+const BATCH_SIZE: usize = 200; // bodies spawned per frame
+
+pub struct BodySpec {
+    pub name: String,
+    pub mass: Precision,
+    pub state: [Precision; 4], // x, y, vx, vy
+}
+
+// Where bodies come from and how far along they are
+#[derive(Resource, Default)]
+pub struct Loader {
+    path: Option<String>,
+    filter: TleFilter,
+    task: Option<Task<Result<Vec<BodySpec>, String>>>,
+    pending: Vec<BodySpec>,
+    total: usize,
+}
+
+impl Loader {
+    pub fn new(path: &str, filter: TleFilter) -> Self {
+        Self {
+            path: Some(path.to_string()),
+            filter,
+            ..default()
+        }
+    }
+
+    fn loading(&self) -> bool {
+        self.task.is_some() || !self.pending.is_empty()
+    }
+}
+
+#[derive(Component)]
+pub struct LoadingBar;
+
+#[derive(Component)]
+pub struct LoadingFill;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+// CSV with a header line and `name,mass,x,y,vx,vy` rows, in kg, m and m/s
+pub fn parse_bodies(source: &str) -> Result<Vec<BodySpec>, String> {
+    source
+        .lines()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let mut fields = line.split(',').map(str::trim);
+            let name = fields.next().unwrap_or_default();
+            let numbers: Vec<Precision> = fields
+                .map(|field| field.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("line {}: expected numbers after the name", i + 1))?;
+            let [mass, x, y, vx, vy] = numbers.as_slice() else {
+                return Err(format!("line {}: expected name,mass,x,y,vx,vy", i + 1));
+            };
+
+            Ok(BodySpec {
+                name: name.to_string(),
+                mass: *mass,
+                state: [*x, *y, *vx, *vy],
+            })
+        })
+        .collect()
+}
+
+// Catalogs keep the objects that pass the filter, at their positions at the epoch
+fn filter_catalog(catalog: Vec<Tle>, filter: &TleFilter, jd: Precision) -> Vec<BodySpec> {
+    let bodies: Vec<BodySpec> = catalog
+        .iter()
+        .filter(|object| filter.matches(object))
+        .map(|object| object.to_spec(jd))
+        .collect();
+    info!(
+        "{} of {} catalog objects pass the filter",
+        bodies.len(),
+        catalog.len()
+    );
+
+    bodies
+}
+
+pub fn start_loading(mut loader: ResMut<Loader>, epoch: Res<Epoch>) {
+    let Some(path) = loader.path.clone() else {
+        return;
+    };
+    let filter = loader.filter.clone();
+    let jd = epoch.0;
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let source = fs::read_to_string(&path).map_err(|error| format!("{}: {}", path, error))?;
+        if path.ends_with(".csv") {
+            parse_bodies(&source)
+        } else if ccsds::is_message(&source) {
+            ccsds::parse_messages(&source).map(|catalog| filter_catalog(catalog, &filter, jd))
+        } else {
+            tle::parse_catalog(&source).map(|catalog| filter_catalog(catalog, &filter, jd))
+        }
+        .map_err(|error| format!("{}: {}", path, error))
+    });
+    loader.task = Some(task);
+}
+
+pub fn spawn_loaded_bodies(
+    mut commands: Commands,
+    mut loader: ResMut<Loader>,
+    mut ids: ResMut<BodyIds>,
+) {
+    if loader.task.as_ref().is_some_and(|task| task.is_finished()) {
+        match block_on(loader.task.take().unwrap()) {
+            Ok(mut bodies) => {
+                info!("Loading {} bodies", bodies.len());
+                // Spawned from the end of the list, so reverse it to keep the file order
+                bodies.reverse();
+                loader.total = bodies.len();
+                loader.pending = bodies;
+            }
+            Err(error) => error!("{}", error),
+        }
+    }
+
+    let batch = loader.pending.len().saturating_sub(BATCH_SIZE);
+    for spec in loader.pending.split_off(batch).into_iter().rev() {
+        let [x, y, vx, vy] = spec.state;
+        let body = Body::new(ids.next(), spec.mass, x, y, vx, vy);
+        // The states are at the start, where the elements of the rails are taken
+        let rails = OnRails::new(&body.current_state, 0.0);
+        let source = GravitySource::of(&body);
+        let mut entity = commands.spawn((body, Name::new(spec.name), BodyStyle::default()));
+        match rails {
+            Some(rails) => entity.insert(rails),
+            None => entity.insert(Fidelity::Analytic),
+        };
+        if let Some(source) = source {
+            entity.insert(source);
+        }
+    }
+}
+
+pub fn show_loading(
+    loader: Res<Loader>,
+    language: Res<Language>,
+    mut bar: Query<&mut Style, (With<LoadingBar>, Without<LoadingFill>)>,
+    mut fill: Query<&mut Style, With<LoadingFill>>,
+    mut text: Query<&mut Text, With<LoadingText>>,
+) {
+    bar.single_mut().display = if loader.loading() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+
+    let (value, fraction) = match (&loader.path, loader.total) {
+        (Some(path), 0) => (language.text("reading", &[path]), 0.0),
+        (Some(path), total) => {
+            let done = total - loader.pending.len();
+            (
+                language.text("loading", &[path, &done, &total]),
+                done as f32 / total as f32,
+            )
+        }
+        _ => (String::new(), 0.0),
+    };
+
+    fill.single_mut().width = Val::Percent(100.0 * fraction);
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,217 @@
+// The mission log: the events that shape a flight (burns, impulses, conjunctions and crashes;
+// apsides and eclipses come round every orbit and stay in the event log) and the notes typed
+// with `note("...")`, each with its sim time and date. It's kept in `mission_log.json` next to
+// the preferences and carries on from one run to the next, every run a new session. `7` shows
+// the latest entries, `8` and `9` page back and forward, and `search_log("...")` keeps the ones
+// that contain some text.
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ephemeris::Epoch;
+use crate::events::{EventKind, SimEvent};
+use crate::i18n::Language;
+use crate::preferences;
+use crate::Precision;
+
+const FILE_NAME: &str = "mission_log.json";
+const MAX_ENTRIES: usize = 10000; // the oldest go first
+const PAGE: usize = 10; // entries the panel shows
+const SAVE_INTERVAL: f32 = 1.0; // s of real time between writes
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub session: u32,
+    pub time: Precision, // sim time in its session
+    pub epoch: String,
+    pub body: Option<usize>, // none for notes
+    pub text: String,
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct MissionLog {
+    entries: Vec<Entry>,
+    #[serde(skip)]
+    session: u32,
+    #[serde(skip)]
+    pub shown: bool,
+    #[serde(skip)]
+    search: String,
+    #[serde(skip)]
+    page: usize, // back from the latest
+    #[serde(skip)]
+    changed: bool, // since the last write
+}
+
+fn path() -> Option<PathBuf> {
+    preferences::config_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+impl MissionLog {
+    // The earlier sessions' entries, with this run as the next session
+    pub fn load() -> Self {
+        let mut log = path()
+            .and_then(|path| {
+                let source = fs::read_to_string(&path).ok()?;
+                serde_json::from_str::<Self>(&source)
+                    .map_err(|error| eprintln!("Ignoring {}: {}", path.display(), error))
+                    .ok()
+            })
+            .unwrap_or_default();
+        log.session = log.entries.last().map_or(1, |entry| entry.session + 1);
+        log
+    }
+
+    // Writes what has changed since the last write. Failed writes aren't retried until
+    // something changes again.
+    pub fn flush(&mut self) {
+        if !std::mem::take(&mut self.changed) {
+            return;
+        }
+        if let Err(error) = self.save() {
+            warn!("Could not save the mission log: {}", error);
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = path().ok_or_else(|| "no config directory".to_string())?;
+        let write = || -> std::io::Result<()> {
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(&path, serde_json::to_string_pretty(self)?)
+        };
+        write().map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    fn add(&mut self, epoch: &Epoch, time: Precision, body: Option<usize>, text: String) {
+        self.entries.push(Entry {
+            session: self.session,
+            time,
+            epoch: epoch.timestamp(time),
+            body,
+            text,
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.page = 0;
+        self.changed = true;
+    }
+
+    pub fn note(&mut self, epoch: &Epoch, time: Precision, text: &str) {
+        self.add(epoch, time, None, text.to_string());
+    }
+
+    // Empty shows every entry again
+    pub fn search(&mut self, text: &str) -> usize {
+        self.search = text.to_lowercase();
+        self.page = 0;
+        self.matching().len()
+    }
+
+    fn matching(&self) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.text.to_lowercase().contains(&self.search))
+            .collect()
+    }
+
+    fn pages(&self) -> usize {
+        self.matching().len().div_ceil(PAGE).max(1)
+    }
+
+    // The page of entries the panel is on, oldest first
+    pub fn describe(&self, language: Language) -> String {
+        let matching = self.matching();
+        if matching.is_empty() {
+            return language.text("log_empty", &[]);
+        }
+        let end = matching.len() - (self.page * PAGE).min(matching.len());
+        let start = end.saturating_sub(PAGE);
+
+        let mut lines = vec![language.text(
+            "log_header",
+            &[&self.session, &(start + 1), &end, &matching.len()],
+        )];
+        if !self.search.is_empty() {
+            lines[0].push_str(&language.text("log_search", &[&self.search]));
+        }
+        for entry in &matching[start..end] {
+            let when = format!("#{} {}", entry.session, entry.epoch);
+            lines.push(match entry.body {
+                Some(body) => language.text("log_event", &[&when, &body, &entry.text]),
+                None => language.text("log_note", &[&when, &entry.text]),
+            });
+        }
+        lines.join("\n")
+    }
+}
+
+// The events worth keeping, as they come in
+pub fn log_events(
+    epoch: Res<Epoch>,
+    mut events: EventReader<SimEvent>,
+    mut log: ResMut<MissionLog>,
+) {
+    for event in events.read() {
+        let kept = !matches!(
+            event.kind,
+            EventKind::Periapsis { .. }
+                | EventKind::Apoapsis { .. }
+                | EventKind::EclipseEntry
+                | EventKind::EclipseExit
+        );
+        if kept {
+            log.add(&epoch, event.time, Some(event.body), event.kind.to_string());
+        }
+    }
+}
+
+// Written back whenever it changes, at most every SAVE_INTERVAL, and on the way out
+pub fn save_log(
+    real_time: Res<Time<Real>>,
+    mut exit: EventReader<AppExit>,
+    mut log: ResMut<MissionLog>,
+    mut last_save: Local<f32>,
+) {
+    let now = real_time.elapsed_seconds();
+    let exiting = exit.read().count() > 0;
+    if !log.changed || (now - *last_save < SAVE_INTERVAL && !exiting) {
+        return;
+    }
+    *last_save = now;
+    log.flush();
+}
+
+// 7 shows or hides the log, 8 pages back to older entries and 9 forward to newer ones
+pub fn browse_log(keyboard: Res<Input<KeyCode>>, mut log: ResMut<MissionLog>) {
+    if keyboard.just_pressed(KeyCode::Key7) {
+        log.shown = !log.shown;
+    }
+    if !log.shown {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Key8) && log.page + 1 < log.pages() {
+        log.page += 1;
+    }
+    if keyboard.just_pressed(KeyCode::Key9) {
+        log.page = log.page.saturating_sub(1);
+    }
+}
+
+#[derive(Component)]
+pub struct LogText;
+
+pub fn show_log(
+    log: Res<MissionLog>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<LogText>>,
+) {
+    text.single_mut().sections[0].value = if log.shown {
+        log.describe(*language)
+    } else {
+        String::new()
+    };
+}
@@ -0,0 +1,165 @@
+// Macros: what the user does while recording, the console commands, warp changes, I impulses
+// and arrow-key burns, written down as a script that can be saved, read, edited and run again
+// on the same or another scenario, from the console with `run_macro` or headless with
+// `--script`. Arrow-key burns become one impulse of the Δv they delivered at their middle.
+use std::collections::HashMap;
+use std::fs;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::attitude::ThrustDirection;
+use crate::autopilot::Pilot;
+use crate::engine::Engine;
+use crate::focus::ControlledBody;
+use crate::script::{self, Call};
+use crate::{Body, Precision, SimTime, TimeStep};
+
+struct MacroLine {
+    time: Precision,
+    command: String,
+    note: Option<String>,
+}
+
+// A burn from the arrow keys still going on
+struct Burn {
+    call: String, // the body's
+    start: Precision,
+    end: Precision,
+    dv: DVec2,
+}
+
+#[derive(Resource, Default)]
+pub struct MacroRecorder {
+    recording: Option<(Precision, Vec<MacroLine>)>, // since when, and what was done
+    burns: HashMap<usize, Burn>,                    // by body id
+}
+
+// How a macro names a body: by name if it has one, which survives reloading the bodies in
+// another order
+pub fn body_call(body: &Body, name: Option<&Name>) -> String {
+    match name {
+        Some(name) => format!("body(\"{}\")", name.as_str()),
+        None => format!("body({})", body.id),
+    }
+}
+
+impl MacroRecorder {
+    pub fn recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn start(&mut self, time: Precision) {
+        self.recording = Some((time, Vec::new()));
+        self.burns.clear();
+    }
+
+    pub fn record(&mut self, time: Precision, command: String) {
+        self.push(time, command, None);
+    }
+
+    fn push(&mut self, time: Precision, command: String, note: Option<String>) {
+        if let Some((_, lines)) = &mut self.recording {
+            lines.push(MacroLine {
+                time,
+                command,
+                note,
+            });
+        }
+    }
+
+    // Closes a burn into an impulse at its middle, on a whole step of `dt`
+    fn finish_burn(&mut self, burn: Burn, dt: Precision) {
+        let middle = ((burn.start + burn.end) / 2.0 / dt).round() * dt;
+        self.push(
+            middle,
+            format!("{}.impulse({:.3}, {:.3})", burn.call, burn.dv.x, burn.dv.y),
+            Some(format!(
+                "arrow-key burn from {} to {} s",
+                burn.start, burn.end
+            )),
+        );
+    }
+
+    // Stops recording and writes the macro, returning how many commands it has
+    pub fn save(&mut self, path: &str, (now, dt): (Precision, Precision)) -> Result<usize, String> {
+        for (_, burn) in std::mem::take(&mut self.burns) {
+            self.finish_burn(burn, dt);
+        }
+        let (start, mut lines) = self
+            .recording
+            .take()
+            .ok_or_else(|| "not recording a macro".to_string())?;
+        lines.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut source = format!("# Macro recorded from t = {} s to t = {} s\n", start, now);
+        for line in &lines {
+            source.push_str(&format!("at {} {}", line.time, line.command));
+            if let Some(note) = &line.note {
+                source.push_str(&format!("  # {}", note));
+            }
+            source.push('\n');
+        }
+        fs::write(path, source).map_err(|error| format!("could not write {}: {}", path, error))?;
+
+        Ok(lines.len())
+    }
+}
+
+// The timed commands of a macro file, shifted so the first comes at `start` if given
+pub fn load(path: &str, start: Option<Precision>) -> Result<Vec<(Precision, Vec<Call>)>, String> {
+    let source = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    let mut lines = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        if let Some(line) =
+            script::parse_line(line).map_err(|error| format!("{}:{}: {}", path, i + 1, error))?
+        {
+            lines.push(line);
+        }
+    }
+
+    if let (Some(start), Some(first)) = (
+        start,
+        lines.iter().map(|(time, _)| *time).reduce(Precision::min),
+    ) {
+        for (time, _) in &mut lines {
+            *time += start - first;
+        }
+    }
+    Ok(lines)
+}
+
+// The craft the arrow keys fly, unless an autopilot has its throttle
+type Flown = (With<ControlledBody>, Without<Pilot>);
+
+// After each physics tick, adds up what the arrow keys delivered to each body they fly
+pub fn record_burns(
+    (time, step): (Res<SimTime>, Res<TimeStep>),
+    keyboard: Res<Input<KeyCode>>,
+    mut recorder: ResMut<MacroRecorder>,
+    mut last: Local<Precision>,
+    bodies: Query<(&Body, Option<&Name>, &Engine), Flown>,
+) {
+    let (start, elapsed) = (*last, time.0 - *last);
+    *last = time.0;
+    if !recorder.recording() || elapsed <= 0.0 {
+        return;
+    }
+
+    let keyed = ThrustDirection::from_keys(&keyboard).is_some();
+    for (body, name, engine) in bodies.iter() {
+        if keyed && engine.firing {
+            let (ax, ay) = engine.delivered.components(&body.current_state);
+            let burn = recorder.burns.entry(body.id).or_insert_with(|| Burn {
+                call: body_call(body, name),
+                start,
+                end: start,
+                dv: DVec2::ZERO,
+            });
+            burn.end = time.0;
+            burn.dv += DVec2::new(ax, ay) * elapsed;
+        } else if let Some(burn) = recorder.burns.remove(&body.id) {
+            recorder.finish_burn(burn, step.0);
+        }
+    }
+}
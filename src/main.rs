@@ -1,59 +1,271 @@
 // Simulates orbit of a small body around the earth
+use bevy::diagnostic::{Diagnostic, RegisterDiagnostic};
+use bevy::ecs::query::QueryItem;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::render::camera::ScalingMode;
-use std::ops;
+use orbitabase_core::{
+    adaptive_step, forcing_from, integrator, substeps, Atmosphere, DormandPrince, Drag, Integrator,
+    Precision, Rk4, Source, State, StateHistory, Thrust, Verlet, ATMOSPHERE_TOP, DEFAULT_DT, EARTH,
+    EARTH_RADIUS, G, J2_EARTH, MASS_EARTH,
+};
+use std::collections::HashMap;
 
-type Precision = f64;
+mod antenna;
+mod apsides;
+mod atmosphere;
+mod attitude;
+mod autopilot;
+mod avoidance;
+mod batch;
+mod broadphase;
+mod calculator;
+mod camera;
+mod ccsds;
+mod challenge;
+mod conservation;
+mod console;
+mod controller;
+mod coverage;
+mod cr3bp;
+mod crash;
+mod demo;
+mod determination;
+mod diff;
+mod dispersion;
+mod elements;
+mod engine;
+mod ephemeris;
+mod events;
+mod fidelity;
+mod focus;
+mod formation;
+mod frames;
+mod frozen;
+mod ghost;
+mod governor;
+mod gravity;
+mod ground;
+mod groundtrack;
+mod headless;
+mod health;
+mod history;
+mod hud;
+mod i18n;
+mod impact;
+mod insertion;
+mod interpolation;
+mod isl;
+mod kalman;
+mod loading;
+mod logbook;
+mod macros;
+mod maneuver;
+mod narration;
+mod nodes;
+mod observation;
+mod optimizer;
+mod origin;
+mod palette;
+mod passes;
+mod perspective;
+mod perturbation;
+mod postmortem;
+mod prediction;
+mod preferences;
+mod propagator;
+mod quicksave;
+mod radiation;
+mod rails;
+mod recorder;
+mod regime;
+mod repeat;
+mod scenario;
+mod scheduler;
+mod script;
+mod sensors;
+mod sets;
+mod sky;
+mod snapshot;
+mod spatial;
+mod stm;
+mod style;
+mod swarm;
+mod sweep;
+mod targeter;
+mod tether;
+mod thermal;
+mod timescales;
+mod tle;
+mod trajectory;
 
-const G: Precision = 6.6743e-11; // m3 kg-1 s-2
-const MASS_EARTH: Precision = 5.972e24;
-const EARTH_RADIUS: Precision = 6.371e6;
-const DT: Precision = 10.0; // 1 second
-const N_HISTORY: usize = 21;
-const N_LOOKAHEAD: usize = 2000;
-const THRUST: Precision = 2.0;
+use attitude::ThrustDirection;
+use autopilot::Pilot;
+use camera::PixelSize;
+use coverage::Sensor;
+use crash::Crossing;
+use engine::{Engine, Tank};
+use fidelity::Fidelity;
+use gravity::GravitySource;
+use maneuver::ManeuverPlan;
+use palette::Palette;
+use regime::RegimeFilter;
+use sky::ViewMode;
+use style::BodyStyle;
+use tether::Tether;
 
-// Bodies have a mass, an id, a current state and a rolling history of states
-#[derive(Component)]
-struct Body {
-    current_state: State,
-    history: StateHistory,
-    mass: Precision,
-    id: usize,
+const DEFAULT_LOOKAHEAD: usize = 2000; // steps
+const DEFAULT_THRUST: Precision = 2.0; // m/s²
+const FAULT_SEED: u64 = 0; // of the random failures in scripts without --random
+
+// The integration step in s, from --dt. It stays the same for the whole run, as the steps
+// already taken keep their length.
+#[derive(Resource, Clone, Copy)]
+struct TimeStep(Precision);
+
+impl Default for TimeStep {
+    fn default() -> Self {
+        Self(DEFAULT_DT)
+    }
 }
 
-#[derive(Copy, Clone)]
-struct State {
-    vx: Precision,
-    vy: Precision,
-    x: Precision,
-    y: Precision,
+// Steps ahead the predicted paths reach, from --lookahead
+#[derive(Resource, Clone, Copy)]
+struct Lookahead(usize);
+
+impl Default for Lookahead {
+    fn default() -> Self {
+        Self(DEFAULT_LOOKAHEAD)
+    }
+}
+
+// The acceleration of engines without a tank, and what autopilots' throttles are fractions of,
+// from --thrust
+#[derive(Resource, Clone, Copy)]
+struct FullThrust(Precision);
+
+impl Default for FullThrust {
+    fn default() -> Self {
+        Self(DEFAULT_THRUST)
+    }
 }
 
-impl State {
-    fn new(x0: Precision, y0: Precision, vx0: Precision, vy0: Precision) -> Self {
-        Self {
-            x: x0,
-            y: y0,
-            vx: vx0,
-            vy: vy0,
+const MAX_WARP: u32 = 1000;
+
+const USAGE: &str = "\
+usage: orbitabase [options]
+
+Scenario:
+  --scenario <path>          start with the bodies of a RON scenario file
+  --bodies <path>            load bodies from a CSV file, CCSDS message or TLE catalog
+  --altitude <min,max>       only catalog objects at mean altitudes in this range, km
+  --inclination <min,max>    only catalog objects with inclinations in this range, deg
+  --name <pattern>           only catalog objects whose names match, * as a wildcard
+  --random <seed>            a random training scenario
+  --script <path>            run a script and exit when it is done
+  --last                     the inputs of the last scenario again
+  --demo                     tour the simulator unattended until a key is pressed
+
+Simulation:
+  --dt <s>                   integration step, 10 by default
+  --tick <hz>                physics ticks a second, 60 by default
+  --speed <warp>             starting time warp, 1 to 1000
+  --lookahead <steps>        steps ahead predicted paths reach, 2000 by default
+  --thrust <m/s²>            acceleration of engines without a tank, 2 by default
+  --integrator <name>        rk4, verlet or dormand_prince
+  --j2                       the earth's equatorial bulge in its gravity
+  --moon                     start with the Moon on its orbit, pulling on everything
+
+Other:
+  --batch <path>             run a batch of scenarios without a window
+  --headless <days>          propagate the starting bodies without a window for this long
+  --output <path>            CSV --headless writes, trajectory.csv by default
+  --sample <s>               seconds between --headless rows, 60 by default
+  --jobs <n>                 worker processes for --batch
+  --diff <a> <b>             what changed between two scenario files or quicksaves
+  --narrate <path>           write the telemetry as text, - for standard output
+  --controller <address>     let external programs fly bodies over TCP
+  --help                     this list
+";
+
+// Simulated seconds since the start of the run
+#[derive(Resource, Default)]
+struct SimTime(Precision);
+
+// Integration steps per physics tick (the warp), or the speed-up over the wall clock in real
+// time
+#[derive(Resource)]
+struct SimulationSpeed(u32);
+
+impl Default for SimulationSpeed {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+impl SimulationSpeed {
+    // Up to the next power of ten, as far as MAX_WARP
+    fn faster(&mut self) {
+        let mut speed = 1;
+        while speed <= self.0 {
+            speed *= 10;
+        }
+        self.0 = speed.min(MAX_WARP);
+    }
+
+    // Down to the previous power of ten, as far as 1
+    fn slower(&mut self) {
+        let mut speed = 1;
+        while speed * 10 < self.0 {
+            speed *= 10;
         }
+        self.0 = speed;
     }
 }
 
-struct StateHistory([Option<State>; N_HISTORY]);
+// In real-time mode one simulated second passes per wall-clock second, times the warp,
+// whatever the tick rate: the wall-clock time piles up and a step runs whenever a whole dt
+// of it has. Otherwise every tick runs `warp` steps.
+#[derive(Resource, Default)]
+struct RealTime {
+    enabled: bool,
+    backlog: Precision, // s of simulated time not stepped yet
+}
+
+impl RealTime {
+    // Integration steps to run this tick. A long stall drops the time that would take more
+    // than MAX_WARP steps to catch up on.
+    fn steps(&mut self, warp: u32, elapsed: Precision, dt: Precision) -> u32 {
+        if !self.enabled {
+            return warp;
+        }
 
-impl StateHistory {
-    fn new() -> Self {
-        StateHistory([None; N_HISTORY])
+        self.backlog += elapsed * warp as Precision;
+        let steps = ((self.backlog / dt).floor() as u32).min(MAX_WARP);
+        self.backlog = (self.backlog - steps as Precision * dt).min(dt);
+        steps
     }
+}
 
-    fn push(&mut self, state: State) {
-        self.0.rotate_right(1);
-        self.0[0] = Some(state);
+// Hands out body ids so that bodies spawned at runtime never share one
+#[derive(Resource, Default)]
+struct BodyIds(usize);
+
+impl BodyIds {
+    fn next(&mut self) -> usize {
+        self.0 += 1;
+        self.0
     }
 }
 
+// The body that per-body tools (pass predictions, ...) report on
+#[derive(Component)]
+struct Selected;
+
+// The core body, as a component
+#[derive(Component, Deref, DerefMut)]
+struct Body(orbitabase_core::Body);
+
 impl Body {
     fn new(
         id: usize,
@@ -63,180 +275,430 @@ impl Body {
         vx0: Precision,
         vy0: Precision,
     ) -> Self {
-        let current_state = State::new(x0, y0, vx0, vy0);
-
-        Self {
-            current_state,
-            history: StateHistory::new(),
-            mass,
-            id,
-        }
+        Self(orbitabase_core::Body::new(id, mass, x0, y0, vx0, vy0))
     }
+}
 
-    fn update_history(&mut self) {
-        self.history.push(self.current_state.clone());
+fn add_body(
+    mut commands: Commands,
+    mut ids: ResMut<BodyIds>,
+    controller: Res<controller::ExternalController>,
+    scenario: Res<scenario::StartingScenario>,
+) {
+    let first = match &scenario.0 {
+        Some(scenario) => scenario.spawn(&mut commands, &mut ids),
+        None => Some(add_station(&mut commands, &mut ids)),
+    };
+
+    // --controller hands the first body to the first external controller that connects
+    if let (Some(listener), Some(first)) = (&controller.0, first) {
+        commands.entity(first).insert(Pilot {
+            name: "external".to_string(),
+            autopilot: Box::new(controller::ExternalPilot::new(listener.clone())),
+            target: None,
+        });
     }
 }
 
-struct Forcing {
-    ax: Precision,
-    ay: Precision,
-    vy: Precision,
-    vx: Precision
+// A tonne of hydrazine on a tonne of dry mass, about 1.6 km/s starting at DEFAULT_THRUST
+const STATION_TANK: Tank = Tank {
+    dry_mass: 1000.0,
+    propellant: 1000.0,
+    isp: 230.0,
+    thrust: 4000.0,
+};
+
+// The station runs start with when no --scenario is given, without its engine
+fn station(id: usize) -> Body {
+    let x: Precision = 0.0;
+    let y: Precision = (EARTH_RADIUS + 408000.0) as Precision; // height of ISS
+    let vx: Precision = 1.1 * 7660.0; // ~ velocida de la ISS
+    let vy: Precision = 0.0;
+
+    let mut body = Body::new(id, STATION_TANK.mass(), x, y, vx, vy);
+    // About the real station's: 420 t with a drag coefficient of 2.2 over 1600 m²
+    body.ballistic_coefficient = Some(120.0);
+    body
 }
 
-impl ops::Add<&Forcing> for &State {
-    type Output = State;
+fn add_station(commands: &mut Commands, ids: &mut BodyIds) -> Entity {
+    let tank = STATION_TANK;
+    let body = station(ids.next());
 
-    fn add(self, rhs: &Forcing) -> Self::Output {
-        State {
-            x: self.x + rhs.vx,
-            y: self.y + rhs.vy,
-            vx: self.vx + rhs.ax,
-            vy: self.vy + rhs.ay,
-        }
-    }
+    commands
+        .spawn((
+            body,
+            Name::new("ISS"),
+            Engine {
+                tank: Some(tank),
+                ..default()
+            },
+            Fidelity::High,
+            focus::ControlledBody,
+            thermal::Thermal::default(),
+            antenna::Antenna::patch(),
+            recorder::Recorder::default(),
+            health::Power::default(),
+            ManeuverPlan::default(),
+            dispersion::BurnErrors::default(),
+            BodyStyle::default(),
+            Sensor {
+                half_angle: 30f64.to_radians(),
+            },
+            Selected,
+        ))
+        .id()
 }
 
-impl ops::Add<&Forcing> for &Forcing {
-    type Output = Forcing;
+type Propagated = (
+    Entity,
+    &'static mut Body,
+    Option<&'static mut Engine>,
+    Option<&'static Pilot>,
+    Option<&'static Fidelity>,
+    Has<focus::ControlledBody>,
+);
+
+// How many steps a tick runs: the speed's, or the top warp's on a jump to the next pass, held
+// down by the governor near events, or as many as real time has piled up, but never past the
+// next scheduled action or planned burn
+#[derive(SystemParam)]
+struct Stepping<'w, 's> {
+    speed: Res<'w, SimulationSpeed>,
+    governor: Res<'w, governor::Governor>,
+    clock: Res<'w, Time>,
+    real_time: ResMut<'w, RealTime>,
+    scheduler: Res<'w, scheduler::Scheduler>,
+    jump: Res<'w, passes::PassJump>,
+    plans: Query<'w, 's, &'static ManeuverPlan>,
+}
 
-    fn add(self, rhs: &Forcing) -> Self::Output {
-        Forcing {
-            ax: self.ax + rhs.ax,
-            ay: self.ay + rhs.ay,
-            vx: self.vx + rhs.vx,
-            vy: self.vy + rhs.vy,
+impl Stepping<'_, '_> {
+    fn steps(&mut self, now: Precision, dt: Precision) -> u32 {
+        // A jump to the next pass runs the top warp whatever the speed or the clock
+        if let Some(steps) = self.jump.steps(now, self.governor.warp(MAX_WARP), dt) {
+            return self.limit(now, steps, dt);
+        }
+        let warp = self.governor.warp(self.speed.0);
+        let steps = self
+            .real_time
+            .steps(warp, self.clock.delta_seconds_f64(), dt);
+        let limited = self.limit(now, steps, dt);
+        // Real time catches up on the rest in the next ticks
+        if self.real_time.enabled {
+            self.real_time.backlog += (steps - limited) as Precision * dt;
         }
+        limited
+    }
+
+    // The tick stops at the step that reaches the next action or burn, which then happen
+    // before the following one
+    fn limit(&self, now: Precision, steps: u32, dt: Precision) -> u32 {
+        let steps = self.scheduler.limit(now, steps, dt);
+        let burn = self
+            .plans
+            .iter()
+            .filter_map(|plan| Some(plan.0.first()?.time))
+            .min_by(Precision::total_cmp);
+        scheduler::limit(now, burn, steps, dt)
     }
 }
 
-impl ops::Mul<&Forcing> for Precision  {
-    type Output = Forcing;
+// What every body flies by this tick
+struct Flight<'a> {
+    dt: Precision,
+    thrust: Precision, // m/s², of engines without a tank
+    integrator: &'a dyn Integrator,
+    air: Option<Atmosphere>,
+    direction: Option<ThrustDirection>,
+    targets: &'a HashMap<Entity, State>, // as they were at the start of the tick
+}
 
-    fn mul(self, rhs: &Forcing) -> Self::Output {
-        Self::Output {
-            ax: self * rhs.ax,
-            ay: self * rhs.ay,
-            vx: self * rhs.vx,
-            vy: self * rhs.vy,
+impl Flight<'_> {
+    // Moves a body `steps` steps on and says where it went under the surface, if it did, from
+    // the start of those steps. Autopilots decide on every step, the arrow keys hold for the
+    // whole tick but point the focused craft's engine anew at each step. Bodies without an
+    // engine (like tethered probes) just coast.
+    fn fly(
+        &self,
+        body: &mut QueryItem<Propagated>,
+        sources: &[Source],
+        steps: u32,
+    ) -> Option<Crossing> {
+        let (_, body, engine, pilot, fidelity, controlled) = body;
+        let fidelity = fidelity.copied().unwrap_or_default();
+        let integrator = (self.integrator, self.dt);
+        let direction = self.direction.filter(|_| *controlled);
+        let crossing = match (engine.as_mut(), pilot, direction) {
+            (None, ..) => fidelity.advance(
+                body,
+                Thrust::default(),
+                steps,
+                sources,
+                self.air,
+                integrator,
+            ),
+            (Some(engine), Some(pilot), _) => {
+                let target = pilot.target.and_then(|target| self.targets.get(&target));
+                (0..steps).find_map(|i| {
+                    let command =
+                        pilot
+                            .autopilot
+                            .control(&body.current_state, target, self.dt, self.thrust);
+                    let thrust = engine.guided(command, self.thrust);
+                    engine.record(thrust, 1, self.dt);
+                    let crossing = fidelity.advance(body, thrust, 1, sources, self.air, integrator);
+                    crossing.map(|crossing| crossing.later(i as Precision * self.dt))
+                })
+            }
+            (Some(engine), None, Some(direction)) => (0..steps).find_map(|i| {
+                let command = direction.command(&body.current_state);
+                let thrust = engine.command(command, self.thrust);
+                engine.record(thrust, 1, self.dt);
+                let crossing = fidelity.advance(body, thrust, 1, sources, self.air, integrator);
+                crossing.map(|crossing| crossing.later(i as Precision * self.dt))
+            }),
+            (Some(engine), None, None) => {
+                engine.record(Thrust::default(), steps, self.dt);
+                fidelity.advance(
+                    body,
+                    Thrust::default(),
+                    steps,
+                    sources,
+                    self.air,
+                    integrator,
+                )
+            }
+        };
+
+        if crossing.is_some() {
+            if let Some(engine) = engine.as_mut() {
+                engine.record(Thrust::default(), 1, self.dt);
+            }
         }
+        if let Some(tank) = engine.as_ref().and_then(|engine| engine.tank) {
+            body.mass = tank.mass();
+        }
+        crossing
     }
 }
 
-fn forcing(state: State, thrust: i8) -> Forcing {
-    let r = (state.x * state.x + state.y * state.y).sqrt();
-    let v = (state.vx * state.vx + state.vy * state.vy).sqrt();
-
-    let f = -G * MASS_EARTH / (r * r * r);
+// System that runs at each physics tick, on the fixed timestep. Under time warp the other
+// physics systems only see every warp-th step, so tethered pairs fly a step at a time, pulled
+// back together after each, and every other body flies the whole tick at once.
+fn system(
+    mut query: Query<Propagated, (Without<rails::OnRails>, Without<crash::Crashed>)>,
+    gravity: Query<(Entity, &GravitySource)>,
+    mut tethers: Query<(Entity, &mut Tether)>,
+    propagation: fidelity::Propagation,
+    mut stepping: Stepping,
+    mut crashes: crash::Crashes,
+    (mut time, full, keyboard): (ResMut<SimTime>, Res<FullThrust>, Res<Input<KeyCode>>),
+) {
+    let dt = propagation.step.0;
+    let steps = stepping.steps(time.0, dt);
 
-    let thrustx = thrust as Precision * THRUST * state.vx / v;
-    let thrusty = thrust as Precision * THRUST * state.vy / v;
+    // Autopilots see their targets as they were at the start of the tick
+    let targets: HashMap<Entity, State> = query
+        .iter()
+        .filter_map(|(_, _, _, pilot, ..)| pilot?.target)
+        .filter_map(|target| Some((target, query.get(target).ok()?.1.current_state)))
+        .collect();
+    let flight = Flight {
+        dt,
+        thrust: full.0,
+        integrator: propagation.integration.0.as_ref(),
+        air: propagation.drag.atmosphere,
+        direction: ThrustDirection::from_keys(&keyboard),
+        targets: &targets,
+    };
+    // Every mass but the body's own pulls on it
+    let sources = |entity: Entity| -> Vec<Source> {
+        gravity
+            .iter()
+            .filter(|(source, _)| *source != entity)
+            .map(|(_, source)| source.source(&propagation.perturbations))
+            .collect()
+    };
 
-    let ax = f * state.x + thrustx; 
-    let ay = f * state.y + thrusty;
+    // A tether to a body on rails or crashed goes slack
+    let pairs: Vec<(Entity, Entity, Precision)> = tethers
+        .iter()
+        .filter(|(entity, tether)| query.contains(*entity) && query.contains(tether.anchor))
+        .map(|(entity, tether)| (entity, tether.anchor, tether.length))
+        .collect();
+    let mut tethered: Vec<Entity> = pairs.iter().flat_map(|(a, b, _)| [*a, *b]).collect();
+    tethered.sort();
+    tethered.dedup();
 
-    Forcing {
-        ax,
-        ay,
-        vx: state.vx,
-        vy: state.vy,
+    for mut body in query.iter_mut() {
+        let entity = body.0;
+        if tethered.binary_search(&entity).is_ok() {
+            continue;
+        }
+        if let Some(crossing) = flight.fly(&mut body, &sources(entity), steps) {
+            crashes.crash(entity, &mut body.1, time.0 + crossing.time, crossing.state);
+        }
     }
-}
 
-fn rk4(state: State, thrust: i8) -> State {
+    let pulls: HashMap<Entity, Vec<Source>> = tethered.iter().map(|&e| (e, sources(e))).collect();
+    let mut crashed: Vec<Entity> = Vec::new();
+    let mut impulses: HashMap<Entity, Precision> = pairs.iter().map(|p| (p.0, 0.0)).collect();
+    for i in 0..steps {
+        for &entity in &tethered {
+            if crashed.contains(&entity) {
+                continue;
+            }
+            let mut body = query.get_mut(entity).unwrap();
+            if let Some(crossing) = flight.fly(&mut body, &pulls[&entity], 1) {
+                let crossing = crossing.later(i as Precision * dt);
+                crashes.crash(entity, &mut body.1, time.0 + crossing.time, crossing.state);
+                crashed.push(entity);
+            }
+        }
+        for &(entity, anchor, length) in &pairs {
+            if crashed.contains(&entity) || crashed.contains(&anchor) {
+                continue;
+            }
+            let [mut body, mut other] = query.get_many_mut([entity, anchor]).unwrap();
+            let impulse = tether::pull(&mut body.1, &mut other.1, length);
+            *impulses.entry(entity).or_default() += impulse;
+        }
+    }
+    // A tick without steps leaves the tensions as the last one had them
+    if steps > 0 {
+        for (entity, mut tether) in tethers.iter_mut() {
+            let impulse = impulses.get(&entity).copied().unwrap_or(0.0);
+            tether.tension = impulse / (steps as Precision * dt);
+        }
+    }
 
-    let k1 = forcing(state, thrust);
-    let k2 = forcing(&state + &(0.5 * DT * &k1), thrust);
-    let k3 = forcing(&state + &(0.5 * DT * &k2), thrust);
-    let k4 = forcing(&state + &(DT * &k3), thrust);
+    time.0 += dt * steps as Precision;
+}
 
-    // Need to make this better without borrowing
-    &state +  &(DT / 6.0 *  &(&k1 + &(&(2.0 * &k2) +  &(&(2.0 * &k3) + &k4))))
+// . and , step the simulation speed through 1x, 10x, 100x and 1000x
+fn change_speed(
+    keyboard: Res<Input<KeyCode>>,
+    time: Res<SimTime>,
+    mut speed: ResMut<SimulationSpeed>,
+    mut recorder: ResMut<macros::MacroRecorder>,
+) {
+    if keyboard.just_pressed(KeyCode::Period) {
+        speed.faster();
+    } else if keyboard.just_pressed(KeyCode::Comma) {
+        speed.slower();
+    } else {
+        return;
+    }
+    info!("Simulation speed {}x", speed.0);
+    recorder.record(time.0, format!("set_warp({})", speed.0));
 }
 
-fn add_body(mut commands: Commands) {
-    // Hardcoded for now.
-    let x: Precision = 0.0;
-    let y: Precision = (EARTH_RADIUS + 408000.0) as Precision; // height of ISS
-    let vx: Precision = 1.1 * 7660.0; // ~ velocida de la ISS
-    let vy: Precision = 0.0;
+// M switches between real time and a fixed number of steps per tick
+fn toggle_real_time(keyboard: Res<Input<KeyCode>>, mut real_time: ResMut<RealTime>) {
+    if !keyboard.just_pressed(KeyCode::M) {
+        return;
+    }
 
-    commands.spawn(Body::new(1, 1.0, x, y, vx, vy));
+    real_time.enabled = !real_time.enabled;
+    real_time.backlog = 0.0;
+    if real_time.enabled {
+        info!("Real time: one simulated second per second, times the warp");
+    } else {
+        info!("Real time off: the warp's steps every tick");
+    }
 }
 
-// System that runs at each frame (I think? I don't know if each iteration is frame-based or not.)
-fn system(
+type Drawn = (
+    &'static Body,
+    Option<&'static Engine>,
+    Option<&'static BodyStyle>,
+    Option<&'static prediction::PredictedTrajectory>,
+    Has<crash::Crashed>,
+);
+
+fn draw_bodies(
     mut gizmos: Gizmos,
-    time: Res<Time>,
-    mut query: Query<&mut Body>,
-    keyboard: Res<Input<KeyCode>>,
+    (pixel, step): (Res<PixelSize>, Res<TimeStep>),
+    filter: Res<RegimeFilter>,
+    palette: Res<Palette>,
+    interpolation: interpolation::Interpolation,
+    query: Query<Drawn>,
 ) {
     // Draw the earth
-    gizmos.circle_2d(Vec2 { x: 0.0, y: 0.0 }, EARTH_RADIUS as f32, Color::BLUE);
+    let view = interpolation.view();
+    gizmos.circle_2d(
+        view.point(0.0, 0.0),
+        EARTH_RADIUS as f32,
+        palette.colors().earth,
+    );
 
-    for mut body in query.iter_mut() {
+    let default_style = BodyStyle::default();
 
-        let mut thrust = 0;
-        let mut body_radius = 50000.0;
-        if keyboard.pressed(KeyCode::Up) {
-            thrust = 1;
-            body_radius = 100000.0;
+    for (body, engine, style, prediction, crashed) in query.iter() {
+        if !filter.shows(body) {
+            continue;
         }
-        if keyboard.pressed(KeyCode::Down) {
-            thrust = -1;
-            body_radius = 100000.0;
-        } 
+        let style = style.unwrap_or(&default_style);
 
-        let mut new_state = rk4(body.current_state, thrust);
-       
-        body.current_state = new_state.clone();
+        // Crashed bodies are crossed out where they hit, with no trail or lookahead
+        if crashed {
+            style::draw_marker(
+                &mut gizmos,
+                style::Marker::Cross,
+                view.position(body),
+                100000.0,
+                palette.colors().warning,
+            );
+            continue;
+        }
 
-        body.update_history();
+        // Bodies firing their engine are drawn bigger
+        let firing = engine.is_some_and(|engine| engine.firing);
+        let body_radius = if firing { 100000.0 } else { 50000.0 };
 
-        gizmos.circle_2d(
-            Vec2 {
-                x: body.current_state.x as f32,
-                y: body.current_state.y as f32,
-            },
+        let new_state = body.current_state;
+
+        style::draw_marker(
+            &mut gizmos,
+            style.marker,
+            view.position(body),
             body_radius,
-            Color::RED,
+            style.color,
         );
 
-        // draw history
-        for state in body.history.0.iter() {
-            if let Some(state) = state {
-                gizmos.circle_2d(
-                    Vec2 {
-                        x: state.x as f32,
-                        y: state.y as f32,
-                    },
-                    10000.0,
-                    Color::RED,
-                );
-            }
-        }
-
-        // draw lookahead assuming no thrust
-        for _ in 0..N_LOOKAHEAD {
-            new_state = rk4(new_state, 0);
-            gizmos.circle_2d(
-                Vec2 {
-                    x: new_state.x as f32,
-                    y: new_state.y as f32,
-                },
-                10000.0,
-                Color::GREEN,
-            );
-        }
-
+        // Zoomed out, only some of the trail samples are drawn
+        let speed = (new_state.vx * new_state.vx + new_state.vy * new_state.vy).sqrt();
+        let stride = pixel.stride(speed, step.0);
 
+        // draw history
+        let history = body.history.0.iter().enumerate().step_by(stride);
+        let history = history.filter_map(|(steps, state)| Some(view.past(state.as_ref()?, steps)));
+        style::draw_line(&mut gizmos, style.trail_style, history, style.trail_color);
 
+        // draw lookahead assuming no thrust, from its prediction
+        let Some(prediction) = prediction else {
+            continue;
+        };
+        let lookahead = prediction
+            .states()
+            .iter()
+            .enumerate()
+            .skip(stride)
+            .step_by(stride);
+        let lookahead = lookahead.map(|(steps, state)| view.ahead(state, steps));
+        style::draw_line(
+            &mut gizmos,
+            style.lookahead_style,
+            lookahead,
+            style.lookahead_color,
+        );
     }
 }
 
-fn setup(mut commands: Commands) {
+fn setup(mut commands: Commands, preferences: Res<preferences::Preferences>) {
     let mut my_2d_camera_bundle = Camera2dBundle::default();
+    my_2d_camera_bundle.projection.scale = preferences.zoom;
 
     my_2d_camera_bundle.projection.scaling_mode = ScalingMode::AutoMax {
         max_height: (EARTH_RADIUS * 6.0) as f32,
@@ -247,11 +709,511 @@ fn setup(mut commands: Commands) {
 }
 
 fn main() {
+    // --script <path> runs a scenario script and exits when it is done, --random <seed> sets up
+    // a training scenario, and --bodies <path> loads extra bodies from a CSV file or a TLE
+    // catalog in the background. Catalogs can be filtered with --altitude <min,max> (km),
+    // --inclination <min,max> (deg) and --name <pattern>. --narrate <path> writes the
+    // telemetry as text for screen readers, and --controller <address> lets an external
+    // process fly the ISS over TCP. --help lists them all.
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print!("{}", USAGE);
+        return;
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--diff") {
+        let (Some(a), Some(b)) = (args.get(i + 1), args.get(i + 2)) else {
+            eprintln!("--diff needs two files");
+            std::process::exit(2);
+        };
+        std::process::exit(diff::run(a, b));
+    }
+    let value = |flag: &str| {
+        let i = args.iter().position(|arg| arg == flag)?;
+        let Some(value) = args.get(i + 1) else {
+            eprintln!("{} needs a value", flag);
+            std::process::exit(2);
+        };
+        Some(value.clone())
+    };
+    let range = |flag: &str, unit: Precision| {
+        let text = value(flag)?;
+        let parse = |text: &str| text.trim().parse::<Precision>().ok();
+        let bounds = text
+            .split_once(',')
+            .and_then(|(low, high)| Some((parse(low)?, parse(high)?)));
+        let Some((low, high)) = bounds else {
+            eprintln!("{} takes a range like 400,600", flag);
+            std::process::exit(2);
+        };
+        Some((low * unit, high * unit))
+    };
+
+    // Simulation parameters for the whole run, batch workers included
+    let positive = |flag: &str| {
+        value(flag).map(|text| match text.parse::<Precision>() {
+            Ok(number) if number > 0.0 && number.is_finite() => number,
+            _ => {
+                eprintln!("{} takes a positive number", flag);
+                std::process::exit(2);
+            }
+        })
+    };
+    let step = positive("--dt").map_or_else(TimeStep::default, TimeStep);
+    let thrust = positive("--thrust").map_or_else(FullThrust::default, FullThrust);
+    let lookahead = value("--lookahead")
+        .map(|steps| match steps.parse::<usize>() {
+            Ok(steps) if steps > 0 => Lookahead(steps),
+            _ => {
+                eprintln!("--lookahead takes a positive whole number of steps");
+                std::process::exit(2);
+            }
+        })
+        .unwrap_or_default();
+
+    let integration = match value("--integrator") {
+        Some(name) => fidelity::Integration(integrator(&name).unwrap_or_else(|error| {
+            eprintln!("--integrator: {}", error);
+            std::process::exit(2);
+        })),
+        None => fidelity::Integration::default(),
+    };
+
+    let perturbations = gravity::Perturbations {
+        j2: args.iter().any(|arg| arg == "--j2"),
+    };
+    let moon = args.iter().any(|arg| arg == "--moon");
+
+    // --batch <file> runs a list of scenarios without a window, over --jobs <n> processes
+    if let Some(path) = value("--batch") {
+        let number = |flag: &str| {
+            value(flag).map(|text| {
+                text.parse::<usize>().unwrap_or_else(|_| {
+                    eprintln!("{} takes a whole number", flag);
+                    std::process::exit(2);
+                })
+            })
+        };
+        let jobs = number("--jobs").unwrap_or(1);
+        let settings = batch::Settings {
+            integrator: integration.0.as_ref(),
+            dt: step.0,
+            perturbations,
+        };
+        std::process::exit(batch::run(&path, jobs, number("--batch-case"), settings));
+    }
+
+    // --last reruns the inputs of the last scenario given on the command line
+    let mut preferences = preferences::Preferences::load();
+    let last = if args.iter().any(|arg| arg == "--last") {
+        preferences.last_scenario.clone()
+    } else {
+        preferences::LastScenario::default()
+    };
+
+    let script_path = value("--script").or(last.script);
+    let script = match &script_path {
+        Some(path) => script::Script::load(path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        }),
+        None => script::Script::default(),
+    };
+    let filter = tle::TleFilter {
+        altitude: range("--altitude", 1000.0),
+        inclination: range("--inclination", (1.0 as Precision).to_radians()),
+        name: value("--name"),
+    };
+    let scenario_path = value("--scenario").or(last.scenario);
+    let scenario = scenario_path.as_ref().map(|path| {
+        scenario::Scenario::load(path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        })
+    });
+    let bodies_path = value("--bodies").or(last.bodies);
+    let loader = match &bodies_path {
+        Some(path) => loading::Loader::new(path, filter),
+        None => loading::Loader::default(),
+    };
+
+    let seed = value("--random")
+        .map(|seed| {
+            seed.parse::<u64>().unwrap_or_else(|_| {
+                eprintln!("--random takes a whole number seed");
+                std::process::exit(2);
+            })
+        })
+        .or(last.seed);
+
+    let controller = match value("--controller") {
+        Some(address) => controller::ExternalController::bind(&address).unwrap_or_else(|error| {
+            eprintln!("{}: {}", address, error);
+            std::process::exit(2);
+        }),
+        None => controller::ExternalController::default(),
+    };
+
+    let narrator = match value("--narrate") {
+        Some(path) => narration::Narrator::open(&path).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path, error);
+            std::process::exit(2);
+        }),
+        None => narration::Narrator::default(),
+    };
+
+    // Random failures follow the --random seed, or a fixed one in scripts, so those runs can be
+    // repeated
+    let faults = engine::RandomFaults::new(seed.or(script_path.as_ref().map(|_| FAULT_SEED)));
+
+    if script_path.is_some() || scenario_path.is_some() || bodies_path.is_some() || seed.is_some() {
+        preferences.last_scenario =
+            preferences::LastScenario::new(script_path, scenario_path, bodies_path, seed);
+    }
+
+    // --headless <days> propagates the starting bodies without a window and writes a CSV
+    if let Some(days) = positive("--headless") {
+        let bodies = match &scenario {
+            Some(scenario) => (1..)
+                .zip(&scenario.bodies)
+                .map(|(id, spec)| (spec.name.clone(), spec.body(id)))
+                .collect(),
+            None => vec![(Some("ISS".to_string()), station(1))],
+        };
+        std::process::exit(headless::run(headless::Run {
+            bodies,
+            days,
+            sample: positive("--sample").unwrap_or(headless::DEFAULT_SAMPLE),
+            output: value("--output").unwrap_or_else(|| headless::DEFAULT_OUTPUT.to_string()),
+            integrator: integration.0.as_ref(),
+            dt: step.0,
+            perturbations,
+        }));
+    }
+
+    let speed = value("--speed")
+        .map(|speed| match speed.parse::<u32>() {
+            Ok(speed) if (1..=MAX_WARP).contains(&speed) => SimulationSpeed(speed),
+            _ => {
+                eprintln!("--speed takes a warp from 1 to {}", MAX_WARP);
+                std::process::exit(2);
+            }
+        })
+        .unwrap_or_default();
+
+    let tick = value("--tick")
+        .map(|tick| match tick.parse::<Precision>() {
+            Ok(tick) if tick > 0.0 => tick,
+            _ => {
+                eprintln!("--tick takes a positive number of physics ticks per second");
+                std::process::exit(2);
+            }
+        })
+        .unwrap_or(interpolation::DEFAULT_TICK);
+
     App::new()
         .insert_resource(ClearColor(Color::WHITE))
+        .init_resource::<SimTime>()
+        .insert_resource(step)
+        .insert_resource(lookahead)
+        .insert_resource(thrust)
+        .init_resource::<BodyIds>()
+        .insert_resource(speed)
+        .init_resource::<governor::Governor>()
+        .init_resource::<RealTime>()
+        .init_resource::<interpolation::PreviousStates>()
+        .insert_resource(Time::<Fixed>::from_hz(tick))
+        .init_resource::<console::Console>()
+        .init_resource::<coverage::Coverage>()
+        .init_resource::<ground::LinkLog>()
+        .init_resource::<antenna::LinkBudget>()
+        .init_resource::<passes::PassPredictions>()
+        .init_resource::<passes::PassJump>()
+        .insert_resource(faults)
+        .init_resource::<ephemeris::Epoch>()
+        .init_resource::<ephemeris::Ephemeris>()
+        .init_resource::<events::EventLog>()
+        .insert_resource(logbook::MissionLog::load())
+        .init_resource::<observation::Tracking>()
+        .init_resource::<kalman::KalmanFilter>()
+        .add_event::<events::SimEvent>()
+        .init_resource::<ViewMode>()
+        .init_resource::<sky::Observer>()
+        .init_resource::<cr3bp::ThreeBody>()
+        .init_resource::<PixelSize>()
+        .init_resource::<impact::ImpactPredictions>()
+        .init_resource::<dispersion::Fans>()
+        .init_resource::<propagator::MassivePaths>()
+        .init_resource::<perspective::Perspective>()
+        .init_resource::<conservation::Conservation>()
+        .init_resource::<repeat::GroundRepeat>()
+        .init_resource::<groundtrack::GroundTrack>()
+        .init_resource::<ghost::Fleet>()
+        .init_resource::<swarm::Groups>()
+        .register_diagnostic(Diagnostic::new(
+            conservation::ENERGY_DRIFT,
+            "energy_drift",
+            conservation::HISTORY,
+        ))
+        .register_diagnostic(Diagnostic::new(
+            conservation::MOMENTUM_DRIFT,
+            "momentum_drift",
+            conservation::HISTORY,
+        ))
+        .insert_resource(integration)
+        .insert_resource(perturbations)
+        .init_resource::<macros::MacroRecorder>()
+        .init_resource::<nodes::NodeEditor>()
+        .init_resource::<isl::Topology>()
+        .init_resource::<history::ElementHistory>()
+        .init_resource::<RegimeFilter>()
+        .init_resource::<i18n::Language>()
+        .init_resource::<Palette>()
+        .init_resource::<spatial::SpatialIndex>()
+        .init_resource::<optimizer::Proposal>()
+        .init_resource::<avoidance::Avoidance>()
+        .init_resource::<calculator::Calculator>()
+        .init_resource::<postmortem::PostMortem>()
+        .init_resource::<atmosphere::AtmosphereBand>()
+        .init_resource::<atmosphere::DragSettings>()
+        .init_resource::<radiation::Radiation>()
+        .init_resource::<camera::AutoZoom>()
+        .init_resource::<camera::Follow>()
+        .init_resource::<origin::FloatingOrigin>()
+        .init_resource::<frames::ReferenceFrame>()
+        .insert_resource(demo::Demo::new(args.iter().any(|arg| arg == "--demo")))
+        .add_event::<passes::WarpToNextPass>()
+        .add_event::<quicksave::Loaded>()
+        .init_resource::<health::HealthPanel>()
+        .init_resource::<scheduler::Scheduler>()
+        .insert_resource(script)
+        .insert_resource(preferences)
+        .insert_resource(loader)
+        .insert_resource(scenario::StartingScenario(scenario))
+        .insert_resource(challenge::Training::new(seed))
+        .insert_resource(narrator)
+        .insert_resource(controller)
         .add_plugins(DefaultPlugins)
+        .add_plugins(autopilot::AutopilotPlugin)
+        .add_plugins(sets::PhysicsSetsPlugin)
         .add_systems(Startup, setup)
+        .add_systems(Startup, perspective::spawn_camera)
+        .add_systems(Startup, preferences::apply_preferences)
         .add_systems(Startup, add_body)
-        .add_systems(Update, system)
+        .add_systems(Startup, gravity::spawn_earth)
+        .add_systems(Startup, hud::setup_hud)
+        .add_systems(Startup, apsides::spawn_labels)
+        .add_systems(Startup, loading::start_loading)
+        .add_systems(Startup, ground::add_stations)
+        .add_systems(
+            Startup,
+            gravity::add_moon.after(add_body).run_if(move || moon),
+        )
+        // After everything else this frame, so the log is saved before an exit
+        .add_systems(Last, logbook::save_log)
+        // Physics
+        .add_systems(
+            FixedUpdate,
+            (
+                (
+                    interpolation::remember_states,
+                    challenge::start_challenge,
+                    engine::random_faults,
+                    health::random_failures,
+                    formation::keep_formation,
+                    maneuver::execute_maneuvers,
+                    governor::govern,
+                )
+                    .chain()
+                    .in_set(sets::PhysicsSet::Forces),
+                (
+                    system,
+                    ghost::fly_ghosts,
+                    macros::record_burns,
+                    rails::follow_rails,
+                    gravity::follow_sources,
+                    ephemeris::update_ephemeris,
+                )
+                    .chain()
+                    .in_set(sets::PhysicsSet::Integrate),
+                (
+                    ground::rotate_stations,
+                    coverage::update_coverage,
+                    spatial::update_spatial_index,
+                    events::detect_events,
+                    conservation::track_invariants,
+                    script::run_scheduled,
+                )
+                    .chain()
+                    .in_set(sets::PhysicsSet::Events),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                (console::toggle_console, console::run_console)
+                    .chain()
+                    .before(sets::Timing),
+                // Controls
+                (
+                    (
+                        tether::deploy_probe,
+                        formation::spawn_deputy,
+                        formation::toggle_formation_keeping,
+                        perturbation::inject_impulse,
+                        engine::cycle_fault,
+                        passes::request_warp,
+                        insertion::open_insertion_wizard,
+                        challenge::next_challenge,
+                        spatial::pick_body,
+                        nodes::place_node,
+                        nodes::edit_node,
+                        perspective::perspective_controls,
+                        toggle_real_time,
+                        change_speed,
+                        governor::toggle_governor,
+                        quicksave::quickload,
+                        focus::cycle_focus,
+                        avoidance::accept_proposal,
+                    ),
+                    // Exports
+                    (
+                        coverage::export_coverage,
+                        ground::export_links,
+                        events::export_events,
+                        snapshot::export_snapshot,
+                        postmortem::export_report,
+                        quicksave::quicksave,
+                        groundtrack::export_ground_track,
+                    ),
+                    // Views
+                    (
+                        sky::toggle_view,
+                        sky::cycle_observer,
+                        cr3bp::three_body_controls,
+                        (
+                            camera::zoom_camera,
+                            camera::pan_camera,
+                            camera::toggle_follow,
+                        ),
+                        camera::toggle_auto_zoom,
+                        history::toggle_history,
+                        regime::toggle_regimes,
+                        calculator::toggle_calculator,
+                        palette::cycle_palette,
+                        kalman::toggle_filter,
+                        atmosphere::toggle_atmosphere,
+                        atmosphere::toggle_lookahead_drag,
+                        radiation::toggle_radiation,
+                        health::toggle_health,
+                        isl::toggle_topology,
+                        perspective::toggle_perspective,
+                        frames::cycle_frame,
+                        conservation::toggle_conservation,
+                        (repeat::toggle_repeat, groundtrack::toggle_ground_track),
+                        logbook::browse_log,
+                    ),
+                )
+                    .run_if(console::console_closed)
+                    .before(sets::Timing),
+                // Tracking and predictions, after however many physics ticks ran this frame
+                (
+                    loading::spawn_loaded_bodies,
+                    (events::record_events, logbook::log_events),
+                    sensors::read_sensors,
+                    observation::observe,
+                    kalman::run_filter,
+                    passes::warp_to_next_pass,
+                    passes::predict_passes,
+                    isl::update_topology,
+                    (impact::predict_impacts, avoidance::screen_conjunctions),
+                    dispersion::predict_fans,
+                    (prediction::add_predictions, prediction::update_predictions).chain(),
+                    (history::record_history, groundtrack::record_ground_track),
+                    postmortem::record_flights,
+                    radiation::accumulate_dose,
+                    thermal::update_thermal,
+                    health::update_power,
+                    recorder::update_recorders,
+                    challenge::score_challenge,
+                    narration::narrate,
+                    (demo::reset_demo, demo::run_demo, script::run_script).chain(),
+                )
+                    .chain()
+                    .in_set(sets::PhysicsSet::Record),
+                // Display
+                (
+                    (
+                        (
+                            formation::show_relative_elements,
+                            coverage::show_coverage,
+                            ground::update_links,
+                            isl::show_topology,
+                            passes::show_passes,
+                            ephemeris::show_ephemeris,
+                            elements::show_elements,
+                            nodes::show_node,
+                            console::show_console,
+                            impact::show_impacts,
+                            avoidance::show_avoidance,
+                        )
+                            .chain(),
+                        (
+                            history::show_drift,
+                            kalman::show_filter,
+                            conservation::show_conservation,
+                            repeat::show_repeat,
+                            groundtrack::show_ground_track,
+                            ghost::show_ghosts,
+                            logbook::show_log,
+                            postmortem::show_report,
+                            radiation::show_dose,
+                            thermal::show_thermal,
+                            recorder::show_recorder,
+                            health::show_health,
+                            loading::show_loading,
+                            challenge::show_challenge,
+                            calculator::show_calculator,
+                            regime::show_regimes,
+                        )
+                            .chain(),
+                    )
+                        .chain(),
+                    hud::label_buttons,
+                    camera::auto_zoom,
+                    camera::follow_selected,
+                    origin::recentre_origin,
+                    camera::update_pixel_size,
+                    perspective::update_perspective,
+                    preferences::apply_default_style,
+                    palette::apply_palette,
+                    preferences::save_preferences,
+                )
+                    .chain()
+                    .in_set(sets::PhysicsSet::Render),
+                (
+                    atmosphere::draw_atmosphere,
+                    radiation::draw_belts,
+                    gravity::draw_spheres_of_influence,
+                    draw_bodies,
+                    attitude::draw_thrust,
+                    tether::draw_tethers,
+                    ground::draw_stations,
+                    ephemeris::draw_ephemeris,
+                    impact::draw_impacts,
+                    dispersion::draw_fans,
+                    ghost::draw_ghosts,
+                    nodes::draw_nodes,
+                    isl::draw_topology,
+                    history::draw_history,
+                    kalman::draw_filter,
+                )
+                    .run_if(resource_equals(ViewMode::Orbit))
+                    .in_set(sets::PhysicsSet::Render),
+                (apsides::draw_apsides, sky::draw_sky, cr3bp::draw_three_body)
+                    .chain()
+                    .in_set(sets::PhysicsSet::Render),
+            )
+                .chain(),
+        )
         .run();
 }
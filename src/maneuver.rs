@@ -0,0 +1,112 @@
+// Planned impulsive burns, carried out when the sim time reaches them, with the body's burn
+// errors if it has any. Plans can be saved to and loaded from JSON files to move them between
+// sessions and scenarios.
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::dispersion::BurnErrors;
+use crate::events::SimEvent;
+use crate::perturbation::apply_impulse;
+use crate::{Body, Precision, SimTime, State};
+
+// Frame of a node's Δv. Orbital is (prograde, outward), perpendicular to the velocity and
+// away from the earth, and is resolved against the state at the time of the burn.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Frame {
+    Inertial,
+    Orbital,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ManeuverNode {
+    pub time: Precision,
+    pub dv: [Precision; 2],
+    pub frame: Frame,
+}
+
+impl ManeuverNode {
+    pub fn inertial_dv(&self, state: &State) -> (Precision, Precision) {
+        let [a, b] = self.dv;
+
+        match self.frame {
+            Frame::Inertial => (a, b),
+            Frame::Orbital => {
+                let v = (state.vx * state.vx + state.vy * state.vy).sqrt();
+                let (tx, ty) = (state.vx / v, state.vy / v);
+                // Turn the prograde direction a quarter turn towards the outside
+                let (nx, ny) = if state.x * ty - state.y * tx > 0.0 {
+                    (ty, -tx)
+                } else {
+                    (-ty, tx)
+                };
+                (a * tx + b * nx, a * ty + b * ny)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ManeuverNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b] = self.dv;
+        match self.frame {
+            Frame::Inertial => write!(f, "t = {} s: ({:.3}, {:.3}) m/s", self.time, a, b),
+            Frame::Orbital => write!(
+                f,
+                "t = {} s: {:.3} m/s prograde, {:.3} m/s outward",
+                self.time, a, b
+            ),
+        }
+    }
+}
+
+// Burns still to be done, in time order
+#[derive(Component, Default)]
+pub struct ManeuverPlan(pub Vec<ManeuverNode>);
+
+impl ManeuverPlan {
+    pub fn add(&mut self, node: ManeuverNode) {
+        let i = self.0.partition_point(|other| other.time <= node.time);
+        self.0.insert(i, node);
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        serde_json::to_writer_pretty(File::create(path)?, &self.0)?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Vec<ManeuverNode>, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        serde_json::from_str(&source).map_err(|error| format!("{}: {}", path, error))
+    }
+}
+
+// Runs before the integration step, so a node is applied to the state at its time
+pub fn execute_maneuvers(
+    time: Res<SimTime>,
+    mut query: Query<(&mut Body, &mut ManeuverPlan, Option<&BurnErrors>)>,
+    mut events: EventWriter<SimEvent>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (mut body, mut plan, errors) in query.iter_mut() {
+        while plan.0.first().is_some_and(|node| node.time <= time.0) {
+            let node = plan.0.remove(0);
+            let mut dv = node.inertial_dv(&body.current_state);
+            if let Some(errors) = errors {
+                dv = errors.perturb(dv, &mut rng);
+            }
+            let (dvx, dvy) = dv;
+            apply_impulse(&mut body, dvx, dvy, time.0, &mut events);
+            info!(
+                "Body {} executed a burn of {:.3} m/s",
+                body.id,
+                (dvx * dvx + dvy * dvy).sqrt()
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// Plain-text telemetry for screen readers: every few seconds a sentence with the altitude,
+// speed and apsides of the selected body (the first one if none is), followed by its
+// warnings, and conjunctions and crashes as soon as they happen. --narrate <path> writes it
+// to a file a screen reader can follow, or to standard output with "-".
+use std::fs::File;
+use std::io::{self, Write};
+
+use bevy::prelude::*;
+
+use crate::elements::OrbitalElements;
+use crate::engine::Engine;
+use crate::events::{EventKind, SimEvent};
+use crate::hud::format_duration;
+use crate::i18n::Language;
+use crate::impact::ImpactPredictions;
+use crate::thermal::{Thermal, ThermalStatus};
+use crate::{Body, Precision, Selected, SimTime, EARTH_RADIUS};
+
+const INTERVAL: f32 = 10.0; // s of real time between reports
+
+#[derive(Resource, Default)]
+pub struct Narrator {
+    output: Option<Box<dyn Write + Send + Sync>>,
+    last_report: Option<f32>,
+}
+
+impl Narrator {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let output: Box<dyn Write + Send + Sync> = if path == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(File::create(path)?)
+        };
+
+        Ok(Self {
+            output: Some(output),
+            last_report: None,
+        })
+    }
+
+    // Stops narrating if the output goes away rather than failing every frame
+    fn say(&mut self, line: &str) {
+        let Some(output) = self.output.as_mut() else {
+            return;
+        };
+        if let Err(error) = writeln!(output, "{}", line).and_then(|_| output.flush()) {
+            warn!("Narration stopped: {}", error);
+            self.output = None;
+        }
+    }
+}
+
+fn warnings(
+    engine: Option<&Engine>,
+    thermal: Option<&Thermal>,
+    impact: Option<String>,
+    language: Language,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if let Some(fault) = engine.and_then(|engine| engine.fault.describe(language)) {
+        warnings.push(fault);
+    }
+    if let Some(thermal) = thermal.filter(|thermal| thermal.status != ThermalStatus::Nominal) {
+        warnings.push(thermal.status.describe(language));
+    }
+    if let Some(impact) = impact {
+        warnings.push(impact);
+    }
+
+    warnings
+}
+
+type Narrated = (
+    Entity,
+    &'static Body,
+    Option<&'static Engine>,
+    Option<&'static Thermal>,
+    Has<Selected>,
+);
+
+pub fn narrate(
+    real_time: Res<Time<Real>>,
+    time: Res<SimTime>,
+    language: Res<Language>,
+    impacts: Res<ImpactPredictions>,
+    mut narrator: ResMut<Narrator>,
+    mut events: EventReader<SimEvent>,
+    bodies: Query<Narrated>,
+) {
+    if narrator.output.is_none() {
+        events.clear();
+        return;
+    }
+
+    for event in events.read() {
+        let line = match event.kind {
+            EventKind::Conjunction { other, distance } => language.text(
+                "narration_conjunction",
+                &[&event.body, &format!("{:.0}", distance), &other],
+            ),
+            EventKind::Crash { speed } => {
+                language.text("narration_crash", &[&event.body, &format!("{:.0}", speed)])
+            }
+            _ => continue,
+        };
+        let line = language.text("warning", &[&line]);
+        narrator.say(&line);
+    }
+
+    let now = real_time.elapsed_seconds();
+    if narrator
+        .last_report
+        .is_some_and(|last| now - last < INTERVAL)
+    {
+        return;
+    }
+    narrator.last_report = Some(now);
+
+    let narrated = bodies
+        .iter()
+        .find(|(.., selected)| *selected)
+        .or_else(|| bodies.iter().min_by_key(|(_, body, ..)| body.id));
+    let Some((entity, body, engine, thermal, _)) = narrated else {
+        return;
+    };
+
+    let state = body.current_state;
+    let altitude = (state.x * state.x + state.y * state.y).sqrt() - EARTH_RADIUS;
+    let speed = (state.vx * state.vx + state.vy * state.vy).sqrt();
+    let elements = OrbitalElements::from_state(&state);
+    let km = |metres: Precision| format!("{:.1}", metres / 1000.0);
+
+    let mut line = language.text(
+        "narration",
+        &[
+            &format_duration(time.0),
+            &body.id,
+            &km(altitude),
+            &format!("{:.2}", speed / 1000.0),
+            &km(elements.periapsis_altitude()),
+        ],
+    );
+    if let Some(apoapsis) = elements.apoapsis_altitude() {
+        line.push_str(&language.text("narration_apoapsis", &[&km(apoapsis)]));
+    } else {
+        line.push_str(&language.text("narration_escaping", &[]));
+    }
+    line.push('.');
+
+    let impact = impacts
+        .get(entity)
+        .map(|impact| impact.describe(time.0, *language));
+    for warning in warnings(engine, thermal, impact, *language) {
+        line.push(' ');
+        line.push_str(&language.text("warning", &[&warning]));
+        line.push('.');
+    }
+
+    narrator.say(&line);
+}
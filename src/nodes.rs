@@ -0,0 +1,250 @@
+// Maneuver nodes placed and edited on the orbit view. Right-clicking the selected body's
+// predicted path puts a node of no Δv in its plan at that point, or picks the node already
+// there; Page Up and Page Down then change the picked node's prograde Δv and Home and End its
+// outward Δv, by 1 m/s a press (10 with Shift), and Delete drops it. The path after the first
+// node is predicted again with the plan's burns and drawn in the planned color, so the effect
+// of a burn shows as it is set. Nodes are burns of the plan like any other, carried out when
+// the clock reaches them.
+use bevy::ecs::system::SystemParam;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::PixelSize;
+use crate::crash::Crashed;
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::maneuver::{Frame, ManeuverNode, ManeuverPlan};
+use crate::palette::Palette;
+use crate::propagator::{Coast, Coasting};
+use crate::sky::ViewMode;
+use crate::{Body, Lookahead, Precision, Selected, SimTime, State};
+
+const PICK_RADIUS: Precision = 10.0; // pixels
+const NODE_RADIUS: f32 = 5.0; // pixels
+const STEP: Precision = 1.0; // m/s a press
+const MAX_LEAD: usize = 10; // lookaheads, later nodes aren't drawn
+
+// The node being edited, by body and time
+#[derive(Resource, Default)]
+pub struct NodeEditor {
+    editing: Option<(Entity, Precision)>,
+}
+
+#[derive(Component)]
+pub struct NodeText;
+
+type Planned<'a> = (Entity, &'a Body, &'a ManeuverPlan);
+type Replanning<'a> = (Entity, &'a Body, &'a mut ManeuverPlan);
+
+// Where the mouse points in the world, and how far a pixel goes there
+#[derive(SystemParam)]
+pub struct Pointer<'w, 's> {
+    pixel: Res<'w, PixelSize>,
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<Camera2d>>,
+    interpolation: Interpolation<'w, 's>,
+}
+
+impl Pointer<'_, '_> {
+    fn world(&self) -> Option<DVec2> {
+        let cursor = self.windows.get_single().ok()?.cursor_position()?;
+        let (camera, transform) = self.cameras.single();
+        // Not while the perspective camera is drawing
+        if !camera.is_active {
+            return None;
+        }
+        let point = camera.viewport_to_world_2d(transform, cursor)?;
+        Some(self.interpolation.world(point))
+    }
+}
+
+// The states every step of the coast until `lookahead` steps past the first node, carrying
+// out the plan's burns on the way, and the step each burn comes at
+fn fly_plan(mut coast: Coast, plan: &ManeuverPlan, lookahead: usize) -> (Vec<State>, Vec<usize>) {
+    let lead = plan.0.first().map_or(0, |node| {
+        let steps = ((node.time - coast.time) / coast.dt()).ceil().max(0.0) as usize;
+        steps.min(MAX_LEAD * lookahead)
+    });
+    let mut states = Vec::with_capacity(lead + lookahead + 1);
+    let mut burns = Vec::new();
+    let mut nodes = plan.0.iter().peekable();
+
+    for i in 0..=lead + lookahead {
+        while let Some(node) = nodes.next_if(|node| node.time <= coast.time) {
+            let (dvx, dvy) = node.inertial_dv(&coast.state);
+            coast.state.vx += dvx;
+            coast.state.vy += dvy;
+            burns.push(i);
+        }
+        states.push(coast.state);
+        coast.step();
+    }
+    (states, burns)
+}
+
+fn position(state: &State) -> DVec2 {
+    DVec2::new(state.x, state.y)
+}
+
+// Right-clicking the selected body's path places a node there, or picks the one it hits
+pub fn place_node(
+    mouse: Res<Input<MouseButton>>,
+    view: Res<ViewMode>,
+    (time, lookahead): (Res<SimTime>, Res<Lookahead>),
+    (pointer, coasting): (Pointer, Coasting),
+    mut editor: ResMut<NodeEditor>,
+    mut selected: Query<Replanning, (With<Selected>, Without<Crashed>)>,
+) {
+    if *view != ViewMode::Orbit || !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Ok((entity, body, mut plan)) = selected.get_single_mut() else {
+        return;
+    };
+    let Some(point) = pointer.world() else {
+        return;
+    };
+    let radius = PICK_RADIUS * pointer.pixel.0;
+
+    let propagator = coasting.propagator();
+    let (states, burns) = fly_plan(propagator.coast(body, time.0), &plan, lookahead.0);
+    let picked = burns
+        .iter()
+        .zip(&plan.0)
+        .find(|(step, _)| position(&states[**step]).distance(point) < radius);
+    if let Some((_, node)) = picked {
+        editor.editing = Some((entity, node.time));
+        return;
+    }
+
+    // Not the body's current position, which has no time left to plan for
+    let Some((step, _)) = states
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, state)| (i, position(state).distance(point)))
+        .filter(|(_, distance)| *distance < radius)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+    else {
+        return;
+    };
+    let node_time = time.0 + step as Precision * propagator.dt;
+    plan.add(ManeuverNode {
+        time: node_time,
+        dv: [0.0, 0.0],
+        frame: Frame::Orbital,
+    });
+    editor.editing = Some((entity, node_time));
+    info!("Body {}: maneuver node at t = {} s", body.id, node_time);
+}
+
+// Page Up and Down, Home and End change the edited node's Δv, Delete drops it
+pub fn edit_node(
+    keyboard: Res<Input<KeyCode>>,
+    mut editor: ResMut<NodeEditor>,
+    mut plans: Query<&mut ManeuverPlan>,
+) {
+    let Some((entity, time)) = editor.editing else {
+        return;
+    };
+    let Ok(mut plan) = plans.get_mut(entity) else {
+        editor.editing = None;
+        return;
+    };
+    let Some(i) = plan.0.iter().position(|node| node.time == time) else {
+        // Carried out or dropped from a script
+        editor.editing = None;
+        return;
+    };
+
+    if keyboard.just_pressed(KeyCode::Delete) {
+        plan.0.remove(i);
+        editor.editing = None;
+        return;
+    }
+    let step = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        10.0 * STEP
+    } else {
+        STEP
+    };
+    let change = [
+        (KeyCode::PageUp, 0, step),
+        (KeyCode::PageDown, 0, -step),
+        (KeyCode::Home, 1, step),
+        (KeyCode::End, 1, -step),
+    ]
+    .into_iter()
+    .find(|(key, ..)| keyboard.just_pressed(*key));
+    if let Some((_, axis, by)) = change {
+        plan.0[i].dv[axis] += by;
+    }
+}
+
+// The path after the first node with the plan's burns, and a circle on each node
+pub fn draw_nodes(
+    mut gizmos: Gizmos,
+    (time, pixel, lookahead): (Res<SimTime>, Res<PixelSize>, Res<Lookahead>),
+    (palette, editor): (Res<Palette>, Res<NodeEditor>),
+    interpolation: Interpolation,
+    coasting: Coasting,
+    selected: Query<Planned, (With<Selected>, Without<Crashed>)>,
+) {
+    let Ok((entity, body, plan)) = selected.get_single() else {
+        return;
+    };
+    let propagator = coasting.propagator();
+    let (states, burns) = fly_plan(propagator.coast(body, time.0), plan, lookahead.0);
+    let view = interpolation.view();
+    let Some(&first) = burns.first() else {
+        return;
+    };
+    let color = palette.colors().planned;
+    let scale = pixel.0 as f32;
+
+    gizmos.linestrip_2d(
+        states
+            .iter()
+            .enumerate()
+            .skip(first)
+            .map(|(step, state)| view.ahead(state, step)),
+        color,
+    );
+    for (step, node) in burns.iter().zip(&plan.0) {
+        let edited = editor.editing == Some((entity, node.time));
+        let radius = if edited { 2.0 } else { 1.0 } * NODE_RADIUS * scale;
+        gizmos.circle_2d(view.ahead(&states[*step], *step), radius, color);
+    }
+}
+
+pub fn show_node(
+    time: Res<SimTime>,
+    editor: Res<NodeEditor>,
+    language: Res<Language>,
+    plans: Query<&ManeuverPlan>,
+    mut text: Query<&mut Text, With<NodeText>>,
+) {
+    let node = editor.editing.and_then(|(entity, node_time)| {
+        plans
+            .get(entity)
+            .ok()?
+            .0
+            .iter()
+            .find(|node| node.time == node_time)
+            .copied()
+    });
+    text.single_mut().sections[0].value = match node {
+        Some(node) => language.text(
+            match node.frame {
+                Frame::Orbital => "node",
+                Frame::Inertial => "node_inertial",
+            },
+            &[
+                &format!("{:.0}", node.time - time.0),
+                &format!("{:.1}", node.dv[0]),
+                &format!("{:.1}", node.dv[1]),
+            ],
+        ),
+        None => String::new(),
+    };
+}
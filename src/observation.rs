@@ -0,0 +1,141 @@
+// Simulated tracking: every minute each ground station that sees a body measures its range
+// and the direction of the line of sight, with Gaussian noise. A body's measurements start
+// over when it burns or takes an impulse, so they always cover a coasting arc.
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+
+use bevy::math::{DVec2, DVec4};
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::events::{EventKind, SimEvent};
+use crate::ground::GroundStation;
+use crate::{Body, Precision, SimTime, State, EARTH_RADIUS};
+
+const INTERVAL: Precision = 60.0; // s
+const MAX_OBSERVATIONS: usize = 1000; // per body, the oldest are dropped
+pub const RANGE_NOISE: Precision = 10.0; // m, standard deviation
+pub const ANGLE_NOISE: Precision = 3.5e-4; // rad, about 0.02°
+
+// A normally distributed sample
+pub fn gaussian(rng: &mut impl Rng, sigma: Precision) -> Precision {
+    let (u, v): (Precision, Precision) = (rng.gen_range(Precision::EPSILON..1.0), rng.gen());
+    sigma * (-2.0 * u.ln()).sqrt() * (TAU * v).cos()
+}
+
+#[derive(Clone, Copy)]
+pub struct Observation {
+    pub time: Precision,
+    pub station: DVec2, // m, the station's position
+    pub range: Precision,
+    pub angle: Precision, // rad, from the station's east horizon towards its zenith
+}
+
+// Local frame of a station: east, then up
+fn station_frame(station: DVec2) -> (DVec2, DVec2) {
+    let up = station / EARTH_RADIUS;
+    (up.perp(), up)
+}
+
+impl Observation {
+    // What the station would measure without noise, and the gradient of the range and the
+    // angle with respect to (x, y, vx, vy)
+    pub fn predict(station: DVec2, state: &State) -> ([Precision; 2], [DVec4; 2]) {
+        let (east, up) = station_frame(station);
+        let d = DVec2::new(state.x, state.y) - station;
+        let (p, q) = (d.dot(east), d.dot(up));
+        let range = d.length();
+
+        let range_gradient = d / range;
+        let angle_gradient = (p * up - q * east) / (range * range);
+        (
+            [range, q.atan2(p)],
+            [
+                DVec4::new(range_gradient.x, range_gradient.y, 0.0, 0.0),
+                DVec4::new(angle_gradient.x, angle_gradient.y, 0.0, 0.0),
+            ],
+        )
+    }
+
+    // Where the measurement puts the body
+    pub fn position(&self) -> DVec2 {
+        let (east, up) = station_frame(self.station);
+        let (sin, cos) = self.angle.sin_cos();
+        self.station + self.range * (cos * east + sin * up)
+    }
+}
+
+struct Track {
+    observations: Vec<Observation>, // the latest, in time order
+    started: Precision,             // s, time of the first one
+    taken: usize,                   // since the start, dropped ones included
+}
+
+// Measurements of each body by id
+#[derive(Resource, Default)]
+pub struct Tracking {
+    tracks: HashMap<usize, Track>,
+    last: Option<Precision>,
+}
+
+impl Tracking {
+    pub fn get(&self, id: usize) -> &[Observation] {
+        self.tracks
+            .get(&id)
+            .map_or(&[], |track| track.observations.as_slice())
+    }
+
+    // When the measurements of a body started and how many have been taken since
+    pub fn progress(&self, id: usize) -> Option<(Precision, usize)> {
+        self.tracks
+            .get(&id)
+            .map(|track| (track.started, track.taken))
+    }
+}
+
+pub fn observe(
+    time: Res<SimTime>,
+    mut tracking: ResMut<Tracking>,
+    mut events: EventReader<SimEvent>,
+    stations: Query<&GroundStation>,
+    bodies: Query<&Body>,
+) {
+    for event in events.read() {
+        if matches!(event.kind, EventKind::BurnStart | EventKind::Impulse { .. }) {
+            tracking.tracks.remove(&event.body);
+        }
+    }
+
+    if tracking.last.is_some_and(|last| time.0 - last < INTERVAL) {
+        return;
+    }
+    tracking.last = Some(time.0);
+
+    let mut rng = rand::thread_rng();
+    for body in bodies.iter() {
+        for station in stations.iter() {
+            if !station.link(&body.current_state).visible() {
+                continue;
+            }
+
+            let (sx, sy) = station.position();
+            let position = DVec2::new(sx, sy);
+            let ([range, angle], _) = Observation::predict(position, &body.current_state);
+            let track = tracking.tracks.entry(body.id).or_insert(Track {
+                observations: Vec::new(),
+                started: time.0,
+                taken: 0,
+            });
+            if track.observations.len() == MAX_OBSERVATIONS {
+                track.observations.remove(0);
+            }
+            track.taken += 1;
+            track.observations.push(Observation {
+                time: time.0,
+                station: position,
+                range: range + gaussian(&mut rng, RANGE_NOISE),
+                angle: angle + gaussian(&mut rng, ANGLE_NOISE),
+            });
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Single-burn optimiser: searches the integration steps of the next orbit for the burn
+// with the least Δv that puts the body on an orbit with given apsides. The burn has to
+// happen where the body's radius lies between the target apsides, and there the target
+// orbit's speed and angular momentum fix the velocity up to the sign of the radial part.
+use std::f64::consts::TAU;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::elements::{OrbitalElements, MU};
+use crate::maneuver::{Frame, ManeuverNode};
+use crate::propagator::Coast;
+use crate::{Precision, State, EARTH_RADIUS};
+
+const MAX_STEPS: usize = 20000;
+
+// A node waiting for the user to accept it
+#[derive(Resource, Default)]
+pub struct Proposal(pub Option<(Entity, ManeuverNode)>);
+
+// Smallest change of velocity at `state` that puts it on the target orbit, if it can
+fn burn_at(state: &State, periapsis: Precision, apoapsis: Precision) -> Option<DVec2> {
+    let position = DVec2::new(state.x, state.y);
+    let velocity = DVec2::new(state.vx, state.vy);
+    let r = position.length();
+    if r < periapsis || r > apoapsis {
+        return None;
+    }
+
+    let a = (periapsis + apoapsis) / 2.0;
+    let h = (MU * 2.0 * periapsis * apoapsis / (periapsis + apoapsis)).sqrt();
+    let speed = (MU * (2.0 / r - 1.0 / a)).sqrt();
+    let transverse = h / r;
+    let radial = (speed * speed - transverse * transverse).max(0.0).sqrt();
+
+    // Keep the sense of rotation
+    let outward = position / r;
+    let sense = if position.perp_dot(velocity) < 0.0 {
+        -1.0
+    } else {
+        1.0
+    };
+    let along = sense * outward.perp();
+
+    [radial, -radial]
+        .into_iter()
+        .map(|radial| outward * radial + along * transverse - velocity)
+        .min_by(|a, b| a.length().total_cmp(&b.length()))
+}
+
+pub fn optimize_burn(
+    mut coast: Coast,
+    periapsis_altitude: Precision,
+    apoapsis_altitude: Precision,
+) -> Result<ManeuverNode, String> {
+    let (periapsis, apoapsis) = (
+        EARTH_RADIUS + periapsis_altitude,
+        EARTH_RADIUS + apoapsis_altitude,
+    );
+    if periapsis_altitude <= 0.0 || apoapsis < periapsis {
+        return Err("expected a periapsis above the surface and below the apoapsis".to_string());
+    }
+
+    let now = coast.time;
+    let elements = OrbitalElements::from_state(&coast.state);
+    if elements.e >= 1.0 || elements.a <= 0.0 {
+        return Err("the body is on an escape orbit".to_string());
+    }
+    let period = TAU * (elements.a.powi(3) / MU).sqrt();
+    let steps = ((period / coast.dt()).ceil() as usize).min(MAX_STEPS);
+
+    // Burns land on integration steps, so every step of an orbit is a candidate
+    let mut best: Option<(usize, DVec2)> = None;
+    for step in 1..=steps {
+        let state = coast.step();
+        if let Some(dv) = burn_at(&state, periapsis, apoapsis) {
+            if best.is_none_or(|(_, other)| dv.length() < other.length()) {
+                best = Some((step, dv));
+            }
+        }
+    }
+
+    let Some((step, dv)) = best else {
+        return Err("the orbit never reaches a radius between the target apsides".to_string());
+    };
+
+    Ok(ManeuverNode {
+        time: now + step as Precision * coast.dt(),
+        dv: [dv.x, dv.y],
+        frame: Frame::Inertial,
+    })
+}
@@ -0,0 +1,27 @@
+// Floating origin: the physics runs in f64 metres, but Bevy draws in f32, which at the Moon's
+// distance only resolves to tens of metres, so bodies, trails and the camera would jitter when
+// zoomed in far from the earth. Drawing coordinates are taken relative to a floating origin kept
+// in f64 instead: every frame, once the camera has moved, the origin moves to where the 2D
+// camera looks and the camera back to the middle of drawing space, so whatever is on screen is
+// drawn at small coordinates at full precision whatever the zoom. Interpolation does the
+// subtraction, in f64, for everything drawn in the orbit view.
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+// The point of the drawing frame, in m, at the centre of drawing space
+#[derive(Resource, Default)]
+pub struct FloatingOrigin(pub DVec2);
+
+// After the camera has followed, zoomed and panned for the frame, before anything is drawn
+pub fn recentre_origin(
+    mut origin: ResMut<FloatingOrigin>,
+    mut camera: Query<&mut Transform, With<OrthographicProjection>>,
+) {
+    let mut transform = camera.single_mut();
+    let centre = transform.translation.truncate();
+    if centre == Vec2::ZERO {
+        return;
+    }
+    origin.0 += centre.as_dvec2();
+    transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+}
@@ -0,0 +1,178 @@
+// Color palettes for everything drawn: the earth, bodies, trails, predictions and the HUD.
+// The classic one is the original red/green/blue on white; the colorblind-safe one uses the
+// Okabe-Ito colors, and the high-contrast one puts bright colors on black.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::style::BodyStyle;
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    #[default]
+    Classic,
+    Colorblind,
+    HighContrast,
+}
+
+const PALETTES: [Palette; 3] = [Palette::Classic, Palette::Colorblind, Palette::HighContrast];
+
+#[derive(Clone, Copy)]
+pub struct Colors {
+    pub background: Color,
+    pub text: Color,
+    pub earth: Color,
+    pub body: Color,
+    pub trail: Color,
+    pub lookahead: Color,
+    pub prediction: Color, // impact points and footprints
+    pub planned: Color,    // maneuver nodes and the path after them
+    pub guide: Color,      // grids, rings and slack tethers
+    pub faint: Color,      // the Moon and the three-body plot axes
+    pub station: Color,
+    pub sun: Color,
+    pub warning: Color,
+}
+
+impl Palette {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "classic" => Ok(Palette::Classic),
+            "colorblind" => Ok(Palette::Colorblind),
+            "high_contrast" => Ok(Palette::HighContrast),
+            _ => Err(format!(
+                "unknown palette `{}`, expected \"classic\", \"colorblind\" or \"high_contrast\"",
+                name
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Classic => "classic",
+            Palette::Colorblind => "colorblind",
+            Palette::HighContrast => "high_contrast",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = PALETTES
+            .iter()
+            .position(|palette| *palette == self)
+            .unwrap();
+        PALETTES[(i + 1) % PALETTES.len()]
+    }
+
+    pub fn colors(self) -> Colors {
+        match self {
+            Palette::Classic => Colors {
+                background: Color::WHITE,
+                text: Color::BLACK,
+                earth: Color::BLUE,
+                body: Color::RED,
+                trail: Color::RED,
+                lookahead: Color::GREEN,
+                prediction: Color::PURPLE,
+                planned: Color::TEAL,
+                guide: Color::GRAY,
+                faint: Color::DARK_GRAY,
+                station: Color::DARK_GREEN,
+                sun: Color::ORANGE,
+                warning: Color::RED,
+            },
+            // Told apart with any of the common color vision deficiencies
+            Palette::Colorblind => Colors {
+                background: Color::WHITE,
+                text: Color::BLACK,
+                earth: Color::rgb_u8(0, 114, 178),        // blue
+                body: Color::rgb_u8(213, 94, 0),          // vermillion
+                trail: Color::rgb_u8(213, 94, 0),         // vermillion
+                lookahead: Color::rgb_u8(86, 180, 233),   // sky blue
+                prediction: Color::rgb_u8(204, 121, 167), // reddish purple
+                planned: Color::rgb_u8(230, 159, 0),      // orange
+                guide: Color::GRAY,
+                faint: Color::DARK_GRAY,
+                station: Color::rgb_u8(0, 158, 115), // bluish green
+                sun: Color::rgb_u8(230, 159, 0),     // orange
+                warning: Color::rgb_u8(213, 94, 0),  // vermillion
+            },
+            Palette::HighContrast => Colors {
+                background: Color::BLACK,
+                text: Color::WHITE,
+                earth: Color::rgb_u8(77, 166, 255),
+                body: Color::YELLOW,
+                trail: Color::YELLOW,
+                lookahead: Color::CYAN,
+                prediction: Color::rgb_u8(255, 102, 255),
+                planned: Color::rgb_u8(255, 165, 0),
+                guide: Color::rgb_u8(176, 176, 176),
+                faint: Color::rgb_u8(128, 128, 128),
+                station: Color::rgb_u8(102, 255, 102),
+                sun: Color::ORANGE,
+                warning: Color::rgb_u8(255, 80, 80),
+            },
+        }
+    }
+}
+
+// Body colors that are the palette's own move to the new palette, so colors set with
+// set_color(...) and friends are kept
+fn recolor(style: &mut BodyStyle, from: &Colors, to: &Colors) {
+    if style.color == from.body {
+        style.color = to.body;
+    }
+    if style.trail_color == from.trail {
+        style.trail_color = to.trail;
+    }
+    if style.lookahead_color == from.lookahead {
+        style.lookahead_color = to.lookahead;
+    }
+}
+
+// P cycles through the palettes
+pub fn cycle_palette(keyboard: Res<Input<KeyCode>>, mut palette: ResMut<Palette>) {
+    if keyboard.just_pressed(KeyCode::P) {
+        *palette = palette.next();
+        info!("Palette: {}", palette.name());
+    }
+}
+
+// New bodies start with classic colors, and the HUD text follows the palette. Runs after
+// the preferred style has been applied to new bodies.
+pub fn apply_palette(
+    palette: Res<Palette>,
+    mut previous: Local<Option<Palette>>,
+    mut clear: ResMut<ClearColor>,
+    mut styles: Query<&mut BodyStyle>,
+    mut texts: Query<(&mut Text, Option<&Parent>)>,
+    buttons: Query<(), With<Button>>,
+) {
+    let changed = *previous != Some(*palette);
+    let from = previous.unwrap_or_default().colors();
+    let colors = palette.colors();
+    *previous = Some(*palette);
+
+    if changed {
+        clear.0 = colors.background;
+    }
+
+    for mut style in styles.iter_mut() {
+        if style.is_added() {
+            recolor(&mut style, &Palette::Classic.colors(), &colors);
+        } else if changed {
+            recolor(&mut style, &from, &colors);
+        }
+    }
+
+    for (mut text, parent) in texts.iter_mut() {
+        // Button labels keep their own color
+        if parent.is_some_and(|parent| buttons.contains(parent.get())) {
+            continue;
+        }
+        if changed || text.is_added() {
+            for section in text.sections.iter_mut() {
+                section.style.color = colors.text;
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+// Upcoming passes of the selected body over the ground stations, along its predicted
+// trajectory, and the jump ahead to the next one
+use bevy::prelude::*;
+
+use crate::ground::GroundStation;
+use crate::hud::format_duration;
+use crate::i18n::Language;
+use crate::prediction::PredictedTrajectory;
+use crate::{Precision, Selected, SimTime, TimeStep, MAX_WARP};
+
+// Predictions are refreshed this often, or right away after a warp
+const REFRESH_INTERVAL: Precision = 60.0; // s
+
+pub struct Pass {
+    pub station: String,
+    pub aos: Precision,         // acquisition of signal, sim time
+    pub los: Option<Precision>, // loss of signal, if it happens within the lookahead
+    pub max_elevation: Precision,
+}
+
+#[derive(Resource, Default)]
+pub struct PassPredictions {
+    pub passes: Vec<Pass>,
+    computed_at: Option<Precision>,
+}
+
+#[derive(Event)]
+pub struct WarpToNextPass;
+
+// The acquisition of signal a jump is under way to. The ticks run the top warp until they
+// reach it, held down by the governor and the scheduler like any other warp.
+#[derive(Resource, Default)]
+pub struct PassJump {
+    until: Option<Precision>,
+}
+
+impl PassJump {
+    // The steps of `dt` a tick at `now` runs with the governor's `warp`, while jumping
+    pub fn steps(&self, now: Precision, warp: u32, dt: Precision) -> Option<u32> {
+        let steps = ((self.until? - now) / dt).ceil().max(0.0) as u32;
+        Some(steps.min(warp))
+    }
+}
+
+#[derive(Component)]
+pub struct PassText;
+
+#[derive(Component)]
+pub struct WarpButton;
+
+// Time within the step of `dt` up to `time` at which the elevation crosses zero
+fn crossing(
+    time: Precision,
+    dt: Precision,
+    previous: Precision,
+    elevation: Precision,
+) -> Precision {
+    time - dt + dt * previous / (previous - elevation)
+}
+
+pub fn predict_passes(
+    time: Res<SimTime>,
+    mut predictions: ResMut<PassPredictions>,
+    stations: Query<&GroundStation>,
+    selected: Query<&PredictedTrajectory, With<Selected>>,
+) {
+    if let Some(computed_at) = predictions.computed_at {
+        if time.0 - computed_at < REFRESH_INTERVAL {
+            return;
+        }
+    }
+    predictions.computed_at = Some(time.0);
+    predictions.passes.clear();
+
+    // The coasting lookahead drawn on screen
+    let Some(prediction) = selected.get_single().ok() else {
+        return;
+    };
+    let states = prediction.states();
+    let Some(first) = states.first() else {
+        // Not flown yet
+        predictions.computed_at = None;
+        return;
+    };
+    let (start, dt) = (prediction.start(), prediction.dt());
+
+    for station in stations.iter() {
+        let mut previous = station.link_in(first, start - time.0).elevation;
+        // A pass already in progress starts now
+        let mut current = (previous > 0.0).then(|| Pass {
+            station: station.name.clone(),
+            aos: time.0,
+            los: None,
+            max_elevation: previous,
+        });
+
+        for (i, state) in states.iter().enumerate().skip(1) {
+            let t = start + i as Precision * dt;
+            let elevation = station.link_in(state, t - time.0).elevation;
+
+            match current.as_mut() {
+                None if elevation > 0.0 => {
+                    current = Some(Pass {
+                        station: station.name.clone(),
+                        aos: crossing(t, dt, previous, elevation),
+                        los: None,
+                        max_elevation: elevation,
+                    });
+                }
+                Some(pass) if elevation <= 0.0 => {
+                    pass.los = Some(crossing(t, dt, previous, elevation));
+                    predictions.passes.extend(current.take());
+                }
+                Some(pass) => pass.max_elevation = pass.max_elevation.max(elevation),
+                None => {}
+            }
+
+            previous = elevation;
+        }
+
+        predictions.passes.extend(current);
+    }
+
+    predictions.passes.sort_by(|a, b| a.aos.total_cmp(&b.aos));
+}
+
+// W or the on-screen button jumps ahead to the next acquisition of signal
+pub fn request_warp(
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<WarpButton>)>,
+    mut events: EventWriter<WarpToNextPass>,
+) {
+    let clicked = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+
+    if clicked || keyboard.just_pressed(KeyCode::W) {
+        events.send(WarpToNextPass);
+    }
+}
+
+// The simulation runs on at the top warp until the next pass starts
+pub fn warp_to_next_pass(
+    mut events: EventReader<WarpToNextPass>,
+    (time, step): (Res<SimTime>, Res<TimeStep>),
+    mut jump: ResMut<PassJump>,
+    mut predictions: ResMut<PassPredictions>,
+) {
+    if jump.steps(time.0, MAX_WARP, step.0) == Some(0) {
+        jump.until = None;
+        predictions.computed_at = None;
+        info!("Warped to the pass at t = {:.0} s", time.0);
+    }
+    if events.read().count() == 0 {
+        return;
+    }
+
+    let Some(aos) = predictions
+        .passes
+        .iter()
+        .map(|pass| pass.aos)
+        .find(|aos| *aos > time.0)
+    else {
+        info!("No pass within the lookahead to warp to");
+        return;
+    };
+
+    jump.until = Some(aos);
+    info!("Warping to the pass at t = {:.0} s", aos);
+}
+
+pub fn show_passes(
+    time: Res<SimTime>,
+    predictions: Res<PassPredictions>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<PassText>>,
+) {
+    let mut value = language.text("passes", &[]);
+    value.push('\n');
+
+    for pass in predictions.passes.iter() {
+        let los = match pass.los {
+            Some(los) => format_duration(los - time.0),
+            None => "-".to_string(),
+        };
+
+        value.push_str(&format!(
+            "{}: {}  {}  {:.0}°\n",
+            pass.station,
+            format_duration(pass.aos - time.0),
+            los,
+            pass.max_elevation.to_degrees()
+        ));
+    }
+
+    text.single_mut().sections[0].value = value;
+}
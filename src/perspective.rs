@@ -0,0 +1,167 @@
+// The perspective camera: the orbit view in 3D, looking down on the orbit plane at an angle from
+// around the selected body, or the earth's centre with none selected. Backslash swings the view
+// from the 2D top-down one over to the tilted one, and back before the 2D camera takes over
+// again. While it is on, dragging with the left mouse button circles the focus and tilts the
+// view, dragging with the right one pans, and the wheel zooms like = and -. Picking bodies and
+// placing nodes wait for the 2D view, and the other views bring it back straight away.
+use bevy::core_pipeline::tonemapping::{DebandDither, Tonemapping};
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+
+use crate::camera::PixelSize;
+use crate::interpolation::Interpolation;
+use crate::sky::ViewMode;
+use crate::{Body, Selected};
+
+const FIELD_OF_VIEW: f32 = std::f32::consts::FRAC_PI_4; // rad, vertical
+const DEFAULT_TILT: f32 = 1.0; // rad from looking straight down
+const MAX_TILT: f32 = 1.5; // rad, just short of edge-on
+const ROTATE_RATE: f32 = 0.005; // rad per pixel dragged
+const ZOOM_FACTOR: f32 = 1.25; // per wheel notch, as for = and -
+const SMOOTHING: f32 = 0.1; // fraction of the way to the wanted view per frame
+const NEAR: f32 = 1000.0; // m
+const FAR: f32 = 1e10; // m
+
+#[derive(Resource)]
+pub struct Perspective {
+    pub enabled: bool,
+    blend: f32, // how far over from the top-down view, 0 to 1
+    tilt: f32,  // rad from looking straight down
+    yaw: f32,   // rad the view is turned about the focus
+    pan: Vec2,  // m from the focus
+}
+
+impl Default for Perspective {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            blend: 0.0,
+            tilt: DEFAULT_TILT,
+            yaw: 0.0,
+            pan: Vec2::ZERO,
+        }
+    }
+}
+
+impl Perspective {
+    // Whether the perspective camera is the one drawing, on or on its way back
+    fn showing(&self) -> bool {
+        self.enabled || self.blend > 0.0
+    }
+}
+
+pub fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            is_active: false,
+            order: 1,
+            ..default()
+        },
+        projection: Projection::Perspective(PerspectiveProjection {
+            fov: FIELD_OF_VIEW,
+            near: NEAR,
+            far: FAR,
+            ..default()
+        }),
+        // Colors as the 2D camera shows them
+        tonemapping: Tonemapping::None,
+        dither: DebandDither::Disabled,
+        ..default()
+    });
+}
+
+// Backslash swings over to the perspective view or back
+pub fn toggle_perspective(
+    keyboard: Res<Input<KeyCode>>,
+    view: Res<ViewMode>,
+    mut perspective: ResMut<Perspective>,
+) {
+    if keyboard.just_pressed(KeyCode::Backslash) && *view == ViewMode::Orbit {
+        perspective.enabled = !perspective.enabled;
+    }
+}
+
+// Left drag circles and tilts, right drag pans, the wheel zooms
+pub fn perspective_controls(
+    mouse: Res<Input<MouseButton>>,
+    pixel: Res<PixelSize>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut perspective: ResMut<Perspective>,
+    mut projection: Query<&mut OrthographicProjection>,
+) {
+    let dragged: Vec2 = motion.read().map(|event| event.delta).sum();
+    let notches: f32 = wheel.read().map(|event| event.y.signum()).sum();
+    if !perspective.enabled {
+        return;
+    }
+
+    if mouse.pressed(MouseButton::Left) {
+        perspective.yaw -= dragged.x * ROTATE_RATE;
+        perspective.tilt = (perspective.tilt - dragged.y * ROTATE_RATE).clamp(0.0, MAX_TILT);
+    }
+    if mouse.pressed(MouseButton::Right) {
+        // Screen right and up on the plane, as turned by the yaw
+        let (sin, cos) = perspective.yaw.sin_cos();
+        let right = Vec2::new(cos, sin);
+        let up = Vec2::new(-sin, cos);
+        let scale = pixel.0 as f32;
+        perspective.pan -= (right * dragged.x - up * dragged.y) * scale;
+    }
+    // The 2D camera's zoom, so the view keeps it on the way back
+    if notches != 0.0 {
+        projection.single_mut().scale /= ZOOM_FACTOR.powf(notches);
+    }
+}
+
+type Flat<'a> = (&'a mut Camera, &'a Transform, &'a OrthographicProjection);
+type Tilted<'a> = (&'a mut Camera, &'a mut Transform);
+
+// Eases the view towards the tilted or top-down one, puts the camera there and switches
+// between the cameras
+pub fn update_perspective(
+    view: Res<ViewMode>,
+    interpolation: Interpolation,
+    mut perspective: ResMut<Perspective>,
+    selected: Query<&Body, With<Selected>>,
+    mut flat: Query<Flat, Without<Camera3d>>,
+    mut tilted: Query<Tilted, With<Camera3d>>,
+) {
+    if *view != ViewMode::Orbit {
+        perspective.enabled = false;
+        perspective.blend = 0.0;
+    }
+    let wanted = if perspective.enabled { 1.0 } else { 0.0 };
+    perspective.blend += (wanted - perspective.blend) * SMOOTHING;
+    if (wanted - perspective.blend).abs() < 1e-3 {
+        perspective.blend = wanted;
+    }
+
+    let showing = perspective.showing();
+    let (mut flat_camera, flat_transform, projection) = flat.single_mut();
+    let (mut camera, mut transform) = tilted.single_mut();
+    flat_camera.is_active = !showing;
+    camera.is_active = showing;
+    if !showing {
+        return;
+    }
+
+    // From the 2D view's centre and span to the focus and the perspective's own angles. At its
+    // distance the focus's plane spans what the 2D view does, so pixels keep their size there.
+    let blend = perspective.blend;
+    let centre = flat_transform.translation.truncate();
+    let focus = selected
+        .get_single()
+        .map_or(interpolation.point(0.0, 0.0), |body| {
+            interpolation.position(body)
+        });
+    let focus = centre.lerp(focus + perspective.pan, blend).extend(0.0);
+    let distance = 0.5 * projection.area.height() / (0.5 * FIELD_OF_VIEW).tan();
+    let (tilt, yaw) = (perspective.tilt * blend, perspective.yaw * blend);
+
+    let (sin_yaw, cos_yaw) = yaw.sin_cos();
+    let (sin_tilt, cos_tilt) = tilt.sin_cos();
+    let offset = Vec3::new(sin_tilt * sin_yaw, -sin_tilt * cos_yaw, cos_tilt) * distance;
+    let up = Vec3::new(-sin_yaw, cos_yaw, 0.0);
+    *transform = Transform::from_translation(focus + offset).looking_at(focus, up);
+}
@@ -0,0 +1,66 @@
+// Debug injection of instantaneous velocity changes (micrometeoroid hits, thruster faults)
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::events::{EventKind, SimEvent};
+use crate::macros::{body_call, MacroRecorder};
+use crate::tether::Untethered;
+use crate::{Body, Precision, SimTime};
+
+const MAX_RANDOM_IMPULSE: Precision = 20.0; // m/s
+const SPECIFIED_IMPULSE: Precision = 5.0; // m/s, along the velocity
+
+pub fn apply_impulse(
+    body: &mut Body,
+    dvx: Precision,
+    dvy: Precision,
+    time: Precision,
+    events: &mut EventWriter<SimEvent>,
+) {
+    body.current_state.vx += dvx;
+    body.current_state.vy += dvy;
+
+    events.send(SimEvent {
+        time,
+        body: body.id,
+        kind: EventKind::Impulse { dvx, dvy },
+    });
+}
+
+// I hits every untethered craft with a random impulse, Shift+I with a fixed prograde one. The
+// Moon and other massive bodies are left alone.
+pub fn inject_impulse(
+    keyboard: Res<Input<KeyCode>>,
+    time: Res<SimTime>,
+    mut query: Query<(&mut Body, Option<&Name>), Untethered>,
+    mut events: EventWriter<SimEvent>,
+    mut recorder: ResMut<MacroRecorder>,
+) {
+    if !keyboard.just_pressed(KeyCode::I) {
+        return;
+    }
+
+    let specified = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let mut rng = rand::thread_rng();
+
+    for (mut body, name) in query.iter_mut() {
+        let (dvx, dvy) = if specified {
+            let state = body.current_state;
+            let v = (state.vx * state.vx + state.vy * state.vy).sqrt();
+            (
+                SPECIFIED_IMPULSE * state.vx / v,
+                SPECIFIED_IMPULSE * state.vy / v,
+            )
+        } else {
+            let magnitude = rng.gen_range(0.0..MAX_RANDOM_IMPULSE);
+            let angle = rng.gen_range(0.0..std::f64::consts::TAU);
+            (magnitude * angle.cos(), magnitude * angle.sin())
+        };
+
+        apply_impulse(&mut body, dvx, dvy, time.0, &mut events);
+        recorder.record(
+            time.0,
+            format!("{}.impulse({:.3}, {:.3})", body_call(&body, name), dvx, dvy),
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// Crash post-mortems: the last ten minutes of every propagated body's telemetry are kept, and
+// when one crashes a report on it comes up with the impact speed, the flight time, the Δv it
+// used and its orbit before it went under. B writes the report to a JSON file and puts the
+// panel away.
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::crash::Crashed;
+use crate::elements::OrbitalElements;
+use crate::engine::Engine;
+use crate::events::{EventKind, SimEvent};
+use crate::hud::format_duration;
+use crate::i18n::Language;
+use crate::rails::OnRails;
+use crate::{Body, Precision, SimTime, State, EARTH_RADIUS};
+
+const TELEMETRY_WINDOW: Precision = 600.0; // s
+const REPORT_PATH: &str = "crash_report.json";
+
+#[derive(Clone, Copy, Serialize)]
+struct Sample {
+    time: Precision,
+    altitude: Precision,
+    speed: Precision,
+    position: [Precision; 2],
+    velocity: [Precision; 2],
+}
+
+impl Sample {
+    fn new(time: Precision, state: &State) -> Self {
+        Self {
+            time,
+            altitude: state.x.hypot(state.y) - EARTH_RADIUS,
+            speed: state.vx.hypot(state.vy),
+            position: [state.x, state.y],
+            velocity: [state.vx, state.vy],
+        }
+    }
+}
+
+struct Flight {
+    launched: Precision,
+    impulses: Precision, // m/s
+    samples: VecDeque<Sample>,
+    above: Option<State>, // the last state above the surface
+}
+
+#[derive(Serialize)]
+struct ElementsRecord {
+    semi_major_axis: Precision,
+    eccentricity: Precision,
+    periapsis_altitude: Precision,
+    apoapsis_altitude: Option<Precision>, // none on escape orbits
+}
+
+#[derive(Serialize)]
+pub struct CrashReport {
+    body: usize,
+    name: Option<String>,
+    time: Precision,
+    impact_speed: Precision,
+    flight_time: Precision,
+    delta_v: Precision,
+    elements_before_entry: ElementsRecord,
+    telemetry: Vec<Sample>,
+}
+
+impl CrashReport {
+    pub fn describe(&self, language: Language) -> String {
+        let elements = &self.elements_before_entry;
+        let km = |metres: Precision| format!("{:.1}", metres / 1000.0);
+        language.text(
+            "crash_report",
+            &[
+                &self.body,
+                &format_duration(self.time),
+                &format!("{:.0}", self.impact_speed),
+                &format_duration(self.flight_time),
+                &format!("{:.1}", self.delta_v),
+                &km(elements.semi_major_axis),
+                &format!("{:.4}", elements.eccentricity),
+                &km(elements.periapsis_altitude),
+            ],
+        )
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        serde_json::to_writer_pretty(File::create(path)?, self)?;
+        Ok(())
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PostMortem {
+    flights: HashMap<usize, Flight>,
+    pub report: Option<CrashReport>,
+    shown: bool,
+}
+
+type Flown = (
+    &'static Body,
+    Option<&'static Name>,
+    Option<&'static Engine>,
+    Has<Crashed>,
+);
+
+pub fn record_flights(
+    time: Res<SimTime>,
+    mut postmortem: ResMut<PostMortem>,
+    mut events: EventReader<SimEvent>,
+    bodies: Query<Flown, Without<OnRails>>,
+) {
+    for (body, _, _, crashed) in bodies.iter() {
+        let flight = postmortem.flights.entry(body.id).or_insert(Flight {
+            launched: time.0,
+            impulses: 0.0,
+            samples: VecDeque::new(),
+            above: None,
+        });
+        let state = body.current_state;
+        if flight
+            .samples
+            .back()
+            .is_some_and(|last| last.time == time.0)
+        {
+            continue;
+        }
+        flight.samples.push_back(Sample::new(time.0, &state));
+        while flight
+            .samples
+            .front()
+            .is_some_and(|first| time.0 - first.time > TELEMETRY_WINDOW)
+        {
+            flight.samples.pop_front();
+        }
+        // Crashed bodies are left at the surface, with the velocity they hit at
+        if !crashed && state.x.hypot(state.y) >= EARTH_RADIUS {
+            flight.above = Some(state);
+        }
+    }
+
+    for event in events.read() {
+        let Some(flight) = postmortem.flights.get_mut(&event.body) else {
+            continue;
+        };
+        let speed = match event.kind {
+            EventKind::Impulse { dvx, dvy } => {
+                flight.impulses += dvx.hypot(dvy);
+                continue;
+            }
+            EventKind::Crash { speed } => speed,
+            _ => continue,
+        };
+        let Some((body, name, engine, _)) = bodies.iter().find(|(body, ..)| body.id == event.body)
+        else {
+            continue;
+        };
+
+        let before = flight.above.unwrap_or(body.current_state);
+        let elements = OrbitalElements::from_state(&before);
+        let report = CrashReport {
+            body: body.id,
+            name: name.map(|name| name.to_string()),
+            time: event.time,
+            impact_speed: speed,
+            flight_time: event.time - flight.launched,
+            delta_v: flight.impulses + engine.map_or(0.0, |engine| engine.delta_v),
+            elements_before_entry: ElementsRecord {
+                semi_major_axis: elements.a,
+                eccentricity: elements.e,
+                periapsis_altitude: elements.a * (1.0 - elements.e) - EARTH_RADIUS,
+                apoapsis_altitude: (elements.e < 1.0)
+                    .then_some(elements.a * (1.0 + elements.e) - EARTH_RADIUS),
+            },
+            telemetry: flight.samples.iter().copied().collect(),
+        };
+        info!("{}", report.describe(Language::English));
+        postmortem.report = Some(report);
+        postmortem.shown = true;
+    }
+}
+
+pub fn export_report(keyboard: Res<Input<KeyCode>>, mut postmortem: ResMut<PostMortem>) {
+    if !keyboard.just_pressed(KeyCode::B) {
+        return;
+    }
+    let Some(report) = postmortem.report.as_ref() else {
+        return;
+    };
+
+    match report.save(REPORT_PATH) {
+        Ok(()) => info!("Crash report written to {}", REPORT_PATH),
+        Err(error) => error!("Could not write {}: {}", REPORT_PATH, error),
+    }
+    postmortem.shown = false;
+}
+
+#[derive(Component)]
+pub struct CrashText;
+
+pub fn show_report(
+    postmortem: Res<PostMortem>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<CrashText>>,
+) {
+    let value = match postmortem.report.as_ref().filter(|_| postmortem.shown) {
+        Some(report) => format!(
+            "{}\n{}",
+            report.describe(*language),
+            language.text("crash_export", &[])
+        ),
+        None => String::new(),
+    };
+    text.single_mut().sections[0].value = value;
+}
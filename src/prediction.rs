@@ -0,0 +1,148 @@
+// Predicted trajectories, kept from frame to frame instead of flown again every frame: each
+// body's coast the lookahead's steps ahead, flown like every other look ahead, so with the
+// integrator the bodies step with, the earth's bulge if that is on and the pull of the massive
+// bodies, like the Moon, where their own predictions have them at each step, so a trans-lunar
+// coast shows its flyby. The massive bodies are flown first, around the earth alone, and their
+// paths kept for the rest. As the body moves along it the steps behind are dropped and new ones
+// added at the far end; it's only flown again from the body's state when the body strays from
+// it, by thrust, an impulse, a burn of its plan or forces the prediction leaves out, when the
+// drag settings, the integrator, the perturbations or the massive bodies change, or when it
+// gets old.
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::fidelity::Propagation;
+use crate::gravity::GravitySource;
+use crate::propagator::{Coast, MassPath, MassivePaths, Propagator};
+use crate::{Body, Lookahead, Precision, SimTime, State};
+
+const TOLERANCE: Precision = 1000.0; // m off the prediction before it's flown again
+const MAX_AGE: Precision = 600.0; // s
+
+#[derive(Component, Default)]
+pub struct PredictedTrajectory {
+    start: Precision,   // sim time of the first state
+    dt: Precision,      // s, the step between states
+    states: Vec<State>, // one every step from `start`
+    computed_at: Option<Precision>,
+}
+
+impl PredictedTrajectory {
+    // The states from now on, the body's current one first
+    pub fn states(&self) -> &[State] {
+        &self.states
+    }
+
+    // Sim time of the first state, now once the prediction has caught up with the body
+    pub fn start(&self) -> Precision {
+        self.start
+    }
+
+    // Seconds between the states
+    pub fn dt(&self) -> Precision {
+        self.dt
+    }
+
+    fn fly(&mut self, coast: Coast, steps: usize) {
+        self.start = coast.time;
+        self.dt = coast.dt();
+        self.computed_at = Some(coast.time);
+        self.states = coast.fly(steps);
+    }
+
+    // On from the last state to `steps` steps
+    fn extend(&mut self, mut coast: Coast, steps: usize) {
+        let Some(last) = self.states.last() else {
+            return self.fly(coast, steps);
+        };
+        coast.state = *last;
+        coast.time = self.start + (self.states.len() - 1) as Precision * self.dt;
+        while self.states.len() <= steps {
+            self.states.push(coast.step());
+        }
+    }
+
+    // Drops the states before `now`, if the prediction reaches it
+    fn advance(&mut self, now: Precision) -> bool {
+        let steps = ((now - self.start) / self.dt).round();
+        if steps < 0.0 || steps as usize >= self.states.len() {
+            return false;
+        }
+        self.states.drain(..steps as usize);
+        self.start += steps * self.dt;
+        true
+    }
+}
+
+fn strayed(predicted: &State, state: &State) -> bool {
+    (predicted.x - state.x).hypot(predicted.y - state.y) > TOLERANCE
+}
+
+// Bodies spawned from anywhere get a prediction to keep
+pub fn add_predictions(
+    mut commands: Commands,
+    bodies: Query<Entity, (With<Body>, Without<PredictedTrajectory>)>,
+) {
+    for entity in bodies.iter() {
+        commands
+            .entity(entity)
+            .insert(PredictedTrajectory::default());
+    }
+}
+
+type Predicted<'a> = (&'a Body, &'a mut PredictedTrajectory, Has<GravitySource>);
+
+pub fn update_predictions(
+    (time, lookahead): (Res<SimTime>, Res<Lookahead>),
+    propagation: Propagation,
+    mut paths: ResMut<MassivePaths>,
+    mut masses: Local<usize>,
+    mut bodies: Query<Predicted, Without<Crashed>>,
+) {
+    let count = bodies.iter().filter(|(.., massive)| *massive).count();
+    let changed = count != *masses
+        || propagation.drag.is_changed()
+        || propagation.integration.is_changed()
+        || propagation.perturbations.is_changed();
+    *masses = count;
+
+    let mut fly = |paths: &MassivePaths, massive: bool| {
+        let propagator = Propagator::of(&propagation, paths);
+        let propagator = if propagation.drag.in_lookahead {
+            propagator
+        } else {
+            propagator.in_vacuum()
+        };
+        let mut flown = Vec::new();
+        for (body, mut prediction, is_massive) in bodies.iter_mut() {
+            if is_massive != massive {
+                continue;
+            }
+            let fresh = !changed
+                && prediction
+                    .computed_at
+                    .is_some_and(|computed_at| time.0 - computed_at < MAX_AGE)
+                && prediction.advance(time.0)
+                && !strayed(&prediction.states[0], &body.current_state);
+
+            let coast = propagator.coast(body, time.0);
+            if fresh {
+                prediction.extend(coast, lookahead.0);
+            } else {
+                prediction.fly(coast, lookahead.0);
+            }
+            if massive {
+                flown.push(MassPath {
+                    id: body.id,
+                    mass: body.mass,
+                    start: prediction.start,
+                    states: prediction.states.clone(),
+                });
+            }
+        }
+        flown
+    };
+
+    paths.0 = fly(&MassivePaths::default(), true);
+    fly(&paths, false);
+}
@@ -0,0 +1,235 @@
+// User preferences kept between sessions in the platform's config directory: the camera
+// and view, the style new bodies get, the HUD language and colors, and the last scenario's
+// command-line inputs. They are written back whenever they change.
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::AutoZoom;
+use crate::i18n::Language;
+use crate::palette::Palette;
+use crate::sky::ViewMode;
+use crate::style::{self, BodyStyle, CustomStyle};
+
+const FILE_NAME: &str = "preferences.json";
+const SAVE_INTERVAL: f32 = 1.0; // s of real time between writes
+
+// Style names as the console takes them
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleNames {
+    pub color: String,
+    pub trail_color: String,
+    pub lookahead_color: String,
+    pub trail_style: String,
+    pub lookahead_style: String,
+    pub marker: String,
+}
+
+impl Default for StyleNames {
+    fn default() -> Self {
+        Self {
+            color: "red".to_string(),
+            trail_color: "red".to_string(),
+            lookahead_color: "green".to_string(),
+            trail_style: "dots".to_string(),
+            lookahead_style: "dots".to_string(),
+            marker: "circle".to_string(),
+        }
+    }
+}
+
+impl StyleNames {
+    fn body_style(&self) -> Result<BodyStyle, String> {
+        Ok(BodyStyle {
+            color: style::parse_color(&self.color)?,
+            trail_color: style::parse_color(&self.trail_color)?,
+            lookahead_color: style::parse_color(&self.lookahead_color)?,
+            trail_style: style::parse_line_style(&self.trail_style)?,
+            lookahead_style: style::parse_line_style(&self.lookahead_style)?,
+            marker: style::parse_marker(&self.marker)?,
+        })
+    }
+}
+
+// The last scenario's inputs, for --last
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastScenario {
+    pub script: Option<String>,
+    pub scenario: Option<String>,
+    pub bodies: Option<String>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub view: ViewMode,
+    pub auto_zoom: bool,
+    pub zoom: f32, // camera scale, 1 shows the default view
+    pub style: StyleNames,
+    pub language: Language,
+    pub palette: Palette,
+    pub last_scenario: LastScenario,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            view: ViewMode::Orbit,
+            auto_zoom: false,
+            zoom: 1.0,
+            style: StyleNames::default(),
+            language: Language::English,
+            palette: Palette::Classic,
+            last_scenario: LastScenario::default(),
+        }
+    }
+}
+
+impl LastScenario {
+    // Paths are stored absolute so --last works from any directory
+    pub fn new(
+        script: Option<String>,
+        scenario: Option<String>,
+        bodies: Option<String>,
+        seed: Option<u64>,
+    ) -> Self {
+        let absolute = |path: String| {
+            fs::canonicalize(&path)
+                .map(|path| path.display().to_string())
+                .unwrap_or(path)
+        };
+
+        Self {
+            script: script.map(absolute),
+            scenario: scenario.map(absolute),
+            bodies: bodies.map(absolute),
+            seed,
+        }
+    }
+}
+
+// $XDG_CONFIG_HOME or ~/.config on Linux, ~/Library/Application Support on macOS and
+// %APPDATA% on Windows
+pub fn config_dir() -> Option<PathBuf> {
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home().map(|home| home.join(".config")))
+    };
+
+    base.map(|base| base.join("orbitabase"))
+}
+
+fn path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+impl Preferences {
+    // Defaults when there is no file yet, or when it can't be read
+    pub fn load() -> Self {
+        let Some(path) = path() else {
+            return Self::default();
+        };
+        let Ok(source) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        serde_json::from_str(&source).unwrap_or_else(|error| {
+            eprintln!("Ignoring {}: {}", path.display(), error);
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = path().ok_or_else(|| "no config directory".to_string())?;
+        let write = || -> std::io::Result<()> {
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(&path, serde_json::to_string_pretty(self)?)
+        };
+
+        write().map_err(|error| format!("{}: {}", path.display(), error))
+    }
+}
+
+// The camera's zoom is set when it is spawned
+pub fn apply_preferences(
+    preferences: Res<Preferences>,
+    mut view: ResMut<ViewMode>,
+    mut auto_zoom: ResMut<AutoZoom>,
+    mut language: ResMut<Language>,
+    mut palette: ResMut<Palette>,
+) {
+    *view = preferences.view;
+    auto_zoom.enabled = preferences.auto_zoom;
+    *language = preferences.language;
+    *palette = preferences.palette;
+}
+
+// New bodies get the preferred style
+pub fn apply_default_style(
+    preferences: Res<Preferences>,
+    mut styles: Query<&mut BodyStyle, (Added<BodyStyle>, Without<CustomStyle>)>,
+    mut warned: Local<bool>,
+) {
+    if styles.is_empty() {
+        return;
+    }
+
+    match preferences.style.body_style() {
+        Ok(preferred) => {
+            for mut style in styles.iter_mut() {
+                *style = preferred.clone();
+            }
+        }
+        Err(error) if !*warned => {
+            warn!("Preferred body style ignored: {}", error);
+            *warned = true;
+        }
+        Err(_) => {}
+    }
+}
+
+pub fn save_preferences(
+    real_time: Res<Time<Real>>,
+    mut preferences: ResMut<Preferences>,
+    (view, auto_zoom): (Res<ViewMode>, Res<AutoZoom>),
+    (language, palette): (Res<Language>, Res<Palette>),
+    projection: Query<&OrthographicProjection>,
+    mut saved: Local<Option<Preferences>>,
+    mut last_save: Local<f32>,
+) {
+    let current = Preferences {
+        view: *view,
+        auto_zoom: auto_zoom.enabled,
+        zoom: projection.single().scale,
+        language: *language,
+        palette: *palette,
+        ..preferences.clone()
+    };
+    if *preferences != current {
+        *preferences = current;
+    }
+
+    let now = real_time.elapsed_seconds();
+    if saved.as_ref() == Some(&*preferences) || now - *last_save < SAVE_INTERVAL {
+        return;
+    }
+    *last_save = now;
+
+    if let Err(error) = preferences.save() {
+        warn!("Could not save preferences: {}", error);
+    }
+    // Failed writes aren't retried until something changes
+    *saved = Some(preferences.clone());
+}
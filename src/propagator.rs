@@ -0,0 +1,174 @@
+// The one way everything that looks ahead flies a coasting body, so the lookaheads, the burn
+// plan, the planners and the exports agree with the path the simulation takes: steps of dt
+// with the integrator the bodies step with, cut into substeps where the orbit turns fast, the
+// earth with its bulge if that is on, the air if the body feels drag, and every massive body
+// but the body itself, each where its own prediction has it at the start of the step.
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::fidelity::Propagation;
+use crate::gravity::{point_mass, Perturbations};
+use crate::{adaptive_step, Atmosphere, Body, Drag, Integrator, Precision, Source, State, Thrust};
+
+// A massive body's predicted path, one state every step from `start`
+pub struct MassPath {
+    pub id: usize,
+    pub mass: Precision,
+    pub start: Precision,
+    pub states: Vec<State>,
+}
+
+// The massive bodies' paths, kept by update_predictions every frame for everything else that
+// looks ahead. The massive bodies themselves fly around the earth alone.
+#[derive(Resource, Default)]
+pub struct MassivePaths(pub Vec<MassPath>);
+
+impl MassivePaths {
+    // Each mass but `except` where its path, stepped every `dt`, has it at `time`, or at its end
+    // if it runs out
+    fn at(
+        &self,
+        time: Precision,
+        dt: Precision,
+        except: Option<usize>,
+    ) -> impl Iterator<Item = Source> + '_ {
+        self.0
+            .iter()
+            .filter(move |path| Some(path.id) != except)
+            .filter_map(move |path| {
+                let step = ((time - path.start) / dt).round().max(0.0) as usize;
+                let state = path.states.get(step).or(path.states.last())?;
+                Some(point_mass(path.mass, state.x, state.y))
+            })
+    }
+}
+
+// What a system flies coasts with
+#[derive(SystemParam)]
+pub struct Coasting<'w> {
+    propagation: Propagation<'w>,
+    paths: Res<'w, MassivePaths>,
+}
+
+impl Coasting<'_> {
+    pub fn propagator(&self) -> Propagator<'_> {
+        Propagator::of(&self.propagation, &self.paths)
+    }
+}
+
+pub struct Propagator<'a> {
+    pub dt: Precision,
+    earth: Source,
+    paths: &'a MassivePaths,
+    atmosphere: Option<Atmosphere>,
+    integrator: &'a dyn Integrator,
+}
+
+impl<'a> Propagator<'a> {
+    pub fn new(
+        integrator: &'a dyn Integrator,
+        dt: Precision,
+        perturbations: &Perturbations,
+        atmosphere: Option<Atmosphere>,
+        paths: &'a MassivePaths,
+    ) -> Self {
+        Self {
+            dt,
+            earth: perturbations.earth(),
+            paths,
+            atmosphere,
+            integrator,
+        }
+    }
+
+    // With the settings the simulation steps the bodies with
+    pub fn of(propagation: &'a Propagation, paths: &'a MassivePaths) -> Self {
+        Self::new(
+            propagation.integration.0.as_ref(),
+            propagation.step.0,
+            &propagation.perturbations,
+            propagation.drag.atmosphere,
+            paths,
+        )
+    }
+
+    // Leaving the air out, like the lookaheads unless Y puts it in
+    pub fn in_vacuum(self) -> Self {
+        Self {
+            atmosphere: None,
+            ..self
+        }
+    }
+
+    // The coast of `body` from its current state at `time`
+    pub fn coast(&'a self, body: &Body, time: Precision) -> Coast<'a> {
+        Coast {
+            propagator: self,
+            id: Some(body.id),
+            drag: body.drag(self.atmosphere),
+            state: body.current_state,
+            time,
+        }
+    }
+
+    // The coast from `state` at `time` of a body with no mass of its own to leave out and no
+    // drag, like a scenario of a batch run
+    pub fn coast_from(&'a self, state: State, time: Precision) -> Coast<'a> {
+        Coast {
+            propagator: self,
+            id: None,
+            drag: None,
+            state,
+            time,
+        }
+    }
+}
+
+// A body coasting on from `state` at `time`; burns are changes of the state's velocity
+#[derive(Clone, Copy)]
+pub struct Coast<'a> {
+    propagator: &'a Propagator<'a>,
+    id: Option<usize>,
+    pub drag: Option<Drag>,
+    pub state: State,
+    pub time: Precision,
+}
+
+impl Coast<'_> {
+    // What pulls on the body now
+    pub fn sources(&self) -> Vec<Source> {
+        let propagator = self.propagator;
+        std::iter::once(propagator.earth)
+            .chain(propagator.paths.at(self.time, propagator.dt, self.id))
+            .collect()
+    }
+
+    // The step it is flown with
+    pub fn dt(&self) -> Precision {
+        self.propagator.dt
+    }
+
+    // One step on
+    pub fn step(&mut self) -> State {
+        let sources = self.sources();
+        let integrator = self.propagator.integrator;
+        self.state = adaptive_step(
+            integrator,
+            self.state,
+            self.dt(),
+            Thrust::default(),
+            &sources,
+            self.drag,
+        );
+        self.time += self.dt();
+        self.state
+    }
+
+    // The state now and after each of the next `steps` steps
+    pub fn fly(mut self, steps: usize) -> Vec<State> {
+        let mut states = Vec::with_capacity(steps + 1);
+        states.push(self.state);
+        states.extend((0..steps).map(|_| self.step()));
+        states
+    }
+}
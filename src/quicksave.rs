@@ -0,0 +1,401 @@
+// Quicksaves: F5 writes the flight so far to quicksave.json, the sim time and epoch, the
+// scheduled commands and every body with its state, mass, drag, trail (the state history), name,
+// planned burns, engine and propellant, crash, fidelity, rails, tether, sensor, thermal and power
+// models, style and whether it's the controlled and the selected one, and F9 puts it all back,
+// so a long burn sequence that went wrong can be tried again from before it. Bodies still around
+// keep their other components (their engine's faults, instruments, autopilot...) and get the
+// saved parts back; those spawned since are removed and those gone since come back with only the
+// saved parts. Ghosts are cleared, and the systems that follow the time between ticks start over.
+use std::collections::HashMap;
+use std::fs::{self, File};
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::conservation::Conservation;
+use crate::coverage::Sensor;
+use crate::crash::Crashed;
+use crate::engine::{Engine, Tank};
+use crate::ephemeris::Epoch;
+use crate::fidelity::Fidelity;
+use crate::focus::ControlledBody;
+use crate::ghost::Fleet;
+use crate::gravity::GravitySource;
+use crate::health::Power;
+use crate::maneuver::{ManeuverNode, ManeuverPlan};
+use crate::rails::OnRails;
+use crate::scheduler::Scheduler;
+use crate::script::{self, format_calls};
+use crate::style::{BodyStyle, CustomStyle};
+use crate::tether::Tether;
+use crate::thermal::Thermal;
+use crate::{Body, BodyIds, Precision, Selected, SimTime, TimeStep};
+
+const SCHEMA: &str = "orbitabase-quicksave/1";
+const PATH: &str = "quicksave.json";
+
+// Sent when a save is loaded, for the systems that keep the time of the last tick
+#[derive(Event)]
+pub struct Loaded;
+
+#[derive(Serialize, Deserialize)]
+pub struct Save {
+    schema: String,
+    pub time: Precision,
+    pub epoch: Precision, // UTC Julian date at t = 0
+    pub dt: Precision,    // s, a save only loads with the step it was made with
+    pub bodies: Vec<SavedBody>,
+    #[serde(default)]
+    pub scheduled: Vec<(Precision, String)>, // the commands as they would be typed
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedTether {
+    pub anchor: usize, // body id
+    pub length: Precision,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedBody {
+    pub body: orbitabase_core::Body,
+    pub name: Option<String>,
+    pub plan: Vec<ManeuverNode>,
+    pub tank: Option<Tank>,
+    pub delta_v: Precision, // m/s the engine has delivered
+    #[serde(default)]
+    pub engine: bool, // with or without a tank
+    pub crashed: Option<Crashed>,
+    #[serde(default)]
+    pub fidelity: Option<Fidelity>,
+    #[serde(default)]
+    pub on_rails: bool, // put back on rails from the saved state
+    #[serde(default)]
+    pub tether: Option<SavedTether>,
+    #[serde(default)]
+    pub sensor: Option<Precision>, // half-angle, rad
+    #[serde(default)]
+    pub thermal: Option<Thermal>,
+    #[serde(default)]
+    pub power: Option<Power>,
+    #[serde(default)]
+    pub style: Option<BodyStyle>,
+    #[serde(default)]
+    pub custom_style: bool, // set where it was spawned, so the preferences leave it alone
+    #[serde(default)]
+    pub controlled: bool,
+    #[serde(default)]
+    pub selected: bool,
+}
+
+type Saved<'a> = (
+    (Entity, &'a Body),
+    Option<&'a Name>,
+    Option<&'a ManeuverPlan>,
+    Option<&'a Engine>,
+    Option<&'a Crashed>,
+    Option<&'a Fidelity>,
+    Has<OnRails>,
+    Option<&'a Tether>,
+    Option<&'a Sensor>,
+    Option<&'a Thermal>,
+    Option<&'a Power>,
+    Option<&'a BodyStyle>,
+    (Has<CustomStyle>, Has<ControlledBody>, Has<Selected>),
+);
+
+fn write(
+    path: &str,
+    (time, dt): (Precision, Precision),
+    epoch: &Epoch,
+    scheduler: &Scheduler,
+    bodies: &Query<Saved>,
+) -> Result<usize, String> {
+    let ids: HashMap<Entity, usize> = bodies
+        .iter()
+        .map(|((entity, body), ..)| (entity, body.id))
+        .collect();
+    let mut bodies: Vec<SavedBody> = bodies
+        .iter()
+        .map(
+            |(
+                (_, body),
+                name,
+                plan,
+                engine,
+                crashed,
+                fidelity,
+                on_rails,
+                tether,
+                sensor,
+                thermal,
+                power,
+                style,
+                (custom_style, controlled, selected),
+            )| SavedBody {
+                body: body.0.clone(),
+                name: name.map(|name| name.to_string()),
+                plan: plan.map(|plan| plan.0.clone()).unwrap_or_default(),
+                tank: engine.and_then(|engine| engine.tank),
+                delta_v: engine.map_or(0.0, |engine| engine.delta_v),
+                engine: engine.is_some(),
+                crashed: crashed.copied(),
+                fidelity: fidelity.copied(),
+                on_rails,
+                tether: tether.and_then(|tether| {
+                    Some(SavedTether {
+                        anchor: *ids.get(&tether.anchor)?,
+                        length: tether.length,
+                    })
+                }),
+                sensor: sensor.map(|sensor| sensor.half_angle),
+                thermal: thermal.cloned(),
+                power: power.cloned(),
+                style: style.cloned(),
+                custom_style,
+                controlled,
+                selected,
+            },
+        )
+        .collect();
+    bodies.sort_by_key(|saved| saved.body.id);
+    let count = bodies.len();
+
+    let save = Save {
+        schema: SCHEMA.to_string(),
+        time,
+        epoch: epoch.0,
+        dt,
+        bodies,
+        scheduled: scheduler
+            .actions()
+            .iter()
+            .map(|action| (action.time, format_calls(&action.calls)))
+            .collect(),
+    };
+    let file = File::create(path).map_err(|error| error.to_string())?;
+    serde_json::to_writer(file, &save).map_err(|error| error.to_string())?;
+    Ok(count)
+}
+
+// A save as it was written, whatever its step
+pub fn load(path: &str) -> Result<Save, String> {
+    let source = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let save: Save = serde_json::from_str(&source).map_err(|error| error.to_string())?;
+    if save.schema != SCHEMA {
+        return Err(format!("not a quicksave ({})", save.schema));
+    }
+    Ok(save)
+}
+
+fn read(path: &str, dt: Precision) -> Result<Save, String> {
+    let save = load(path)?;
+    if save.dt != dt {
+        return Err(format!("saved with --dt {}, not {}", save.dt, dt));
+    }
+    Ok(save)
+}
+
+// F5 saves
+pub fn quicksave(
+    keyboard: Res<Input<KeyCode>>,
+    (time, step): (Res<SimTime>, Res<TimeStep>),
+    (epoch, scheduler): (Res<Epoch>, Res<Scheduler>),
+    bodies: Query<Saved>,
+) {
+    if !keyboard.just_pressed(KeyCode::F5) {
+        return;
+    }
+    match write(PATH, (time.0, step.0), &epoch, &scheduler, &bodies) {
+        Ok(count) => info!("{} bodies at t = {} s saved to {}", count, time.0, PATH),
+        Err(error) => error!("Could not write {}: {}", PATH, error),
+    }
+}
+
+// Puts the saved fidelity, rails, sensor, thermal and power models, style and markers back on a
+// body, and takes off those it didn't have then. Tethers wait until every body has its entity.
+// Saves from before styles were saved leave the style as it is.
+fn restore(entity: &mut EntityCommands, saved: &mut SavedBody, time: Precision) {
+    match saved.fidelity {
+        Some(fidelity) => entity.insert(fidelity),
+        None => entity.remove::<Fidelity>(),
+    };
+    let rails = OnRails::new(&saved.body.current_state, time);
+    match rails.filter(|_| saved.on_rails) {
+        Some(rails) => entity.insert(rails),
+        None => entity.remove::<OnRails>(),
+    };
+    match saved.sensor {
+        Some(half_angle) => entity.insert(Sensor { half_angle }),
+        None => entity.remove::<Sensor>(),
+    };
+    match saved.thermal.take() {
+        Some(thermal) => entity.insert(thermal),
+        None => entity.remove::<Thermal>(),
+    };
+    match saved.power.take() {
+        Some(power) => entity.insert(power),
+        None => entity.remove::<Power>(),
+    };
+    if let Some(style) = saved.style.take() {
+        entity.insert(style);
+    }
+    if saved.custom_style {
+        entity.insert(CustomStyle);
+    } else {
+        entity.remove::<CustomStyle>();
+    }
+    if saved.controlled {
+        entity.insert(ControlledBody);
+    } else {
+        entity.remove::<ControlledBody>();
+    }
+    if saved.selected {
+        entity.insert(Selected);
+    } else {
+        entity.remove::<Selected>();
+    }
+}
+
+type LoadedBody<'a> = (
+    Entity,
+    &'a mut Body,
+    Option<&'a mut ManeuverPlan>,
+    Option<&'a mut Engine>,
+);
+
+// F9 loads the last save
+pub fn quickload(
+    keyboard: Res<Input<KeyCode>>,
+    mut commands: Commands,
+    (mut time, mut epoch, step): (ResMut<SimTime>, ResMut<Epoch>, Res<TimeStep>),
+    mut ids: ResMut<BodyIds>,
+    (mut fleet, mut conservation): (ResMut<Fleet>, ResMut<Conservation>),
+    (mut scheduler, mut loaded): (ResMut<Scheduler>, EventWriter<Loaded>),
+    mut bodies: Query<LoadedBody>,
+) {
+    if !keyboard.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let save = match read(PATH, step.0) {
+        Ok(save) => save,
+        Err(error) => {
+            error!("Could not load {}: {}", PATH, error);
+            return;
+        }
+    };
+    let scheduled: Result<Vec<_>, String> = save
+        .scheduled
+        .iter()
+        .map(|(time, calls)| Ok((*time, script::parse(calls)?)))
+        .collect();
+    let scheduled = match scheduled {
+        Ok(scheduled) => scheduled,
+        Err(error) => {
+            error!("Could not load {}: a scheduled command {}", PATH, error);
+            return;
+        }
+    };
+
+    let mut entities: HashMap<usize, Entity> = HashMap::new();
+    let mut tethers: Vec<(Entity, Option<SavedTether>)> = Vec::new();
+    let mut saved: Vec<Option<SavedBody>> = save.bodies.into_iter().map(Some).collect();
+    for (entity, mut body, plan, engine) in bodies.iter_mut() {
+        let found = saved
+            .iter_mut()
+            .find(|saved| saved.as_ref().is_some_and(|saved| saved.body.id == body.id));
+        let Some(mut saved) = found.and_then(Option::take) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        entities.insert(body.id, entity);
+        tethers.push((entity, saved.tether.take()));
+
+        let mut entity = commands.entity(entity);
+        restore(&mut entity, &mut saved, save.time);
+        match saved.name {
+            Some(name) => entity.insert(Name::new(name)),
+            None => entity.remove::<Name>(),
+        };
+        match saved.crashed {
+            Some(crashed) => entity.insert(crashed),
+            None => entity.remove::<Crashed>(),
+        };
+        **body = saved.body;
+        match plan {
+            Some(mut plan) => plan.0 = saved.plan,
+            None => {
+                entity.insert(ManeuverPlan(saved.plan));
+            }
+        }
+        match engine {
+            Some(mut engine) => {
+                engine.tank = saved.tank;
+                engine.delta_v = saved.delta_v;
+            }
+            None if saved.engine => {
+                entity.insert(Engine {
+                    tank: saved.tank,
+                    delta_v: saved.delta_v,
+                    ..default()
+                });
+            }
+            None => {}
+        }
+    }
+
+    // Bodies gone since the save come back with what it has of them
+    for mut saved in saved.into_iter().flatten() {
+        let mut entity = commands.spawn(BodyStyle::default());
+        restore(&mut entity, &mut saved, save.time);
+        entities.insert(saved.body.id, entity.id());
+        tethers.push((entity.id(), saved.tether.take()));
+
+        let body = Body(saved.body);
+        ids.0 = ids.0.max(body.id);
+        let source = GravitySource::of(&body);
+        entity.insert((body, ManeuverPlan(saved.plan)));
+        if let Some(name) = saved.name {
+            entity.insert(Name::new(name));
+        }
+        // Saves from before tankless engines were kept only have the tank
+        if saved.engine || saved.tank.is_some() {
+            entity.insert(Engine {
+                tank: saved.tank,
+                delta_v: saved.delta_v,
+                ..default()
+            });
+        }
+        if let Some(crashed) = saved.crashed {
+            entity.insert(crashed);
+        }
+        if let Some(source) = source {
+            entity.insert(source);
+        }
+    }
+
+    for (entity, tether) in tethers {
+        let anchor = tether
+            .as_ref()
+            .and_then(|tether| entities.get(&tether.anchor));
+        match (tether, anchor) {
+            (Some(tether), Some(&anchor)) => commands.entity(entity).insert(Tether {
+                anchor,
+                length: tether.length,
+                tension: 0.0,
+            }),
+            _ => commands.entity(entity).remove::<Tether>(),
+        };
+    }
+
+    scheduler.clear();
+    for (time, calls) in scheduled {
+        scheduler.schedule(time, calls);
+    }
+    time.0 = save.time;
+    epoch.0 = save.epoch;
+    fleet.clear();
+    conservation.reset = true;
+    loaded.send(Loaded);
+    info!("Loaded {} from t = {} s", PATH, save.time);
+}
@@ -0,0 +1,118 @@
+// Van Allen-style radiation belts in the equatorial plane, as two Gaussian shells in the
+// distance from the earth's centre, and the dose each body has taken from them. It is a toy
+// model with rough magnitudes behind light shielding, enough to tell LEO, MEO and GEO apart.
+// U shows the belts and the selected body's dose.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::i18n::Language;
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::{Body, Precision, Selected, SimTime, EARTH_RADIUS};
+
+// Centre and width in earth radii, peak dose rate in mGy/h
+const BELTS: [(Precision, Precision, Precision); 2] = [(1.5, 0.25, 10.0), (4.5, 1.0, 3.0)];
+const BACKGROUND: Precision = 0.01; // mGy/h, cosmic rays everywhere
+const OUTER_LIMIT: Precision = 8.0; // earth radii, how far out the belts are drawn
+const RINGS: usize = 140;
+const SEGMENTS: usize = 256;
+const MAX_ALPHA: f32 = 0.5;
+const HOUR: Precision = 3600.0;
+
+// mGy/h at a distance from the earth's centre, in metres
+pub fn dose_rate(r: Precision) -> Precision {
+    let l = r / EARTH_RADIUS;
+    BACKGROUND
+        + BELTS
+            .iter()
+            .map(|(centre, width, peak)| peak * (-((l - centre) / width).powi(2)).exp())
+            .sum::<Precision>()
+}
+
+#[derive(Resource, Default)]
+pub struct Radiation {
+    pub shown: bool,
+    doses: HashMap<usize, Precision>, // mGy, by body id
+    last: Option<Precision>,
+}
+
+impl Radiation {
+    pub fn dose(&self, id: usize) -> Precision {
+        self.doses.get(&id).copied().unwrap_or(0.0)
+    }
+}
+
+pub fn toggle_radiation(keyboard: Res<Input<KeyCode>>, mut radiation: ResMut<Radiation>) {
+    if keyboard.just_pressed(KeyCode::U) {
+        radiation.shown = !radiation.shown;
+    }
+}
+
+// At the rate where each body is now, over the time since the last frame
+pub fn accumulate_dose(time: Res<SimTime>, mut radiation: ResMut<Radiation>, bodies: Query<&Body>) {
+    let elapsed = radiation.last.map_or(0.0, |last| time.0 - last);
+    radiation.last = Some(time.0);
+    if elapsed <= 0.0 {
+        return;
+    }
+
+    for body in bodies.iter() {
+        let state = body.current_state;
+        let rate = dose_rate(state.x.hypot(state.y));
+        *radiation.doses.entry(body.id).or_default() += rate * elapsed / HOUR;
+    }
+}
+
+pub fn draw_belts(
+    mut gizmos: Gizmos,
+    radiation: Res<Radiation>,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+) {
+    if !radiation.shown {
+        return;
+    }
+
+    let peak = BELTS
+        .iter()
+        .map(|(.., peak)| *peak)
+        .fold(0.0, Precision::max);
+    let color = palette.colors().warning;
+    let centre = interpolation.point(0.0, 0.0);
+    for i in 0..RINGS {
+        let l = 1.0 + (OUTER_LIMIT - 1.0) * (i as Precision + 0.5) / RINGS as Precision;
+        let opacity = (dose_rate(l * EARTH_RADIUS) - BACKGROUND) / peak;
+        gizmos
+            .circle_2d(
+                centre,
+                (l * EARTH_RADIUS) as f32,
+                color.with_a(MAX_ALPHA * opacity as f32),
+            )
+            .segments(SEGMENTS);
+    }
+}
+
+#[derive(Component)]
+pub struct RadiationText;
+
+pub fn show_dose(
+    radiation: Res<Radiation>,
+    language: Res<Language>,
+    selected: Query<&Body, With<Selected>>,
+    mut text: Query<&mut Text, With<RadiationText>>,
+) {
+    let mut value = String::new();
+    if let (true, Ok(body)) = (radiation.shown, selected.get_single()) {
+        let state = body.current_state;
+        value = language.text(
+            "radiation",
+            &[
+                &body.id,
+                &format!("{:.2}", radiation.dose(body.id)),
+                &format!("{:.3}", dose_rate(state.x.hypot(state.y))),
+            ],
+        );
+    }
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,88 @@
+// On-rails bodies: the position comes straight from stored elements at the current time, with
+// no integration and no trail history, which keeps large catalogs cheap. A body knocked off
+// its rails (by an impulse, say) is put back on new ones through the state it was given. A
+// body whose rails take it under the surface crashes where its orbit meets it.
+use bevy::prelude::*;
+
+use crate::crash::{Crashed, Crashes};
+use crate::elements::{OrbitalElements, MU};
+use crate::{Body, Precision, SimTime, State, EARTH_RADIUS};
+
+const BISECTIONS: usize = 30;
+
+#[derive(Component)]
+pub struct OnRails {
+    elements: OrbitalElements, // at the epoch
+    epoch: Precision,          // s
+    clockwise: bool,
+    placed: Option<(Precision, State)>, // last time and state it was put at
+}
+
+impl OnRails {
+    // Only elliptic orbits can be put on rails
+    pub fn new(state: &State, epoch: Precision) -> Option<Self> {
+        let elements = OrbitalElements::from_state(state);
+        (elements.e < 1.0).then_some(Self {
+            elements,
+            epoch,
+            clockwise: state.x * state.vy - state.y * state.vx < 0.0,
+            placed: None,
+        })
+    }
+
+    fn at(&self, time: Precision) -> State {
+        let mut elements = self.elements;
+        elements.mean_anomaly += (MU / elements.a.powi(3)).sqrt() * (time - self.epoch);
+        elements.to_state(self.clockwise)
+    }
+}
+
+fn below(state: &State) -> bool {
+    state.x.hypot(state.y) < EARTH_RADIUS
+}
+
+fn moved(a: &State, b: &State) -> bool {
+    (a.x, a.y, a.vx, a.vy) != (b.x, b.y, b.vx, b.vy)
+}
+
+// Runs after the integration step, once the time is up to date
+pub fn follow_rails(
+    time: Res<SimTime>,
+    mut crashes: Crashes,
+    mut query: Query<(Entity, &mut Body, &mut OnRails), Without<Crashed>>,
+) {
+    for (entity, mut body, mut rails) in query.iter_mut() {
+        if let Some((placed_at, placed)) = rails.placed {
+            if moved(&placed, &body.current_state) {
+                match OnRails::new(&body.current_state, placed_at) {
+                    Some(new) => *rails = new,
+                    // Knocked onto an escape orbit, so it gets integrated from now on
+                    None => {
+                        crashes.commands.entity(entity).remove::<OnRails>();
+                        continue;
+                    }
+                }
+            }
+        }
+
+        let last = rails.placed;
+        body.current_state = rails.at(time.0);
+        rails.placed = Some((time.0, body.current_state));
+
+        // The orbit is known between ticks, so the crossing is found on it
+        if let Some((mut above, placed)) = last {
+            if !below(&placed) && below(&body.current_state) {
+                let mut under = time.0;
+                for _ in 0..BISECTIONS {
+                    let middle = 0.5 * (above + under);
+                    if below(&rails.at(middle)) {
+                        under = middle;
+                    } else {
+                        above = middle;
+                    }
+                }
+                crashes.crash(entity, &mut body, under, rails.at(under));
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+// Onboard data recorders: a body's instruments fill its recorder at their data rates, and it
+// empties at the downlink rate of the link budget whenever a ground station hears it with a
+// margin to spare. What doesn't fit in a full recorder is lost. A failing transmitter slows
+// or stops the downlink, and a flat battery shuts the whole payload down.
+use bevy::prelude::*;
+
+use crate::antenna::{Antenna, LinkBudget};
+use crate::ground::GroundStation;
+use crate::health::Power;
+use crate::i18n::Language;
+use crate::{Body, Precision, Selected, SimTime, State};
+
+const MEGABYTE: Precision = 8e6; // bits
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum CommsFault {
+    #[default]
+    Nominal,
+    Reduced(Precision), // fraction of the downlink rate still available
+    Dead,
+}
+
+impl CommsFault {
+    pub fn describe(self, language: Language) -> String {
+        match self {
+            CommsFault::Nominal => language.text("health_nominal", &[]),
+            CommsFault::Reduced(fraction) => {
+                language.text("comms_reduced", &[&format!("{:.0}", 100.0 * fraction)])
+            }
+            CommsFault::Dead => language.text("comms_dead", &[]),
+        }
+    }
+
+    fn rate_fraction(self) -> Precision {
+        match self {
+            CommsFault::Nominal => 1.0,
+            CommsFault::Reduced(fraction) => fraction,
+            CommsFault::Dead => 0.0,
+        }
+    }
+}
+
+pub struct Instrument {
+    pub name: String,
+    pub rate: Precision, // bit/s
+}
+
+#[derive(Component)]
+pub struct Recorder {
+    pub capacity: Precision, // bits
+    pub instruments: Vec<Instrument>,
+    pub fault: CommsFault,
+    stored: Precision,     // bits
+    downlinked: Precision, // bits so far
+    lost: Precision,       // bits so far
+    contact: bool,         // whether it was downlinking in the last update
+    updated: Option<Precision>,
+}
+
+impl Recorder {
+    pub fn new(capacity_megabytes: Precision) -> Self {
+        Self {
+            capacity: capacity_megabytes * MEGABYTE,
+            instruments: Vec::new(),
+            fault: CommsFault::Nominal,
+            stored: 0.0,
+            downlinked: 0.0,
+            lost: 0.0,
+            contact: false,
+            updated: None,
+        }
+    }
+
+    fn generation(&self) -> Precision {
+        self.instruments
+            .iter()
+            .map(|instrument| instrument.rate)
+            .sum()
+    }
+
+    pub fn contact(&self) -> bool {
+        self.contact
+    }
+
+    pub fn describe(&self, id: usize, language: Language) -> String {
+        let megabytes = |bits: Precision| format!("{:.1}", bits / MEGABYTE);
+        let contact = if self.contact {
+            "recorder_contact"
+        } else {
+            "recorder_no_contact"
+        };
+        language.text(
+            "recorder",
+            &[
+                &id,
+                &format!("{:.0}", 100.0 * self.stored / self.capacity),
+                &megabytes(self.stored),
+                &megabytes(self.capacity),
+                &megabytes(self.downlinked),
+                &megabytes(self.lost),
+                &language.text(contact, &[]),
+            ],
+        )
+    }
+}
+
+// A 32 MB recorder with a camera and the housekeeping telemetry
+impl Default for Recorder {
+    fn default() -> Self {
+        let mut recorder = Self::new(32.0);
+        recorder.instruments = vec![
+            Instrument {
+                name: "camera".to_string(),
+                rate: 20e3,
+            },
+            Instrument {
+                name: "housekeeping".to_string(),
+                rate: 1e3,
+            },
+        ];
+        recorder
+    }
+}
+
+// Whether any station closes the downlink from a body in `state`
+fn in_contact(
+    state: &State,
+    antenna: Option<&Antenna>,
+    budget: &LinkBudget,
+    stations: &Query<(&GroundStation, &Antenna)>,
+) -> bool {
+    stations.iter().any(|(station, receiver)| {
+        station.link(state).visible() && budget.margin(antenna, state, receiver, station) >= 0.0
+    })
+}
+
+// Fills and drains each recorder over the time since the last frame, with the contact and
+// power as they are now
+pub fn update_recorders(
+    time: Res<SimTime>,
+    budget: Res<LinkBudget>,
+    stations: Query<(&GroundStation, &Antenna)>,
+    mut bodies: Query<(&Body, &mut Recorder, Option<&Antenna>, Option<&Power>)>,
+) {
+    for (body, mut recorder, antenna, power) in bodies.iter_mut() {
+        let elapsed = recorder.updated.map_or(0.0, |updated| time.0 - updated);
+        recorder.updated = Some(time.0);
+        let powered = !power.is_some_and(|power| power.flat());
+        recorder.contact = powered
+            && recorder.fault != CommsFault::Dead
+            && in_contact(&body.current_state, antenna, &budget, &stations);
+        if elapsed <= 0.0 || !powered {
+            continue;
+        }
+
+        let mut volume = recorder.stored + recorder.generation() * elapsed;
+        if recorder.contact {
+            let rate = budget.data_rate * recorder.fault.rate_fraction();
+            let sent = volume.min(rate * elapsed);
+            recorder.downlinked += sent;
+            volume -= sent;
+        }
+        let overflow = (volume - recorder.capacity).max(0.0);
+        recorder.lost += overflow;
+        recorder.stored = volume - overflow;
+    }
+}
+
+#[derive(Component)]
+pub struct RecorderText;
+
+pub fn show_recorder(
+    language: Res<Language>,
+    selected: Query<(&Body, &Recorder), With<Selected>>,
+    mut text: Query<&mut Text, With<RecorderText>>,
+) {
+    let value = match selected.get_single() {
+        Ok((body, recorder)) => recorder.describe(body.id, *language),
+        Err(_) => String::new(),
+    };
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,126 @@
+// Orbital regime of each body from its elements, and which regimes the orbit view shows
+use bevy::prelude::*;
+
+use crate::elements::OrbitalElements;
+use crate::i18n::Language;
+use crate::{Body, Precision, EARTH_RADIUS};
+
+const LEO_CEILING: Precision = 2000e3; // m, apoapsis altitude
+const GEO_RADIUS: Precision = 42164e3; // m
+const GEO_TOLERANCE: Precision = 500e3; // m, on the semi-major axis
+const GEO_MAX_ECCENTRICITY: Precision = 0.01;
+const HEO_MIN_ECCENTRICITY: Precision = 0.25;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Regime {
+    Leo,
+    Meo,
+    Geo,
+    Heo,
+    Escape,
+}
+
+pub const REGIMES: [Regime; 5] = [
+    Regime::Leo,
+    Regime::Meo,
+    Regime::Geo,
+    Regime::Heo,
+    Regime::Escape,
+];
+
+impl Regime {
+    pub fn of(body: &Body) -> Self {
+        let elements = OrbitalElements::from_state(&body.current_state);
+
+        if elements.e >= 1.0 || elements.a <= 0.0 {
+            Regime::Escape
+        } else if elements.a * (1.0 + elements.e) - EARTH_RADIUS < LEO_CEILING {
+            Regime::Leo
+        } else if (elements.a - GEO_RADIUS).abs() < GEO_TOLERANCE
+            && elements.e < GEO_MAX_ECCENTRICITY
+        {
+            Regime::Geo
+        } else if elements.e >= HEO_MIN_ECCENTRICITY {
+            Regime::Heo
+        } else {
+            Regime::Meo
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Regime::Leo => "LEO",
+            Regime::Meo => "MEO",
+            Regime::Geo => "GEO",
+            Regime::Heo => "HEO",
+            Regime::Escape => "escape",
+        }
+    }
+
+    fn index(&self) -> usize {
+        REGIMES.iter().position(|regime| regime == self).unwrap()
+    }
+}
+
+// Regimes left out of the orbit view, toggled with 1 to 5
+#[derive(Resource, Default)]
+pub struct RegimeFilter {
+    hidden: [bool; 5],
+}
+
+impl RegimeFilter {
+    pub fn shows(&self, body: &Body) -> bool {
+        !self.hidden[Regime::of(body).index()]
+    }
+}
+
+#[derive(Component)]
+pub struct RegimeText;
+
+pub fn toggle_regimes(keyboard: Res<Input<KeyCode>>, mut filter: ResMut<RegimeFilter>) {
+    let keys = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+    ];
+
+    for (i, key) in keys.iter().enumerate() {
+        if keyboard.just_pressed(*key) {
+            filter.hidden[i] = !filter.hidden[i];
+        }
+    }
+}
+
+pub fn show_regimes(
+    filter: Res<RegimeFilter>,
+    bodies: Query<&Body>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<RegimeText>>,
+) {
+    let mut counts = [0; 5];
+    for body in bodies.iter() {
+        counts[Regime::of(body).index()] += 1;
+    }
+
+    let entries: Vec<String> = REGIMES
+        .iter()
+        .enumerate()
+        .map(|(i, regime)| {
+            let hidden = if filter.hidden[i] {
+                language.text("hidden", &[])
+            } else {
+                String::new()
+            };
+            // The orbit regimes are abbreviations that read the same in every language
+            let name = match regime {
+                Regime::Escape => language.text("escape", &[]),
+                _ => regime.name().to_string(),
+            };
+            format!("{} {}: {}{}", i + 1, name, counts[i], hidden)
+        })
+        .collect();
+
+    text.single_mut().sections[0].value = language.text("regimes", &[&entries.join(", ")]);
+}
@@ -0,0 +1,160 @@
+// Repeating ground tracks: an orbit whose period fits a whole number of times into a whole
+// number of sidereal days brings the body back over the same ground at the same point of its
+// orbit, N orbits in D days. Slash shows the selected body's orbits a day, and either the
+// cycle its track repeats on or the nearest orbit that repeats within MAX_DAYS, with the
+// prograde burn that gets it there. `body(...).repeat_orbit([max_days])` proposes that burn.
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::crash::Crashed;
+use crate::elements::{OrbitalElements, MU};
+use crate::i18n::Language;
+use crate::maneuver::{Frame, ManeuverNode};
+use crate::propagator::Coast;
+use crate::{Body, Precision, Selected, State, EARTH_RADIUS};
+
+pub const SIDEREAL_DAY: Precision = 86164.0905; // s, one turn of the earth
+pub const MAX_DAYS: u32 = 10; // longest repeat cycle looked for
+const TOLERANCE: Precision = 10e3; // m, ground missed at the equator after a cycle
+
+#[derive(Resource, Default)]
+pub struct GroundRepeat {
+    pub shown: bool,
+}
+
+// N orbits in D sidereal days
+#[derive(Clone, Copy)]
+pub struct Cycle {
+    pub orbits: u32,
+    pub days: u32,
+}
+
+impl Cycle {
+    fn period(&self) -> Precision {
+        self.days as Precision * SIDEREAL_DAY / self.orbits as Precision
+    }
+
+    fn semi_major_axis(&self) -> Precision {
+        (MU * (self.period() / TAU).powi(2)).cbrt()
+    }
+}
+
+pub struct Repeat {
+    pub orbits_per_day: Precision,
+    pub nearest: Cycle,
+    pub miss: Precision, // m, how far the track is off the ground it started over after a cycle
+}
+
+impl Repeat {
+    // The orbit's revolutions a day and the cycle within `max_days` closest to it
+    pub fn of(state: &State, max_days: u32) -> Option<Self> {
+        let period = OrbitalElements::from_state(state).period()?;
+        let orbits_per_day = SIDEREAL_DAY / period;
+        let nearest = (1..=max_days.max(1))
+            .filter_map(|days| {
+                let orbits = (orbits_per_day * days as Precision).round() as u32;
+                // 22 orbits in 2 days is 11 in 1 kept up
+                (orbits > 0 && gcd(orbits, days) == 1).then_some(Cycle { orbits, days })
+            })
+            .min_by(|a, b| {
+                let off = |cycle: &Cycle| (cycle.period() - period).abs();
+                off(a).total_cmp(&off(b))
+            })?;
+        // The earth turns this much more or less than the cycle's whole days
+        let turn = nearest.orbits as Precision * period / SIDEREAL_DAY - nearest.days as Precision;
+        Some(Self {
+            orbits_per_day,
+            nearest,
+            miss: turn.abs() * TAU * EARTH_RADIUS,
+        })
+    }
+
+    pub fn repeats(&self) -> bool {
+        self.miss < TOLERANCE
+    }
+
+    pub fn describe(&self, language: Language) -> String {
+        let key = if self.repeats() {
+            "repeat_cycle"
+        } else {
+            "repeat_nearest"
+        };
+        language.text(
+            key,
+            &[
+                &format!("{:.3}", self.orbits_per_day),
+                &self.nearest.orbits,
+                &self.nearest.days,
+                &format!("{:.0}", self.miss / 1000.0),
+            ],
+        )
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Prograde Δv at `state` that gives it the cycle's period
+fn nudge(state: &State, cycle: Cycle) -> Option<Precision> {
+    let r = state.x.hypot(state.y);
+    let speed = (MU * (2.0 / r - 1.0 / cycle.semi_major_axis())).sqrt();
+    speed.is_finite().then(|| speed - state.vx.hypot(state.vy))
+}
+
+// A node one step along the coast onto the nearest repeating orbit within `max_days`
+pub fn repeat_orbit(mut coast: Coast, max_days: u32) -> Result<(Repeat, ManeuverNode), String> {
+    let now = coast.time;
+    let repeat = Repeat::of(&coast.state, max_days).ok_or("the body is on an escape orbit")?;
+    let state = coast.step();
+    let dv = nudge(&state, repeat.nearest).ok_or("the repeat orbit can't be reached from here")?;
+    Ok((
+        repeat,
+        ManeuverNode {
+            time: now + coast.dt(),
+            dv: [dv, 0.0],
+            frame: Frame::Orbital,
+        },
+    ))
+}
+
+// Slash shows or hides the readout
+pub fn toggle_repeat(keyboard: Res<Input<KeyCode>>, mut repeat: ResMut<GroundRepeat>) {
+    if keyboard.just_pressed(KeyCode::Slash) {
+        repeat.shown = !repeat.shown;
+    }
+}
+
+#[derive(Component)]
+pub struct RepeatText;
+
+pub fn show_repeat(
+    repeat: Res<GroundRepeat>,
+    language: Res<Language>,
+    selected: Query<&Body, (With<Selected>, Without<Crashed>)>,
+    mut text: Query<&mut Text, With<RepeatText>>,
+) {
+    let mut value = String::new();
+    if repeat.shown {
+        if let Ok(body) = selected.get_single() {
+            let state = body.current_state;
+            value = match Repeat::of(&state, MAX_DAYS) {
+                None => language.text("repeat_escape", &[]),
+                Some(repeat) => {
+                    let mut value = repeat.describe(*language);
+                    if let Some(dv) = nudge(&state, repeat.nearest).filter(|_| !repeat.repeats()) {
+                        value.push('\n');
+                        value.push_str(&language.text("repeat_nudge", &[&format!("{:.3}", dv)]));
+                    }
+                    value
+                }
+            };
+        }
+    }
+    text.single_mut().sections[0].value = value;
+}
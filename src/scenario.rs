@@ -0,0 +1,153 @@
+// Scenario files: the bodies a run starts with, read from a RON file given with --scenario
+// instead of the built-in station. Each body has a mass, an initial position and velocity, and
+// optionally a name, a color and a ballistic coefficient for drag; the first one is selected.
+// Heavy ones pull on the others, as bodies loaded with --bodies do.
+use std::fs;
+
+use bevy::prelude::*;
+use ron::extensions::Extensions;
+use ron::Options;
+use serde::Deserialize;
+
+use crate::gravity::GravitySource;
+use crate::maneuver::ManeuverPlan;
+use crate::style::{self, BodyStyle, CustomStyle};
+use crate::{Body, BodyIds, Precision, Selected};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioBody {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub mass: Precision,                  // kg
+    pub position: (Precision, Precision), // m
+    pub velocity: (Precision, Precision), // m/s
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub ballistic_coefficient: Option<Precision>, // kg/m²
+}
+
+impl ScenarioBody {
+    pub fn body(&self, id: usize) -> Body {
+        let (x, y) = self.position;
+        let (vx, vy) = self.velocity;
+        let mut body = Body::new(id, self.mass, x, y, vx, vy);
+        body.ballistic_coefficient = self.ballistic_coefficient;
+        body
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub bodies: Vec<ScenarioBody>,
+}
+
+// The --scenario a run starts from, if any
+#[derive(Resource)]
+pub struct StartingScenario(pub Option<Scenario>);
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        // `name: "ISS"` rather than `name: Some("ISS")`
+        let options = Options::default().with_default_extension(Extensions::IMPLICIT_SOME);
+        let scenario: Self = options.from_str(text).map_err(|error| error.to_string())?;
+
+        // Colors are checked up front so a typo stops the run rather than a body
+        for body in &scenario.bodies {
+            if let Some(color) = &body.color {
+                style::parse_color(color)?;
+            }
+            if body.mass <= 0.0 {
+                return Err("bodies need a positive mass".to_string());
+            }
+        }
+        Ok(scenario)
+    }
+
+    // Spawns the bodies, returning the first
+    pub fn spawn(&self, commands: &mut Commands, ids: &mut BodyIds) -> Option<Entity> {
+        let mut first = None;
+        for spec in &self.bodies {
+            let body = spec.body(ids.next());
+            let source = GravitySource::of(&body);
+
+            let mut entity = commands.spawn((body, ManeuverPlan::default()));
+            if let Some(name) = &spec.name {
+                entity.insert(Name::new(name.clone()));
+            }
+            match spec.color.as_deref().map(style::parse_color) {
+                Some(Ok(color)) => entity.insert((
+                    BodyStyle {
+                        color,
+                        trail_color: color,
+                        ..default()
+                    },
+                    CustomStyle,
+                )),
+                _ => entity.insert(BodyStyle::default()),
+            };
+            if let Some(source) = source {
+                entity.insert(source);
+            }
+            if first.is_none() {
+                entity.insert(Selected);
+                first = Some(entity.id());
+            }
+        }
+        first
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_bodies_with_optional_fields() {
+        let scenario = Scenario::parse(
+            r#"(bodies: [
+                (name: "ISS", mass: 420000, position: (6779000, 0), velocity: (0, 7668),
+                 color: "red", ballistic_coefficient: 120),
+                (mass: 1, position: (0, 7000000), velocity: (-7546, 0)),
+            ])"#,
+        )
+        .unwrap();
+        let [iss, probe] = scenario.bodies.as_slice() else {
+            panic!("{} bodies", scenario.bodies.len());
+        };
+        assert_eq!(iss.name.as_deref(), Some("ISS"));
+        assert_eq!(iss.color.as_deref(), Some("red"));
+        let body = iss.body(7);
+        assert_eq!((body.id, body.mass), (7, 420000.0));
+        assert_eq!(
+            (body.current_state.x, body.current_state.vy),
+            (6779000.0, 7668.0)
+        );
+        assert_eq!(body.ballistic_coefficient, Some(120.0));
+        assert!(probe.name.is_none() && probe.body(2).ballistic_coefficient.is_none());
+    }
+
+    #[test]
+    fn rejects_bad_bodies() {
+        let error = |body: &str| {
+            Scenario::parse(&format!("(bodies: [{}])", body))
+                .err()
+                .unwrap()
+        };
+        let orbit = "position: (7000000, 0), velocity: (0, 7546)";
+        assert_eq!(
+            error(&format!("(mass: 0, {})", orbit)),
+            "bodies need a positive mass"
+        );
+        assert!(error(&format!("(mass: 1, {}, color: \"mauve\")", orbit)).contains("mauve"));
+        assert!(error(&format!("(mass: 1, {}, colour: \"red\")", orbit)).contains("colour"));
+        assert!(error("(mass: 1, position: (0, 0))").contains("velocity"));
+    }
+}
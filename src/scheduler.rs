@@ -0,0 +1,64 @@
+// Actions queued for absolute simulated times, `at(3600).body("ISS").fail("comms")` from a
+// script or the console: any command, burns, failures, spawns or settings alike. The physics
+// ends its tick at the step that reaches the next action, which then runs before the following
+// tick, so it happens at the same point of the flight whatever the speed or the frame rate.
+// Actions due at the same time run in the order they were queued.
+use bevy::prelude::*;
+
+use crate::script::Call;
+use crate::Precision;
+
+const EPSILON: Precision = 1e-6; // s, rounding in the clock
+
+pub struct Action {
+    pub time: Precision,
+    pub calls: Vec<Call>,
+}
+
+#[derive(Resource, Default)]
+pub struct Scheduler {
+    actions: Vec<Action>, // by time, then in the order they were queued
+}
+
+impl Scheduler {
+    pub fn schedule(&mut self, time: Precision, calls: Vec<Call>) {
+        let i = self.actions.partition_point(|action| action.time <= time);
+        self.actions.insert(i, Action { time, calls });
+    }
+
+    // Steps of `dt` a tick can run from `now` without going past the next action
+    pub fn limit(&self, now: Precision, steps: u32, dt: Precision) -> u32 {
+        let next = self.actions.first().map(|action| action.time);
+        limit(now, next, steps, dt)
+    }
+
+    // The actions the clock has reached, out of the queue
+    pub fn take_due(&mut self, now: Precision) -> Vec<Action> {
+        let due = self
+            .actions
+            .partition_point(|action| action.time <= now + EPSILON);
+        self.actions.drain(..due).collect()
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    // How many actions were dropped
+    pub fn clear(&mut self) -> usize {
+        let count = self.actions.len();
+        self.actions.clear();
+        count
+    }
+}
+
+// Steps of `dt` a tick can run from `now` without going past `next`, the time of whatever has
+// to happen on its own step
+pub fn limit(now: Precision, next: Option<Precision>, steps: u32, dt: Precision) -> u32 {
+    match next {
+        Some(next) if next > now + EPSILON => {
+            steps.min(((next - now - EPSILON) / dt).ceil() as u32)
+        }
+        _ => steps,
+    }
+}
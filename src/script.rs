@@ -0,0 +1,1933 @@
+// Command language shared by scenario scripts and the console. A command is a call,
+// `set_warp(100)`, or a method on a body picked by name or id, `body("ISS").elements()`.
+//
+// Scripts are made of timed commands, one per line, with `#` starting a comment:
+//
+//     at 3600 assert_within("apoapsis", 42164e3, 1e4)
+//
+// Once every line has run the app exits, with a non-zero code if any command failed,
+// so scripts can be used as regression tests.
+use std::fmt;
+use std::fs;
+
+use bevy::ecs::system::SystemParam;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::antenna::{Antenna, LinkBudget, Pointing};
+use crate::atmosphere::DragSettings;
+use crate::autopilot::{Autopilots, Pilot};
+use crate::avoidance::Avoidance;
+use crate::calculator;
+use crate::camera::AutoZoom;
+use crate::conservation::Conservation;
+use crate::determination;
+use crate::dispersion::BurnErrors;
+use crate::elements::OrbitalElements;
+use crate::engine::{Engine, EngineFault, RandomFaults, Tank};
+use crate::ephemeris::Epoch;
+use crate::events::SimEvent;
+use crate::fidelity::{Fidelity, Integration};
+use crate::focus::{self, ControlledBody};
+use crate::frames::ReferenceFrame;
+use crate::frozen;
+use crate::ghost::{self, Fleet, MAX_GHOSTS};
+use crate::governor::Governor;
+use crate::gravity;
+use crate::groundtrack::GroundTrack;
+use crate::health::{Failure, Power};
+use crate::i18n::Language;
+use crate::impact::ImpactPredictions;
+use crate::insertion;
+use crate::isl::Topology;
+use crate::logbook::MissionLog;
+use crate::macros::{self, MacroRecorder};
+use crate::maneuver::{Frame, ManeuverNode, ManeuverPlan};
+use crate::observation::Tracking;
+use crate::optimizer::{self, Proposal};
+use crate::palette::Palette;
+use crate::perturbation::apply_impulse;
+use crate::postmortem::PostMortem;
+use crate::propagator::{MassivePaths, Propagator};
+use crate::radiation::{self, Radiation};
+use crate::rails::OnRails;
+use crate::recorder::{CommsFault, Instrument, Recorder};
+use crate::repeat;
+use crate::scheduler::Scheduler;
+use crate::sensors::{self, Accelerometer, Gps};
+use crate::sets::SetTimings;
+use crate::snapshot;
+use crate::spatial::SpatialIndex;
+use crate::stm;
+use crate::style::{self, BodyStyle};
+use crate::swarm::{self, Groups};
+use crate::sweep;
+use crate::targeter::{self, Constraint};
+use crate::thermal::{Thermal, ThermalLimits, KELVIN};
+use crate::trajectory;
+use crate::{
+    integrator, Atmosphere, Body, BodyIds, FullThrust, Precision, Selected, SimTime,
+    SimulationSpeed, TimeStep, EARTH_RADIUS, MAX_WARP,
+};
+
+#[derive(Clone)]
+pub enum Argument {
+    Number(Precision),
+    Text(String),
+}
+
+#[derive(Clone)]
+pub struct Call {
+    name: String,
+    arguments: Vec<Argument>,
+}
+
+impl fmt::Display for Argument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Argument::Number(value) => write!(f, "{}", value),
+            Argument::Text(text) => write!(f, "\"{}\"", text),
+        }
+    }
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arguments: Vec<String> = self.arguments.iter().map(|a| a.to_string()).collect();
+        write!(f, "{}({})", self.name, arguments.join(", "))
+    }
+}
+
+// A chain of calls as it would be typed
+pub fn format_calls(calls: &[Call]) -> String {
+    calls
+        .iter()
+        .map(|call| call.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn parse_argument(text: &str) -> Result<Argument, String> {
+    let text = text.trim();
+
+    if let Some(text) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        return Ok(Argument::Text(text.to_string()));
+    }
+
+    text.parse()
+        .map(Argument::Number)
+        .map_err(|_| format!("bad argument `{}`", text))
+}
+
+// Position of the first `c` outside a quoted string
+fn find_unquoted(text: &str, c: char) -> Option<usize> {
+    let mut in_string = false;
+
+    for (i, other) in text.char_indices() {
+        match other {
+            '"' => in_string = !in_string,
+            _ if other == c && !in_string => return Some(i),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// The pieces of `text` between the `c`s outside quoted strings
+fn split_unquoted(text: &str, c: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(i) = find_unquoted(rest, c) {
+        parts.push(&rest[..i]);
+        rest = &rest[i + c.len_utf8()..];
+    }
+    parts.push(rest);
+    parts
+}
+
+// One `name(arguments)`, and whatever follows it
+fn parse_call(text: &str) -> Result<(Call, &str), String> {
+    let (name, rest) = text
+        .split_once('(')
+        .ok_or_else(|| format!("expected a call, found `{}`", text))?;
+    let end = find_unquoted(rest, ')').ok_or_else(|| "missing `)`".to_string())?;
+    let (arguments, rest) = (&rest[..end], &rest[end + 1..]);
+
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(format!("bad command name `{}`", name));
+    }
+
+    let arguments = if arguments.trim().is_empty() {
+        Vec::new()
+    } else {
+        split_unquoted(arguments, ',')
+            .into_iter()
+            .map(parse_argument)
+            .collect::<Result<_, _>>()?
+    };
+
+    Ok((
+        Call {
+            name: name.to_string(),
+            arguments,
+        },
+        rest,
+    ))
+}
+
+// A chain of calls separated by dots
+pub fn parse(text: &str) -> Result<Vec<Call>, String> {
+    let mut calls = Vec::new();
+    let mut rest = text.trim();
+
+    loop {
+        let (call, tail) = parse_call(rest)?;
+        calls.push(call);
+
+        rest = tail.trim();
+        if rest.is_empty() {
+            return Ok(calls);
+        }
+        rest = rest
+            .strip_prefix('.')
+            .ok_or_else(|| format!("unexpected `{}`", rest))?;
+    }
+}
+
+// Value of a named quantity for a body, or None if the name is unknown
+pub fn quantity(body: &Body, name: &str) -> Option<Precision> {
+    let state = body.current_state;
+    let elements = OrbitalElements::from_state(&state);
+    let r = (state.x * state.x + state.y * state.y).sqrt();
+
+    let value = match name {
+        "a" | "semi_major_axis" => elements.a,
+        "e" | "eccentricity" => elements.e,
+        "apoapsis" => elements.a * (1.0 + elements.e),
+        "periapsis" => elements.a * (1.0 - elements.e),
+        "radius" => r,
+        "altitude" => r - EARTH_RADIUS,
+        "speed" => (state.vx * state.vx + state.vy * state.vy).sqrt(),
+        "radial_velocity" => (state.x * state.vx + state.y * state.vy) / r,
+        "x" => state.x,
+        "y" => state.y,
+        "vx" => state.vx,
+        "vy" => state.vy,
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+type BodyData = (
+    Entity,
+    &'static mut Body,
+    Option<&'static Name>,
+    Has<Selected>,
+    Option<&'static mut ManeuverPlan>,
+    Option<&'static mut BodyStyle>,
+    Option<&'static Gps>,
+    Option<&'static Accelerometer>,
+    Option<&'static Pilot>,
+    Option<&'static Fidelity>,
+    Has<OnRails>,
+    Option<&'static mut Thermal>,
+    Option<&'static mut Recorder>,
+    Option<&'static mut Engine>,
+    Option<&'static mut Power>,
+);
+
+// The parts of the simulation commands can read and change
+#[derive(SystemParam)]
+pub struct Sim<'w, 's> {
+    commands: Commands<'w, 's>,
+    time: Res<'w, SimTime>,
+    epoch: Res<'w, Epoch>,
+    ids: ResMut<'w, BodyIds>,
+    auto_zoom: ResMut<'w, AutoZoom>,
+    bodies: Query<'w, 's, BodyData>,
+    events: EventWriter<'w, SimEvent>,
+    impacts: Res<'w, ImpactPredictions>,
+    index: Res<'w, SpatialIndex>,
+    proposal: ResMut<'w, Proposal>,
+    autopilots: Res<'w, Autopilots>,
+    records: Records<'w>,
+    timings: Res<'w, SetTimings>,
+    ground_track: Res<'w, GroundTrack>,
+    physics: Physics<'w>,
+    settings: Settings<'w>,
+}
+
+// How the bodies fly: the step, the engines' thrust and the gravity field
+#[derive(SystemParam)]
+pub struct Physics<'w> {
+    step: Res<'w, TimeStep>,
+    thrust: Res<'w, FullThrust>,
+    perturbations: ResMut<'w, gravity::Perturbations>,
+}
+
+// What commands can configure
+#[derive(SystemParam)]
+pub struct Settings<'w> {
+    speed: ResMut<'w, SimulationSpeed>,
+    language: ResMut<'w, Language>,
+    palette: ResMut<'w, Palette>,
+    budget: ResMut<'w, LinkBudget>,
+    scheduler: ResMut<'w, Scheduler>,
+    drag: ResMut<'w, DragSettings>,
+    macros: ResMut<'w, MacroRecorder>,
+    topology: ResMut<'w, Topology>,
+    integration: ResMut<'w, Integration>,
+    conservation: ResMut<'w, Conservation>,
+    fleet: ResMut<'w, Fleet>,
+    switches: Switches<'w>,
+    log: ResMut<'w, MissionLog>,
+    groups: ResMut<'w, Groups>,
+    avoidance: ResMut<'w, Avoidance>,
+    frame: ResMut<'w, ReferenceFrame>,
+}
+
+// What commands turn on and off
+#[derive(SystemParam)]
+pub struct Switches<'w> {
+    governor: ResMut<'w, Governor>,
+    faults: ResMut<'w, RandomFaults>,
+}
+
+// What the simulation has measured and kept about the bodies
+#[derive(SystemParam)]
+pub struct Records<'w> {
+    tracking: Res<'w, Tracking>,
+    postmortem: Res<'w, PostMortem>,
+    radiation: Res<'w, Radiation>,
+    paths: Res<'w, MassivePaths>,
+}
+
+impl Sim<'_, '_> {
+    fn find(&self, arguments: &[Argument]) -> Result<Entity, String> {
+        let found = self
+            .bodies
+            .iter()
+            .find(|(_, body, name, ..)| match arguments {
+                [Argument::Text(wanted)] => name.is_some_and(|name| name.as_str() == wanted),
+                [Argument::Number(id)] => body.id as Precision == *id,
+                _ => false,
+            });
+
+        match (found, arguments) {
+            (Some((entity, ..)), _) => Ok(entity),
+            (None, [Argument::Text(wanted)]) => Err(format!("no body named \"{}\"", wanted)),
+            (None, [Argument::Number(id)]) => Err(format!("no body with id {}", id)),
+            (None, _) => Err("usage: body(\"name\") or body(id)".to_string()),
+        }
+    }
+
+    fn selected(&self) -> Result<Entity, String> {
+        self.bodies
+            .iter()
+            .find(|(_, _, _, selected, ..)| *selected)
+            .map(|(entity, ..)| entity)
+            .ok_or_else(|| "no body is selected".to_string())
+    }
+
+    fn global(&mut self, call: &Call) -> Result<String, String> {
+        match (call.name.as_str(), call.arguments.as_slice()) {
+            ("help", []) => Ok(
+                "commands: bodies(), time(), set_warp(n), set_governor(\"on|off\"), \
+                set_faults(\"on|off\"), set_j2(\"on|off\"), \
+                set_frame(\"inertial|earth_fixed|body\"), \
+                set_target(...), clear_target(), \
+                spawn_moon(), spawn_craft(x, y, vx, vy, [\"name\"]), \
+                spawn_frozen(altitude, [\"name\"]), \
+                at(time).<command>, scheduled(), clear_schedule(), \
+                groups(), group(\"name\").<method>, group(\"name\").members(), \
+                group(\"name\").spread(burn_in, duration), group(\"name\").disband(), \
+                record_macro(), save_macro(\"path\"), run_macro(\"path\", [start_in]), \
+                links(), set_link_range(range), \
+                set_integrator(\"rk4|verlet|dormand_prince\"), integrator(), \
+                drift(), reset_drift(), ghosts(), clear_ghosts(), timings(), \
+                note(\"text\"), mission_log(), search_log(\"text\"), \
+                export_snapshot(\"path\"), crash_report(), export_crash_report(\"path\"), \
+                export_ground_track(\"path\"), \
+                set_language(\"en|es\"), set_palette(\"classic|colorblind|high_contrast\"), \
+                set_atmosphere(\"table|vacuum\"), set_atmosphere(surface_density, scale_height), \
+                vis_viva(r, a), circular_speed(r), escape_speed(r), period(a), hohmann(r1, r2), \
+                assert_within(\"quantity\", expected, tolerance), body(...).elements(), \
+                body(...).state(), body(...).impact(), body(...).<quantity>(), \
+                body(...).impulse(dvx, dvy), body(...).set_velocity(vx, vy), \
+                body(...).set_ballistic_coefficient(b), body(...).remove_drag(), \
+                body(...).add_tank(dry_mass, propellant, isp, thrust), body(...).propellant(), \
+                body(...).stm(duration), body(...).sweep(duration), body(...).determine_orbit(), \
+                body(...).add_gps(noise, [bias_x, bias_y]), \
+                body(...).add_accelerometer(noise, [bias_x, bias_y]), body(...).gps(), \
+                body(...).accelerometer(), body(...).remove_sensors(), autopilots(), \
+                body(...).engage_autopilot(\"name\", [target]), body(...).autopilot(), \
+                body(...).focus(), body(...).join(\"group\"), body(...).leave(\"group\"), \
+                body(...).disengage_autopilot(), body(...).spawn_ghosts(\"strategy\", ...), \
+                body(...).set_fidelity(\"high|standard|analytic\"), body(...).fidelity(), \
+                body(...).put_on_rails(), body(...).take_off_rails(), body(...).dose(), \
+                body(...).add_thermal([dissipation]), body(...).temperature(), \
+                body(...).set_antenna(gain, beamwidth, [\"nadir|zenith|tracking\"]), \
+                set_downlink_rate(rate), body(...).add_recorder(capacity), \
+                body(...).add_instrument(\"name\", rate), body(...).recorder(), \
+                body(...).set_thermal_limits(low, high, min, max), \
+                body(...).add_power([capacity, array, load]), \
+                body(...).fail(\"engine|comms|battery\", [fraction]), body(...).repair(), \
+                body(...).neighbours(radius), \
+                body(...).target_altitude(altitude, burn_in, flight_time), \
+                body(...).target_periapsis(altitude, burn_in, flight_time), body(...).plan(), \
+                body(...).clear_plan(), body(...).plan_burn(burn_in, prograde, outward), \
+                body(...).set_burn_errors(magnitude_percent, direction_degrees), \
+                body(...).remove_burn_errors(), \
+                body(...).insert_orbit(periapsis_altitude, eccentricity), \
+                body(...).optimize_burn(periapsis_altitude, apoapsis_altitude), accept_proposal(), \
+                conjunctions(), set_avoidance(threshold, target_miss), \
+                body(...).repeat_orbit([max_days]), body(...).freeze_orbit(), \
+                body(...).export_plan(\"path\"), body(...).import_plan(\"path\", [start_in]), \
+                body(...).export_ephemeris(\"path\", duration, [step]), \
+                body(...).set_color(\"color\"), set_trail_color, set_lookahead_color, \
+                set_trail_style(\"dots|dashed|solid\"), set_lookahead_style, \
+                set_marker(\"circle|square|cross\")"
+                    .to_string(),
+            ),
+            ("bodies", []) => Ok(self
+                .bodies
+                .iter()
+                .map(|(_, body, name, ..)| match name {
+                    Some(name) => format!("{} \"{}\"", body.id, name),
+                    None => body.id.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")),
+            ("time", []) => Ok(format!("t = {} s", self.time.0)),
+            ("scheduled", []) => {
+                let actions = self.settings.scheduler.actions();
+                if actions.is_empty() {
+                    return Ok("nothing scheduled".to_string());
+                }
+                Ok(actions
+                    .iter()
+                    .map(|action| format!("at {} s: {}", action.time, format_calls(&action.calls)))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            ("clear_schedule", []) => Ok(format!(
+                "dropped {} scheduled actions",
+                self.settings.scheduler.clear()
+            )),
+            ("links", []) => Ok(self.settings.topology.describe(Language::English)),
+            ("set_link_range", [Argument::Number(range)]) if *range > 0.0 => {
+                self.settings.topology.range = *range;
+                Ok(format!("inter-satellite links reach {} km", range / 1000.0))
+            }
+            ("record_macro", []) => {
+                if self.settings.macros.recording() {
+                    return Err("already recording a macro, save it first".to_string());
+                }
+                self.settings.macros.start(self.time.0);
+                Ok(format!("recording a macro from t = {} s", self.time.0))
+            }
+            ("save_macro", [Argument::Text(path)]) => {
+                let count = self
+                    .settings
+                    .macros
+                    .save(path, (self.time.0, self.physics.step.0))?;
+                Ok(format!("saved a macro of {} commands to {}", count, path))
+            }
+            ("run_macro", [Argument::Text(path)]) => self.run_macro(path, None),
+            ("run_macro", [Argument::Text(path), Argument::Number(start_in)])
+                if *start_in >= 0.0 =>
+            {
+                self.run_macro(path, Some(self.time.0 + start_in))
+            }
+            ("autopilots", []) => Ok(self.autopilots.names().join(", ")),
+            ("crash_report", []) => match &self.records.postmortem.report {
+                Some(report) => Ok(report.describe(Language::English)),
+                None => Err("nothing has crashed".to_string()),
+            },
+            ("export_crash_report", [Argument::Text(path)]) => {
+                let report = self
+                    .records
+                    .postmortem
+                    .report
+                    .as_ref()
+                    .ok_or("nothing has crashed")?;
+                report
+                    .save(path)
+                    .map(|()| format!("crash report written to {}", path))
+                    .map_err(|error| format!("could not write {}: {}", path, error))
+            }
+            ("spawn_moon", []) => {
+                let (time, epoch) = (self.time.0, &self.epoch);
+                let id = gravity::spawn_moon(&mut self.commands, &mut self.ids, time, epoch);
+                Ok(format!("spawned the Moon as body {}", id))
+            }
+            (
+                "spawn_craft",
+                [Argument::Number(x), Argument::Number(y), Argument::Number(vx), Argument::Number(vy), name @ ..],
+            ) if name.len() <= 1 => {
+                let name = match name {
+                    [Argument::Text(name)] => Some(name.as_str()),
+                    [] => None,
+                    _ => return Err("a craft's name is text".to_string()),
+                };
+                let state = crate::State::new(*x, *y, *vx, *vy);
+                let id = focus::spawn_craft(&mut self.commands, &mut self.ids, state, name);
+                Ok(format!("spawned craft {}", id))
+            }
+            ("spawn_frozen", [Argument::Number(altitude), name @ ..])
+                if *altitude > 0.0 && name.len() <= 1 =>
+            {
+                let name = match name {
+                    [Argument::Text(name)] => Some(name.as_str()),
+                    [] => None,
+                    _ => return Err("a craft's name is text".to_string()),
+                };
+                let state = frozen::frozen_state(*altitude, &self.physics.perturbations.earth());
+                let id = focus::spawn_craft(&mut self.commands, &mut self.ids, state, name);
+                Ok(format!(
+                    "spawned craft {} on the frozen orbit at {} km, at {:.3} m/s",
+                    id,
+                    altitude / 1000.0,
+                    state.vy
+                ))
+            }
+            ("set_target", [_]) => {
+                let entity = self.find(&call.arguments)?;
+                self.auto_zoom.target = Some(entity);
+                Ok("target set".to_string())
+            }
+            ("clear_target", []) => {
+                self.auto_zoom.target = None;
+                Ok("target cleared, using the nearest body".to_string())
+            }
+            ("set_warp", [Argument::Number(warp)]) => {
+                if !(1.0..=MAX_WARP as Precision).contains(warp) || warp.fract() != 0.0 {
+                    return Err(format!(
+                        "warp must be a whole number from 1 to {}",
+                        MAX_WARP
+                    ));
+                }
+                self.settings.speed.0 = *warp as u32;
+                Ok(format!("warp set to {}x", self.settings.speed.0))
+            }
+            ("set_governor", [Argument::Text(state)]) => {
+                self.settings.switches.governor.enabled = match state.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("unknown governor setting \"{}\"", state)),
+                };
+                Ok(format!("warp governor {}", state))
+            }
+            ("set_faults", [Argument::Text(state)]) => {
+                self.settings.switches.faults.enabled = match state.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("unknown fault setting \"{}\"", state)),
+                };
+                Ok(format!("random failures {}", state))
+            }
+            ("set_j2", [Argument::Text(state)]) => {
+                self.physics.perturbations.j2 = match state.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("unknown J2 setting \"{}\"", state)),
+                };
+                Ok(format!("the earth's bulge {}", state))
+            }
+            ("set_frame", [Argument::Text(name)]) => {
+                *self.settings.frame = ReferenceFrame::parse(name)
+                    .ok_or_else(|| format!("unknown reference frame \"{}\"", name))?;
+                Ok(format!(
+                    "drawing in the {} frame",
+                    self.settings.frame.describe()
+                ))
+            }
+            (
+                "assert_within",
+                [Argument::Text(name), Argument::Number(expected), Argument::Number(tolerance)],
+            ) => {
+                let entity = self.selected()?;
+                let (_, body, _, _, _, _, gps, accelerometer, ..) =
+                    self.bodies.get(entity).unwrap();
+                let value = quantity(body, name)
+                    .or_else(|| sensors::quantity(gps, accelerometer, name))
+                    .ok_or_else(|| format!("no quantity named `{}`", name))?;
+
+                if (value - expected).abs() <= *tolerance {
+                    Ok(format!(
+                        "{} = {} is within {} of {}",
+                        name, value, tolerance, expected
+                    ))
+                } else {
+                    Err(format!(
+                        "assertion failed, {} = {} is not within {} of {}",
+                        name, value, tolerance, expected
+                    ))
+                }
+            }
+            ("export_ground_track", [Argument::Text(path)]) => self
+                .ground_track
+                .export(path, &self.epoch)
+                .map(|count| format!("{} ground track samples written to {}", count, path))
+                .map_err(|error| format!("could not write {}: {}", path, error)),
+            ("export_snapshot", [Argument::Text(path)]) => {
+                let bodies = self.bodies.iter().map(|(_, body, name, ..)| (body, name));
+                snapshot::write_snapshot(path, &self.epoch, self.time.0, bodies)
+                    .map(|count| format!("snapshot of {} bodies written to {}", count, path))
+                    .map_err(|error| format!("could not write {}: {}", path, error))
+            }
+            ("accept_proposal", []) => {
+                let Some((entity, node)) = self.proposal.0.take() else {
+                    return Err("no burn has been proposed".to_string());
+                };
+                let Ok((_, body, _, _, Some(mut plan), ..)) = self.bodies.get_mut(entity) else {
+                    return Err("the body of the proposal is gone".to_string());
+                };
+                plan.add(node);
+                Ok(format!("planned a burn of body {} at {}", body.id, node))
+            }
+            ("conjunctions", []) => {
+                let warning = self
+                    .settings
+                    .avoidance
+                    .describe(self.time.0, Language::English);
+                if warning.is_empty() {
+                    Ok("no conjunction above the risk threshold".to_string())
+                } else {
+                    Ok(warning)
+                }
+            }
+            ("set_avoidance", [Argument::Number(threshold), Argument::Number(target)])
+                if (0.0..=1.0).contains(threshold) && *target > 0.0 =>
+            {
+                let avoidance = &mut self.settings.avoidance;
+                avoidance.threshold = *threshold;
+                avoidance.target = *target;
+                avoidance.rescreen();
+                Ok(format!(
+                    "flagging conjunctions with a risk of {} or more, avoiding them by {} m",
+                    threshold, target
+                ))
+            }
+            ("set_avoidance", _) => Err(
+                "usage: set_avoidance(threshold, target_miss), a risk from 0 to 1 and metres"
+                    .to_string(),
+            ),
+            ("set_downlink_rate", [Argument::Number(rate)]) if *rate > 0.0 => {
+                self.settings.budget.data_rate = *rate;
+                Ok(format!("downlink rate set to {} bit/s", rate))
+            }
+            ("set_integrator", [Argument::Text(name)]) => {
+                self.settings.integration.0 = integrator(name)?;
+                Ok(format!(
+                    "integrating with {}",
+                    self.settings.integration.0.name()
+                ))
+            }
+            ("integrator", []) => Ok(format!(
+                "integrating with {}",
+                self.settings.integration.0.name()
+            )),
+            ("groups", []) => Ok(self.settings.groups.describe()),
+            ("timings", []) => Ok(self.timings.describe()),
+            ("ghosts", []) => Ok(self.settings.fleet.describe(Language::English)),
+            ("clear_ghosts", []) => {
+                self.settings.fleet.clear();
+                Ok("ghosts cleared".to_string())
+            }
+            ("note", [Argument::Text(text)]) => {
+                self.settings.log.note(&self.epoch, self.time.0, text);
+                Ok("noted in the mission log".to_string())
+            }
+            ("search_log", [Argument::Text(text)]) => {
+                let count = self.settings.log.search(text);
+                Ok(format!("{} mission log entries with \"{}\"", count, text))
+            }
+            ("mission_log", []) => Ok(self.settings.log.describe(Language::English)),
+            ("drift", []) => Ok(self.settings.conservation.describe(*self.settings.language)),
+            ("reset_drift", []) => {
+                self.settings.conservation.reset = true;
+                Ok("drift measured from the next tick on".to_string())
+            }
+            ("set_language", [Argument::Text(name)]) => {
+                *self.settings.language = Language::parse(name)?;
+                Ok(format!("language set to {}", name))
+            }
+            ("set_palette", [Argument::Text(name)]) => {
+                *self.settings.palette = Palette::parse(name)?;
+                Ok(format!("palette set to {}", name))
+            }
+            ("set_atmosphere", [Argument::Text(name)]) => {
+                self.settings.drag.atmosphere = match name.as_str() {
+                    "table" => Some(Atmosphere::Tabulated),
+                    "vacuum" => None,
+                    _ => return Err(format!("unknown atmosphere \"{}\"", name)),
+                };
+                Ok(format!("atmosphere set to {}", name))
+            }
+            (
+                "set_atmosphere",
+                [Argument::Number(surface_density), Argument::Number(scale_height)],
+            ) if *surface_density >= 0.0 && *scale_height > 0.0 => {
+                self.settings.drag.atmosphere = Some(Atmosphere::Exponential {
+                    surface_density: *surface_density,
+                    scale_height: *scale_height,
+                });
+                Ok(format!(
+                    "atmosphere set to {} kg/m³ at sea level with a scale height of {} m",
+                    surface_density, scale_height
+                ))
+            }
+            (
+                "help" | "bodies" | "time" | "autopilots" | "crash_report" | "accept_proposal"
+                | "spawn_moon" | "scheduled" | "clear_schedule" | "record_macro" | "links"
+                | "integrator" | "drift" | "reset_drift" | "ghosts" | "clear_ghosts"
+                | "mission_log" | "groups" | "conjunctions" | "timings",
+                _,
+            ) => Err(format!("usage: {}()", call.name)),
+            ("set_integrator", _) => {
+                Err("usage: set_integrator(\"rk4|verlet|dormand_prince\")".to_string())
+            }
+            ("set_link_range", _) => Err("usage: set_link_range(metres)".to_string()),
+            ("save_macro", _) => Err("usage: save_macro(\"path\")".to_string()),
+            ("run_macro", _) => Err(
+                "usage: run_macro(\"path\") at the recorded times, or run_macro(\"path\", \
+                start_in) to start `start_in` seconds from now"
+                    .to_string(),
+            ),
+            ("at", _) => Err("usage: at(time).command(...), at an absolute time in s".to_string()),
+            ("set_warp", _) => Err("usage: set_warp(n)".to_string()),
+            ("note", _) => Err("usage: note(\"text\")".to_string()),
+            ("search_log", _) => {
+                Err("usage: search_log(\"text\"), or \"\" for every entry".to_string())
+            }
+            ("spawn_craft", _) => Err("usage: spawn_craft(x, y, vx, vy, [\"name\"])".to_string()),
+            ("spawn_frozen", _) => {
+                Err("usage: spawn_frozen(altitude, [\"name\"]), altitude above 0".to_string())
+            }
+            ("set_governor", _) => Err("usage: set_governor(\"on|off\")".to_string()),
+            ("set_faults", _) => Err("usage: set_faults(\"on|off\")".to_string()),
+            ("set_j2", _) => Err("usage: set_j2(\"on|off\")".to_string()),
+            ("set_frame", _) => Err("usage: set_frame(\"inertial|earth_fixed|body\")".to_string()),
+            ("set_downlink_rate", _) => {
+                Err("usage: set_downlink_rate(bits_per_second)".to_string())
+            }
+            ("set_target", _) => Err("usage: set_target(\"name\") or set_target(id)".to_string()),
+            ("clear_target", _) => Err("usage: clear_target()".to_string()),
+            ("export_snapshot", _) => Err("usage: export_snapshot(\"path\")".to_string()),
+            ("export_ground_track", _) => Err("usage: export_ground_track(\"path\")".to_string()),
+            ("export_crash_report", _) => Err("usage: export_crash_report(\"path\")".to_string()),
+            ("set_palette", _) => {
+                Err("usage: set_palette(\"classic|colorblind|high_contrast\")".to_string())
+            }
+            ("set_atmosphere", _) => Err("usage: set_atmosphere(\"table|vacuum\") or \
+                set_atmosphere(surface_density, scale_height)"
+                .to_string()),
+            ("set_language", _) => {
+                Err("usage: set_language(\"en\") or set_language(\"es\")".to_string())
+            }
+            ("assert_within", _) => {
+                Err("usage: assert_within(\"quantity\", expected, tolerance)".to_string())
+            }
+            (name, arguments) => {
+                let numbers: Option<Vec<Precision>> = arguments
+                    .iter()
+                    .map(|argument| match argument {
+                        Argument::Number(value) => Some(*value),
+                        Argument::Text(_) => None,
+                    })
+                    .collect();
+                // Text arguments get the formula's usage message
+                let numbers = numbers.unwrap_or_default();
+                calculator::evaluate(name, &numbers)
+                    .unwrap_or_else(|| Err(format!("unknown command `{}`", name)))
+            }
+        }
+    }
+
+    // The arrow keys fly this craft from now on
+    fn focus(&mut self, entity: Entity) -> Result<String, String> {
+        let (_, body, .., engine, _) = self.bodies.get(entity).unwrap();
+        if engine.is_none() {
+            return Err(format!("body {} has no engine to fly", body.id));
+        }
+        let id = body.id;
+        for (other, ..) in self.bodies.iter() {
+            self.commands
+                .entity(other)
+                .remove::<(ControlledBody, Selected)>();
+        }
+        self.commands
+            .entity(entity)
+            .insert((ControlledBody, Selected));
+        Ok(format!("flying body {}", id))
+    }
+
+    // The entities of a group's members that are still around, in id order
+    fn members(&self, group: &str) -> Result<Vec<(Entity, usize)>, String> {
+        let ids = self
+            .settings
+            .groups
+            .members(group)
+            .ok_or_else(|| format!("no group \"{}\"", group))?;
+        let mut members: Vec<(Entity, usize)> = self
+            .bodies
+            .iter()
+            .filter(|(_, body, ..)| ids.contains(&body.id))
+            .map(|(entity, body, ..)| (entity, body.id))
+            .collect();
+        if members.is_empty() {
+            return Err(format!("group \"{}\" has no bodies left", group));
+        }
+        members.sort_by_key(|(_, id)| *id);
+        Ok(members)
+    }
+
+    // A method on every member of a group, failing if it fails on any of them
+    fn group(&mut self, arguments: &[Argument], call: &Call) -> Result<String, String> {
+        let [Argument::Text(group)] = arguments else {
+            return Err("usage: group(\"name\")".to_string());
+        };
+        match (call.name.as_str(), call.arguments.as_slice()) {
+            ("members", []) => {
+                let ids: Vec<String> = self
+                    .members(group)?
+                    .iter()
+                    .map(|(_, id)| id.to_string())
+                    .collect();
+                return Ok(format!("\"{}\": {}", group, ids.join(", ")));
+            }
+            ("disband", []) => {
+                return match self.settings.groups.disband(group) {
+                    Some(ids) => Ok(format!("disbanded \"{}\", {} bodies", group, ids.len())),
+                    None => Err(format!("no group \"{}\"", group)),
+                };
+            }
+            ("spread", [Argument::Number(burn_in), Argument::Number(duration)])
+                if *burn_in >= 0.0 && *duration > 0.0 =>
+            {
+                return self.spread(group, *burn_in, *duration);
+            }
+            ("spread", _) => return Err("usage: spread(burn_in, duration)".to_string()),
+            _ => {}
+        }
+
+        let mut failed = false;
+        let mut lines = Vec::new();
+        for (entity, id) in self.members(group)? {
+            let output = self.method(entity, call).unwrap_or_else(|error| {
+                failed = true;
+                format!("failed: {}", error)
+            });
+            lines.push(format!("body {}: {}", id, output));
+        }
+        let output = lines.join("\n");
+        if failed {
+            Err(output)
+        } else {
+            Ok(output)
+        }
+    }
+
+    // Plans the burns that spread a group around its orbit
+    fn spread(
+        &mut self,
+        group: &str,
+        burn_in: Precision,
+        duration: Precision,
+    ) -> Result<String, String> {
+        let members = self.members(group)?;
+        let mut states = Vec::new();
+        for (entity, id) in &members {
+            let (_, body, _, _, plan, ..) = self.bodies.get(*entity).unwrap();
+            if plan.is_none() {
+                return Err(format!("body {} can't plan burns", id));
+            }
+            states.push((*id, body.current_state));
+        }
+
+        let burns = swarm::spread(
+            &states,
+            (self.time.0, self.physics.step.0),
+            burn_in,
+            duration,
+        )?;
+        let mut largest: Precision = 0.0;
+        for ((entity, _), (_, nodes)) in members.iter().skip(1).zip(&burns) {
+            let (_, _, _, _, plan, ..) = self.bodies.get_mut(*entity).unwrap();
+            let mut plan = plan.unwrap();
+            for node in nodes {
+                plan.add(*node);
+            }
+            largest = largest.max(nodes[0].dv[0]);
+        }
+        Ok(format!(
+            "planned {} pairs of burns, up to {:.3} m/s each, to spread \"{}\" by t = {} s",
+            burns.len(),
+            largest,
+            group,
+            burns
+                .first()
+                .map_or(self.time.0, |(_, nodes)| nodes[1].time)
+        ))
+    }
+
+    fn method(&mut self, entity: Entity, call: &Call) -> Result<String, String> {
+        if call.name == "focus" && call.arguments.is_empty() {
+            return self.focus(entity);
+        }
+        let time = self.time.0;
+        let target = match call.arguments.as_slice() {
+            [_, target] if call.name == "engage_autopilot" => {
+                Some(self.find(std::slice::from_ref(target))?)
+            }
+            _ => None,
+        };
+        // Whatever a command flies on flies like the lookaheads
+        let propagator = Propagator::new(
+            self.settings.integration.0.as_ref(),
+            self.physics.step.0,
+            &self.physics.perturbations,
+            self.settings.drag.atmosphere,
+            &self.records.paths,
+        );
+        let (
+            _,
+            mut body,
+            name,
+            _,
+            plan,
+            style,
+            gps,
+            accelerometer,
+            pilot,
+            fidelity,
+            on_rails,
+            thermal,
+            mut recorder,
+            mut engine,
+            mut power,
+        ) = self.bodies.get_mut(entity).unwrap();
+
+        match (call.name.as_str(), call.arguments.as_slice()) {
+            ("join", [Argument::Text(group)]) => {
+                if self.settings.groups.join(group, body.id) {
+                    Ok(format!("body {} joined \"{}\"", body.id, group))
+                } else {
+                    Ok(format!("body {} is already in \"{}\"", body.id, group))
+                }
+            }
+            ("leave", [Argument::Text(group)]) => {
+                if self.settings.groups.leave(group, body.id) {
+                    Ok(format!("body {} left \"{}\"", body.id, group))
+                } else {
+                    Err(format!("body {} isn't in \"{}\"", body.id, group))
+                }
+            }
+            ("impact", []) => Ok(match self.impacts.get(entity) {
+                Some(impact) => impact.describe(time, Language::English),
+                None => "no impact predicted".to_string(),
+            }),
+            ("elements", []) => {
+                let elements = OrbitalElements::from_state(&body.current_state);
+                Ok(format!(
+                    "a = {:.3} km, e = {:.6}, ω = {:.3}°, M = {:.3}°",
+                    elements.a / 1000.0,
+                    elements.e,
+                    elements.argument_of_periapsis.to_degrees(),
+                    elements.mean_anomaly.to_degrees()
+                ))
+            }
+            ("state", []) => {
+                let state = body.current_state;
+                Ok(format!(
+                    "x = {:.1} m, y = {:.1} m, vx = {:.3} m/s, vy = {:.3} m/s",
+                    state.x, state.y, state.vx, state.vy
+                ))
+            }
+            ("stm", [Argument::Number(duration)]) if *duration >= 0.0 => {
+                let steps = (duration / propagator.dt).round() as usize;
+                let (_, phi) = stm::propagate(propagator.coast(&body, time), steps);
+                let rows: Vec<String> = (0..4)
+                    .map(|i| {
+                        let row = phi.row(i);
+                        format!("{:.4e} {:.4e} {:.4e} {:.4e}", row.x, row.y, row.z, row.w)
+                    })
+                    .collect();
+                Ok(format!("Φ({} s) =\n{}", duration, rows.join("\n")))
+            }
+            ("determine_orbit", []) => {
+                let observations = self.records.tracking.get(body.id);
+                let coast = propagator.coast(&body, time);
+                let solution = determination::determine(observations, coast)?;
+                let (estimate, position_sigma, velocity_sigma) =
+                    determination::propagate(&solution, time, coast);
+                let truth = body.current_state;
+                let arc = observations[observations.len() - 1].time - solution.epoch;
+                Ok(format!(
+                    "{} observations over {:.0} s, {} iterations, residuals {:.1} m and {:.4}°\n\
+                     estimate: ({:.0}, {:.0}) m, ({:.3}, {:.3}) m/s\n\
+                     truth: ({:.0}, {:.0}) m, ({:.3}, {:.3}) m/s\n\
+                     error: {:.1} m (σ {:.1} m), {:.4} m/s (σ {:.4} m/s)",
+                    observations.len(),
+                    arc,
+                    solution.iterations,
+                    solution.rms[0],
+                    solution.rms[1].to_degrees(),
+                    estimate.x,
+                    estimate.y,
+                    estimate.vx,
+                    estimate.vy,
+                    truth.x,
+                    truth.y,
+                    truth.vx,
+                    truth.vy,
+                    (estimate.x - truth.x).hypot(estimate.y - truth.y),
+                    position_sigma,
+                    (estimate.vx - truth.vx).hypot(estimate.vy - truth.vy),
+                    velocity_sigma
+                ))
+            }
+            ("add_gps" | "add_accelerometer", [Argument::Number(noise), bias @ ..])
+                if *noise >= 0.0 =>
+            {
+                let bias = match bias {
+                    [Argument::Number(x), Argument::Number(y)] => DVec2::new(*x, *y),
+                    [] => DVec2::ZERO,
+                    _ => {
+                        return Err(format!(
+                            "usage: {}(noise) or {}(noise, bias_x, bias_y)",
+                            call.name, call.name
+                        ))
+                    }
+                };
+                if call.name == "add_gps" {
+                    self.commands.entity(entity).insert(Gps::new(*noise, bias, propagator.dt));
+                    Ok(format!("body {}: GPS with {} m of noise", body.id, noise))
+                } else {
+                    let accelerometer = Accelerometer::new(*noise, bias);
+                    self.commands.entity(entity).insert(accelerometer);
+                    Ok(format!(
+                        "body {}: accelerometer with {} m/s² of noise",
+                        body.id, noise
+                    ))
+                }
+            }
+            ("engage_autopilot", [Argument::Text(name), ..]) if call.arguments.len() <= 2 => {
+                let autopilot = self.autopilots.create(name)?;
+                self.commands.entity(entity).insert(Pilot {
+                    name: name.clone(),
+                    autopilot,
+                    target,
+                });
+                Ok(format!("body {}: {} autopilot engaged", body.id, name))
+            }
+            ("spawn_ghosts", strategies) if (1..=MAX_GHOSTS).contains(&strategies.len()) => {
+                let strategies: Vec<String> = strategies
+                    .iter()
+                    .map(|strategy| match strategy {
+                        Argument::Text(strategy) => Ok(strategy.clone()),
+                        Argument::Number(_) => Err("strategies are autopilot names"),
+                    })
+                    .collect::<Result<_, _>>()?;
+                let ghosts = strategies.len();
+                self.settings.fleet.spawn(
+                    &self.autopilots,
+                    (entity, &body, engine.as_deref()),
+                    pilot.and_then(|pilot| pilot.target),
+                    &strategies,
+                    time,
+                )?;
+                Ok(format!("body {}: {} ghosts spawned", body.id, ghosts))
+            }
+            ("disengage_autopilot", []) => {
+                self.commands.entity(entity).remove::<Pilot>();
+                Ok(format!("body {}: autopilot disengaged", body.id))
+            }
+            ("autopilot", []) => Ok(match pilot {
+                Some(pilot) => format!("body {}: {} autopilot", body.id, pilot.name),
+                None => format!("body {} has no autopilot", body.id),
+            }),
+            ("set_fidelity", [Argument::Text(name)]) => {
+                let fidelity = Fidelity::parse(name)?;
+                self.commands.entity(entity).insert(fidelity);
+                Ok(format!("body {}: {} fidelity", body.id, fidelity.name()))
+            }
+            (
+                "set_antenna",
+                [Argument::Number(peak_gain), Argument::Number(beamwidth), pointing @ ..],
+            ) if *beamwidth > 0.0 => {
+                let pointing = match pointing {
+                    [] => Pointing::Nadir,
+                    [Argument::Text(name)] => Pointing::parse(name)?,
+                    _ => return Err("the pointing is nadir, zenith or tracking".to_string()),
+                };
+                self.commands.entity(entity).insert(Antenna {
+                    peak_gain: *peak_gain,
+                    beamwidth: beamwidth.to_radians(),
+                    pointing,
+                });
+                Ok(format!(
+                    "body {}: {} dBi antenna, {}° wide, pointing {}",
+                    body.id,
+                    peak_gain,
+                    beamwidth,
+                    pointing.name()
+                ))
+            }
+            ("add_recorder", [Argument::Number(capacity)]) if *capacity > 0.0 => {
+                self.commands
+                    .entity(entity)
+                    .insert(Recorder::new(*capacity));
+                Ok(format!("body {}: empty {} MB recorder added", body.id, capacity))
+            }
+            ("add_instrument", [Argument::Text(name), Argument::Number(rate)]) if *rate >= 0.0 => {
+                let mut recorder =
+                    recorder.ok_or_else(|| format!("body {} has no recorder", body.id))?;
+                recorder.instruments.push(Instrument {
+                    name: name.clone(),
+                    rate: *rate,
+                });
+                Ok(format!("body {}: {} recording {} bit/s", body.id, name, rate))
+            }
+            ("recorder", []) => {
+                let recorder =
+                    recorder.ok_or_else(|| format!("body {} has no recorder", body.id))?;
+                let instruments: Vec<String> = recorder
+                    .instruments
+                    .iter()
+                    .map(|instrument| format!("{} at {} bit/s", instrument.name, instrument.rate))
+                    .collect();
+                Ok(format!(
+                    "{}; instruments: {}",
+                    recorder.describe(body.id, Language::English),
+                    if instruments.is_empty() {
+                        "none".to_string()
+                    } else {
+                        instruments.join(", ")
+                    }
+                ))
+            }
+            ("add_power", []) => {
+                self.commands.entity(entity).insert(Power::default());
+                Ok(format!("body {}: battery and solar array added", body.id))
+            }
+            (
+                "add_power",
+                [Argument::Number(capacity), Argument::Number(array), Argument::Number(load)],
+            ) if *capacity > 0.0 && *array >= 0.0 && *load >= 0.0 => {
+                self.commands
+                    .entity(entity)
+                    .insert(Power::new(*capacity, *array, *load));
+                Ok(format!(
+                    "body {}: {} Wh battery, {} W array, {} W load",
+                    body.id, capacity, array, load
+                ))
+            }
+            ("fail", [Argument::Text(subsystem), fraction @ ..]) => {
+                let fraction = match fraction {
+                    [] => None,
+                    [Argument::Number(fraction)] if (0.0..=1.0).contains(fraction) => {
+                        Some(*fraction)
+                    }
+                    _ => return Err("the fraction left goes from 0 to 1".to_string()),
+                };
+                let failure = match (subsystem.as_str(), fraction) {
+                    ("engine", None) => Failure::Engine(EngineFault::StuckOff),
+                    ("engine", Some(left)) => Failure::Engine(EngineFault::Reduced(left)),
+                    ("comms", None) => Failure::Comms(CommsFault::Dead),
+                    ("comms", Some(left)) => Failure::Comms(CommsFault::Reduced(left)),
+                    ("battery", Some(left)) => Failure::Battery(left),
+                    ("battery", None) => {
+                        return Err("usage: fail(\"battery\", fraction_left)".to_string())
+                    }
+                    _ => {
+                        return Err(format!(
+                            "unknown subsystem \"{}\", try engine, comms or battery",
+                            subsystem
+                        ))
+                    }
+                };
+                let (engine, recorder, power) =
+                    (engine.as_deref_mut(), recorder.as_deref_mut(), power.as_deref_mut());
+                if !failure.apply(&body, engine, recorder, power, time) {
+                    return Err(format!("body {} has no {}", body.id, subsystem));
+                }
+                Ok(format!("body {}: {} failed", body.id, subsystem))
+            }
+            ("repair", []) => {
+                for failure in [
+                    Failure::Engine(EngineFault::Nominal),
+                    Failure::Comms(CommsFault::Nominal),
+                    Failure::Battery(1.0),
+                ] {
+                    let (engine, recorder, power) =
+                        (engine.as_deref_mut(), recorder.as_deref_mut(), power.as_deref_mut());
+                    failure.apply(&body, engine, recorder, power, time);
+                }
+                Ok(format!("body {}: repaired", body.id))
+            }
+            ("dose", []) => {
+                let state = body.current_state;
+                Ok(format!(
+                    "body {}: {:.3} mGy so far, {:.4} mGy/h now",
+                    body.id,
+                    self.records.radiation.dose(body.id),
+                    radiation::dose_rate(state.x.hypot(state.y))
+                ))
+            }
+            ("add_thermal", []) => {
+                self.commands.entity(entity).insert(Thermal::default());
+                Ok(format!("body {}: thermal model added at 20 °C", body.id))
+            }
+            ("add_thermal", [Argument::Number(dissipation)]) if *dissipation >= 0.0 => {
+                self.commands
+                    .entity(entity)
+                    .insert(Thermal::new(*dissipation));
+                Ok(format!(
+                    "body {}: thermal model added at 20 °C, dissipating {} W",
+                    body.id, dissipation
+                ))
+            }
+            ("temperature", []) => {
+                let thermal =
+                    thermal.ok_or_else(|| format!("body {} has no thermal model", body.id))?;
+                let limits = thermal.limits;
+                Ok(format!(
+                    "body {}: {:.1} °C, {}, operating {:.0} to {:.0} °C, \
+                    survival {:.0} to {:.0} °C",
+                    body.id,
+                    thermal.celsius(),
+                    thermal.status.describe(Language::English),
+                    limits.operating.0 - KELVIN,
+                    limits.operating.1 - KELVIN,
+                    limits.survival.0 - KELVIN,
+                    limits.survival.1 - KELVIN
+                ))
+            }
+            (
+                "set_thermal_limits",
+                [
+                    Argument::Number(low),
+                    Argument::Number(high),
+                    Argument::Number(min),
+                    Argument::Number(max),
+                ],
+            ) => {
+                let mut thermal =
+                    thermal.ok_or_else(|| format!("body {} has no thermal model", body.id))?;
+                thermal.limits = ThermalLimits::from_celsius((*low, *high), (*min, *max))?;
+                Ok(format!("body {}: thermal limits set", body.id))
+            }
+            ("fidelity", []) if on_rails => Ok(format!("body {}: on rails", body.id)),
+            ("fidelity", []) => Ok(format!(
+                "body {}: {} fidelity",
+                body.id,
+                fidelity.copied().unwrap_or_default().name()
+            )),
+            ("put_on_rails", []) => {
+                let rails = OnRails::new(&body.current_state, time)
+                    .ok_or("only bodies on elliptic orbits can go on rails")?;
+                self.commands.entity(entity).insert(rails);
+                Ok(format!("body {}: on rails", body.id))
+            }
+            ("take_off_rails", []) => {
+                self.commands.entity(entity).remove::<OnRails>();
+                Ok(format!("body {}: off the rails", body.id))
+            }
+            ("remove_sensors", []) => {
+                self.commands
+                    .entity(entity)
+                    .remove::<(Gps, Accelerometer)>();
+                Ok(format!("body {}: sensors removed", body.id))
+            }
+            ("gps", []) => {
+                let gps = gps.ok_or_else(|| format!("body {} has no GPS", body.id))?;
+                match gps.fix {
+                    Some(fix) => Ok(format!(
+                        "fix at t = {} s: x = {:.1} m, y = {:.1} m",
+                        fix.time, fix.value.x, fix.value.y
+                    )),
+                    None => Ok("no fix yet".to_string()),
+                }
+            }
+            ("accelerometer", []) => {
+                let accelerometer = accelerometer
+                    .ok_or_else(|| format!("body {} has no accelerometer", body.id))?;
+                match accelerometer.reading {
+                    Some(reading) => Ok(format!(
+                        "reading at t = {} s: ax = {:.6} m/s², ay = {:.6} m/s²",
+                        reading.time, reading.value.x, reading.value.y
+                    )),
+                    None => Ok("no reading yet".to_string()),
+                }
+            }
+            ("sweep", [Argument::Number(duration)]) if *duration > 0.0 => {
+                let rows = sweep::run_sweep(body.current_state, *duration)?;
+                sweep::write_csv(sweep::CSV_PATH, &rows)
+                    .map_err(|error| format!("could not write {}: {}", sweep::CSV_PATH, error))?;
+                Ok(format!(
+                    "{}\nwritten to {}",
+                    sweep::format_table(&rows),
+                    sweep::CSV_PATH
+                ))
+            }
+            ("neighbours", [Argument::Number(radius)]) if *radius > 0.0 => {
+                let here = DVec2::new(body.current_state.x, body.current_state.y);
+                let mut found = self.index.within(here, *radius);
+                found.retain(|(entry, _)| entry.entity != entity);
+                found.sort_by(|a, b| a.1.total_cmp(&b.1));
+                if found.is_empty() {
+                    return Ok(format!("no bodies within {} m", radius));
+                }
+                let lines: Vec<String> = found
+                    .iter()
+                    .map(|(entry, distance)| format!("body {} at {:.0} m", entry.id, distance))
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            ("impulse", [Argument::Number(dvx), Argument::Number(dvy)]) => {
+                apply_impulse(&mut body, *dvx, *dvy, time, &mut self.events);
+                Ok(format!(
+                    "applied ({}, {}) m/s to body {}",
+                    dvx, dvy, body.id
+                ))
+            }
+            ("set_velocity", [Argument::Number(vx), Argument::Number(vy)]) => {
+                body.current_state.vx = *vx;
+                body.current_state.vy = *vy;
+                Ok(format!(
+                    "body {} velocity set to ({}, {}) m/s",
+                    body.id, vx, vy
+                ))
+            }
+            ("set_ballistic_coefficient", [Argument::Number(b)]) if *b > 0.0 => {
+                body.ballistic_coefficient = Some(*b);
+                Ok(format!(
+                    "body {} ballistic coefficient set to {} kg/m²",
+                    body.id, b
+                ))
+            }
+            ("remove_drag", []) => {
+                body.ballistic_coefficient = None;
+                Ok(format!("body {} no longer feels drag", body.id))
+            }
+            (
+                "set_burn_errors",
+                [Argument::Number(magnitude), Argument::Number(direction)],
+            ) if *magnitude >= 0.0 && *direction >= 0.0 => {
+                self.commands.entity(entity).insert(BurnErrors {
+                    magnitude: magnitude / 100.0,
+                    direction: direction.to_radians(),
+                });
+                Ok(format!(
+                    "body {}: burns off by {}% in size and {}° in direction (1σ)",
+                    body.id, magnitude, direction
+                ))
+            }
+            ("remove_burn_errors", []) => {
+                self.commands.entity(entity).remove::<BurnErrors>();
+                Ok(format!("body {}: burns carried out exactly", body.id))
+            }
+            (
+                "add_tank",
+                [Argument::Number(dry_mass), Argument::Number(propellant), Argument::Number(isp), Argument::Number(thrust)],
+            ) if *dry_mass > 0.0 && *propellant >= 0.0 && *isp > 0.0 && *thrust > 0.0 => {
+                let mut engine = engine.ok_or_else(|| format!("body {} has no engine", body.id))?;
+                let tank = Tank {
+                    dry_mass: *dry_mass,
+                    propellant: *propellant,
+                    isp: *isp,
+                    thrust: *thrust,
+                };
+                engine.tank = Some(tank);
+                body.mass = tank.mass();
+                Ok(format!(
+                    "body {}: {} kg of propellant, {:.0} m/s of Δv",
+                    body.id,
+                    propellant,
+                    tank.delta_v()
+                ))
+            }
+            ("propellant", []) => {
+                let engine = engine.ok_or_else(|| format!("body {} has no engine", body.id))?;
+                let tank = engine
+                    .tank
+                    .ok_or_else(|| format!("body {} has unlimited propellant", body.id))?;
+                Ok(format!(
+                    "body {}: {:.1} kg of propellant, {:.0} m/s of Δv left at {:.3} m/s²",
+                    body.id,
+                    tank.propellant,
+                    tank.delta_v(),
+                    engine.full_thrust(self.physics.thrust.0)
+                ))
+            }
+            (
+                "target_altitude" | "target_periapsis",
+                [Argument::Number(altitude), Argument::Number(burn_in), Argument::Number(flight_time)],
+            ) if *burn_in >= 0.0 && *flight_time >= propagator.dt => {
+                let Some(mut plan) = plan else {
+                    return Err(format!("body {} can't plan burns", body.id));
+                };
+
+                let mut constraints = vec![Constraint::Altitude(*altitude)];
+                if call.name == "target_periapsis" {
+                    constraints.push(Constraint::RadialVelocity(0.0));
+                }
+
+                // Burns are planned at least one step ahead, so they are never in the past
+                let burn_steps = (burn_in / propagator.dt).ceil().max(1.0) as usize;
+                let flight_steps = (flight_time / propagator.dt).round() as usize;
+                let coast = propagator.coast(&body, time);
+                let (dvx, dvy) = targeter::solve(coast, burn_steps, flight_steps, &constraints)?;
+
+                let node = ManeuverNode {
+                    time: time + burn_steps as Precision * propagator.dt,
+                    dv: [dvx, dvy],
+                    frame: Frame::Inertial,
+                };
+                plan.add(node);
+                Ok(format!("planned a burn at {}", node))
+            }
+            (
+                "plan_burn",
+                [Argument::Number(burn_in), Argument::Number(prograde), Argument::Number(outward)],
+            ) if *burn_in >= 0.0 => {
+                let Some(mut plan) = plan else {
+                    return Err(format!("body {} can't plan burns", body.id));
+                };
+
+                let node = ManeuverNode {
+                    time: time + (burn_in / propagator.dt).ceil().max(1.0) * propagator.dt,
+                    dv: [*prograde, *outward],
+                    frame: Frame::Orbital,
+                };
+                plan.add(node);
+                Ok(format!("planned a burn at {}", node))
+            }
+            ("insert_orbit", [Argument::Number(altitude), Argument::Number(eccentricity)]) => {
+                let Some(mut plan) = plan else {
+                    return Err(format!("body {} can't plan burns", body.id));
+                };
+
+                let nodes =
+                    insertion::plan_insertion(
+                    &body.current_state,
+                    (time, propagator.dt),
+                    *altitude,
+                    *eccentricity,
+                )?;
+                if nodes.is_empty() {
+                    return Ok("the body is already on the target orbit".to_string());
+                }
+                let total: Precision = nodes.iter().map(|node| node.dv[0].abs()).sum();
+                let lines: Vec<String> = nodes
+                    .iter()
+                    .map(|node| {
+                        plan.add(*node);
+                        format!("planned a burn at {}", node)
+                    })
+                    .collect();
+                Ok(format!("{}\ntotal Δv {:.3} m/s", lines.join("\n"), total))
+            }
+            ("optimize_burn", [Argument::Number(periapsis), Argument::Number(apoapsis)]) => {
+                if plan.is_none() {
+                    return Err(format!("body {} can't plan burns", body.id));
+                }
+
+                let coast = propagator.coast(&body, time);
+                let node = optimizer::optimize_burn(coast, *periapsis, *apoapsis)?;
+                self.proposal.0 = Some((entity, node));
+                let [dvx, dvy] = node.dv;
+                Ok(format!(
+                    "proposed a burn of {:.3} m/s at {}, accept_proposal() adds it to the plan",
+                    (dvx * dvx + dvy * dvy).sqrt(),
+                    node
+                ))
+            }
+            ("repeat_orbit", days) => {
+                let days = match days {
+                    [] => repeat::MAX_DAYS,
+                    [Argument::Number(days)] if *days >= 1.0 => *days as u32,
+                    _ => return Err("usage: repeat_orbit([max_days]), max_days at least 1".to_string()),
+                };
+                if plan.is_none() {
+                    return Err(format!("body {} can't plan burns", body.id));
+                }
+
+                let (repeat, node) = repeat::repeat_orbit(propagator.coast(&body, time), days)?;
+                self.proposal.0 = Some((entity, node));
+                Ok(format!(
+                    "{}\nproposed a burn of {:.3} m/s at {}, accept_proposal() adds it to the plan",
+                    repeat.describe(Language::English),
+                    node.dv[0].abs(),
+                    node
+                ))
+            }
+            ("freeze_orbit", []) => {
+                if plan.is_none() {
+                    return Err(format!("body {} can't plan burns", body.id));
+                }
+
+                let earth = self.physics.perturbations.earth();
+                let (freeze, node) = frozen::freeze_orbit(propagator.coast(&body, time), &earth)?;
+                self.proposal.0 = Some((entity, node));
+                let [dvx, dvy] = node.dv;
+                Ok(format!(
+                    "{}\nproposed a burn of {:.3} m/s at {}, accept_proposal() adds it to the plan",
+                    freeze.describe(),
+                    dvx.hypot(dvy),
+                    node
+                ))
+            }
+            ("plan", []) => match plan {
+                Some(plan) if !plan.0.is_empty() => Ok(plan
+                    .0
+                    .iter()
+                    .map(|node| node.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")),
+                _ => Ok("no burns planned".to_string()),
+            },
+            ("clear_plan", []) => {
+                if let Some(mut plan) = plan {
+                    plan.0.clear();
+                }
+                Ok(format!("cleared the plan of body {}", body.id))
+            }
+            ("export_ephemeris", [Argument::Text(path), Argument::Number(duration), step @ ..])
+                if *duration > 0.0 =>
+            {
+                let step = match step {
+                    [] => trajectory::DEFAULT_STEP,
+                    [Argument::Number(step)] if *step > 0.0 => *step,
+                    _ => return Err("step must be positive".to_string()),
+                };
+                let coast = propagator.coast(&body, time);
+                let samples = trajectory::predict(coast, *duration, step);
+                let format = trajectory::Format::of(path);
+                let id = body.id;
+                let object = (id, name.map(|name| name.as_str()));
+                trajectory::write_ephemeris(path, format, object, &self.epoch, time, &samples)
+                    .map(|count| format!("{} states of body {} written to {}", count, id, path))
+                    .map_err(|error| format!("could not write {}: {}", path, error))
+            }
+            ("export_plan", [Argument::Text(path)]) => {
+                let Some(plan) = plan else {
+                    return Err(format!("body {} can't plan burns", body.id));
+                };
+                plan.save(path)
+                    .map_err(|error| format!("could not write {}: {}", path, error))?;
+                Ok(format!("wrote {} burns to {}", plan.0.len(), path))
+            }
+            ("import_plan", [Argument::Text(path), rest @ ..])
+                if matches!(rest, [] | [Argument::Number(_)]) =>
+            {
+                let Some(mut plan) = plan else {
+                    return Err(format!("body {} can't plan burns", body.id));
+                };
+                let mut nodes = ManeuverPlan::load(path)?;
+
+                // With a start time the plan is shifted so its first burn comes that many
+                // seconds from now, which is how it's moved onto another scenario
+                if let ([Argument::Number(start_in)], Some(first)) = (rest, nodes.first()) {
+                    let shift = time + start_in - first.time;
+                    for node in nodes.iter_mut() {
+                        node.time += shift;
+                    }
+                }
+
+                let total = nodes.len();
+                let mut added = 0;
+                for node in nodes {
+                    if node.time > time {
+                        plan.add(node);
+                        added += 1;
+                    }
+                }
+                Ok(format!(
+                    "imported {} burns from {}, skipped {} in the past",
+                    added,
+                    path,
+                    total - added
+                ))
+            }
+            ("target_altitude" | "target_periapsis", _) => Err(format!(
+                "usage: {}(altitude, burn_in, flight_time), with flight_time of at least {} s",
+                call.name, propagator.dt
+            )),
+            (
+                "set_color"
+                | "set_trail_color"
+                | "set_lookahead_color"
+                | "set_trail_style"
+                | "set_lookahead_style"
+                | "set_marker",
+                [Argument::Text(value)],
+            ) => {
+                let Some(mut style) = style else {
+                    return Err(format!("body {} has no style", body.id));
+                };
+
+                match call.name.as_str() {
+                    "set_color" => style.color = style::parse_color(value)?,
+                    "set_trail_color" => style.trail_color = style::parse_color(value)?,
+                    "set_lookahead_color" => style.lookahead_color = style::parse_color(value)?,
+                    "set_trail_style" => style.trail_style = style::parse_line_style(value)?,
+                    "set_lookahead_style" => {
+                        style.lookahead_style = style::parse_line_style(value)?
+                    }
+                    _ => style.marker = style::parse_marker(value)?,
+                }
+                Ok(format!("body {}: {} to {}", body.id, call.name, value))
+            }
+            (
+                "set_color"
+                | "set_trail_color"
+                | "set_lookahead_color"
+                | "set_trail_style"
+                | "set_lookahead_style"
+                | "set_marker",
+                _,
+            ) => Err(format!("usage: {}(\"value\")", call.name)),
+            ("plan_burn", _) => Err("usage: plan_burn(burn_in, prograde, outward)".to_string()),
+            ("join", _) => Err("usage: join(\"group\")".to_string()),
+            ("leave", _) => Err("usage: leave(\"group\")".to_string()),
+            ("optimize_burn", _) => {
+                Err("usage: optimize_burn(periapsis_altitude, apoapsis_altitude)".to_string())
+            }
+            ("insert_orbit", _) => {
+                Err("usage: insert_orbit(periapsis_altitude, eccentricity)".to_string())
+            }
+            ("export_plan", _) => Err("usage: export_plan(\"path\")".to_string()),
+            ("export_ephemeris", _) => {
+                Err("usage: export_ephemeris(\"path\", duration, [step])".to_string())
+            }
+            ("import_plan", _) => {
+                Err("usage: import_plan(\"path\") or import_plan(\"path\", start_in)".to_string())
+            }
+            ("stm", _) => Err("usage: stm(duration)".to_string()),
+            ("neighbours", _) => Err("usage: neighbours(radius)".to_string()),
+            ("sweep", _) => Err("usage: sweep(duration)".to_string()),
+            ("add_gps" | "add_accelerometer", _) => Err(format!(
+                "usage: {}(noise) or {}(noise, bias_x, bias_y)",
+                call.name, call.name
+            )),
+            ("set_fidelity", _) => {
+                Err("usage: set_fidelity(\"high|standard|analytic\")".to_string())
+            }
+            ("set_antenna", _) => Err(
+                "usage: set_antenna(gain, beamwidth) or set_antenna(gain, beamwidth, \
+                \"nadir|zenith|tracking\")"
+                    .to_string(),
+            ),
+            ("add_recorder", _) => Err("usage: add_recorder(capacity_in_megabytes)".to_string()),
+            ("add_instrument", _) => {
+                Err("usage: add_instrument(\"name\", bits_per_second)".to_string())
+            }
+            ("add_power", _) => {
+                Err("usage: add_power() or add_power(capacity_wh, array_w, load_w)".to_string())
+            }
+            ("fail", _) => Err(
+                "usage: fail(\"engine|comms\") or fail(\"engine|comms|battery\", fraction_left)"
+                    .to_string(),
+            ),
+            ("add_thermal", _) => {
+                Err("usage: add_thermal() or add_thermal(dissipation)".to_string())
+            }
+            ("set_thermal_limits", _) => Err(
+                "usage: set_thermal_limits(low, high, min, max), the operating and survival ranges \
+                in °C"
+                    .to_string(),
+            ),
+            ("spawn_ghosts", _) => Err(format!(
+                "usage: spawn_ghosts(\"strategy\", ...), with 1 to {} autopilot names or \
+                \"{}\"",
+                MAX_GHOSTS,
+                ghost::COAST
+            )),
+            ("engage_autopilot", _) => Err(
+                "usage: engage_autopilot(\"name\") or engage_autopilot(\"name\", target)"
+                    .to_string(),
+            ),
+            (
+                "gps"
+                | "accelerometer"
+                | "remove_sensors"
+                | "disengage_autopilot"
+                | "autopilot"
+                | "fidelity"
+                | "dose"
+                | "temperature"
+                | "recorder"
+                | "repair"
+                | "put_on_rails"
+                | "take_off_rails"
+                | "remove_drag"
+                | "remove_burn_errors"
+                | "freeze_orbit"
+                | "propellant",
+                _,
+            ) => Err(format!("usage: {}()", call.name)),
+            ("impulse", _) => Err("usage: impulse(dvx, dvy)".to_string()),
+            ("set_velocity", _) => Err("usage: set_velocity(vx, vy)".to_string()),
+            ("set_burn_errors", _) => Err(
+                "usage: set_burn_errors(magnitude_percent, direction_degrees)".to_string(),
+            ),
+            ("add_tank", _) => Err(
+                "usage: add_tank(dry_mass, propellant, isp, thrust), in kg, kg, s and N"
+                    .to_string(),
+            ),
+            ("set_ballistic_coefficient", _) => {
+                Err("usage: set_ballistic_coefficient(kilograms_per_square_metre)".to_string())
+            }
+            (name, []) => quantity(&body, name)
+                .or_else(|| sensors::quantity(gps, accelerometer, name))
+                .map(|value| format!("{} = {}", name, value))
+                .ok_or_else(|| format!("unknown method `{}`", name)),
+            (name, _) => Err(format!("unknown method `{}`", name)),
+        }
+    }
+
+    // Queues a macro's commands, but those whose time has passed
+    fn run_macro(&mut self, path: &str, start: Option<Precision>) -> Result<String, String> {
+        let lines = macros::load(path, start)?;
+        let total = lines.len();
+        let mut queued = 0;
+        for (time, calls) in lines {
+            if time >= self.time.0 {
+                self.settings.scheduler.schedule(time, calls);
+                queued += 1;
+            }
+        }
+        if queued < total {
+            return Ok(format!(
+                "queued {} commands of {}, the others' times have passed",
+                queued, total
+            ));
+        }
+        Ok(format!("queued {} commands from {}", queued, path))
+    }
+
+    // A command typed at the console, which goes into the macro being recorded if it runs
+    pub fn run_typed(&mut self, input: &str) -> Result<String, String> {
+        let calls = parse(input)?;
+        let output = self.execute(&calls)?;
+        if !matches!(calls.as_slice(), [call] if call.name.ends_with("_macro")) {
+            let command = format_calls(&calls);
+            self.settings.macros.record(self.time.0, command);
+        }
+        Ok(output)
+    }
+
+    pub fn language(&self) -> Language {
+        *self.settings.language
+    }
+
+    pub fn execute(&mut self, calls: &[Call]) -> Result<String, String> {
+        match calls {
+            [at, action @ ..] if at.name == "at" => {
+                let [Argument::Number(time)] = at.arguments.as_slice() else {
+                    return Err(
+                        "usage: at(time).command(...), at an absolute time in s".to_string()
+                    );
+                };
+                match action {
+                    [_] => {}
+                    [body, _] if body.name == "body" || body.name == "group" => {}
+                    _ => return Err("expected at(time).command(...)".to_string()),
+                }
+                if *time < self.time.0 {
+                    return Err(format!("t = {} s has already passed", time));
+                }
+                self.settings.scheduler.schedule(*time, action.to_vec());
+                Ok(format!("scheduled {} at {} s", format_calls(action), time))
+            }
+            [call] => self.global(call),
+            [body, method] if body.name == "body" => {
+                let entity = self.find(&body.arguments)?;
+                self.method(entity, method)
+            }
+            [group, method] if group.name == "group" => self.group(&group.arguments, method),
+            _ => Err("expected `command(...)` or `body(...).method(...)`".to_string()),
+        }
+    }
+}
+
+struct ScriptLine {
+    number: usize,
+    time: Precision,
+    calls: Vec<Call>,
+}
+
+#[derive(Resource, Default)]
+pub struct Script {
+    lines: Vec<ScriptLine>,
+    next: usize,
+    failures: usize,
+}
+
+// The line up to a `#` that isn't inside a string
+fn strip_comment(line: &str) -> &str {
+    find_unquoted(line, '#').map_or(line, |i| &line[..i])
+}
+
+pub fn parse_line(line: &str) -> Result<Option<(Precision, Vec<Call>)>, String> {
+    let line = strip_comment(line).trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    let rest = line
+        .strip_prefix("at ")
+        .ok_or_else(|| "lines must start with `at <time>`".to_string())?;
+    let (time, command) = rest
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| "missing command".to_string())?;
+    let time = time.parse().map_err(|_| format!("bad time `{}`", time))?;
+
+    Ok(Some((time, parse(command)?)))
+}
+
+impl Script {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        let mut lines = Vec::new();
+
+        for (i, line) in source.lines().enumerate() {
+            let number = i + 1;
+            match parse_line(line) {
+                Ok(Some((time, calls))) => lines.push(ScriptLine {
+                    number,
+                    time,
+                    calls,
+                }),
+                Ok(None) => {}
+                Err(error) => return Err(format!("{}:{}: {}", path, number, error)),
+            }
+        }
+
+        lines.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Ok(Self {
+            lines,
+            next: 0,
+            failures: 0,
+        })
+    }
+}
+
+// Runs the actions the clock has reached, right after the tick that reached them
+pub fn run_scheduled(mut sim: Sim) {
+    let now = sim.time.0;
+    for action in sim.settings.scheduler.take_due(now) {
+        match sim.execute(&action.calls) {
+            Ok(output) => info!("t = {} s: {}", now, output),
+            Err(error) => error!("t = {} s: {}: {}", now, format_calls(&action.calls), error),
+        }
+    }
+}
+
+pub fn run_script(mut script: ResMut<Script>, mut sim: Sim) {
+    if script.lines.is_empty() {
+        return;
+    }
+
+    while let Some(line) = script.lines.get(script.next) {
+        if line.time > sim.time.0 {
+            return;
+        }
+
+        match sim.execute(&line.calls) {
+            Ok(output) => info!("line {}: {}", line.number, output),
+            Err(error) => {
+                error!("line {}: {}", line.number, error);
+                script.failures += 1;
+            }
+        }
+        script.next += 1;
+    }
+
+    // Regression suites only look at the exit code, so there's no need for a graceful shutdown
+    // beyond keeping the mission log
+    sim.settings.log.flush();
+    info!(
+        "Script finished: {} of {} commands failed",
+        script.failures,
+        script.lines.len()
+    );
+    std::process::exit(if script.failures == 0 { 0 } else { 1 });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calls(text: &str) -> String {
+        format_calls(&parse(text).unwrap())
+    }
+
+    #[test]
+    fn splits_outside_strings_only() {
+        assert_eq!(
+            split_unquoted(r#"1, "a, b", 2"#, ','),
+            vec!["1", r#" "a, b""#, " 2"]
+        );
+        assert_eq!(split_unquoted("", ','), vec![""]);
+        assert_eq!(find_unquoted(r#""(" ("#, '('), Some(4));
+    }
+
+    #[test]
+    fn parses_a_call_and_leaves_the_rest() {
+        let (call, rest) = parse_call(r#"body("a.b(c)") .impulse(1, -2.5)"#).unwrap();
+        assert_eq!(call.to_string(), r#"body("a.b(c)")"#);
+        assert_eq!(rest, " .impulse(1, -2.5)");
+
+        let (call, rest) = parse_call(" time ( ) ").unwrap();
+        assert_eq!(call.to_string(), "time()");
+        assert_eq!(rest, " ");
+    }
+
+    #[test]
+    fn parses_chains() {
+        assert_eq!(
+            calls(r#"body("ISS") . impulse(1e3, -0.5)"#),
+            r#"body("ISS").impulse(1000, -0.5)"#
+        );
+        assert_eq!(
+            calls(r#"group("a, b").members()"#),
+            r#"group("a, b").members()"#
+        );
+    }
+
+    #[test]
+    fn rejects_bad_calls() {
+        for text in [
+            "",
+            "time",
+            "time(",
+            "bad name()",
+            "time() x",
+            "impulse(1, x)",
+            "time().",
+        ] {
+            assert!(parse(text).is_err(), "{} parsed", text);
+        }
+    }
+
+    #[test]
+    fn lines_take_a_time_and_drop_comments() {
+        let (time, calls) = parse_line(r##"at 90.5  body("#1").state()  # comment"##)
+            .unwrap()
+            .unwrap();
+        assert_eq!(time, 90.5);
+        assert_eq!(format_calls(&calls), r##"body("#1").state()"##);
+        assert!(parse_line("  # only a comment").unwrap().is_none());
+        assert!(parse_line("time()").is_err());
+        assert!(parse_line("at soon time()").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+// Onboard sensors for navigation experiments: a GPS-like receiver that gives position fixes
+// with Gaussian noise and a constant bias, and an accelerometer that measures what the engine
+// delivers (gravity can't be felt in free fall) with its own noise and bias. The latest
+// readings are what scripts see, so they can be fed to a navigation algorithm.
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::engine::Engine;
+use crate::observation::gaussian;
+use crate::{Body, Precision, SimTime};
+
+#[derive(Clone, Copy)]
+pub struct Reading {
+    pub time: Precision,
+    pub value: DVec2,
+}
+
+#[derive(Component)]
+pub struct Gps {
+    pub noise: Precision, // m, standard deviation on each axis
+    pub bias: DVec2,      // m
+    pub interval: Precision,
+    pub fix: Option<Reading>,
+}
+
+impl Gps {
+    // A fix every `interval` seconds, every step to begin with
+    pub fn new(noise: Precision, bias: DVec2, interval: Precision) -> Self {
+        Self {
+            noise,
+            bias,
+            interval,
+            fix: None,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Accelerometer {
+    pub noise: Precision, // m/s², standard deviation on each axis
+    pub bias: DVec2,      // m/s²
+    pub reading: Option<Reading>,
+}
+
+impl Accelerometer {
+    pub fn new(noise: Precision, bias: DVec2) -> Self {
+        Self {
+            noise,
+            bias,
+            reading: None,
+        }
+    }
+}
+
+fn noisy(rng: &mut impl rand::Rng, value: DVec2, noise: Precision, bias: DVec2) -> DVec2 {
+    value + bias + DVec2::new(gaussian(rng, noise), gaussian(rng, noise))
+}
+
+pub fn read_sensors(
+    time: Res<SimTime>,
+    mut gps: Query<(&Body, &mut Gps)>,
+    mut accelerometers: Query<(&Body, Option<&Engine>, &mut Accelerometer)>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (body, mut gps) in gps.iter_mut() {
+        if gps.fix.is_some_and(|fix| time.0 - fix.time < gps.interval) {
+            continue;
+        }
+        let state = body.current_state;
+        let position = noisy(&mut rng, DVec2::new(state.x, state.y), gps.noise, gps.bias);
+        gps.fix = Some(Reading {
+            time: time.0,
+            value: position,
+        });
+    }
+
+    for (body, engine, mut accelerometer) in accelerometers.iter_mut() {
+        // Only once per step, not again on frames where the simulation stood still
+        if accelerometer
+            .reading
+            .is_some_and(|reading| reading.time == time.0)
+        {
+            continue;
+        }
+        let (ax, ay) = engine
+            .filter(|engine| engine.firing)
+            .map_or((0.0, 0.0), |engine| {
+                engine.delivered.components(&body.current_state)
+            });
+        let (noise, bias) = (accelerometer.noise, accelerometer.bias);
+        accelerometer.reading = Some(Reading {
+            time: time.0,
+            value: noisy(&mut rng, DVec2::new(ax, ay), noise, bias),
+        });
+    }
+}
+
+// The sensor outputs scripts can ask a body for, like its other quantities
+pub fn quantity(
+    gps: Option<&Gps>,
+    accelerometer: Option<&Accelerometer>,
+    name: &str,
+) -> Option<Precision> {
+    let fix = gps.and_then(|gps| gps.fix);
+    let reading = accelerometer.and_then(|accelerometer| accelerometer.reading);
+
+    match name {
+        "gps_x" => fix.map(|fix| fix.value.x),
+        "gps_y" => fix.map(|fix| fix.value.y),
+        "gps_time" => fix.map(|fix| fix.time),
+        "accel_x" => reading.map(|reading| reading.value.x),
+        "accel_y" => reading.map(|reading| reading.value.y),
+        _ => None,
+    }
+}
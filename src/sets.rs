@@ -0,0 +1,143 @@
+// The order the simulation runs in, as labelled system sets a plugin can add its own systems to:
+// each physics tick runs Forces (burns, faults, the governor), then Integrate (the step, ghosts,
+// rails, tethers), then Events (the spatial index, events, invariants, scheduled commands), and
+// each frame, after however many ticks that was, Record (sensors, predictions, history, scripts)
+// and then Render (the panels, the camera and the drawing). A system added with
+// `.in_set(PhysicsSet::Forces)` pushes on the bodies before they are stepped, one added to
+// Record sees them after every tick of the frame. How long each set took, last time and on
+// average, is kept in SetTimings, and `timings()` prints them.
+use std::time::{Duration, Instant};
+
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+
+const SETS: usize = 5;
+const SMOOTHING: f64 = 0.05; // weight of the last run in the average
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhysicsSet {
+    Forces,
+    Integrate,
+    Events,
+    Record,
+    Render,
+}
+
+impl PhysicsSet {
+    const ALL: [PhysicsSet; SETS] = [
+        PhysicsSet::Forces,
+        PhysicsSet::Integrate,
+        PhysicsSet::Events,
+        PhysicsSet::Record,
+        PhysicsSet::Render,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            PhysicsSet::Forces => "forces",
+            PhysicsSet::Integrate => "integrate",
+            PhysicsSet::Events => "events",
+            PhysicsSet::Record => "record",
+            PhysicsSet::Render => "render",
+        }
+    }
+}
+
+// The timing systems around the sets. Whatever runs in the same schedule first, like the
+// controls each frame, goes before it, so it isn't counted in the first set's time.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Timing;
+
+#[derive(Clone, Copy, Default)]
+pub struct SetTiming {
+    pub last: Duration,
+    pub mean: Duration, // smoothed over the runs
+    pub runs: u64,
+}
+
+// Wall time from the start of each set to its end, per tick for the physics sets and per frame
+// for the others
+#[derive(Resource, Default)]
+pub struct SetTimings {
+    started: [Option<Instant>; SETS],
+    timings: [SetTiming; SETS],
+}
+
+impl SetTimings {
+    pub fn get(&self, set: PhysicsSet) -> SetTiming {
+        self.timings[set as usize]
+    }
+
+    pub fn describe(&self) -> String {
+        PhysicsSet::ALL
+            .iter()
+            .map(|set| {
+                let timing = self.get(*set);
+                format!(
+                    "{}: {:.3} ms last, {:.3} ms on average over {} runs",
+                    set.name(),
+                    timing.last.as_secs_f64() * 1e3,
+                    timing.mean.as_secs_f64() * 1e3,
+                    timing.runs
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// A system to run just before `set`
+pub fn start(set: PhysicsSet) -> impl FnMut(ResMut<SetTimings>) {
+    move |mut timings| timings.started[set as usize] = Some(Instant::now())
+}
+
+// A system to run just after `set`
+pub fn finish(set: PhysicsSet) -> impl FnMut(ResMut<SetTimings>) {
+    move |mut timings| {
+        let Some(started) = timings.started[set as usize].take() else {
+            return;
+        };
+        let last = started.elapsed();
+        let timing = &mut timings.timings[set as usize];
+        timing.mean = if timing.runs == 0 {
+            last
+        } else {
+            timing.mean.mul_f64(1.0 - SMOOTHING) + last.mul_f64(SMOOTHING)
+        };
+        timing.last = last;
+        timing.runs += 1;
+    }
+}
+
+// Times `sets`, a chain of them in `schedule`. Between two sets the first one's time is taken
+// before the next one's clock starts.
+fn time(app: &mut App, schedule: impl ScheduleLabel + Clone, sets: &[PhysicsSet]) {
+    let (Some(&first), Some(&last)) = (sets.first(), sets.last()) else {
+        return;
+    };
+    app.add_systems(schedule.clone(), start(first).before(first).in_set(Timing));
+    for pair in sets.windows(2) {
+        let (previous, next) = (pair[0], pair[1]);
+        app.configure_sets(schedule.clone(), next.after(previous));
+        app.add_systems(
+            schedule.clone(),
+            (finish(previous).after(previous), start(next).before(next))
+                .chain()
+                .in_set(Timing),
+        );
+    }
+    app.add_systems(schedule.clone(), finish(last).after(last).in_set(Timing));
+}
+
+// Puts the sets in order in their schedules, with the timing around each
+pub struct PhysicsSetsPlugin;
+
+impl Plugin for PhysicsSetsPlugin {
+    fn build(&self, app: &mut App) {
+        use PhysicsSet::*;
+
+        app.init_resource::<SetTimings>();
+        time(app, FixedUpdate, &[Forces, Integrate, Events]);
+        time(app, Update, &[Record, Render]);
+    }
+}
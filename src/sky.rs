@@ -0,0 +1,140 @@
+// Sky view: azimuth-elevation polar plot of the bodies as seen from a ground station
+use std::f64::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ground::{GroundStation, Link};
+use crate::i18n::Language;
+use crate::palette::Palette;
+use crate::prediction::PredictedTrajectory;
+use crate::{Body, Precision, SimTime, TimeStep, EARTH_RADIUS};
+
+const PLOT_RADIUS: f32 = (EARTH_RADIUS * 2.5) as f32;
+const SKY_LOOKAHEAD: usize = 300; // steps of the predicted trajectory
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewMode {
+    #[default]
+    Orbit,
+    Sky,
+    ThreeBody,
+}
+
+// Station the sky view is drawn from; the first one when unset
+#[derive(Resource, Default)]
+pub struct Observer(Option<Entity>);
+
+#[derive(Component)]
+pub struct SkyText;
+
+// V cycles through the orbit, sky and three-body views
+pub fn toggle_view(keyboard: Res<Input<KeyCode>>, mut view: ResMut<ViewMode>) {
+    if keyboard.just_pressed(KeyCode::V) {
+        *view = match *view {
+            ViewMode::Orbit => ViewMode::Sky,
+            ViewMode::Sky => ViewMode::ThreeBody,
+            ViewMode::ThreeBody => ViewMode::Orbit,
+        };
+    }
+}
+
+// O moves the observer to the next station
+pub fn cycle_observer(
+    keyboard: Res<Input<KeyCode>>,
+    mut observer: ResMut<Observer>,
+    stations: Query<Entity, With<GroundStation>>,
+) {
+    if !keyboard.just_pressed(KeyCode::O) {
+        return;
+    }
+
+    let stations: Vec<Entity> = stations.iter().collect();
+    let next = match observer
+        .0
+        .and_then(|current| stations.iter().position(|s| *s == current))
+    {
+        Some(i) => stations.get(i + 1).or(stations.first()),
+        None => stations.get(1),
+    };
+    observer.0 = next.copied();
+}
+
+// Zenith at the centre, horizon on the outer ring, north up
+fn sky_point(link: &Link) -> Option<Vec2> {
+    if !link.visible() {
+        return None;
+    }
+
+    let r = (1.0 - link.elevation / FRAC_PI_2) as f32 * PLOT_RADIUS;
+    let (sin, cos) = (link.azimuth as f32).sin_cos();
+    Some(Vec2::new(r * sin, r * cos))
+}
+
+pub fn draw_sky(
+    mut gizmos: Gizmos,
+    view: Res<ViewMode>,
+    (language, palette): (Res<Language>, Res<Palette>),
+    (observer, time, step): (Res<Observer>, Res<SimTime>, Res<TimeStep>),
+    stations: Query<(Entity, &GroundStation)>,
+    bodies: Query<(&Body, Option<&PredictedTrajectory>)>,
+    mut text: Query<&mut Text, With<SkyText>>,
+) {
+    let mut text = text.single_mut();
+
+    let station = match observer.0 {
+        Some(entity) => stations.get(entity).ok(),
+        None => stations.iter().next(),
+    };
+    let (ViewMode::Sky, Some((_, station))) = (view.as_ref(), station) else {
+        text.sections[0].value.clear();
+        return;
+    };
+
+    text.sections[0].value = language.text("sky_view", &[&station.name]);
+
+    let colors = palette.colors();
+
+    // Horizon and the 30° and 60° elevation rings
+    for ring in 1..=3 {
+        gizmos.circle_2d(Vec2::ZERO, PLOT_RADIUS * ring as f32 / 3.0, colors.guide);
+    }
+    gizmos.line_2d(
+        Vec2::new(-PLOT_RADIUS, 0.0),
+        Vec2::new(PLOT_RADIUS, 0.0),
+        colors.guide,
+    );
+    gizmos.line_2d(
+        Vec2::new(0.0, -PLOT_RADIUS),
+        Vec2::new(0.0, PLOT_RADIUS),
+        colors.guide,
+    );
+
+    for (body, prediction) in bodies.iter() {
+        for (steps, state) in body.history.0.iter().enumerate() {
+            let Some(state) = state else {
+                continue;
+            };
+            let link = station.link_in(state, -(steps as Precision) * step.0);
+            if let Some(point) = sky_point(&link) {
+                gizmos.circle_2d(point, 30000.0, colors.trail);
+            }
+        }
+
+        if let Some(prediction) = prediction {
+            let start = prediction.start() - time.0;
+            let ahead = prediction.states().iter().enumerate();
+            for (steps, state) in ahead.skip(1).take(SKY_LOOKAHEAD) {
+                let link = station.link_in(state, start + steps as Precision * prediction.dt());
+                if let Some(point) = sky_point(&link) {
+                    gizmos.circle_2d(point, 30000.0, colors.lookahead);
+                }
+            }
+        }
+
+        if let Some(point) = sky_point(&station.link(&body.current_state)) {
+            gizmos.circle_2d(point, 150000.0, colors.body);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Every body's state vector and osculating elements at one epoch, as a JSON file for other
+// propagation tools. The schema is documented in the README.
+use std::fs::File;
+use std::io;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::elements::{OrbitalElements, MU};
+use crate::ephemeris::Epoch;
+use crate::{Body, Precision, SimTime};
+
+const SCHEMA: &str = "orbitabase-snapshot/1";
+const FRAME: &str = "earth-centred inertial, equatorial plane, +x towards the vernal equinox";
+const PATH: &str = "snapshot.json";
+
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    schema: &'a str,
+    epoch: String,
+    julian_date: Precision,
+    julian_date_tt: Precision,
+    sim_time: Precision,
+    frame: &'a str,
+    mu: Precision,
+    bodies: Vec<BodyRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct BodyRecord<'a> {
+    id: usize,
+    name: Option<&'a str>,
+    mass: Precision,
+    position: [Precision; 2],
+    velocity: [Precision; 2],
+    elements: ElementsRecord,
+}
+
+#[derive(Serialize)]
+struct ElementsRecord {
+    semi_major_axis: Precision,
+    eccentricity: Precision,
+    argument_of_periapsis: Precision, // deg
+    mean_anomaly: Option<Precision>,  // deg, only for closed orbits
+    clockwise: bool,
+}
+
+pub fn write_snapshot<'a>(
+    path: &str,
+    epoch: &Epoch,
+    time: Precision,
+    bodies: impl Iterator<Item = (&'a Body, Option<&'a Name>)>,
+) -> io::Result<usize> {
+    let mut bodies: Vec<BodyRecord> = bodies
+        .map(|(body, name)| {
+            let state = body.current_state;
+            let elements = OrbitalElements::from_state(&state);
+            BodyRecord {
+                id: body.id,
+                name: name.map(|name| name.as_str()),
+                mass: body.mass,
+                position: [state.x, state.y],
+                velocity: [state.vx, state.vy],
+                elements: ElementsRecord {
+                    semi_major_axis: elements.a,
+                    eccentricity: elements.e,
+                    argument_of_periapsis: elements.argument_of_periapsis.to_degrees(),
+                    mean_anomaly: (elements.e < 1.0).then(|| elements.mean_anomaly.to_degrees()),
+                    clockwise: state.x * state.vy - state.y * state.vx < 0.0,
+                },
+            }
+        })
+        .collect();
+    bodies.sort_by_key(|record| record.id);
+    let count = bodies.len();
+
+    let snapshot = Snapshot {
+        schema: SCHEMA,
+        epoch: epoch.timestamp(time),
+        julian_date: epoch.julian_date(time),
+        julian_date_tt: epoch.terrestrial_time(time),
+        sim_time: time,
+        frame: FRAME,
+        mu: MU,
+        bodies,
+    };
+    serde_json::to_writer_pretty(File::create(path)?, &snapshot)?;
+
+    Ok(count)
+}
+
+// S writes a snapshot of every body to snapshot.json
+pub fn export_snapshot(
+    keyboard: Res<Input<KeyCode>>,
+    epoch: Res<Epoch>,
+    time: Res<SimTime>,
+    bodies: Query<(&Body, Option<&Name>)>,
+) {
+    if !keyboard.just_pressed(KeyCode::S) {
+        return;
+    }
+
+    match write_snapshot(PATH, &epoch, time.0, bodies.iter()) {
+        Ok(count) => info!("Snapshot of {} bodies written to {}", count, PATH),
+        Err(error) => error!("Could not write {}: {}", PATH, error),
+    }
+}
@@ -0,0 +1,131 @@
+// Uniform grid over the simulation plane, rebuilt after every step, so that proximity
+// queries look at a few cells instead of every body. Used for mouse picking, the warp governor's
+// watch and the console's neighbour lists; conjunctions go through the broadphase instead.
+use std::collections::HashMap;
+
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::PixelSize;
+use crate::interpolation::Interpolation;
+use crate::{Body, Precision, Selected};
+
+const CELL_SIZE: Precision = 50e3; // m
+const PICK_RADIUS: Precision = 10.0; // pixels
+
+#[derive(Clone, Copy)]
+pub struct Entry {
+    pub entity: Entity,
+    pub id: usize,
+    pub position: DVec2,
+}
+
+#[derive(Resource, Default)]
+pub struct SpatialIndex {
+    cells: HashMap<(i64, i64), Vec<Entry>>,
+}
+
+fn cell(position: DVec2) -> (i64, i64) {
+    let cell = (position / CELL_SIZE).floor();
+    (cell.x as i64, cell.y as i64)
+}
+
+impl SpatialIndex {
+    // Bodies no further than `radius` from `point`, with their distances
+    pub fn within(&self, point: DVec2, radius: Precision) -> Vec<(Entry, Precision)> {
+        let (low, high) = (cell(point - radius), cell(point + radius));
+        let mut found = Vec::new();
+
+        for i in low.0..=high.0 {
+            for j in low.1..=high.1 {
+                for entry in self.cells.get(&(i, j)).into_iter().flatten() {
+                    let distance = entry.position.distance(point);
+                    if distance <= radius {
+                        found.push((*entry, distance));
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    pub fn nearest(&self, point: DVec2, radius: Precision) -> Option<Entry> {
+        self.within(point, radius)
+            .into_iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(entry, _)| entry)
+    }
+
+    // Every pair of bodies closer than `radius`, each pair once
+    pub fn pairs_within(&self, radius: Precision) -> Vec<(Entry, Entry, Precision)> {
+        let mut pairs = Vec::new();
+
+        for entries in self.cells.values() {
+            for a in entries {
+                for (b, distance) in self.within(a.position, radius) {
+                    if a.id < b.id {
+                        pairs.push((*a, b, distance));
+                    }
+                }
+            }
+        }
+
+        pairs
+    }
+}
+
+pub fn update_spatial_index(mut index: ResMut<SpatialIndex>, bodies: Query<(Entity, &Body)>) {
+    index.cells.clear();
+
+    for (entity, body) in bodies.iter() {
+        let position = DVec2::new(body.current_state.x, body.current_state.y);
+        index.cells.entry(cell(position)).or_default().push(Entry {
+            entity,
+            id: body.id,
+            position,
+        });
+    }
+}
+
+// Clicking on a body selects it
+pub fn pick_body(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    (index, pixel): (Res<SpatialIndex>, Res<PixelSize>),
+    interpolation: Interpolation,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    selected: Query<Entity, With<Selected>>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    let (camera, transform) = cameras.single();
+    // Not while the perspective camera is drawing
+    if !camera.is_active {
+        return;
+    }
+    let Some(point) = camera.viewport_to_world_2d(transform, cursor) else {
+        return;
+    };
+
+    let point = interpolation.world(point);
+    let Some(entry) = index.nearest(point, PICK_RADIUS * pixel.0) else {
+        return;
+    };
+
+    for entity in selected.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+    commands.entity(entry.entity).insert(Selected);
+    info!("Selected body {}", entry.id);
+}
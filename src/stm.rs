@@ -0,0 +1,155 @@
+// State transition matrix, propagated alongside the state with the variational equations
+// dΦ/dt = A(t) Φ. The state vector is ordered (x, y, vx, vy), so Φ maps a deviation in the
+// initial state to the deviation it causes after the propagation. The state flies with the
+// coast's own integrator and substeps, like the lookaheads; Φ takes RK4 through the same
+// substeps alongside it, with the coast's sources, the earth's bulge included, and its air. The
+// matrix leaves out how the drag changes with the state, and thrust is not modelled.
+use bevy::math::{DMat4, DVec4};
+
+use crate::propagator::Coast;
+use crate::{forcing_from, substeps, Drag, Precision, Source, State, Thrust, G};
+
+fn vector(state: &State) -> DVec4 {
+    DVec4::new(state.x, state.y, state.vx, state.vy)
+}
+
+fn from_vector(v: DVec4) -> State {
+    State::new(v.x, v.y, v.z, v.w)
+}
+
+// Jacobian of the equations of motion at a position
+fn jacobian(x: Precision, y: Precision, sources: &[Source]) -> DMat4 {
+    let (mut gxx, mut gxy, mut gyy) = (0.0, 0.0, 0.0);
+    for source in sources {
+        let (dx, dy) = (x - source.x, y - source.y);
+        let r2 = dx * dx + dy * dy;
+        let r = r2.sqrt();
+        let mu = G * source.mass;
+        // The bulge's pull goes as 1/r⁴, so the gradient of μ r (1/r³ + k/r⁵) is
+        // μ [(3/r⁵ + 5k/r⁷) r rᵀ - (1/r³ + k/r⁵) I]
+        let k = 1.5 * source.j2 * source.radius * source.radius;
+        let outer = mu * (3.0 + 5.0 * k / r2) / (r2 * r2 * r);
+        let diagonal = mu * (1.0 + k / r2) / (r2 * r);
+        gxx += outer * dx * dx - diagonal;
+        gxy += outer * dx * dy;
+        gyy += outer * dy * dy - diagonal;
+    }
+
+    DMat4::from_cols(
+        DVec4::new(0.0, 0.0, gxx, gxy),
+        DVec4::new(0.0, 0.0, gxy, gyy),
+        DVec4::new(1.0, 0.0, 0.0, 0.0),
+        DVec4::new(0.0, 1.0, 0.0, 0.0),
+    )
+}
+
+fn derivative(state: DVec4, phi: DMat4, sources: &[Source], drag: Option<Drag>) -> (DVec4, DMat4) {
+    let f = forcing_from(from_vector(state), Thrust::default(), sources, drag);
+    (
+        DVec4::new(f.vx, f.vy, f.ax, f.ay),
+        jacobian(state.x, state.y, sources) * phi,
+    )
+}
+
+// One step of the coast and its transition matrix. Within the step Φ follows its own RK4
+// path of the state, which starts each step from where the coast's integrator has got to.
+fn step(coast: &mut Coast, mut phi: DMat4) -> DMat4 {
+    let (sources, drag, dt) = (coast.sources(), coast.drag, coast.dt());
+    let n = substeps(&coast.state, dt, &sources);
+    let h = dt / n as Precision;
+    let derivative = |state, phi| derivative(state, phi, &sources, drag);
+    let mut state = vector(&coast.state);
+    for _ in 0..n {
+        let (k1, l1) = derivative(state, phi);
+        let (k2, l2) = derivative(state + 0.5 * h * k1, phi + l1 * (0.5 * h));
+        let (k3, l3) = derivative(state + 0.5 * h * k2, phi + l2 * (0.5 * h));
+        let (k4, l4) = derivative(state + h * k3, phi + l3 * h);
+        state += h / 6.0 * (k1 + 2.0 * k2 + 2.0 * k3 + k4);
+        phi += (l1 + l2 * 2.0 + l3 * 2.0 + l4) * (h / 6.0);
+    }
+
+    coast.step();
+    phi
+}
+
+// State after `steps` integration steps of the coast, and the transition matrix from its
+// initial state to it
+pub fn propagate(mut coast: Coast, steps: usize) -> (State, DMat4) {
+    let mut phi = DMat4::IDENTITY;
+
+    for _ in 0..steps {
+        phi = step(&mut coast, phi);
+    }
+
+    (coast.state, phi)
+}
+
+// The transition matrix from the initial state to each of the `steps` states after it, the
+// identity first
+pub fn transitions(mut coast: Coast, steps: usize) -> Vec<DMat4> {
+    let mut phi = DMat4::IDENTITY;
+    let mut transitions = vec![phi];
+
+    for _ in 0..steps {
+        phi = step(&mut coast, phi);
+        transitions.push(phi);
+    }
+
+    transitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity::Perturbations;
+    use crate::propagator::{MassivePaths, Propagator};
+    use crate::{DormandPrince, EARTH_RADIUS};
+
+    #[test]
+    fn state_flies_with_the_coasts_integrator() {
+        let paths = MassivePaths::default();
+        let perturbations = Perturbations { j2: true };
+        let integrator = DormandPrince::default();
+        let propagator = Propagator::new(&integrator, 10.0, &perturbations, None, &paths);
+        let r = EARTH_RADIUS + 400e3;
+        let start = State::new(r, 0.0, 0.0, 8000.0);
+
+        let mut coast = propagator.coast_from(start, 0.0);
+        for _ in 0..600 {
+            coast.step();
+        }
+        let (state, _) = propagate(propagator.coast_from(start, 0.0), 600);
+        assert_eq!((state.x, state.y), (coast.state.x, coast.state.y));
+        assert_eq!((state.vx, state.vy), (coast.state.vx, coast.state.vy));
+    }
+
+    #[test]
+    fn transition_matrix_matches_finite_differences() {
+        let paths = MassivePaths::default();
+        let perturbations = Perturbations { j2: true };
+        let integrator = DormandPrince::default();
+        let propagator = Propagator::new(&integrator, 10.0, &perturbations, None, &paths);
+        let start = vector(&State::new(EARTH_RADIUS + 400e3, 0.0, 0.0, 7800.0));
+        let (_, phi) = propagate(propagator.coast_from(from_vector(start), 0.0), 300);
+
+        // Central differences of the end state, 1 m and 1 mm/s apart
+        let nudges = [1.0, 1.0, 1e-3, 1e-3];
+        for (i, nudge) in nudges.into_iter().enumerate() {
+            let mut offset = DVec4::ZERO;
+            offset[i] = nudge;
+            let end = |start: DVec4| {
+                let coast = propagator.coast_from(from_vector(start), 0.0);
+                vector(&propagate(coast, 300).0)
+            };
+            let column = (end(start + offset) - end(start - offset)) / (2.0 * nudge);
+            let error = (column - phi.col(i)).length() / phi.col(i).length();
+            assert!(
+                error < 1e-4,
+                "column {}: {} against {}",
+                i,
+                column,
+                phi.col(i)
+            );
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// How each body is drawn in the orbit view: colors, line styles and marker shape
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const DOT_RADIUS: f32 = 10000.0;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LineStyle {
+    Dots,
+    Dashed,
+    Solid,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Marker {
+    Circle,
+    Square,
+    Cross,
+}
+
+// Marks a style set where the body was spawned, which the preferred style leaves alone
+#[derive(Component)]
+pub struct CustomStyle;
+
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct BodyStyle {
+    pub color: Color,
+    pub trail_color: Color,
+    pub lookahead_color: Color,
+    pub trail_style: LineStyle,
+    pub lookahead_style: LineStyle,
+    pub marker: Marker,
+}
+
+impl Default for BodyStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::RED,
+            trail_color: Color::RED,
+            lookahead_color: Color::GREEN,
+            trail_style: LineStyle::Dots,
+            lookahead_style: LineStyle::Dots,
+            marker: Marker::Circle,
+        }
+    }
+}
+
+// A color name or a hex code like "#ff8800"
+pub fn parse_color(name: &str) -> Result<Color, String> {
+    let color = match name {
+        "red" => Color::RED,
+        "green" => Color::GREEN,
+        "blue" => Color::BLUE,
+        "black" => Color::BLACK,
+        "gray" | "grey" => Color::GRAY,
+        "orange" => Color::ORANGE,
+        "purple" => Color::PURPLE,
+        "yellow" => Color::YELLOW,
+        "cyan" => Color::CYAN,
+        "pink" => Color::PINK,
+        _ => return Color::hex(name).map_err(|_| format!("unknown color `{}`", name)),
+    };
+
+    Ok(color)
+}
+
+pub fn parse_line_style(name: &str) -> Result<LineStyle, String> {
+    match name {
+        "dots" => Ok(LineStyle::Dots),
+        "dashed" => Ok(LineStyle::Dashed),
+        "solid" => Ok(LineStyle::Solid),
+        _ => Err(format!("unknown line style `{}`", name)),
+    }
+}
+
+pub fn parse_marker(name: &str) -> Result<Marker, String> {
+    match name {
+        "circle" => Ok(Marker::Circle),
+        "square" => Ok(Marker::Square),
+        "cross" => Ok(Marker::Cross),
+        _ => Err(format!("unknown marker `{}`", name)),
+    }
+}
+
+pub fn draw_marker(gizmos: &mut Gizmos, marker: Marker, position: Vec2, size: f32, color: Color) {
+    match marker {
+        Marker::Circle => {
+            gizmos.circle_2d(position, size, color);
+        }
+        Marker::Square => gizmos.rect_2d(position, 0.0, Vec2::splat(2.0 * size), color),
+        Marker::Cross => {
+            gizmos.line_2d(
+                position - Vec2::splat(size),
+                position + Vec2::splat(size),
+                color,
+            );
+            gizmos.line_2d(
+                position + Vec2::new(-size, size),
+                position + Vec2::new(size, -size),
+                color,
+            );
+        }
+    }
+}
+
+// Draws a sequence of trail samples. Dashes join every other pair of samples.
+pub fn draw_line(
+    gizmos: &mut Gizmos,
+    style: LineStyle,
+    points: impl IntoIterator<Item = Vec2>,
+    color: Color,
+) {
+    match style {
+        LineStyle::Dots => {
+            for point in points {
+                gizmos.circle_2d(point, DOT_RADIUS, color);
+            }
+        }
+        LineStyle::Dashed => {
+            let points: Vec<Vec2> = points.into_iter().collect();
+            for pair in points.chunks_exact(2) {
+                gizmos.line_2d(pair[0], pair[1], color);
+            }
+        }
+        LineStyle::Solid => gizmos.linestrip_2d(points, color),
+    }
+}
@@ -0,0 +1,101 @@
+// Swarms: bodies join named groups with `body(...).join("name")`, and `group("name").<method>`
+// runs a body method on every member in id order, so one command, or one `at(...)` line, burns
+// or plans for the whole group at once. `group("name").spread(burn_in, duration)` plans the
+// coordinated pair of burns that spreads a bunched group evenly around its orbit: each member
+// burns prograde by a little more than the one before, drifts back, and burns the same
+// retrograde `duration` seconds later. The drift is first order, so the spacing comes out
+// closest when `duration` spans many orbits.
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::elements::OrbitalElements;
+use crate::maneuver::{Frame, ManeuverNode};
+use crate::{Precision, State};
+
+#[derive(Resource, Default)]
+pub struct Groups(BTreeMap<String, Vec<usize>>); // body ids, in order
+
+impl Groups {
+    // Whether the body wasn't in the group already
+    pub fn join(&mut self, group: &str, id: usize) -> bool {
+        let members = self.0.entry(group.to_string()).or_default();
+        let Err(i) = members.binary_search(&id) else {
+            return false;
+        };
+        members.insert(i, id);
+        true
+    }
+
+    // Whether the body was in the group. A group with no one left goes.
+    pub fn leave(&mut self, group: &str, id: usize) -> bool {
+        let Some(members) = self.0.get_mut(group) else {
+            return false;
+        };
+        let Ok(i) = members.binary_search(&id) else {
+            return false;
+        };
+        members.remove(i);
+        if members.is_empty() {
+            self.0.remove(group);
+        }
+        true
+    }
+
+    pub fn disband(&mut self, group: &str) -> Option<Vec<usize>> {
+        self.0.remove(group)
+    }
+
+    pub fn members(&self, group: &str) -> Option<&[usize]> {
+        self.0.get(group).map(Vec::as_slice)
+    }
+
+    pub fn describe(&self) -> String {
+        if self.0.is_empty() {
+            return "no groups".to_string();
+        }
+        self.0
+            .iter()
+            .map(|(group, members)| {
+                let ids: Vec<String> = members.iter().map(|id| id.to_string()).collect();
+                format!("\"{}\": {}", group, ids.join(", "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// The two prograde burns, there and back, that put each of `members` (by id, bunched together
+// now) its share of the orbit behind the first after `duration` seconds; the first member stays
+// as it is. They start in `burn_in` seconds, on the step of `dt` after it, like `plan_burn`'s.
+pub fn spread(
+    members: &[(usize, State)],
+    (time, dt): (Precision, Precision),
+    burn_in: Precision,
+    duration: Precision,
+) -> Result<Vec<(usize, [ManeuverNode; 2])>, String> {
+    let start = time + (burn_in / dt).ceil().max(1.0) * dt;
+    let end = start + (duration / dt).round().max(1.0) * dt;
+    let count = members.len() as Precision;
+
+    members
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(k, (id, state))| {
+            let period = OrbitalElements::from_state(state)
+                .period()
+                .ok_or_else(|| format!("body {} is escaping, with no orbit to spread on", id))?;
+            // Falling behind by k / count of an orbit over `duration` takes a mean motion
+            // that much slower, and dv / v = -(1/3) dn / n on a near circular orbit
+            let speed = state.vx.hypot(state.vy);
+            let dv = speed * k as Precision / count * period / (end - start) / 3.0;
+            let burn = |time, dv| ManeuverNode {
+                time,
+                dv: [dv, 0.0],
+                frame: Frame::Orbital,
+            };
+            Ok((*id, [burn(start, dv), burn(end, -dv)]))
+        })
+        .collect()
+}
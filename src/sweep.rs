@@ -0,0 +1,160 @@
+// Step-size sweep: coasts one initial state for the same duration with a range of step
+// sizes and integrators, the simulation's own and two Euler baselines, and compares the final
+// positions with the Kepler solution, to see how much accuracy a step size buys and what it
+// costs in wall time
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::elements::{OrbitalElements, MU};
+use crate::{
+    forcing_from, DormandPrince, Drag, Integrator, Precision, Rk4, Source, State, Thrust, Verlet,
+    EARTH,
+};
+
+pub const CSV_PATH: &str = "sweep.csv";
+const STEP_SIZES: [Precision; 7] = [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0]; // s
+
+// Explicit Euler, for the baseline
+struct Euler;
+
+impl Integrator for Euler {
+    fn name(&self) -> &'static str {
+        "euler"
+    }
+
+    fn step(
+        &self,
+        state: State,
+        dt: Precision,
+        thrust: Thrust,
+        sources: &[Source],
+        drag: Option<Drag>,
+    ) -> State {
+        &state + &(dt * &forcing_from(state, thrust, sources, drag))
+    }
+}
+
+// Semi-implicit Euler: the velocity first, then the position with the new velocity
+struct SymplecticEuler;
+
+impl Integrator for SymplecticEuler {
+    fn name(&self) -> &'static str {
+        "symplectic_euler"
+    }
+
+    fn step(
+        &self,
+        state: State,
+        dt: Precision,
+        thrust: Thrust,
+        sources: &[Source],
+        drag: Option<Drag>,
+    ) -> State {
+        let k = forcing_from(state, thrust, sources, drag);
+        let (vx, vy) = (state.vx + dt * k.ax, state.vy + dt * k.ay);
+        State::new(state.x + dt * vx, state.y + dt * vy, vx, vy)
+    }
+}
+
+const INTEGRATORS: [&dyn Integrator; 5] = [
+    &Euler,
+    &SymplecticEuler,
+    &Verlet,
+    &Rk4,
+    &DormandPrince { tolerance: 1e-10 },
+];
+
+// Whole steps, then a shorter one for what is left of the duration, coasting around the earth
+fn propagate(
+    integrator: &dyn Integrator,
+    mut state: State,
+    dt: Precision,
+    duration: Precision,
+) -> State {
+    let coast = Thrust::default();
+    let steps = (duration / dt).floor() as usize;
+    for _ in 0..steps {
+        state = integrator.step(state, dt, coast, &[EARTH], None);
+    }
+    let rest = duration - steps as Precision * dt;
+    if rest > 0.0 {
+        state = integrator.step(state, rest, coast, &[EARTH], None);
+    }
+    state
+}
+
+pub struct SweepRow {
+    integrator: &'static dyn Integrator,
+    dt: Precision,
+    error: Precision, // m
+    wall: Duration,
+}
+
+// The state `duration` seconds later on the unperturbed orbit
+fn kepler(state: &State, duration: Precision) -> Result<State, String> {
+    let mut elements = OrbitalElements::from_state(state);
+    if elements.e >= 1.0 {
+        return Err("the sweep needs an elliptic orbit".to_string());
+    }
+
+    let mean_motion = (MU / elements.a.powi(3)).sqrt();
+    elements.mean_anomaly += mean_motion * duration;
+    let clockwise = state.x * state.vy - state.y * state.vx < 0.0;
+    Ok(elements.to_state(clockwise))
+}
+
+pub fn run_sweep(state: State, duration: Precision) -> Result<Vec<SweepRow>, String> {
+    let reference = kepler(&state, duration)?;
+    let mut rows = Vec::new();
+
+    for integrator in INTEGRATORS {
+        for dt in STEP_SIZES {
+            let start = Instant::now();
+            let end = propagate(integrator, state, dt, duration);
+            let wall = start.elapsed();
+
+            rows.push(SweepRow {
+                integrator,
+                dt,
+                error: ((end.x - reference.x).powi(2) + (end.y - reference.y).powi(2)).sqrt(),
+                wall,
+            });
+        }
+    }
+
+    Ok(rows)
+}
+
+pub fn format_table(rows: &[SweepRow]) -> String {
+    let mut table = format!(
+        "{:<18} {:>6} {:>14} {:>10}",
+        "integrator", "dt (s)", "error (m)", "wall (ms)"
+    );
+    for row in rows {
+        table.push_str(&format!(
+            "\n{:<18} {:>6} {:>14.4e} {:>10.3}",
+            row.integrator.name(),
+            row.dt,
+            row.error,
+            row.wall.as_secs_f64() * 1000.0
+        ));
+    }
+    table
+}
+
+pub fn write_csv(path: &str, rows: &[SweepRow]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "integrator,dt,error,wall_time")?;
+    for row in rows {
+        writeln!(
+            file,
+            "{},{},{},{}",
+            row.integrator.name(),
+            row.dt,
+            row.error,
+            row.wall.as_secs_f64()
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+// Differential corrector: adjusts the Δv of an impulsive burn with Newton iterations on the
+// state transition matrix until the trajectory meets constraints at a later time
+use bevy::math::{DMat2, DVec2, DVec4};
+
+use crate::propagator::Coast;
+use crate::{stm, Precision, State, EARTH_RADIUS};
+
+const MAX_ITERATIONS: usize = 25;
+const MAX_CORRECTION: Precision = 500.0; // m/s per iteration
+
+// Conditions on the state at the end of the coast after the burn
+pub enum Constraint {
+    Altitude(Precision),
+    RadialVelocity(Precision),
+}
+
+impl Constraint {
+    // How far the state is from meeting the constraint, and the gradient of that with
+    // respect to (x, y, vx, vy)
+    fn evaluate(&self, state: &State) -> (Precision, DVec4) {
+        let r = (state.x * state.x + state.y * state.y).sqrt();
+
+        match self {
+            Constraint::Altitude(altitude) => (
+                r - EARTH_RADIUS - altitude,
+                DVec4::new(state.x / r, state.y / r, 0.0, 0.0),
+            ),
+            Constraint::RadialVelocity(target) => {
+                let radial = (state.x * state.vx + state.y * state.vy) / r;
+                (
+                    radial - target,
+                    DVec4::new(
+                        state.vx / r - radial * state.x / (r * r),
+                        state.vy / r - radial * state.y / (r * r),
+                        state.x / r,
+                        state.y / r,
+                    ),
+                )
+            }
+        }
+    }
+
+    fn tolerance(&self) -> Precision {
+        match self {
+            Constraint::Altitude(_) => 1.0,          // m
+            Constraint::RadialVelocity(_) => 1.0e-3, // m/s
+        }
+    }
+}
+
+// Δv of a burn `burn_steps` integration steps along the coast that meets the constraints
+// `flight_steps` steps after it. One constraint leaves a degree of freedom, and the
+// smallest correction is taken at each iteration.
+pub fn solve(
+    coast: Coast,
+    burn_steps: usize,
+    flight_steps: usize,
+    constraints: &[Constraint],
+) -> Result<(Precision, Precision), String> {
+    let mut burn = coast;
+    for _ in 0..burn_steps {
+        burn.step();
+    }
+
+    let mut dv = DVec2::ZERO;
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut start = burn;
+        start.state.vx += dv.x;
+        start.state.vy += dv.y;
+        let (end, phi) = stm::propagate(start, flight_steps);
+
+        let mut errors = Vec::new();
+        let mut rows = Vec::new();
+        let mut converged = true;
+
+        for constraint in constraints {
+            let (error, gradient) = constraint.evaluate(&end);
+            converged &= error.abs() < constraint.tolerance();
+            errors.push(error);
+            // Only the velocity columns matter, the burn can't move the body
+            rows.push(DVec2::new(
+                gradient.dot(phi.col(2)),
+                gradient.dot(phi.col(3)),
+            ));
+        }
+
+        if converged {
+            return Ok((dv.x, dv.y));
+        }
+
+        let correction = match (errors.as_slice(), rows.as_slice()) {
+            ([error], [row]) => *row * (*error / row.length_squared()),
+            ([e1, e2], [r1, r2]) => {
+                let jacobian = DMat2::from_cols(DVec2::new(r1.x, r2.x), DVec2::new(r1.y, r2.y));
+                if jacobian.determinant().abs() < 1e-12 {
+                    return Err("the constraints can't be met independently".to_string());
+                }
+                jacobian.inverse() * DVec2::new(*e1, *e2)
+            }
+            _ => return Err("the targeter takes one or two constraints".to_string()),
+        };
+
+        if !correction.is_finite() {
+            return Err("the targeter diverged".to_string());
+        }
+        dv -= correction.clamp_length_max(MAX_CORRECTION);
+    }
+
+    Err(format!("no solution after {} iterations", MAX_ITERATIONS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::MU;
+    use crate::gravity::Perturbations;
+    use crate::propagator::{MassivePaths, Propagator};
+    use crate::DormandPrince;
+
+    const R: Precision = EARTH_RADIUS + 400e3;
+
+    fn end(
+        coast: Coast,
+        burn_steps: usize,
+        flight_steps: usize,
+        dv: (Precision, Precision),
+    ) -> State {
+        let mut coast = coast;
+        for _ in 0..burn_steps {
+            coast.step();
+        }
+        coast.state.vx += dv.0;
+        coast.state.vy += dv.1;
+        for _ in 0..flight_steps {
+            coast.step();
+        }
+        coast.state
+    }
+
+    #[test]
+    fn meets_an_altitude_and_a_radial_velocity() {
+        let paths = MassivePaths::default();
+        let perturbations = Perturbations { j2: false };
+        let integrator = DormandPrince::default();
+        let propagator = Propagator::new(&integrator, 10.0, &perturbations, None, &paths);
+        let coast = propagator.coast_from(State::new(R, 0.0, 0.0, (MU / R).sqrt()), 0.0);
+        let constraints = [Constraint::Altitude(800e3), Constraint::RadialVelocity(0.0)];
+
+        let dv = solve(coast, 10, 280, &constraints).unwrap();
+        let state = end(coast, 10, 280, dv);
+        for constraint in &constraints {
+            let (error, _) = constraint.evaluate(&state);
+            assert!(error.abs() < constraint.tolerance(), "off by {}", error);
+        }
+    }
+
+    #[test]
+    fn takes_one_or_two_constraints() {
+        let paths = MassivePaths::default();
+        let perturbations = Perturbations { j2: false };
+        let integrator = DormandPrince::default();
+        let propagator = Propagator::new(&integrator, 10.0, &perturbations, None, &paths);
+        let coast = propagator.coast_from(State::new(R, 0.0, 0.0, (MU / R).sqrt()), 0.0);
+        let constraints = [
+            Constraint::Altitude(800e3),
+            Constraint::RadialVelocity(0.0),
+            Constraint::Altitude(900e3),
+        ];
+        assert!(solve(coast, 0, 280, &constraints).is_err());
+    }
+}
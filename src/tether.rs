@@ -0,0 +1,127 @@
+// Inextensible tethers between bodies, enforced as a constraint after every step the pair flies,
+// under warp too
+use bevy::prelude::*;
+
+use crate::gravity::GravitySource;
+use crate::interpolation::Interpolation;
+use crate::palette::Palette;
+use crate::style::BodyStyle;
+use crate::{Body, BodyIds, Precision};
+
+const TETHER_LENGTH: Precision = 20000.0; // m
+const PROBE_MASS: Precision = 0.1;
+
+// Ties a body to an anchor body. The tether only pulls when taut.
+#[derive(Component)]
+pub struct Tether {
+    pub anchor: Entity,
+    pub length: Precision,
+    pub tension: Precision, // N, averaged over the last tick's steps
+}
+
+// Craft free of a tether
+pub type Untethered = (Without<Tether>, Without<GravitySource>);
+
+// Pressing T lets out a probe on a tether below every untethered craft, though not below the
+// Moon or another massive body.
+pub fn deploy_probe(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    mut ids: ResMut<BodyIds>,
+    query: Query<(Entity, &Body), Untethered>,
+) {
+    if !keyboard.just_pressed(KeyCode::T) {
+        return;
+    }
+
+    for (anchor, body) in query.iter() {
+        let id = ids.next();
+        let state = body.current_state;
+        let r = (state.x * state.x + state.y * state.y).sqrt();
+        // Start straight below the anchor, moving with the same angular velocity
+        let scale = (r - TETHER_LENGTH) / r;
+
+        commands.spawn((
+            Body::new(
+                id,
+                PROBE_MASS,
+                state.x * scale,
+                state.y * scale,
+                state.vx * scale,
+                state.vy * scale,
+            ),
+            BodyStyle::default(),
+            Tether {
+                anchor,
+                length: TETHER_LENGTH,
+                tension: 0.0,
+            },
+        ));
+
+        info!("Deployed tethered probe {} from body {}", id, body.id);
+    }
+}
+
+// Pulls a tethered pair back to the tether length and removes their separating velocity, and
+// gives the impulse that took, N s. Corrections are weighted by inverse mass so the pair's
+// momentum is conserved, which is what makes momentum-exchange manoeuvres work.
+pub fn pull(body: &mut Body, anchor: &mut Body, length: Precision) -> Precision {
+    let dx = body.current_state.x - anchor.current_state.x;
+    let dy = body.current_state.y - anchor.current_state.y;
+    let d = (dx * dx + dy * dy).sqrt();
+
+    if d <= length {
+        return 0.0;
+    }
+
+    let (nx, ny) = (dx / d, dy / d);
+    let w_body = 1.0 / body.mass;
+    let w_anchor = 1.0 / anchor.mass;
+    let w = w_body + w_anchor;
+
+    let correction = (d - length) / w;
+    body.current_state.x -= w_body * correction * nx;
+    body.current_state.y -= w_body * correction * ny;
+    anchor.current_state.x += w_anchor * correction * nx;
+    anchor.current_state.y += w_anchor * correction * ny;
+
+    let separating = (body.current_state.vx - anchor.current_state.vx) * nx
+        + (body.current_state.vy - anchor.current_state.vy) * ny;
+    if separating <= 0.0 {
+        return 0.0;
+    }
+
+    let impulse = separating / w;
+    body.current_state.vx -= w_body * impulse * nx;
+    body.current_state.vy -= w_body * impulse * ny;
+    anchor.current_state.vx += w_anchor * impulse * nx;
+    anchor.current_state.vy += w_anchor * impulse * ny;
+    impulse
+}
+
+pub fn draw_tethers(
+    mut gizmos: Gizmos,
+    palette: Res<Palette>,
+    interpolation: Interpolation,
+    tethers: Query<(Entity, &Tether)>,
+    bodies: Query<&Body>,
+) {
+    let colors = palette.colors();
+    for (entity, tether) in tethers.iter() {
+        let Ok([body, anchor]) = bodies.get_many([entity, tether.anchor]) else {
+            continue;
+        };
+
+        let color = if tether.tension > 0.0 {
+            colors.text
+        } else {
+            colors.guide
+        };
+
+        gizmos.line_2d(
+            interpolation.position(body),
+            interpolation.position(anchor),
+            color,
+        );
+    }
+}
@@ -0,0 +1,204 @@
+// Lumped thermal model: a body with a Thermal component is a single node at one temperature,
+// heated by the Sun while it is out of the earth's shadow and by its own electronics, and
+// cooled by radiating to space. Leaving the operating range raises a warning; leaving the
+// survival range is a thermal failure that takes the engine out until it is reset.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{Engine, EngineFault};
+use crate::ephemeris::Ephemeris;
+use crate::i18n::Language;
+use crate::{Body, Precision, Selected, SimTime};
+
+const SOLAR_FLUX: Precision = 1361.0; // W/m² at 1 AU
+const STEFAN_BOLTZMANN: Precision = 5.670374e-8; // W m⁻² K⁻⁴
+const MAX_SUBSTEP: Precision = 60.0; // s, well under the thermal time constant
+pub const KELVIN: Precision = 273.15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ThermalStatus {
+    Nominal,
+    Cold,
+    Hot,
+    Failed, // stays failed until the model is reset
+}
+
+impl ThermalStatus {
+    pub fn describe(self, language: Language) -> String {
+        let key = match self {
+            ThermalStatus::Nominal => "thermal_nominal",
+            ThermalStatus::Cold => "thermal_cold",
+            ThermalStatus::Hot => "thermal_hot",
+            ThermalStatus::Failed => "thermal_failed",
+        };
+        language.text(key, &[])
+    }
+}
+
+// Lowest and highest temperatures, in K
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ThermalLimits {
+    pub operating: (Precision, Precision),
+    pub survival: (Precision, Precision),
+}
+
+impl ThermalLimits {
+    // From °C, checking the operating range lies within the survival one
+    pub fn from_celsius(
+        operating: (Precision, Precision),
+        survival: (Precision, Precision),
+    ) -> Result<Self, String> {
+        if !(survival.0 <= operating.0 && operating.0 < operating.1 && operating.1 <= survival.1) {
+            return Err("the operating range must lie within the survival range".to_string());
+        }
+        Ok(Self {
+            operating: (operating.0 + KELVIN, operating.1 + KELVIN),
+            survival: (survival.0 + KELVIN, survival.1 + KELVIN),
+        })
+    }
+}
+
+impl Default for ThermalLimits {
+    // Typical of spacecraft electronics, -10 to 50 °C operating and -40 to 70 °C survival
+    fn default() -> Self {
+        Self::from_celsius((-10.0, 50.0), (-40.0, 70.0)).unwrap()
+    }
+}
+
+// A small satellite: 100 kg of aluminium-like heat capacity, a square metre facing the Sun
+// and two radiating
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct Thermal {
+    pub temperature: Precision, // K
+    heat_capacity: Precision,   // J/K
+    absorbing_area: Precision,  // m²
+    radiating_area: Precision,  // m²
+    absorptivity: Precision,
+    emissivity: Precision,
+    pub dissipation: Precision, // W from the electronics
+    pub limits: ThermalLimits,
+    pub status: ThermalStatus,
+    updated: Option<Precision>, // simulated time of the last update
+}
+
+impl Thermal {
+    pub fn new(dissipation: Precision) -> Self {
+        Self {
+            temperature: 20.0 + KELVIN,
+            heat_capacity: 9e4,
+            absorbing_area: 1.0,
+            radiating_area: 2.0,
+            absorptivity: 0.6,
+            emissivity: 0.8,
+            dissipation,
+            limits: ThermalLimits::default(),
+            status: ThermalStatus::Nominal,
+            updated: None,
+        }
+    }
+
+    pub fn celsius(&self) -> Precision {
+        self.temperature - KELVIN
+    }
+
+    // K/s
+    fn rate(&self, sunlit: bool) -> Precision {
+        let solar = if sunlit {
+            self.absorptivity * SOLAR_FLUX * self.absorbing_area
+        } else {
+            0.0
+        };
+        let radiated =
+            self.emissivity * STEFAN_BOLTZMANN * self.radiating_area * self.temperature.powi(4);
+        (solar + self.dissipation - radiated) / self.heat_capacity
+    }
+
+    fn classify(&self) -> ThermalStatus {
+        let (low, high) = self.limits.operating;
+        let (min, max) = self.limits.survival;
+        match self.temperature {
+            _ if self.status == ThermalStatus::Failed => ThermalStatus::Failed,
+            t if t < min || t > max => ThermalStatus::Failed,
+            t if t < low => ThermalStatus::Cold,
+            t if t > high => ThermalStatus::Hot,
+            _ => ThermalStatus::Nominal,
+        }
+    }
+}
+
+impl Default for Thermal {
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+// Integrates each body's temperature over the time since the last frame, in the light or
+// shadow it is in now
+pub fn update_thermal(
+    time: Res<SimTime>,
+    ephemeris: Res<Ephemeris>,
+    mut query: Query<(&Body, &mut Thermal, Option<&mut Engine>)>,
+) {
+    for (body, mut thermal, engine) in query.iter_mut() {
+        let elapsed = thermal.updated.map_or(0.0, |updated| time.0 - updated);
+        thermal.updated = Some(time.0);
+        if elapsed <= 0.0 {
+            continue;
+        }
+
+        let sunlit = !ephemeris.in_eclipse(&body.current_state);
+        let substeps = (elapsed / MAX_SUBSTEP).ceil();
+        let dt = elapsed / substeps;
+        for _ in 0..substeps as usize {
+            thermal.temperature += thermal.rate(sunlit) * dt;
+        }
+
+        let status = thermal.classify();
+        if status == thermal.status {
+            continue;
+        }
+        thermal.status = status;
+        let celsius = thermal.celsius();
+        match status {
+            ThermalStatus::Failed => {
+                error!(
+                    "t = {:.0} s: body {} failed thermally at {:.1} °C",
+                    time.0, body.id, celsius
+                );
+                if let Some(mut engine) = engine {
+                    engine.set_fault(EngineFault::StuckOff, body, time.0);
+                }
+            }
+            ThermalStatus::Cold | ThermalStatus::Hot => warn!(
+                "t = {:.0} s: body {} is out of its operating range at {:.1} °C",
+                time.0, body.id, celsius
+            ),
+            ThermalStatus::Nominal => info!(
+                "t = {:.0} s: body {} is back within its operating range at {:.1} °C",
+                time.0, body.id, celsius
+            ),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ThermalText;
+
+pub fn show_thermal(
+    language: Res<Language>,
+    selected: Query<(&Body, &Thermal), With<Selected>>,
+    mut text: Query<&mut Text, With<ThermalText>>,
+) {
+    let value = match selected.get_single() {
+        Ok((body, thermal)) => language.text(
+            "thermal",
+            &[
+                &body.id,
+                &format!("{:.1}", thermal.celsius()),
+                &thermal.status.describe(*language),
+            ],
+        ),
+        Err(_) => String::new(),
+    };
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,129 @@
+// Time scales: simulated time counts SI seconds, as TAI does, while timestamps are in UTC,
+// which falls a second further behind TAI at every leap second, and the Sun and Moon
+// ephemerides want TT, a fixed 32.184 s ahead of TAI. Julian dates here are all days of
+// 86400 s in their own scale. UTC before 1972, when it still had rubber seconds, is taken as
+// TAI - 10 s.
+use crate::Precision;
+
+pub const TT_MINUS_TAI: Precision = 32.184; // s
+const DAY: Precision = 86400.0; // s
+
+// First UTC day with each value of TAI - UTC, in s
+const LEAP_SECONDS: [(i64, i64, Precision); 28] = [
+    (1972, 1, 10.0),
+    (1972, 7, 11.0),
+    (1973, 1, 12.0),
+    (1974, 1, 13.0),
+    (1975, 1, 14.0),
+    (1976, 1, 15.0),
+    (1977, 1, 16.0),
+    (1978, 1, 17.0),
+    (1979, 1, 18.0),
+    (1980, 1, 19.0),
+    (1981, 7, 20.0),
+    (1982, 7, 21.0),
+    (1983, 7, 22.0),
+    (1985, 7, 23.0),
+    (1988, 1, 24.0),
+    (1990, 1, 25.0),
+    (1991, 1, 26.0),
+    (1992, 7, 27.0),
+    (1993, 7, 28.0),
+    (1994, 7, 29.0),
+    (1996, 1, 30.0),
+    (1997, 7, 31.0),
+    (1999, 1, 32.0),
+    (2006, 1, 33.0),
+    (2009, 1, 34.0),
+    (2012, 7, 35.0),
+    (2015, 7, 36.0),
+    (2017, 1, 37.0),
+];
+
+// Julian date at the start of a Gregorian calendar day (Meeus, chapter 7)
+pub fn julian_day(year: i64, month: i64, day: i64) -> Precision {
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 12)
+    } else {
+        (year, month)
+    };
+    let a = year.div_euclid(100);
+    let b = 2 - a + a.div_euclid(4);
+
+    (365.25 * (year + 4716) as Precision).floor()
+        + (30.6001 * (month + 1) as Precision).floor()
+        + (day + b) as Precision
+        - 1524.5
+}
+
+// The UTC Julian date each value of TAI - UTC starts at, latest first
+fn leap_seconds() -> impl Iterator<Item = (Precision, Precision)> {
+    LEAP_SECONDS
+        .iter()
+        .rev()
+        .map(|&(year, month, offset)| (julian_day(year, month, 1), offset))
+}
+
+// s at a UTC Julian date
+pub fn tai_minus_utc(utc: Precision) -> Precision {
+    leap_seconds()
+        .find(|(start, _)| utc >= *start)
+        .map_or(LEAP_SECONDS[0].2, |(_, offset)| offset)
+}
+
+// SI seconds from one UTC Julian date to another, leap seconds included
+pub fn seconds_between(from: Precision, to: Precision) -> Precision {
+    (to - from) * DAY + tai_minus_utc(to) - tai_minus_utc(from)
+}
+
+pub fn tai_from_utc(utc: Precision) -> Precision {
+    utc + tai_minus_utc(utc) / DAY
+}
+
+// The UTC Julian date of a TAI one, and whether it falls within a leap second, when UTC reads
+// 23:59:60 and the date stays at the start of the next day
+pub fn utc_from_tai(tai: Precision) -> (Precision, bool) {
+    for (start, offset) in leap_seconds() {
+        if tai >= start + offset / DAY {
+            return (tai - offset / DAY, false);
+        }
+        // UTC started at TAI - 10 s without a leap second
+        if offset > LEAP_SECONDS[0].2 && tai >= start + (offset - 1.0) / DAY {
+            return (start, true);
+        }
+    }
+    (tai - LEAP_SECONDS[0].2 / DAY, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn julian_days_of_calendar_days() {
+        assert_eq!(julian_day(2000, 1, 1), 2451544.5);
+        assert_eq!(julian_day(1957, 10, 4), 2436115.5);
+        assert_eq!(julian_day(2024, 3, 1) - julian_day(2024, 2, 1), 29.0);
+    }
+
+    #[test]
+    fn leap_seconds_start_on_their_day() {
+        let (before, after) = (julian_day(2016, 12, 31), julian_day(2017, 1, 1));
+        assert_eq!(tai_minus_utc(before), 36.0);
+        assert_eq!(tai_minus_utc(after), 37.0);
+        assert_eq!(tai_minus_utc(julian_day(1960, 1, 1)), 10.0);
+        assert!((seconds_between(before, after) - 86401.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn utc_reads_23_59_60_in_a_leap_second() {
+        let midnight = julian_day(2017, 1, 1);
+        // Half a second into the leap second, UTC stays at the start of the next day
+        let tai = midnight + 36.5 / DAY;
+        assert_eq!(utc_from_tai(tai), (midnight, true));
+
+        let utc = midnight + 0.3;
+        let (back, leap) = utc_from_tai(tai_from_utc(utc));
+        assert!(!leap && (back - utc).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,209 @@
+// Two-line element catalogs. Each object is propagated on its mean elements from the TLE
+// epoch to the simulation epoch and projected onto the equatorial simulation plane, so the
+// inclination only matters for the import filters.
+use std::f64::consts::TAU;
+
+use crate::elements::{OrbitalElements, MU};
+use crate::loading::BodySpec;
+use crate::timescales;
+use crate::{Precision, EARTH_RADIUS};
+
+pub struct Tle {
+    pub name: String,
+    pub epoch: Precision,       // Julian date
+    pub inclination: Precision, // rad
+    pub raan: Precision,        // rad
+    pub e: Precision,
+    pub argument_of_perigee: Precision, // rad
+    pub mean_anomaly: Precision,        // rad
+    pub mean_motion: Precision,         // rad/s
+}
+
+impl Tle {
+    pub fn semi_major_axis(&self) -> Precision {
+        (MU / (self.mean_motion * self.mean_motion)).cbrt()
+    }
+
+    pub fn to_spec(&self, jd: Precision) -> BodySpec {
+        let elapsed = timescales::seconds_between(self.epoch, jd);
+        let retrograde = self.inclination > TAU / 4.0;
+        // Seen from above the equator a retrograde orbit's node moves the other way
+        let node = if retrograde { -self.raan } else { self.raan };
+        let elements = OrbitalElements {
+            a: self.semi_major_axis(),
+            e: self.e,
+            argument_of_periapsis: (node + self.argument_of_perigee).rem_euclid(TAU),
+            mean_anomaly: (self.mean_anomaly + self.mean_motion * elapsed).rem_euclid(TAU),
+        };
+        let state = elements.to_state(retrograde);
+
+        BodySpec {
+            name: self.name.clone(),
+            mass: 1.0,
+            state: [state.x, state.y, state.vx, state.vy],
+        }
+    }
+}
+
+// Objects to keep from a catalog. Altitudes are of the semi-major axis above the surface.
+#[derive(Clone, Default)]
+pub struct TleFilter {
+    pub altitude: Option<(Precision, Precision)>,    // m
+    pub inclination: Option<(Precision, Precision)>, // rad
+    pub name: Option<String>,                        // with * wildcards, any case
+}
+
+impl TleFilter {
+    pub fn matches(&self, tle: &Tle) -> bool {
+        let within = |range: Option<(Precision, Precision)>, value: Precision| {
+            range.is_none_or(|(low, high)| low <= value && value <= high)
+        };
+
+        within(self.altitude, tle.semi_major_axis() - EARTH_RADIUS)
+            && within(self.inclination, tle.inclination)
+            && self.name.as_ref().is_none_or(|pattern| {
+                wildcard_match(&pattern.to_lowercase(), &tle.name.to_lowercase())
+            })
+    }
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((head, rest)) => {
+            let Some(text) = text.strip_prefix(head) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|&i| text.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &text[i..]))
+        }
+    }
+}
+
+// Digits at one-based columns `start..=end` of a TLE line
+fn field(line: &str, start: usize, end: usize) -> Result<Precision, String> {
+    let text = line
+        .get(start - 1..end)
+        .ok_or_else(|| format!("line too short for columns {}-{}", start, end))?;
+    text.trim()
+        .parse()
+        .map_err(|_| format!("bad number `{}` in columns {}-{}", text.trim(), start, end))
+}
+
+fn parse_tle(name: &str, line1: &str, line2: &str) -> Result<Tle, String> {
+    let year = field(line1, 19, 20)? as i64;
+    let year = if year < 57 { 2000 + year } else { 1900 + year };
+    let day = field(line1, 21, 32)?;
+
+    Ok(Tle {
+        name: name.trim().to_string(),
+        epoch: timescales::julian_day(year, 1, 1) + day - 1.0,
+        inclination: field(line2, 9, 16)?.to_radians(),
+        raan: field(line2, 18, 25)?.to_radians(),
+        // Written with an implied leading decimal point
+        e: field(line2, 27, 33)? * 1e-7,
+        argument_of_perigee: field(line2, 35, 42)?.to_radians(),
+        mean_anomaly: field(line2, 44, 51)?.to_radians(),
+        mean_motion: field(line2, 53, 63)? * TAU / 86400.0,
+    })
+}
+
+// Catalogs in the three-line format, a name line before each element pair. Pairs without a
+// name line are named after their catalog number.
+pub fn parse_catalog(source: &str) -> Result<Vec<Tle>, String> {
+    let lines: Vec<(usize, &str)> = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+
+    let mut objects = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (number, line) = lines[i];
+        let (name, first) = if line.starts_with("1 ") {
+            (line.get(2..7).unwrap_or("").trim(), i)
+        } else {
+            (line.strip_prefix("0 ").unwrap_or(line), i + 1)
+        };
+
+        let (Some((_, line1)), Some((_, line2))) = (lines.get(first), lines.get(first + 1)) else {
+            return Err(format!("line {}: incomplete element set", number + 1));
+        };
+        if !line1.starts_with("1 ") || !line2.starts_with("2 ") {
+            return Err(format!("line {}: expected TLE lines 1 and 2", number + 1));
+        }
+
+        objects.push(
+            parse_tle(name, line1, line2)
+                .map_err(|error| format!("line {}: {}", number + 1, error))?,
+        );
+        i = first + 2;
+    }
+
+    Ok(objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISS: &str = "\
+ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537
+";
+
+    #[test]
+    fn reads_the_fields() {
+        let tle = &parse_catalog(ISS).unwrap()[0];
+        assert_eq!(tle.name, "ISS (ZARYA)");
+        assert!((tle.epoch - 2454730.01782528).abs() < 1e-8);
+        assert!((tle.inclination.to_degrees() - 51.6416).abs() < 1e-9);
+        assert!((tle.raan.to_degrees() - 247.4627).abs() < 1e-9);
+        assert!((tle.e - 0.0006703).abs() < 1e-12);
+        assert!((tle.mean_motion * 86400.0 / TAU - 15.72125391).abs() < 1e-9);
+        let altitude = tle.semi_major_axis() - EARTH_RADIUS;
+        assert!((300e3..400e3).contains(&altitude), "{} m", altitude);
+    }
+
+    #[test]
+    fn unnamed_pairs_take_their_catalog_number() {
+        let pair = ISS.split_once('\n').unwrap().1;
+        let catalog = format!("{}\n{}", pair, ISS);
+        let names: Vec<String> = parse_catalog(&catalog)
+            .unwrap()
+            .into_iter()
+            .map(|tle| tle.name)
+            .collect();
+        assert_eq!(names, ["25544", "ISS (ZARYA)"]);
+    }
+
+    #[test]
+    fn reports_broken_sets() {
+        let error = |lines: &[&str]| parse_catalog(&lines.join("\n")).err().unwrap();
+        let lines: Vec<&str> = ISS.lines().collect();
+        assert!(error(&lines[..2]).contains("incomplete"));
+        assert!(error(&[lines[0], lines[2], lines[1]]).contains("expected TLE lines"));
+        let short = error(&[lines[0], lines[1], "2 25544  51.6416"]);
+        assert!(short.starts_with("line 1: line too short"), "{}", short);
+    }
+
+    #[test]
+    fn filters_by_name_with_wildcards() {
+        let tle = &parse_catalog(ISS).unwrap()[0];
+        let named = |pattern: &str| TleFilter {
+            name: Some(pattern.to_string()),
+            ..TleFilter::default()
+        };
+        assert!(named("iss*").matches(tle));
+        assert!(named("*zarya)").matches(tle));
+        assert!(!named("iss").matches(tle));
+        let low = TleFilter {
+            altitude: Some((500e3, 600e3)),
+            ..TleFilter::default()
+        };
+        assert!(!low.matches(tle));
+    }
+}
@@ -0,0 +1,145 @@
+// Predicted trajectories as ephemeris files for professional tools, to cross-validate the
+// simulation against GMAT, STK or Orekit: a CCSDS Orbit Ephemeris Message (OEM, in its
+// key = value form) or an STK .e file, picked by the file's extension. The trajectory coasts
+// from the body's current state like the lookaheads, with drag if it feels any. The
+// simulation plane is the equator, so z and vz are always zero, and times are UTC.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ephemeris::{calendar_date, Epoch};
+use crate::propagator::Coast;
+use crate::{Precision, State};
+
+pub const DEFAULT_STEP: Precision = 60.0; // s
+const ORIGINATOR: &str = "orbitabase";
+const UNIX_EPOCH_JD: Precision = 2440587.5; // 1970-01-01 00:00 UTC
+const DECIMALS: usize = 3; // of the second, in the OEM's times
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Oem,
+    Stk,
+}
+
+impl Format {
+    // STK's .e, and OEM for anything else
+    pub fn of(path: &str) -> Self {
+        if path.ends_with(".e") {
+            Format::Stk
+        } else {
+            Format::Oem
+        }
+    }
+}
+
+// States every `step` seconds (rounded to whole steps of the coast's) over `duration` of the
+// coast, timed from its start
+pub fn predict(mut coast: Coast, duration: Precision, step: Precision) -> Vec<(Precision, State)> {
+    let dt = coast.dt();
+    let stride = (step / dt).round().max(1.0) as usize;
+    let steps = (duration / dt).ceil() as usize;
+
+    let mut samples = vec![(0.0, coast.state)];
+    for i in 1..=steps {
+        let state = coast.step();
+        if i % stride == 0 || i == steps {
+            samples.push((i as Precision * dt, state));
+        }
+    }
+    samples
+}
+
+// The wall clock's UTC time, Unix time leaving out leap seconds as UTC does
+fn creation_date() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+    Epoch(UNIX_EPOCH_JD + now / 86400.0).timestamp(0.0)
+}
+
+// Writes samples taken from sim time `start` on, returning how many
+pub fn write_ephemeris(
+    path: &str,
+    format: Format,
+    (id, name): (usize, Option<&str>),
+    epoch: &Epoch,
+    start: Precision,
+    samples: &[(Precision, State)],
+) -> io::Result<usize> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let name = name.map_or_else(|| format!("BODY {}", id), str::to_string);
+
+    match format {
+        Format::Oem => {
+            let last = samples.last().map_or(0.0, |(time, _)| *time);
+            writeln!(file, "CCSDS_OEM_VERS = 2.0")?;
+            writeln!(file, "CREATION_DATE = {}", creation_date())?;
+            writeln!(file, "ORIGINATOR = {}", ORIGINATOR)?;
+            writeln!(file)?;
+            writeln!(file, "META_START")?;
+            writeln!(file, "OBJECT_NAME = {}", name)?;
+            writeln!(file, "OBJECT_ID = {}", id)?;
+            writeln!(file, "CENTER_NAME = EARTH")?;
+            writeln!(file, "REF_FRAME = EME2000")?;
+            writeln!(file, "TIME_SYSTEM = UTC")?;
+            let stamp = |time: Precision| epoch.timestamp_to(time, DECIMALS);
+            writeln!(file, "START_TIME = {}", stamp(start))?;
+            writeln!(file, "STOP_TIME = {}", stamp(start + last))?;
+            writeln!(file, "META_STOP")?;
+            writeln!(file)?;
+            // km and km/s
+            for (time, state) in samples {
+                writeln!(
+                    file,
+                    "{} {:.6} {:.6} 0.000000 {:.9} {:.9} 0.000000000",
+                    stamp(start + time),
+                    state.x / 1000.0,
+                    state.y / 1000.0,
+                    state.vx / 1000.0,
+                    state.vy / 1000.0
+                )?;
+            }
+        }
+        Format::Stk => {
+            let (year, month, day, seconds) = calendar_date(epoch.julian_date(start));
+            writeln!(file, "stk.v.11.0")?;
+            writeln!(file, "# {} from {}", name, ORIGINATOR)?;
+            writeln!(file)?;
+            writeln!(file, "BEGIN Ephemeris")?;
+            writeln!(file, "NumberOfEphemerisPoints {}", samples.len())?;
+            writeln!(
+                file,
+                "ScenarioEpoch {} {} {} {:02}:{:02}:{:06.3}",
+                day,
+                MONTHS[month as usize - 1],
+                year,
+                (seconds / 3600.0).floor(),
+                (seconds / 60.0).floor() % 60.0,
+                seconds % 60.0
+            )?;
+            writeln!(file, "InterpolationMethod Lagrange")?;
+            writeln!(file, "InterpolationSamplesM1 7")?;
+            writeln!(file, "CentralBody Earth")?;
+            writeln!(file, "CoordinateSystem J2000")?;
+            writeln!(file)?;
+            // s from the epoch, m and m/s
+            writeln!(file, "EphemerisTimePosVel")?;
+            for (time, state) in samples {
+                writeln!(
+                    file,
+                    "{:.3} {:.3} {:.3} 0.000 {:.6} {:.6} 0.000000",
+                    time, state.x, state.y, state.vx, state.vy
+                )?;
+            }
+            writeln!(file)?;
+            writeln!(file, "END Ephemeris")?;
+        }
+    }
+    file.flush()?;
+
+    Ok(samples.len())
+}